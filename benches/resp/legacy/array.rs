@@ -0,0 +1,90 @@
+use std::ops::Deref;
+
+use bytes::Buf;
+
+use super::{RespDecode, RespEncode, RespError, RespFrame};
+
+use super::{calc_total_length, parse_length2, BUF_CAP, CRLF_LEN};
+
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord)]
+pub enum RespArray {
+    Array(Vec<RespFrame>),
+    Null,
+}
+
+impl RespEncode for RespArray {
+    fn encode(self) -> Vec<u8> {
+        match self {
+            RespArray::Array(v) => {
+                let mut buf = Vec::with_capacity(BUF_CAP);
+                buf.extend_from_slice(&format!("*{}\r\n", v.len()).into_bytes());
+                for frame in v {
+                    buf.extend_from_slice(&frame.encode());
+                }
+                buf
+            }
+            RespArray::Null => b"*-1\r\n".to_vec(),
+        }
+    }
+}
+
+impl RespDecode for RespArray {
+    const PREFIX: &'static str = "*";
+
+    fn decode(buf: &mut bytes::BytesMut) -> Result<Self, super::RespError>
+    where
+        Self: Sized,
+    {
+        let (end, len) = parse_length2(buf, Self::PREFIX)?;
+        if len == -1 {
+            return Ok(RespArray::new_null());
+        }
+
+        let len = len as usize;
+
+        let total_len = calc_total_length(buf, end, len, Self::PREFIX)?;
+
+        if buf.len() < total_len {
+            return Err(RespError::NotComplete);
+        }
+
+        buf.advance(end + CRLF_LEN);
+
+        let mut frames = Vec::with_capacity(len);
+        for _ in 0..len {
+            frames.push(RespFrame::decode(buf)?);
+        }
+
+        Ok(RespArray::new(frames))
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, super::RespError> {
+        let (end, len) = parse_length2(buf, Self::PREFIX)?;
+        if len == -1 {
+            return Ok(end + CRLF_LEN);
+        }
+        let len = len as usize;
+        calc_total_length(buf, end, len, Self::PREFIX)
+    }
+}
+
+impl RespArray {
+    pub fn new(s: impl Into<Vec<RespFrame>>) -> Self {
+        RespArray::Array(s.into())
+    }
+
+    pub fn new_null() -> Self {
+        RespArray::Null
+    }
+}
+
+impl Deref for RespArray {
+    type Target = Vec<RespFrame>;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            RespArray::Array(v) => v,
+            RespArray::Null => panic!("Null array"),
+        }
+    }
+}
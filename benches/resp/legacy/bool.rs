@@ -0,0 +1,28 @@
+use super::{RespDecode, RespEncode, RespError};
+
+use super::extract_fixed_data;
+
+impl RespEncode for bool {
+    fn encode(self) -> Vec<u8> {
+        format!("#{}\r\n", if self { "t" } else { "f" }).into_bytes()
+    }
+}
+
+impl RespDecode for bool {
+    const PREFIX: &'static str = "#";
+
+    fn decode(buf: &mut bytes::BytesMut) -> Result<Self, super::RespError> {
+        match extract_fixed_data(buf, "#t\r\n", "Bool") {
+            Ok(_) => Ok(true),
+            Err(RespError::NotComplete) => Err(RespError::NotComplete),
+            Err(_) => match extract_fixed_data(buf, "#f\r\n", "Bool") {
+                Ok(_) => Ok(false),
+                Err(e) => Err(e),
+            },
+        }
+    }
+
+    fn expect_length(_buf: &[u8]) -> Result<usize, super::RespError> {
+        Ok(4)
+    }
+}
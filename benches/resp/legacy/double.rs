@@ -0,0 +1,57 @@
+use bytes::BytesMut;
+
+use super::{extract_simple_frame_data, RespDecode, RespEncode, RespError};
+
+use super::CRLF_LEN;
+
+#[derive(Debug, Clone, PartialOrd)]
+pub struct Double(pub(crate) f64);
+
+impl PartialEq for Double {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+#[allow(clippy::derive_ord_xor_partial_ord)]
+impl Ord for Double {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.partial_cmp(&other.0).unwrap_or_else(|| {
+            if self.0.is_nan() && other.0.is_nan() {
+                std::cmp::Ordering::Equal
+            } else if self.0.is_nan() {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Less
+            }
+        })
+    }
+}
+
+impl Eq for Double {}
+
+impl RespEncode for Double {
+    fn encode(self) -> Vec<u8> {
+        format!(",{}\r\n", self.0).into_bytes()
+    }
+}
+
+impl From<f64> for Double {
+    fn from(f: f64) -> Self {
+        Double(f)
+    }
+}
+
+impl RespDecode for Double {
+    const PREFIX: &'static str = ",";
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        let end = extract_simple_frame_data(buf, Self::PREFIX)?;
+        let s = String::from_utf8_lossy(&buf[Self::PREFIX.len()..end]);
+        Ok(s.parse::<f64>()?.into())
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        let end = extract_simple_frame_data(buf, Self::PREFIX)?;
+        Ok(end + CRLF_LEN)
+    }
+}
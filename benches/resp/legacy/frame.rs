@@ -0,0 +1,115 @@
+use bytes::BytesMut;
+use enum_dispatch::enum_dispatch;
+
+use super::{
+    array::RespArray, bulk_string::BulkString, double::Double, map::RespMap, null::RespNull,
+    set::RespSet, simple_error::SimpleError, simple_string::SimpleString, RespDecode, RespError,
+};
+
+#[enum_dispatch(RespEncode)]
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord)]
+pub enum RespFrame {
+    SimpleString(SimpleString),
+    SimpleError(SimpleError),
+    Integer(i64),
+    Double(Double),
+    Boolean(bool),
+    BulkString(BulkString),
+    Null(RespNull),
+    Map(RespMap),
+    Array(RespArray),
+    Set(RespSet),
+}
+
+impl RespDecode for RespFrame {
+    const PREFIX: &'static str = "";
+
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError>
+    where
+        Self: Sized,
+    {
+        let mut iter = buf.iter().peekable();
+        match iter.peek() {
+            Some(b'+') => {
+                let frame = SimpleString::decode(buf)?;
+                Ok(frame.into())
+            }
+            Some(b'-') => {
+                let frame = SimpleError::decode(buf)?;
+                Ok(frame.into())
+            }
+            Some(b':') => {
+                let frame = i64::decode(buf)?;
+                Ok(RespFrame::Integer(frame))
+            }
+            Some(b'$') => {
+                let frame = BulkString::decode(buf)?;
+                Ok(frame.into())
+            }
+            Some(b'*') => {
+                let frame = RespArray::decode(buf)?;
+                Ok(frame.into())
+            }
+            Some(b'#') => {
+                let frame = bool::decode(buf)?;
+                Ok(frame.into())
+            }
+            Some(b',') => {
+                let frame = Double::decode(buf)?;
+                Ok(frame.into())
+            }
+            Some(b'%') => {
+                let frame = RespMap::decode(buf)?;
+                Ok(frame.into())
+            }
+            Some(b'_') => {
+                let frame = RespNull::decode(buf)?;
+                Ok(frame.into())
+            }
+            Some(b'~') => {
+                let frame = RespSet::decode(buf)?;
+                Ok(frame.into())
+            }
+            None => Err(RespError::NotComplete),
+            _ => Err(RespError::InvalidFrameType(format!(
+                "expect: frame type, got: {:?}",
+                buf
+            ))),
+        }
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        let mut iter = buf.iter().peekable();
+        match iter.peek() {
+            Some(b'*') => RespArray::expect_length(buf),
+            Some(b'~') => RespSet::expect_length(buf),
+            Some(b'%') => RespMap::expect_length(buf),
+            Some(b'$') => BulkString::expect_length(buf),
+            Some(b':') => i64::expect_length(buf),
+            Some(b'+') => SimpleString::expect_length(buf),
+            Some(b'-') => SimpleError::expect_length(buf),
+            Some(b'#') => bool::expect_length(buf),
+            Some(b',') => Double::expect_length(buf),
+            Some(b'_') => RespNull::expect_length(buf),
+            _ => Err(RespError::NotComplete),
+        }
+    }
+}
+
+impl From<&str> for RespFrame {
+    fn from(s: &str) -> Self {
+        SimpleString::new(s).into()
+    }
+}
+
+impl From<&[u8]> for RespFrame {
+    fn from(s: &[u8]) -> Self {
+        BulkString::new(s.to_vec()).into()
+    }
+}
+
+impl<const N: usize> From<&[u8; N]> for RespFrame {
+    fn from(s: &[u8; N]) -> Self {
+        BulkString::new(s.to_vec()).into()
+    }
+}
@@ -0,0 +1,27 @@
+use bytes::BytesMut;
+
+use super::{RespDecode, RespEncode, RespError};
+
+use super::{extract_simple_frame_data, CRLF_LEN};
+
+impl RespEncode for i64 {
+    fn encode(self) -> Vec<u8> {
+        // let sign = if *self < 0 { "" } else { "+" };
+        format!(":{}\r\n", self).into_bytes()
+    }
+}
+
+impl RespDecode for i64 {
+    const PREFIX: &'static str = ":";
+
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        let end = extract_simple_frame_data(buf, Self::PREFIX)?;
+        let s = String::from_utf8_lossy(&buf[Self::PREFIX.len()..end]);
+        Ok(s.parse()?)
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        let end = extract_simple_frame_data(buf, Self::PREFIX)?;
+        Ok(end + CRLF_LEN)
+    }
+}
@@ -0,0 +1,27 @@
+use bytes::BytesMut;
+
+use super::{RespDecode, RespEncode, RespError};
+
+use super::extract_fixed_data;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RespNull;
+
+// - null: "_\r\n"
+impl RespEncode for RespNull {
+    fn encode(self) -> Vec<u8> {
+        b"_\r\n".to_vec()
+    }
+}
+
+impl RespDecode for RespNull {
+    const PREFIX: &'static str = "_";
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        extract_fixed_data(buf, "_\r\n", "Null")?;
+        Ok(RespNull)
+    }
+
+    fn expect_length(_buf: &[u8]) -> Result<usize, RespError> {
+        Ok(3)
+    }
+}
@@ -0,0 +1,51 @@
+use std::ops::Deref;
+
+use bytes::BytesMut;
+
+use super::{extract_simple_frame_data, RespDecode, RespEncode, RespError};
+
+use super::CRLF_LEN;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SimpleError(pub(crate) String);
+
+impl RespEncode for SimpleError {
+    fn encode(self) -> Vec<u8> {
+        format!("-{}\r\n", self.0).into_bytes()
+    }
+}
+
+impl RespDecode for SimpleError {
+    const PREFIX: &'static str = "-";
+
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        let end = extract_simple_frame_data(buf, Self::PREFIX)?;
+        let s = String::from_utf8_lossy(&buf[Self::PREFIX.len()..end]);
+        Ok(Self(s.to_string()))
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        let end = extract_simple_frame_data(buf, Self::PREFIX)?;
+        Ok(end + CRLF_LEN)
+    }
+}
+
+impl SimpleError {
+    pub fn new(s: impl Into<String>) -> Self {
+        Self(s.into())
+    }
+}
+
+impl From<&str> for SimpleError {
+    fn from(s: &str) -> Self {
+        Self(s.to_string())
+    }
+}
+
+impl Deref for SimpleError {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
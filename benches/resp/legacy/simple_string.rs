@@ -0,0 +1,67 @@
+use std::ops::Deref;
+
+use bytes::BytesMut;
+
+use super::{extract_simple_frame_data, CRLF_LEN};
+
+use super::{RespDecode, RespEncode, RespError};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SimpleString(pub(crate) String);
+
+impl SimpleString {
+    pub fn new(s: impl Into<String>) -> Self {
+        Self(s.into())
+    }
+}
+
+// - simple string: "+OK\r\n"
+impl RespEncode for SimpleString {
+    fn encode(self) -> Vec<u8> {
+        format!("+{}\r\n", self.0).into_bytes()
+    }
+}
+
+impl RespDecode for SimpleString {
+    const PREFIX: &'static str = "+";
+
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        let end = extract_simple_frame_data(buf, Self::PREFIX)?;
+        // split the buffer
+        let data = buf.split_to(end + CRLF_LEN);
+        // println!("debug SimpleString data: {:?}", data);
+        let s = String::from_utf8_lossy(&data[Self::PREFIX.len()..end]);
+        Ok(SimpleString::new(s.to_string()))
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        let end = extract_simple_frame_data(buf, Self::PREFIX)?;
+        Ok(end + CRLF_LEN)
+    }
+}
+
+impl From<&str> for SimpleString {
+    fn from(s: &str) -> Self {
+        Self(s.to_string())
+    }
+}
+
+impl From<String> for SimpleString {
+    fn from(s: String) -> Self {
+        Self(s)
+    }
+}
+
+impl AsRef<str> for SimpleString {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for SimpleString {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
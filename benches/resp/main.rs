@@ -0,0 +1,126 @@
+mod legacy;
+
+use bytes::BytesMut;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use simple_redis::{BulkString, RespArray, RespDecode, RespDecoder, RespEncode, RespFrame};
+
+// MSET with 1000 key-value pairs, about 30KB on the wire
+fn mset(pairs: usize) -> Vec<u8> {
+    let mut buf = format!("*{}\r\n$4\r\nMSET\r\n", pairs * 2 + 1).into_bytes();
+    for i in 0..pairs {
+        let (key, value) = (format!("key:{:06}", i), format!("value:{:08}", i));
+        buf.extend_from_slice(format!("${}\r\n{}\r\n", key.len(), key).as_bytes());
+        buf.extend_from_slice(format!("${}\r\n{}\r\n", value.len(), value).as_bytes());
+    }
+    buf
+}
+
+// roughly MSS sized segments, cut between elements
+fn segments(data: &[u8], size: usize) -> Vec<&[u8]> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    while start < data.len() {
+        let mut end = (start + size).min(data.len());
+        while end < data.len() && !(data[end] == b'$' && data[end - 1] == b'\n') {
+            end -= 1;
+        }
+        segments.push(&data[start..end]);
+        start = end;
+    }
+    segments
+}
+
+// the baseline's RespFrame::decode
+fn legacy_decode(buf: &mut BytesMut) -> Result<legacy::RespFrame, legacy::RespError> {
+    <legacy::RespFrame as legacy::RespDecode>::decode(buf)
+}
+
+// each group has the decoder before the single-pass rewrite as the
+// baseline, `cargo bench --bench resp` reports both side by side
+fn bench_decode(c: &mut Criterion) {
+    let data = mset(1000);
+    let frame = legacy_decode(&mut BytesMut::from(&data[..])).unwrap();
+    assert_eq!(
+        legacy::RespEncode::encode(frame),
+        RespFrame::decode(&mut BytesMut::from(&data[..]))
+            .unwrap()
+            .encode()
+    );
+
+    let mut group = c.benchmark_group("decode mset 1000");
+    group.bench_function("legacy", |b| {
+        b.iter(|| {
+            let mut buf = BytesMut::from(&data[..]);
+            black_box(legacy_decode(&mut buf).unwrap())
+        })
+    });
+    group.bench_function("single-pass", |b| {
+        b.iter(|| {
+            let mut buf = BytesMut::from(&data[..]);
+            black_box(RespDecoder::new().decode(&mut buf).unwrap().unwrap())
+        })
+    });
+    // decodes a copy of the buffer, so that nothing is consumed if the
+    // frame isn't complete
+    group.bench_function("RespFrame::decode", |b| {
+        b.iter(|| {
+            let mut buf = BytesMut::from(&data[..]);
+            black_box(RespFrame::decode(&mut buf).unwrap())
+        })
+    });
+    group.finish();
+
+    // as the codec sees it: the old decoder starts over on every read
+    let mut group = c.benchmark_group("decode mset 1000 in 1460 byte segments");
+    group.bench_function("legacy", |b| {
+        b.iter(|| {
+            let mut buf = BytesMut::new();
+            for chunk in segments(&data, 1460) {
+                buf.extend_from_slice(chunk);
+                match legacy_decode(&mut buf) {
+                    Ok(frame) => return black_box(frame),
+                    Err(legacy::RespError::NotComplete) => {}
+                    Err(e) => panic!("{}", e),
+                }
+            }
+            unreachable!()
+        })
+    });
+    group.bench_function("single-pass", |b| {
+        b.iter(|| {
+            let mut decoder = RespDecoder::new();
+            let mut buf = BytesMut::new();
+            for chunk in segments(&data, 1460) {
+                buf.extend_from_slice(chunk);
+                if let Some(frame) = decoder.decode(&mut buf).unwrap() {
+                    return black_box(frame);
+                }
+            }
+            unreachable!()
+        })
+    });
+    group.finish();
+}
+
+fn bench_encode(c: &mut Criterion) {
+    // what HGETALL on a hash with 1000 fields replies with
+    let reply: RespFrame = RespArray::new(
+        (0..1000)
+            .flat_map(|i| [format!("field:{:06}", i), format!("value:{:08}", i)])
+            .map(|s| BulkString::new(s).into())
+            .collect::<Vec<RespFrame>>(),
+    )
+    .into();
+
+    c.bench_function("encode hgetall 1000 into a reused buffer", |b| {
+        let mut buf = BytesMut::with_capacity(reply.encoded_len());
+        b.iter(|| {
+            buf.clear();
+            reply.encode_to(&mut buf);
+            black_box(buf.len())
+        })
+    });
+}
+
+criterion_group!(benches, bench_decode, bench_encode);
+criterion_main!(benches);
@@ -0,0 +1,120 @@
+use std::sync::RwLock;
+
+pub const SLOT_COUNT: usize = 16384;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// The hash slots served by this node. Every slot is owned until it is
+/// explicitly released with `CLUSTER DELSLOTS`.
+#[derive(Debug)]
+pub(crate) struct SlotOwnership {
+    bits: RwLock<Vec<u64>>,
+}
+
+impl Default for SlotOwnership {
+    fn default() -> Self {
+        Self {
+            bits: RwLock::new(vec![u64::MAX; SLOT_COUNT / WORD_BITS]),
+        }
+    }
+}
+
+impl SlotOwnership {
+    pub(crate) fn owns(&self, slot: u16) -> bool {
+        let bits = self.bits.read().unwrap();
+        let slot = slot as usize;
+        bits[slot / WORD_BITS] & (1 << (slot % WORD_BITS)) != 0
+    }
+
+    /// Mark `slot` as owned, returns false if it already was.
+    pub(crate) fn add(&self, slot: u16) -> bool {
+        let mut bits = self.bits.write().unwrap();
+        let slot = slot as usize;
+        let mask = 1 << (slot % WORD_BITS);
+        let owned = bits[slot / WORD_BITS] & mask != 0;
+        bits[slot / WORD_BITS] |= mask;
+        !owned
+    }
+
+    /// Mark `slot` as no longer owned, returns false if it wasn't.
+    pub(crate) fn del(&self, slot: u16) -> bool {
+        let mut bits = self.bits.write().unwrap();
+        let slot = slot as usize;
+        let mask = 1 << (slot % WORD_BITS);
+        let owned = bits[slot / WORD_BITS] & mask != 0;
+        bits[slot / WORD_BITS] &= !mask;
+        owned
+    }
+}
+
+/// Map a key (or shard channel) to its hash slot the way Redis Cluster does:
+/// CRC16 of the key modulo 16384, where only the part inside the first
+/// non-empty `{...}` is hashed if there is one.
+pub fn key_hash_slot(key: &[u8]) -> u16 {
+    let key = match key.iter().position(|&c| c == b'{') {
+        Some(start) => match key[start + 1..].iter().position(|&c| c == b'}') {
+            Some(0) | None => key,
+            Some(len) => &key[start + 1..start + 1 + len],
+        },
+        None => key,
+    };
+    crc16(key) % SLOT_COUNT as u16
+}
+
+// CRC16-CCITT (XMODEM): polynomial 0x1021, initial value 0
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc16() {
+        assert_eq!(crc16(b"123456789"), 0x31c3);
+    }
+
+    #[test]
+    fn test_key_hash_slot() {
+        assert_eq!(key_hash_slot(b"foo"), 12182);
+        assert_eq!(key_hash_slot(b"bar"), 5061);
+        assert_eq!(
+            key_hash_slot(b"{user1000}.following"),
+            key_hash_slot(b"user1000")
+        );
+        assert_eq!(
+            key_hash_slot(b"{user1000}.followers"),
+            key_hash_slot(b"user1000")
+        );
+        // empty hashtag: the whole key is hashed
+        assert_eq!(key_hash_slot(b"foo{}{bar}"), crc16(b"foo{}{bar}") % 16384);
+        // only the first hashtag counts
+        assert_eq!(key_hash_slot(b"foo{{bar}}zap"), key_hash_slot(b"{bar"));
+        assert_eq!(key_hash_slot(b"foo{bar}{zap}"), key_hash_slot(b"bar"));
+    }
+
+    #[test]
+    fn test_slot_ownership() {
+        let slots = SlotOwnership::default();
+        assert!(slots.owns(0));
+        assert!(slots.owns(16383));
+        assert!(slots.del(100));
+        assert!(!slots.owns(100));
+        assert!(!slots.del(100));
+        assert!(slots.add(100));
+        assert!(!slots.add(100));
+        assert!(slots.owns(100));
+    }
+}
@@ -1,17 +1,65 @@
-use std::{collections::BTreeSet, ops::Deref, sync::Arc};
+mod cluster;
+mod notify;
+mod pubsub;
+mod tracking;
+mod watch;
 
-use dashmap::DashMap;
+use std::{
+    collections::BTreeSet,
+    ops::Deref,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
 
-use crate::RespFrame;
+use bytes::Bytes;
+use dashmap::{mapref::entry::Entry, DashMap};
+use tokio::{
+    sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time,
+};
+
+use crate::{cmd::PluginRegistry, script::ScriptEngine, wasm::WasmHost, DecodeLimits, RespFrame};
+
+pub(crate) use self::cluster::SlotOwnership;
+pub use self::cluster::{key_hash_slot, SLOT_COUNT};
+pub(crate) use self::notify::*;
+pub(crate) use self::pubsub::{
+    glob_match, ChannelRegistry, Subscriber, DEFAULT_SUBSCRIBER_BUFFER_CAP,
+};
+pub(crate) use self::tracking::{Caller, TrackingOptions, TrackingTable};
+pub(crate) use self::watch::WatchedKeys;
+
+// how often a client waiting for the execution lock checks whether the
+// script holding it has gone busy
+const BUSY_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 #[derive(Debug, Clone)]
 pub struct Backend(Arc<BackendInner>);
 
 #[derive(Debug)]
 pub struct BackendInner {
-    pub(crate) map: DashMap<String, RespFrame>,
-    pub(crate) hmap: DashMap<String, DashMap<String, RespFrame>>,
-    pub(crate) set: DashMap<String, BTreeSet<RespFrame>>,
+    // keys and hash fields are binary safe, like everything else in Redis
+    pub(crate) map: DashMap<Bytes, RespFrame>,
+    pub(crate) hmap: DashMap<Bytes, DashMap<Bytes, RespFrame>>,
+    pub(crate) set: DashMap<Bytes, BTreeSet<RespFrame>>,
+    pub(crate) pubsub: ChannelRegistry,
+    pub(crate) slots: SlotOwnership,
+    pub(crate) watched: WatchedKeys,
+    pub(crate) tracking: TrackingTable,
+    pub(crate) scripts: ScriptEngine,
+    pub(crate) wasm: WasmHost,
+    pub(crate) plugins: PluginRegistry,
+    notify_flags: AtomicU32,
+    next_client_id: AtomicU64,
+    decode_limits: Mutex<DecodeLimits>,
+    subscriber_buffer_cap: AtomicUsize,
+    rejected_clients: AtomicU64,
+    // commands run under the shared side, EXEC and scripts take the
+    // exclusive side so nothing interleaves with them
+    exec_lock: RwLock<()>,
 }
 
 impl Deref for Backend {
@@ -28,6 +76,19 @@ impl Default for BackendInner {
             map: DashMap::new(),
             hmap: DashMap::new(),
             set: DashMap::new(),
+            pubsub: ChannelRegistry::default(),
+            slots: SlotOwnership::default(),
+            watched: WatchedKeys::default(),
+            tracking: TrackingTable::default(),
+            scripts: ScriptEngine::default(),
+            wasm: WasmHost::default(),
+            plugins: PluginRegistry::default(),
+            notify_flags: AtomicU32::new(0),
+            next_client_id: AtomicU64::new(1),
+            decode_limits: Mutex::default(),
+            subscriber_buffer_cap: AtomicUsize::new(DEFAULT_SUBSCRIBER_BUFFER_CAP),
+            rejected_clients: AtomicU64::new(0),
+            exec_lock: RwLock::new(()),
         }
     }
 }
@@ -42,28 +103,138 @@ impl Backend {
     pub fn new() -> Self {
         Self::default()
     }
-    pub fn get(&self, key: &str) -> Option<RespFrame> {
+    pub fn get(&self, key: &[u8]) -> Option<RespFrame> {
+        self.track_read(key);
         self.map.get(key).map(|v| v.value().clone())
     }
-    pub fn set(&self, key: String, value: RespFrame) {
-        self.map.insert(key, value);
+    pub fn set(&self, key: Bytes, value: RespFrame) {
+        let entry = match self.map.entry(key) {
+            Entry::Occupied(mut entry) => {
+                entry.insert(value);
+                entry.into_ref()
+            }
+            Entry::Vacant(entry) => {
+                let entry = entry.insert(value);
+                self.notify_keyspace_event(NOTIFY_NEW, "new", entry.key());
+                entry
+            }
+        };
+        self.touch_key(entry.key());
+        self.notify_keyspace_event(NOTIFY_STRING, "set", entry.key());
     }
 
-    pub fn hget(&self, key: &str, field: &str) -> Option<RespFrame> {
+    pub fn hget(&self, key: &[u8], field: &[u8]) -> Option<RespFrame> {
+        self.track_read(key);
         self.hmap
             .get(key)
             .and_then(|v| v.get(field).map(|v| v.value().clone()))
     }
 
-    pub fn hset(&self, key: String, field: String, value: RespFrame) {
-        let hmap = self.hmap.entry(key).or_default();
+    pub fn hset(&self, key: Bytes, field: Bytes, value: RespFrame) {
+        let hmap = match self.hmap.entry(key) {
+            Entry::Occupied(entry) => entry.into_ref(),
+            Entry::Vacant(entry) => {
+                let entry = entry.insert(DashMap::new());
+                self.notify_keyspace_event(NOTIFY_NEW, "new", entry.key());
+                entry
+            }
+        };
         hmap.insert(field, value);
+        self.touch_key(hmap.key());
+        self.notify_keyspace_event(NOTIFY_HASH, "hset", hmap.key());
     }
-    pub fn hgetall(&self, key: &str) -> Option<DashMap<String, RespFrame>> {
+    pub fn hgetall(&self, key: &[u8]) -> Option<DashMap<Bytes, RespFrame>> {
+        self.track_read(key);
         self.hmap.get(key).map(|v| v.clone())
     }
-    pub fn sadd(&self, key: String, value: RespFrame) -> bool {
-        let mut set = self.set.entry(key).or_default();
-        set.insert(value)
+    /// Called on every write to `key`: invalidates WATCHes on it and the
+    /// client side caches tracking it.
+    pub(crate) fn touch_key(&self, key: &[u8]) {
+        self.watched.touch(key);
+        self.invalidate_key(key);
+    }
+
+    /// Wait for the shared side of the execution lock. Gives up with None
+    /// once a script has held the lock past busy-reply-threshold.
+    pub(crate) async fn lock_shared(&self) -> Option<RwLockReadGuard<'_, ()>> {
+        loop {
+            if self.scripts.is_busy() {
+                return None;
+            }
+            if let Ok(guard) = time::timeout(BUSY_POLL_INTERVAL, self.exec_lock.read()).await {
+                return Some(guard);
+            }
+        }
+    }
+
+    pub(crate) async fn lock_exclusive(&self) -> Option<RwLockWriteGuard<'_, ()>> {
+        loop {
+            if self.scripts.is_busy() {
+                return None;
+            }
+            if let Ok(guard) = time::timeout(BUSY_POLL_INTERVAL, self.exec_lock.write()).await {
+                return Some(guard);
+            }
+        }
+    }
+
+    /// Stop serving `slot`: its shard channel subscribers are unsubscribed
+    /// and notified. Returns false if the slot wasn't served.
+    pub(crate) fn del_slot(&self, slot: u16) -> bool {
+        if !self.slots.del(slot) {
+            return false;
+        }
+        self.pubsub.evict_shard_slot(slot);
+        true
+    }
+
+    pub(crate) fn next_client_id(&self) -> u64 {
+        self.next_client_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// The limits connections decode requests with, picked up by each of
+    /// them between commands.
+    pub fn decode_limits(&self) -> DecodeLimits {
+        *self.decode_limits.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn update_decode_limits(&self, f: impl FnOnce(&mut DecodeLimits)) {
+        f(&mut self.decode_limits.lock().unwrap_or_else(|e| e.into_inner()));
+    }
+
+    /// How many pub/sub messages a connection may have pending, fixed for
+    /// each connection when it's accepted.
+    pub(crate) fn subscriber_buffer_cap(&self) -> usize {
+        self.subscriber_buffer_cap.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_subscriber_buffer_cap(&self, cap: usize) {
+        self.subscriber_buffer_cap.store(cap, Ordering::Relaxed);
+    }
+
+    /// How many clients were disconnected for a protocol error, limits
+    /// exceeded included.
+    pub fn rejected_clients(&self) -> u64 {
+        self.rejected_clients.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn reject_client(&self) {
+        self.rejected_clients.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn sadd(&self, key: Bytes, value: RespFrame) -> bool {
+        let mut set = match self.set.entry(key) {
+            Entry::Occupied(entry) => entry.into_ref(),
+            Entry::Vacant(entry) => {
+                let entry = entry.insert(BTreeSet::new());
+                self.notify_keyspace_event(NOTIFY_NEW, "new", entry.key());
+                entry
+            }
+        };
+        let added = set.insert(value);
+        if added {
+            self.touch_key(set.key());
+        }
+        added
     }
 }
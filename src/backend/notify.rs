@@ -0,0 +1,194 @@
+use std::sync::atomic::Ordering;
+
+use crate::BulkString;
+
+use super::Backend;
+
+// notify-keyspace-events classes, same bit layout as Redis
+pub(crate) const NOTIFY_KEYSPACE: u32 = 1 << 0; // K
+pub(crate) const NOTIFY_KEYEVENT: u32 = 1 << 1; // E
+pub(crate) const NOTIFY_GENERIC: u32 = 1 << 2; // g
+pub(crate) const NOTIFY_STRING: u32 = 1 << 3; // $
+pub(crate) const NOTIFY_LIST: u32 = 1 << 4; // l
+pub(crate) const NOTIFY_SET: u32 = 1 << 5; // s
+pub(crate) const NOTIFY_HASH: u32 = 1 << 6; // h
+pub(crate) const NOTIFY_ZSET: u32 = 1 << 7; // z
+pub(crate) const NOTIFY_EXPIRED: u32 = 1 << 8; // x
+pub(crate) const NOTIFY_EVICTED: u32 = 1 << 9; // e
+pub(crate) const NOTIFY_STREAM: u32 = 1 << 10; // t
+pub(crate) const NOTIFY_KEY_MISS: u32 = 1 << 11; // m
+pub(crate) const NOTIFY_NEW: u32 = 1 << 14; // n
+
+// A: alias for "g$lshzxet", key-miss and new-key events must be opted in
+pub(crate) const NOTIFY_ALL: u32 = NOTIFY_GENERIC
+    | NOTIFY_STRING
+    | NOTIFY_LIST
+    | NOTIFY_SET
+    | NOTIFY_HASH
+    | NOTIFY_ZSET
+    | NOTIFY_EXPIRED
+    | NOTIFY_EVICTED
+    | NOTIFY_STREAM;
+
+const CLASS_CHARS: [(u32, char); 10] = [
+    (NOTIFY_GENERIC, 'g'),
+    (NOTIFY_STRING, '$'),
+    (NOTIFY_LIST, 'l'),
+    (NOTIFY_SET, 's'),
+    (NOTIFY_HASH, 'h'),
+    (NOTIFY_ZSET, 'z'),
+    (NOTIFY_EXPIRED, 'x'),
+    (NOTIFY_EVICTED, 'e'),
+    (NOTIFY_STREAM, 't'),
+    (NOTIFY_NEW, 'n'),
+];
+
+/// Parse a `notify-keyspace-events` value such as "KEA" or "Ex".
+pub(crate) fn parse_notify_flags(s: &str) -> Option<u32> {
+    let mut flags = 0;
+    for c in s.chars() {
+        flags |= match c {
+            'A' => NOTIFY_ALL,
+            'K' => NOTIFY_KEYSPACE,
+            'E' => NOTIFY_KEYEVENT,
+            'm' => NOTIFY_KEY_MISS,
+            c => CLASS_CHARS.iter().find(|(_, ch)| *ch == c)?.0,
+        };
+    }
+    Some(flags)
+}
+
+pub(crate) fn format_notify_flags(flags: u32) -> String {
+    let mut s = String::new();
+    if flags & NOTIFY_ALL == NOTIFY_ALL {
+        s.push('A');
+        if flags & NOTIFY_NEW != 0 {
+            s.push('n');
+        }
+    } else {
+        for (flag, c) in CLASS_CHARS {
+            if flags & flag != 0 {
+                s.push(c);
+            }
+        }
+    }
+    if flags & NOTIFY_KEYSPACE != 0 {
+        s.push('K');
+    }
+    if flags & NOTIFY_KEYEVENT != 0 {
+        s.push('E');
+    }
+    if flags & NOTIFY_KEY_MISS != 0 {
+        s.push('m');
+    }
+    s
+}
+
+impl Backend {
+    pub(crate) fn notify_flags(&self) -> u32 {
+        self.notify_flags.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_notify_flags(&self, flags: u32) {
+        self.notify_flags.store(flags, Ordering::Relaxed);
+    }
+
+    /// Publish `event` on `__keyspace@0__:<key>` and `key` on
+    /// `__keyevent@0__:<event>`, as enabled by notify-keyspace-events. With
+    /// notifications off (the default) this is a single atomic load.
+    pub(crate) fn notify_keyspace_event(&self, class: u32, event: &str, key: &[u8]) {
+        let flags = self.notify_flags();
+        if flags & class == 0 {
+            return;
+        }
+
+        if flags & NOTIFY_KEYSPACE != 0 {
+            let channel = [b"__keyspace@0__:", key].concat();
+            self.pubsub.publish(&channel, BulkString::from(event));
+        }
+        if flags & NOTIFY_KEYEVENT != 0 {
+            let channel = format!("__keyevent@0__:{}", event);
+            self.pubsub
+                .publish(channel.as_bytes(), BulkString::from(key));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::{Subscriber, DEFAULT_SUBSCRIBER_BUFFER_CAP};
+
+    #[test]
+    fn test_parse_notify_flags() {
+        assert_eq!(parse_notify_flags(""), Some(0));
+        assert_eq!(
+            parse_notify_flags("Ex"),
+            Some(NOTIFY_KEYEVENT | NOTIFY_EXPIRED)
+        );
+        assert_eq!(
+            parse_notify_flags("KEA"),
+            Some(NOTIFY_KEYSPACE | NOTIFY_KEYEVENT | NOTIFY_ALL)
+        );
+        assert_eq!(parse_notify_flags("Kq"), None);
+    }
+
+    #[test]
+    fn test_format_notify_flags() {
+        assert_eq!(format_notify_flags(0), "");
+        assert_eq!(format_notify_flags(parse_notify_flags("xE").unwrap()), "xE");
+        assert_eq!(
+            format_notify_flags(parse_notify_flags("KEA").unwrap()),
+            "AKE"
+        );
+        assert_eq!(
+            format_notify_flags(parse_notify_flags("$gKm").unwrap()),
+            "g$Km"
+        );
+    }
+
+    #[test]
+    fn test_notify_keyspace_event() {
+        let backend = Backend::new();
+        let (tx, mut rx) = mpsc::channel(DEFAULT_SUBSCRIBER_BUFFER_CAP);
+        backend.pubsub.subscribe(
+            b"__keyevent@0__:set",
+            Subscriber::new(1, tx, Arc::default()),
+        );
+
+        // disabled by default
+        backend.notify_keyspace_event(NOTIFY_STRING, "set", b"foo");
+        assert!(rx.try_recv().is_err());
+
+        backend.set_notify_flags(parse_notify_flags("E$").unwrap());
+        backend.notify_keyspace_event(NOTIFY_HASH, "hset", b"foo");
+        assert!(rx.try_recv().is_err());
+
+        backend.notify_keyspace_event(NOTIFY_STRING, "set", b"foo");
+        let message = rx.try_recv().unwrap();
+        assert_eq!(message[1], BulkString::from("__keyevent@0__:set").into());
+        assert_eq!(message[2], BulkString::from("foo").into());
+    }
+
+    #[test]
+    fn test_keyspace_channel_keeps_binary_keys() {
+        let backend = Backend::new();
+        let (tx, mut rx) = mpsc::channel(DEFAULT_SUBSCRIBER_BUFFER_CAP);
+        backend
+            .pubsub
+            .psubscribe(b"__keyspace@0__:*", Subscriber::new(1, tx, Arc::default()));
+
+        backend.set_notify_flags(parse_notify_flags("K$").unwrap());
+        backend.notify_keyspace_event(NOTIFY_STRING, "set", b"\xff\x01");
+        let message = rx.try_recv().unwrap();
+        assert_eq!(
+            message[2],
+            BulkString::new(b"__keyspace@0__:\xff\x01".to_vec()).into()
+        );
+        assert_eq!(message[3], BulkString::from("set").into());
+    }
+}
@@ -0,0 +1,418 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use crate::{BulkString, RespPush};
+
+use super::key_hash_slot;
+
+/// How many pushed messages a subscriber may have pending before it is
+/// considered too slow and gets disconnected, unless `pubsub-buffer-limit`
+/// says otherwise.
+pub(crate) const DEFAULT_SUBSCRIBER_BUFFER_CAP: usize = 1024;
+
+/// The sending half of a connection's out-of-band channel. Publishers never
+/// wait on it: when the buffer is full the subscriber is flagged as
+/// overflowed and its connection is closed by `stream_handler`.
+#[derive(Debug, Clone)]
+pub(crate) struct Subscriber {
+    pub(crate) id: u64,
+    tx: mpsc::Sender<RespPush>,
+    overflowed: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ChannelRegistry {
+    channels: DashMap<Bytes, HashMap<u64, Subscriber>>,
+    patterns: DashMap<Bytes, HashMap<u64, Subscriber>>,
+    // sharded pub/sub lives apart from the classic channels: a message
+    // published with SPUBLISH only reaches SSUBSCRIBE clients and vice versa
+    shard_channels: DashMap<Bytes, HashMap<u64, Subscriber>>,
+}
+
+impl Subscriber {
+    pub(crate) fn new(id: u64, tx: mpsc::Sender<RespPush>, overflowed: Arc<AtomicBool>) -> Self {
+        Self { id, tx, overflowed }
+    }
+
+    pub(crate) fn send(&self, frame: RespPush) -> bool {
+        match self.tx.try_send(frame) {
+            Ok(_) => true,
+            Err(TrySendError::Full(_)) => {
+                self.overflowed.store(true, Ordering::Relaxed);
+                false
+            }
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+}
+
+impl ChannelRegistry {
+    pub(crate) fn subscribe(&self, channel: &[u8], subscriber: Subscriber) {
+        add_subscriber(&self.channels, channel, subscriber);
+    }
+
+    pub(crate) fn unsubscribe(&self, channel: &[u8], id: u64) {
+        remove_subscriber(&self.channels, channel, id);
+    }
+
+    pub(crate) fn psubscribe(&self, pattern: &[u8], subscriber: Subscriber) {
+        add_subscriber(&self.patterns, pattern, subscriber);
+    }
+
+    pub(crate) fn punsubscribe(&self, pattern: &[u8], id: u64) {
+        remove_subscriber(&self.patterns, pattern, id);
+    }
+
+    /// The subscription of client `id` to `channel`, if any.
+    pub(crate) fn subscriber(&self, channel: &[u8], id: u64) -> Option<Subscriber> {
+        self.channels.get(channel)?.get(&id).cloned()
+    }
+
+    /// Deliver a message to every subscriber of `channel` and of every
+    /// pattern matching it. Returns the number of clients that received it.
+    pub(crate) fn publish(&self, channel: &[u8], message: BulkString) -> usize {
+        let mut receivers = 0;
+
+        if let Some(subscribers) = self.channels.get(channel) {
+            for subscriber in subscribers.values() {
+                let frame = RespPush::new([
+                    BulkString::from("message").into(),
+                    BulkString::new(Bytes::copy_from_slice(channel)).into(),
+                    message.clone().into(),
+                ]);
+                if subscriber.send(frame) {
+                    receivers += 1;
+                }
+            }
+        }
+
+        for entry in self.patterns.iter() {
+            if !glob_match(entry.key(), channel, false) {
+                continue;
+            }
+            for subscriber in entry.value().values() {
+                let frame = RespPush::new([
+                    BulkString::from("pmessage").into(),
+                    BulkString::new(entry.key().clone()).into(),
+                    BulkString::new(Bytes::copy_from_slice(channel)).into(),
+                    message.clone().into(),
+                ]);
+                if subscriber.send(frame) {
+                    receivers += 1;
+                }
+            }
+        }
+
+        receivers
+    }
+
+    pub(crate) fn ssubscribe(&self, channel: &[u8], subscriber: Subscriber) {
+        add_subscriber(&self.shard_channels, channel, subscriber);
+    }
+
+    pub(crate) fn sunsubscribe(&self, channel: &[u8], id: u64) {
+        remove_subscriber(&self.shard_channels, channel, id);
+    }
+
+    pub(crate) fn spublish(&self, channel: &[u8], message: BulkString) -> usize {
+        let mut receivers = 0;
+
+        if let Some(subscribers) = self.shard_channels.get(channel) {
+            for subscriber in subscribers.values() {
+                let frame = RespPush::new([
+                    BulkString::from("smessage").into(),
+                    BulkString::new(Bytes::copy_from_slice(channel)).into(),
+                    message.clone().into(),
+                ]);
+                if subscriber.send(frame) {
+                    receivers += 1;
+                }
+            }
+        }
+
+        receivers
+    }
+
+    /// Drop every subscription to the shard channels hashing to `slot` and
+    /// let the subscribers know with a `sunsubscribe` push. The subscriber's
+    /// own session fills in its remaining subscription count. The push is
+    /// only a notice: a session that never gets it still finds the channel
+    /// gone from here.
+    pub(crate) fn evict_shard_slot(&self, slot: u16) {
+        let channels = self
+            .shard_channels
+            .iter()
+            .filter(|entry| key_hash_slot(entry.key()) == slot)
+            .map(|entry| entry.key().clone())
+            .collect::<Vec<_>>();
+
+        for channel in channels {
+            if let Some((_, subscribers)) = self.shard_channels.remove(&channel) {
+                for subscriber in subscribers.values() {
+                    subscriber.send(RespPush::new([
+                        BulkString::from("sunsubscribe").into(),
+                        BulkString::new(channel.clone()).into(),
+                    ]));
+                }
+            }
+        }
+    }
+
+    pub(crate) fn shard_channels(&self, pattern: Option<&[u8]>) -> Vec<Bytes> {
+        self.shard_channels
+            .iter()
+            .filter(|entry| pattern.is_none_or(|p| glob_match(p, entry.key(), false)))
+            .map(|entry| entry.key().clone())
+            .collect()
+    }
+
+    /// Whether client `id` is still subscribed to the shard `channel`.
+    pub(crate) fn is_shard_subscriber(&self, channel: &[u8], id: u64) -> bool {
+        self.shard_channels
+            .get(channel)
+            .is_some_and(|subscribers| subscribers.contains_key(&id))
+    }
+
+    pub(crate) fn shard_numsub(&self, channel: &[u8]) -> usize {
+        self.shard_channels.get(channel).map_or(0, |v| v.len())
+    }
+
+    /// Active channels, i.e. channels with at least one subscriber,
+    /// optionally filtered by a glob-style pattern.
+    pub(crate) fn channels(&self, pattern: Option<&[u8]>) -> Vec<Bytes> {
+        self.channels
+            .iter()
+            .filter(|entry| pattern.is_none_or(|p| glob_match(p, entry.key(), false)))
+            .map(|entry| entry.key().clone())
+            .collect()
+    }
+
+    pub(crate) fn numsub(&self, channel: &[u8]) -> usize {
+        self.channels.get(channel).map_or(0, |v| v.len())
+    }
+
+    pub(crate) fn numpat(&self) -> usize {
+        self.patterns.len()
+    }
+}
+
+fn add_subscriber(map: &DashMap<Bytes, HashMap<u64, Subscriber>>, name: &[u8], sub: Subscriber) {
+    map.entry(Bytes::copy_from_slice(name))
+        .or_default()
+        .insert(sub.id, sub);
+}
+
+fn remove_subscriber(map: &DashMap<Bytes, HashMap<u64, Subscriber>>, name: &[u8], id: u64) {
+    if let Some(mut subscribers) = map.get_mut(name) {
+        subscribers.remove(&id);
+    }
+    map.remove_if(name, |_, v| v.is_empty());
+}
+
+/// Redis-style glob matching: `*`, `?`, `[abc]`, `[^a]`, `[a-z]` and `\`
+/// escapes.
+pub(crate) fn glob_match(pattern: &[u8], string: &[u8], nocase: bool) -> bool {
+    let eq = |a: u8, b: u8| {
+        if nocase {
+            a.eq_ignore_ascii_case(&b)
+        } else {
+            a == b
+        }
+    };
+
+    let (mut p, mut s) = (0, 0);
+    // position to resume from when the last `*` has to swallow one more byte
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while s < string.len() {
+        if p < pattern.len() {
+            match pattern[p] {
+                b'*' => {
+                    while p < pattern.len() && pattern[p] == b'*' {
+                        p += 1;
+                    }
+                    if p == pattern.len() {
+                        return true;
+                    }
+                    backtrack = Some((p, s));
+                    continue;
+                }
+                b'?' => {
+                    p += 1;
+                    s += 1;
+                    continue;
+                }
+                b'[' => {
+                    if let Some((matched, next)) = match_class(pattern, p + 1, string[s], nocase) {
+                        if matched {
+                            p = next;
+                            s += 1;
+                            continue;
+                        }
+                    }
+                }
+                b'\\' if p + 1 < pattern.len() => {
+                    if eq(pattern[p + 1], string[s]) {
+                        p += 2;
+                        s += 1;
+                        continue;
+                    }
+                }
+                c => {
+                    if eq(c, string[s]) {
+                        p += 1;
+                        s += 1;
+                        continue;
+                    }
+                }
+            }
+        }
+
+        match backtrack {
+            Some((bp, bs)) => {
+                p = bp;
+                s = bs + 1;
+                backtrack = Some((bp, bs + 1));
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+// match `c` against the class starting right after `[`, returns whether it
+// matched and the pattern index after the closing `]`
+fn match_class(pattern: &[u8], mut p: usize, c: u8, nocase: bool) -> Option<(bool, usize)> {
+    let fold = |b: u8| if nocase { b.to_ascii_lowercase() } else { b };
+    let c = fold(c);
+
+    let negate = pattern.get(p) == Some(&b'^');
+    if negate {
+        p += 1;
+    }
+
+    let mut matched = false;
+    loop {
+        match pattern.get(p) {
+            None => return None,
+            Some(b']') => break,
+            Some(b'\\') if p + 1 < pattern.len() => {
+                if fold(pattern[p + 1]) == c {
+                    matched = true;
+                }
+                p += 2;
+            }
+            Some(&start) if pattern.get(p + 1) == Some(&b'-') && p + 2 < pattern.len() => {
+                let (mut lo, mut hi) = (fold(start), fold(pattern[p + 2]));
+                if lo > hi {
+                    std::mem::swap(&mut lo, &mut hi);
+                }
+                if c >= lo && c <= hi {
+                    matched = true;
+                }
+                p += 3;
+            }
+            Some(&b) => {
+                if fold(b) == c {
+                    matched = true;
+                }
+                p += 1;
+            }
+        }
+    }
+
+    Some((matched != negate, p + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(b"news.*", b"news.tech", false));
+        assert!(!glob_match(b"news.*", b"sport.tech", false));
+        assert!(glob_match(b"h?llo", b"hello", false));
+        assert!(glob_match(b"h[ae]llo", b"hallo", false));
+        assert!(!glob_match(b"h[^e]llo", b"hello", false));
+        assert!(glob_match(b"h[a-c]llo", b"hbllo", false));
+        assert!(glob_match(b"*a*b", b"xaab", false));
+        assert!(glob_match(b"h\\*llo", b"h*llo", false));
+        assert!(!glob_match(b"h\\*llo", b"hello", false));
+        assert!(glob_match(b"HELLO", b"hello", true));
+        assert!(glob_match(b"*", b"", false));
+    }
+
+    #[test]
+    fn test_publish() {
+        let registry = ChannelRegistry::default();
+        let (tx, mut rx) = mpsc::channel(DEFAULT_SUBSCRIBER_BUFFER_CAP);
+        let subscriber = Subscriber::new(1, tx, Arc::default());
+
+        registry.subscribe(b"news", subscriber.clone());
+        registry.psubscribe(b"n*", subscriber);
+
+        assert_eq!(registry.publish(b"news", BulkString::from("hello")), 2);
+        assert_eq!(registry.publish(b"nope", BulkString::from("hello")), 1);
+        assert_eq!(registry.publish(b"other", BulkString::from("hello")), 0);
+
+        assert_eq!(
+            rx.try_recv().unwrap()[0],
+            BulkString::from("message").into()
+        );
+        assert_eq!(
+            rx.try_recv().unwrap()[0],
+            BulkString::from("pmessage").into()
+        );
+
+        registry.unsubscribe(b"news", 1);
+        assert_eq!(registry.numsub(b"news"), 0);
+        assert!(registry.channels(None).is_empty());
+        assert_eq!(registry.numpat(), 1);
+    }
+
+    #[test]
+    fn test_spublish_is_separate_from_publish() {
+        let registry = ChannelRegistry::default();
+        let (tx, mut rx) = mpsc::channel(DEFAULT_SUBSCRIBER_BUFFER_CAP);
+        registry.ssubscribe(b"orders", Subscriber::new(1, tx, Arc::default()));
+
+        assert_eq!(registry.publish(b"orders", BulkString::from("a")), 0);
+        assert_eq!(registry.spublish(b"orders", BulkString::from("b")), 1);
+        assert_eq!(
+            rx.try_recv().unwrap()[0],
+            BulkString::from("smessage").into()
+        );
+        assert!(registry.channels(None).is_empty());
+        assert_eq!(registry.shard_channels(None), vec!["orders"]);
+
+        registry.evict_shard_slot(key_hash_slot(b"orders"));
+        assert_eq!(registry.shard_numsub(b"orders"), 0);
+        assert_eq!(
+            rx.try_recv().unwrap()[0],
+            BulkString::from("sunsubscribe").into()
+        );
+    }
+
+    #[test]
+    fn test_slow_subscriber_overflow() {
+        let registry = ChannelRegistry::default();
+        let (tx, _rx) = mpsc::channel(1);
+        let overflowed = Arc::new(AtomicBool::new(false));
+        registry.subscribe(b"news", Subscriber::new(1, tx, overflowed.clone()));
+
+        assert_eq!(registry.publish(b"news", BulkString::from("a")), 1);
+        assert_eq!(registry.publish(b"news", BulkString::from("b")), 0);
+        assert!(overflowed.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,358 @@
+use std::{
+    cell::Cell,
+    collections::{BTreeSet, HashSet},
+};
+
+use bytes::Bytes;
+use dashmap::DashMap;
+
+use crate::{BulkString, RespArray, RespFrame, RespPush};
+
+use super::{Backend, Subscriber};
+
+/// Channel a RESP2 client subscribes to in order to receive the
+/// invalidations of the clients redirecting to it.
+pub(crate) const INVALIDATE_CHANNEL: &str = "__redis__:invalidate";
+
+thread_local! {
+    // the client whose command is running on this thread, see Caller::run
+    static CALLER: Cell<Option<Caller>> = const { Cell::new(None) };
+}
+
+/// CLIENT TRACKING settings of one client.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct TrackingOptions {
+    pub(crate) redirect: Option<u64>,
+    pub(crate) bcast: bool,
+    pub(crate) prefixes: BTreeSet<Bytes>,
+    pub(crate) optin: bool,
+    pub(crate) optout: bool,
+    pub(crate) noloop: bool,
+}
+
+#[derive(Debug)]
+struct TrackingClient {
+    options: TrackingOptions,
+    subscriber: Subscriber,
+    protocol: u8,
+    // what it has in `TrackingTable::keys`, to take it out when it stops
+    keys: HashSet<Bytes>,
+}
+
+/// Who gets told about a key changing: clients that read it (default mode)
+/// and clients with a matching BCAST prefix. A key's readers are forgotten
+/// once they are sent its invalidation, they have to read it again to get
+/// the next one.
+#[derive(Debug, Default)]
+pub(crate) struct TrackingTable {
+    clients: DashMap<u64, TrackingClient>,
+    keys: DashMap<Bytes, HashSet<u64>>,
+    prefixes: DashMap<Bytes, HashSet<u64>>,
+}
+
+/// The connection a command runs for, as seen by the backend while it runs.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Caller {
+    pub(crate) id: u64,
+    // whether keys read now go into the tracking table
+    pub(crate) track_reads: bool,
+}
+
+impl Caller {
+    pub(crate) fn current() -> Option<Caller> {
+        CALLER.with(|caller| caller.get())
+    }
+
+    /// Run `f` with reads and writes on the backend attributed to this
+    /// client. Commands never await, so a thread local is enough.
+    pub(crate) fn run<R>(self, f: impl FnOnce() -> R) -> R {
+        let previous = CALLER.with(|caller| caller.replace(Some(self)));
+        let ret = f();
+        CALLER.with(|caller| caller.set(previous));
+        ret
+    }
+}
+
+impl TrackingTable {
+    pub(crate) fn enable(
+        &self,
+        id: u64,
+        options: TrackingOptions,
+        subscriber: Subscriber,
+        protocol: u8,
+    ) {
+        // the keys it read stay tracked when only the options change
+        let keys = self
+            .remove(id)
+            .map(|client| client.keys)
+            .unwrap_or_default();
+        for prefix in &options.prefixes {
+            self.prefixes.entry(prefix.clone()).or_default().insert(id);
+        }
+        if options.bcast && options.prefixes.is_empty() {
+            self.prefixes.entry(Bytes::new()).or_default().insert(id);
+        }
+        self.clients.insert(
+            id,
+            TrackingClient {
+                options,
+                subscriber,
+                protocol,
+                keys,
+            },
+        );
+    }
+
+    /// Turn tracking off and forget the keys the client read.
+    pub(crate) fn disable(&self, id: u64) {
+        let Some(client) = self.remove(id) else {
+            return;
+        };
+        for key in client.keys {
+            if let Some(mut ids) = self.keys.get_mut(&key) {
+                ids.remove(&id);
+            }
+            self.keys.remove_if(&key, |_, ids| ids.is_empty());
+        }
+    }
+
+    // the client and its prefixes, the keys are left to the caller
+    fn remove(&self, id: u64) -> Option<TrackingClient> {
+        let (_, client) = self.clients.remove(&id)?;
+        let mut prefixes = client.options.prefixes.clone();
+        if client.options.bcast {
+            prefixes.insert(Bytes::new());
+        }
+        for prefix in prefixes {
+            if let Some(mut ids) = self.prefixes.get_mut(&prefix) {
+                ids.remove(&id);
+            }
+            self.prefixes.remove_if(&prefix, |_, ids| ids.is_empty());
+        }
+        Some(client)
+    }
+
+    pub(crate) fn options(&self, id: u64) -> Option<TrackingOptions> {
+        self.clients.get(&id).map(|client| client.options.clone())
+    }
+
+    fn record_read(&self, id: u64, key: &[u8]) {
+        let key = Bytes::copy_from_slice(key);
+        // not holding the client while locking the key's shard
+        match self.clients.get_mut(&id) {
+            Some(mut client) => client.keys.insert(key.clone()),
+            None => return,
+        };
+        self.keys.entry(key).or_default().insert(id);
+    }
+
+    /// Clients to notify about `key`: its readers, which are forgotten, and
+    /// the BCAST clients with a matching prefix.
+    fn take_interested(&self, key: &[u8]) -> HashSet<u64> {
+        let mut ids = self
+            .keys
+            .remove(key)
+            .map(|(_, ids)| ids)
+            .unwrap_or_default();
+        for id in &ids {
+            if let Some(mut client) = self.clients.get_mut(id) {
+                client.keys.remove(key);
+            }
+        }
+        for entry in self.prefixes.iter() {
+            if key.starts_with(entry.key()) {
+                ids.extend(entry.value().iter().copied());
+            }
+        }
+        ids
+    }
+}
+
+impl Backend {
+    /// Remember that the running client read `key`, if it tracks its reads.
+    pub(crate) fn track_read(&self, key: &[u8]) {
+        if let Some(caller) = Caller::current() {
+            if caller.track_reads {
+                self.tracking.record_read(caller.id, key);
+            }
+        }
+    }
+
+    /// Send an invalidation for `key` to the clients caching it. Under
+    /// NOLOOP the client making the change is skipped.
+    pub(crate) fn invalidate_key(&self, key: &[u8]) {
+        let ids = self.tracking.take_interested(key);
+        if ids.is_empty() {
+            return;
+        }
+        let writer = Caller::current().map(|caller| caller.id);
+        let keys = RespFrame::from(RespArray::new([BulkString::from(key).into()]));
+        for id in ids {
+            let Some(client) = self.tracking.clients.get(&id) else {
+                continue;
+            };
+            if client.options.noloop && writer == Some(id) {
+                continue;
+            }
+            match client.options.redirect {
+                // the redirect target reads them as pub/sub messages
+                Some(target) => {
+                    if let Some(subscriber) = self
+                        .pubsub
+                        .subscriber(INVALIDATE_CHANNEL.as_bytes(), target)
+                    {
+                        subscriber.send(RespPush::new([
+                            BulkString::from("message").into(),
+                            BulkString::from(INVALIDATE_CHANNEL).into(),
+                            keys.clone(),
+                        ]));
+                    }
+                }
+                // a RESP2 connection can't take them in between replies
+                None if client.protocol < 3 => {}
+                None => {
+                    client.subscriber.send(RespPush::new([
+                        BulkString::from("invalidate").into(),
+                        keys.clone(),
+                    ]));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::DEFAULT_SUBSCRIBER_BUFFER_CAP;
+
+    fn invalidated(message: RespPush) -> RespFrame {
+        assert_eq!(message[0], BulkString::from("invalidate").into());
+        message[1].clone()
+    }
+
+    #[test]
+    fn test_track_default_mode() {
+        let backend = Backend::new();
+        let (tx, mut rx) = mpsc::channel(DEFAULT_SUBSCRIBER_BUFFER_CAP);
+        let subscriber = Subscriber::new(1, tx, Arc::default());
+        backend
+            .tracking
+            .enable(1, TrackingOptions::default(), subscriber, 3);
+
+        // only keys read by the client are tracked
+        backend.set("foo".into(), BulkString::from("a").into());
+        assert!(rx.try_recv().is_err());
+        let reader = Caller {
+            id: 1,
+            track_reads: true,
+        };
+        reader.run(|| backend.get(b"foo"));
+        backend.set("foo".into(), BulkString::from("b").into());
+        assert_eq!(
+            invalidated(rx.try_recv().unwrap()),
+            RespArray::new([BulkString::from("foo").into()]).into()
+        );
+
+        // until read again
+        backend.set("foo".into(), BulkString::from("c").into());
+        assert!(rx.try_recv().is_err());
+
+        backend.tracking.disable(1);
+        reader.run(|| backend.get(b"foo"));
+        backend.set("foo".into(), BulkString::from("d").into());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_disable_forgets_keys() {
+        let backend = Backend::new();
+        let (tx, _rx) = mpsc::channel(DEFAULT_SUBSCRIBER_BUFFER_CAP);
+        let subscriber = Subscriber::new(1, tx, Arc::default());
+        let tracking = &backend.tracking;
+        tracking.enable(1, TrackingOptions::default(), subscriber.clone(), 3);
+        tracking.enable(2, TrackingOptions::default(), subscriber.clone(), 3);
+
+        tracking.record_read(1, b"foo");
+        tracking.record_read(1, b"bar");
+        tracking.record_read(2, b"bar");
+        assert_eq!(tracking.keys.len(), 2);
+
+        // changing the options keeps the keys read so far
+        tracking.enable(1, TrackingOptions::default(), subscriber, 3);
+        assert_eq!(tracking.keys.len(), 2);
+
+        tracking.disable(1);
+        assert!(!tracking.keys.contains_key(&b"foo"[..]));
+        assert_eq!(tracking.keys.get(&b"bar"[..]).unwrap().len(), 1);
+
+        // readers that got an invalidation aren't listed for the key anymore
+        assert_eq!(tracking.take_interested(b"bar"), HashSet::from([2]));
+        assert!(tracking.clients.get(&2).unwrap().keys.is_empty());
+        tracking.disable(2);
+        assert!(tracking.keys.is_empty());
+    }
+
+    #[test]
+    fn test_track_bcast_noloop() {
+        let backend = Backend::new();
+        let (tx, mut rx) = mpsc::channel(DEFAULT_SUBSCRIBER_BUFFER_CAP);
+        let options = TrackingOptions {
+            bcast: true,
+            prefixes: BTreeSet::from([Bytes::from("user:")]),
+            noloop: true,
+            ..Default::default()
+        };
+        backend
+            .tracking
+            .enable(1, options, Subscriber::new(1, tx, Arc::default()), 3);
+
+        backend.hset("user:1".into(), "name".into(), BulkString::from("a").into());
+        assert_eq!(
+            invalidated(rx.try_recv().unwrap()),
+            RespArray::new([BulkString::from("user:1").into()]).into()
+        );
+        backend.set("item:1".into(), BulkString::from("a").into());
+        assert!(rx.try_recv().is_err());
+
+        // NOLOOP: the client's own writes don't come back
+        let writer = Caller {
+            id: 1,
+            track_reads: false,
+        };
+        writer.run(|| backend.set("user:2".into(), BulkString::from("a").into()));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_track_redirect() {
+        let backend = Backend::new();
+        let (tx, mut rx) = mpsc::channel(DEFAULT_SUBSCRIBER_BUFFER_CAP);
+        backend.pubsub.subscribe(
+            INVALIDATE_CHANNEL.as_bytes(),
+            Subscriber::new(2, tx, Arc::default()),
+        );
+        let (tx, mut own_rx) = mpsc::channel(DEFAULT_SUBSCRIBER_BUFFER_CAP);
+        let options = TrackingOptions {
+            redirect: Some(2),
+            bcast: true,
+            ..Default::default()
+        };
+        backend
+            .tracking
+            .enable(1, options, Subscriber::new(1, tx, Arc::default()), 2);
+
+        backend.sadd("s".into(), BulkString::from("a").into());
+        let message = rx.try_recv().unwrap();
+        assert_eq!(message[1], BulkString::from(INVALIDATE_CHANNEL).into());
+        assert_eq!(
+            message[2],
+            RespArray::new([BulkString::from("s").into()]).into()
+        );
+        assert!(own_rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use bytes::Bytes;
+use dashmap::{mapref::entry::Entry, DashMap};
+
+/// Modification versions of the keys some client is WATCHing. Keys nobody
+/// watches are not tracked, so touching them is a single failed lookup.
+#[derive(Debug, Default)]
+pub(crate) struct WatchedKeys {
+    keys: DashMap<Bytes, WatchedKey>,
+}
+
+#[derive(Debug, Default)]
+struct WatchedKey {
+    version: u64,
+    watchers: usize,
+}
+
+impl WatchedKeys {
+    /// Register a watcher for `key`, returning its current version.
+    pub(crate) fn watch(&self, key: &[u8]) -> u64 {
+        let mut entry = self.keys.entry(Bytes::copy_from_slice(key)).or_default();
+        entry.watchers += 1;
+        entry.version
+    }
+
+    pub(crate) fn unwatch(&self, key: &[u8]) {
+        if let Entry::Occupied(mut entry) = self.keys.entry(Bytes::copy_from_slice(key)) {
+            entry.get_mut().watchers -= 1;
+            if entry.get().watchers == 0 {
+                entry.remove();
+            }
+        }
+    }
+
+    pub(crate) fn version(&self, key: &[u8]) -> u64 {
+        self.keys.get(key).map_or(0, |v| v.version)
+    }
+
+    pub(crate) fn touch(&self, key: &[u8]) {
+        if let Some(mut entry) = self.keys.get_mut(key) {
+            entry.version += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watched_keys() {
+        let watched = WatchedKeys::default();
+        watched.touch(b"foo");
+        assert_eq!(watched.watch(b"foo"), 0);
+        assert_eq!(watched.watch(b"foo"), 0);
+
+        watched.touch(b"foo");
+        assert_eq!(watched.version(b"foo"), 1);
+
+        watched.unwatch(b"foo");
+        assert_eq!(watched.version(b"foo"), 1);
+        watched.unwatch(b"foo");
+        assert!(watched.keys.is_empty());
+    }
+}
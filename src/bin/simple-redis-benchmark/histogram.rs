@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+// values below this are counted exactly, above it with 7 significant bits,
+// i.e. within 1%
+const EXACT: u64 = 128;
+const HALF: u64 = EXACT / 2;
+
+/// Latencies in microseconds, in log-linear buckets the way HdrHistogram
+/// keeps them: a few kilobytes however many requests are recorded.
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    counts: Vec<u64>,
+    total: u64,
+    sum: u128,
+    min: u64,
+    max: u64,
+}
+
+impl Histogram {
+    pub fn record(&mut self, latency: Duration, count: u64) {
+        let micros = latency.as_micros().min(u64::MAX as u128) as u64;
+        let index = bucket(micros);
+        if index >= self.counts.len() {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += count;
+        if self.total == 0 || micros < self.min {
+            self.min = micros;
+        }
+        self.max = self.max.max(micros);
+        self.total += count;
+        self.sum += micros as u128 * count as u128;
+    }
+
+    pub fn merge(&mut self, other: &Histogram) {
+        if other.total == 0 {
+            return;
+        }
+        if other.counts.len() > self.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+        self.min = if self.total == 0 {
+            other.min
+        } else {
+            self.min.min(other.min)
+        };
+        self.max = self.max.max(other.max);
+        self.total += other.total;
+        self.sum += other.sum;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn min(&self) -> Duration {
+        Duration::from_micros(self.min)
+    }
+
+    pub fn max(&self) -> Duration {
+        Duration::from_micros(self.max)
+    }
+
+    pub fn mean(&self) -> Duration {
+        match self.total {
+            0 => Duration::ZERO,
+            n => Duration::from_micros((self.sum / n as u128) as u64),
+        }
+    }
+
+    /// The latency `percentile` percent of the requests didn't exceed, to
+    /// the precision of its bucket.
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        if self.total == 0 {
+            return Duration::ZERO;
+        }
+        let rank = ((percentile / 100.0 * self.total as f64).ceil() as u64).clamp(1, self.total);
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Duration::from_micros(highest_in(index).min(self.max));
+            }
+        }
+        self.max()
+    }
+}
+
+fn bucket(value: u64) -> usize {
+    if value < EXACT {
+        return value as usize;
+    }
+    let shift = (u64::BITS - value.leading_zeros()) - EXACT.trailing_zeros();
+    let mantissa = value >> shift;
+    (EXACT + (shift as u64 - 1) * HALF + (mantissa - HALF)) as usize
+}
+
+// the highest value that lands in a bucket
+fn highest_in(index: usize) -> u64 {
+    let index = index as u64;
+    if index < EXACT {
+        return index;
+    }
+    let shift = (index - EXACT) / HALF + 1;
+    let mantissa = (index - EXACT) % HALF + HALF;
+    ((mantissa + 1) << shift) - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets() {
+        for value in [0, 1, 127, 128, 129, 255, 256, 1000, 123_456, u64::MAX / 3] {
+            let index = bucket(value);
+            assert!(highest_in(index) >= value, "{}", value);
+            // within 1%
+            assert!(highest_in(index) - value <= value / 64, "{}", value);
+            if index > 0 {
+                assert!(highest_in(index - 1) < value, "{}", value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let mut a = Histogram::default();
+        let mut b = Histogram::default();
+        for micros in 1..=1000 {
+            let h = if micros % 2 == 0 { &mut a } else { &mut b };
+            h.record(Duration::from_micros(micros), 1);
+        }
+        a.merge(&b);
+        assert_eq!(a.total(), 1000);
+        assert_eq!(a.min(), Duration::from_micros(1));
+        assert_eq!(a.max(), Duration::from_micros(1000));
+        assert_eq!(a.mean(), Duration::from_micros(500));
+
+        let p50 = a.percentile(50.0).as_micros();
+        assert!((500..=505).contains(&p50), "{}", p50);
+        let p99 = a.percentile(99.0).as_micros();
+        assert!((990..=1000).contains(&p99), "{}", p99);
+        assert_eq!(a.percentile(100.0), a.max());
+        assert_eq!(Histogram::default().percentile(99.0), Duration::ZERO);
+    }
+}
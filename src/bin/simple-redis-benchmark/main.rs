@@ -0,0 +1,311 @@
+//! simple-redis-benchmark: a load generator for simple-redis, or any Redis,
+//! in the manner of redis-benchmark.
+
+mod histogram;
+mod report;
+mod workload;
+
+use std::{
+    path::PathBuf,
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use anyhow::{anyhow, Result};
+use bytes::Bytes;
+use clap::{ArgAction, Parser};
+use simple_redis::{
+    client::{cmd, ClientOptions, ConnectAddr, Connection},
+    RespArray, RespFrame,
+};
+use tokio::task::JoinSet;
+
+use crate::{
+    histogram::Histogram,
+    report::{Setup, Stats},
+    workload::{Mix, Op, Rng},
+};
+
+const DEFAULT_TESTS: &str = "ping,set,get,hset,hget,sadd,sismember";
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "simple-redis-benchmark",
+    version,
+    about,
+    disable_help_flag = true
+)]
+struct Args {
+    /// Server hostname.
+    #[arg(short = 'h', long, default_value = "127.0.0.1")]
+    host: String,
+    /// Server port.
+    #[arg(short, long, default_value_t = 6379)]
+    port: u16,
+    /// Server socket, overrides hostname and port.
+    #[arg(short, long)]
+    socket: Option<PathBuf>,
+    /// Username to authenticate with, with -a.
+    #[arg(long)]
+    user: Option<String>,
+    /// Password to authenticate with.
+    #[arg(short = 'a', long = "pass")]
+    password: Option<String>,
+    /// Talk RESP3.
+    #[arg(short = '3')]
+    resp3: bool,
+    /// Number of parallel connections.
+    #[arg(short, long, default_value_t = 50, value_parser = clap::value_parser!(u64).range(1..))]
+    clients: u64,
+    /// Total number of requests of each test.
+    #[arg(short = 'n', long, default_value_t = 100_000)]
+    requests: u64,
+    /// Requests sent at once on a connection.
+    #[arg(short = 'P', default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    pipeline: u64,
+    /// Data size of the values in bytes.
+    #[arg(short, long = "datasize", default_value_t = 3)]
+    data_size: usize,
+    /// Use random keys out of this many, instead of the same key every time.
+    #[arg(short = 'r', long, default_value_t = 0)]
+    keyspace: u64,
+    /// Comma separated tests to run one after the other. INCR, LPUSH,
+    /// RPUSH, LPOP, RPOP, ZADD and MSET only work against Redis, tests the
+    /// server's COMMAND doesn't list are refused.
+    #[arg(short, long, default_value = DEFAULT_TESTS)]
+    tests: String,
+    /// Run a single test picking commands at random by weight, e.g.
+    /// get:9,set:1, instead of the tests.
+    #[arg(long, conflicts_with = "tests")]
+    mix: Option<String>,
+    /// Only print the throughput and latency of each test.
+    #[arg(short, long)]
+    quiet: bool,
+    /// Print the results as CSV.
+    #[arg(long)]
+    csv: bool,
+    /// Print the results as JSON.
+    #[arg(long, conflicts_with = "csv")]
+    json: bool,
+    /// Print help.
+    #[arg(long, action = ArgAction::Help)]
+    help: Option<bool>,
+}
+
+impl Args {
+    fn addr(&self) -> ConnectAddr {
+        #[cfg(unix)]
+        if let Some(path) = &self.socket {
+            return ConnectAddr::Unix(path.clone());
+        }
+        ConnectAddr::Tcp(format!("{}:{}", self.host, self.port))
+    }
+
+    fn options(&self) -> ClientOptions {
+        let auth = self.password.clone().map(|pass| {
+            let user = self.user.clone().unwrap_or_else(|| "default".to_string());
+            (user, pass)
+        });
+        ClientOptions {
+            protocol: if self.resp3 { 3 } else { 2 },
+            auth,
+            ..Default::default()
+        }
+    }
+
+    fn mixes(&self) -> Result<Vec<Mix>> {
+        if let Some(mix) = &self.mix {
+            return Ok(vec![Mix::parse(mix).map_err(|e| anyhow!(e))?]);
+        }
+        self.tests
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(|t| {
+                Op::parse(t)
+                    .map(Mix::single)
+                    .ok_or_else(|| anyhow!("Unknown test '{}'", t))
+            })
+            .collect()
+    }
+
+    fn setup(&self) -> Setup {
+        Setup {
+            clients: self.clients as usize,
+            data_size: self.data_size,
+            pipeline: self.pipeline as usize,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Args::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(args: Args) -> Result<()> {
+    let mixes = args.mixes()?;
+    check_supported(&args, &mixes).await?;
+    let setup = args.setup();
+    if args.csv {
+        println!("{}", report::csv_header());
+    }
+    let mut results = Vec::new();
+    for mix in &mixes {
+        let stats = bench(&args, mix).await?;
+        if args.csv {
+            println!("{}", report::csv_row(&stats));
+        } else if args.quiet {
+            println!("{}", report::quiet(&stats));
+        } else if !args.json {
+            println!("{}", report::human(&stats, &setup));
+        }
+        results.push(stats);
+    }
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report::json(&results, &setup))?
+        );
+    }
+    Ok(())
+}
+
+/// Refuse to run commands the server doesn't list in COMMAND: simple-redis
+/// replies OK to commands it doesn't know, which would look like a very
+/// fast server.
+async fn check_supported(args: &Args, mixes: &[Mix]) -> Result<()> {
+    let mut ops: Vec<Op> = Vec::new();
+    for op in mixes.iter().flat_map(Mix::ops) {
+        if !ops.contains(&op) {
+            ops.push(op);
+        }
+    }
+    let addr = args.addr();
+    let mut conn = Connection::connect(addr.clone(), &args.options())
+        .await
+        .map_err(|e| anyhow!("Could not connect to Redis at {}: {}", addr, e))?;
+    let info = cmd("COMMAND").arg("INFO").args(ops.iter().map(Op::name));
+    let entries = match conn.send(info).await? {
+        RespFrame::Array(RespArray::Array(entries)) if entries.len() == ops.len() => entries,
+        reply => {
+            eprintln!(
+                "WARNING: could not check the commands the server supports: {:?}",
+                reply
+            );
+            return Ok(());
+        }
+    };
+    // commands the server doesn't know get a nil entry
+    let missing: Vec<Op> = ops
+        .into_iter()
+        .zip(entries)
+        .filter(|(_, entry)| !matches!(entry, RespFrame::Array(RespArray::Array(_))))
+        .map(|(op, _)| op)
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    let names = missing.iter().map(Op::name).collect::<Vec<_>>().join(", ");
+    let hint = if missing.iter().all(Op::redis_only) {
+        ", tests that only run against Redis"
+    } else {
+        ""
+    };
+    Err(anyhow!("The server doesn't support {}{}", names, hint))
+}
+
+/// Run one test: every client connects first, then they share out the
+/// requests a pipeline at a time until all are sent.
+async fn bench(args: &Args, mix: &Mix) -> Result<Stats> {
+    let addr = args.addr();
+    let options = args.options();
+    let mut conns = Vec::with_capacity(args.clients as usize);
+    for _ in 0..args.clients {
+        let conn = Connection::connect(addr.clone(), &options)
+            .await
+            .map_err(|e| anyhow!("Could not connect to Redis at {}: {}", addr, e))?;
+        conns.push(conn);
+    }
+
+    let issued = Arc::new(AtomicU64::new(0));
+    let value = Bytes::from(vec![b'x'; args.data_size]);
+    let start = Instant::now();
+    let mut clients = JoinSet::new();
+    for (i, conn) in conns.into_iter().enumerate() {
+        let client = Worker {
+            conn,
+            rng: Rng::new(i as u64),
+            mix: mix.clone(),
+            value: value.clone(),
+            issued: issued.clone(),
+            requests: args.requests,
+            pipeline: args.pipeline,
+            keyspace: args.keyspace,
+        };
+        clients.spawn(client.run());
+    }
+
+    let mut latency = Histogram::default();
+    let mut errors = 0;
+    while let Some(result) = clients.join_next().await {
+        let (h, e) = result??;
+        latency.merge(&h);
+        errors += e;
+    }
+    Ok(Stats {
+        name: mix.name(),
+        elapsed: start.elapsed(),
+        errors,
+        latency,
+    })
+}
+
+struct Worker {
+    conn: Connection,
+    rng: Rng,
+    mix: Mix,
+    value: Bytes,
+    issued: Arc<AtomicU64>,
+    requests: u64,
+    pipeline: u64,
+    keyspace: u64,
+}
+
+impl Worker {
+    // every request of a pipeline takes as long as the whole pipeline
+    async fn run(mut self) -> Result<(Histogram, u64)> {
+        let mut latency = Histogram::default();
+        let mut errors = 0;
+        loop {
+            let first = self.issued.fetch_add(self.pipeline, Ordering::Relaxed);
+            if first >= self.requests {
+                return Ok((latency, errors));
+            }
+            let count = self.pipeline.min(self.requests - first);
+            let cmds = (0..count)
+                .map(|_| {
+                    let op = self.mix.pick(&mut self.rng);
+                    op.cmd(&mut self.rng, self.keyspace, &self.value)
+                })
+                .collect();
+            let start = Instant::now();
+            let replies = self.conn.pipeline(cmds).await?;
+            latency.record(start.elapsed(), count);
+            errors += replies
+                .iter()
+                .filter(|r| matches!(r, RespFrame::SimpleError(_) | RespFrame::BulkError(_)))
+                .count() as u64;
+        }
+    }
+}
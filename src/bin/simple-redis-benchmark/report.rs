@@ -0,0 +1,190 @@
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::histogram::Histogram;
+
+const PERCENTILES: [f64; 7] = [50.0, 75.0, 90.0, 95.0, 99.0, 99.9, 100.0];
+
+/// What one test measured.
+#[derive(Debug, Clone)]
+pub struct Stats {
+    pub name: String,
+    pub elapsed: Duration,
+    pub errors: u64,
+    pub latency: Histogram,
+}
+
+/// The settings a test ran with, printed along with the results.
+#[derive(Debug, Clone, Copy)]
+pub struct Setup {
+    pub clients: usize,
+    pub data_size: usize,
+    pub pipeline: usize,
+}
+
+impl Stats {
+    pub fn requests(&self) -> u64 {
+        self.latency.total()
+    }
+
+    pub fn rps(&self) -> f64 {
+        match self.elapsed.as_secs_f64() {
+            secs if secs > 0.0 => self.requests() as f64 / secs,
+            _ => 0.0,
+        }
+    }
+
+    // avg, min, p50, p95, p99, p999, max
+    fn summary(&self) -> [f64; 7] {
+        let h = &self.latency;
+        [
+            h.mean(),
+            h.min(),
+            h.percentile(50.0),
+            h.percentile(95.0),
+            h.percentile(99.0),
+            h.percentile(99.9),
+            h.max(),
+        ]
+        .map(millis)
+    }
+}
+
+pub fn human(stats: &Stats, setup: &Setup) -> String {
+    let mut out = format!("====== {} ======\n", stats.name);
+    out += &format!(
+        "  {} requests completed in {:.2} seconds\n",
+        stats.requests(),
+        stats.elapsed.as_secs_f64()
+    );
+    out += &format!("  {} parallel clients\n", setup.clients);
+    out += &format!("  {} bytes payload\n", setup.data_size);
+    out += &format!("  {} requests per pipeline\n", setup.pipeline);
+    if stats.errors > 0 {
+        out += &format!("  {} error replies\n", stats.errors);
+    }
+    out += "\nLatency by percentile distribution:\n";
+    for p in PERCENTILES {
+        out += &format!(
+            "{:>8.3}% <= {:.3} milliseconds\n",
+            p,
+            millis(stats.latency.percentile(p))
+        );
+    }
+    out += "\nSummary:\n";
+    out += &format!(
+        "  throughput summary: {:.2} requests per second\n",
+        stats.rps()
+    );
+    out += "  latency summary (msec):\n";
+    out += &format!(
+        "  {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}\n",
+        "avg", "min", "p50", "p95", "p99", "p999", "max"
+    );
+    let row = stats.summary().map(|ms| format!("{:>9.3}", ms));
+    out += &format!("  {}\n", row.join(" "));
+    out
+}
+
+pub fn quiet(stats: &Stats) -> String {
+    format!(
+        "{}: {:.2} requests per second, p50={:.3} msec, p99={:.3} msec, p999={:.3} msec",
+        stats.name,
+        stats.rps(),
+        millis(stats.latency.percentile(50.0)),
+        millis(stats.latency.percentile(99.0)),
+        millis(stats.latency.percentile(99.9)),
+    )
+}
+
+pub fn csv_header() -> &'static str {
+    "\"test\",\"rps\",\"avg_latency_ms\",\"min_latency_ms\",\"p50_latency_ms\",\"p95_latency_ms\",\"p99_latency_ms\",\"p999_latency_ms\",\"max_latency_ms\",\"errors\""
+}
+
+pub fn csv_row(stats: &Stats) -> String {
+    let summary = stats.summary().map(|ms| format!("\"{:.3}\"", ms));
+    format!(
+        "\"{}\",\"{:.2}\",{},\"{}\"",
+        stats.name.replace('"', "\"\""),
+        stats.rps(),
+        summary.join(","),
+        stats.errors
+    )
+}
+
+pub fn json(stats: &[Stats], setup: &Setup) -> Value {
+    let tests = stats
+        .iter()
+        .map(|s| {
+            // to the microsecond the latencies were measured in
+            let [avg, min, p50, p95, p99, p999, max] =
+                s.summary().map(|ms| (ms * 1000.0).round() / 1000.0);
+            json!({
+                "test": s.name,
+                "requests": s.requests(),
+                "errors": s.errors,
+                "seconds": s.elapsed.as_secs_f64(),
+                "rps": s.rps(),
+                "latency_ms": {
+                    "avg": avg,
+                    "min": min,
+                    "p50": p50,
+                    "p95": p95,
+                    "p99": p99,
+                    "p999": p999,
+                    "max": max,
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "clients": setup.clients,
+        "data_size": setup.data_size,
+        "pipeline": setup.pipeline,
+        "tests": tests,
+    })
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let mut latency = Histogram::default();
+        latency.record(Duration::from_micros(100), 3);
+        latency.record(Duration::from_micros(2000), 1);
+        let stats = Stats {
+            name: "SET".to_string(),
+            elapsed: Duration::from_millis(500),
+            errors: 1,
+            latency,
+        };
+        let setup = Setup {
+            clients: 2,
+            data_size: 3,
+            pipeline: 1,
+        };
+
+        assert_eq!(stats.rps(), 8.0);
+        assert_eq!(
+            csv_row(&stats),
+            r#""SET","8.00","0.575","0.100","0.100","2.000","2.000","2.000","2.000","1""#
+        );
+        assert_eq!(
+            quiet(&stats),
+            "SET: 8.00 requests per second, p50=0.100 msec, p99=2.000 msec, p999=2.000 msec"
+        );
+        assert!(human(&stats, &setup).contains("  4 requests completed in 0.50 seconds\n"));
+
+        let json = json(&[stats], &setup);
+        assert_eq!(json["tests"][0]["test"], "SET");
+        assert_eq!(json["tests"][0]["requests"], 4);
+        assert_eq!(json["tests"][0]["latency_ms"]["p50"], 0.1);
+    }
+}
@@ -0,0 +1,222 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use simple_redis::client::{cmd, Cmd};
+
+/// The commands the benchmark can send, with the arguments redis-benchmark
+/// uses for them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Ping,
+    Set,
+    Get,
+    Incr,
+    HSet,
+    HGet,
+    SAdd,
+    SIsMember,
+    LPush,
+    RPush,
+    LPop,
+    RPop,
+    ZAdd,
+    MSet,
+}
+
+const OPS: [(Op, &str); 14] = [
+    (Op::Ping, "PING"),
+    (Op::Set, "SET"),
+    (Op::Get, "GET"),
+    (Op::Incr, "INCR"),
+    (Op::HSet, "HSET"),
+    (Op::HGet, "HGET"),
+    (Op::SAdd, "SADD"),
+    (Op::SIsMember, "SISMEMBER"),
+    (Op::LPush, "LPUSH"),
+    (Op::RPush, "RPUSH"),
+    (Op::LPop, "LPOP"),
+    (Op::RPop, "RPOP"),
+    (Op::ZAdd, "ZADD"),
+    (Op::MSet, "MSET"),
+];
+
+impl Op {
+    pub fn parse(name: &str) -> Option<Op> {
+        OPS.iter()
+            .find(|(_, n)| n.eq_ignore_ascii_case(name))
+            .map(|(op, _)| *op)
+    }
+
+    pub fn name(&self) -> &'static str {
+        OPS.iter()
+            .find(|(op, _)| op == self)
+            .map(|(_, n)| *n)
+            .unwrap_or_default()
+    }
+
+    /// Commands only Redis has, simple-redis replies OK to them without
+    /// doing anything.
+    pub fn redis_only(&self) -> bool {
+        matches!(
+            self,
+            Op::Incr | Op::LPush | Op::RPush | Op::LPop | Op::RPop | Op::ZAdd | Op::MSet
+        )
+    }
+
+    pub fn cmd(&self, rng: &mut Rng, keyspace: u64, value: &[u8]) -> Cmd {
+        let mut rand = |prefix: &str| match keyspace {
+            // without a keyspace every request hits the same key
+            0 => format!("{}__rand_int__", prefix),
+            n => format!("{}{:012}", prefix, rng.below(n)),
+        };
+        match self {
+            Op::Ping => cmd("PING"),
+            Op::Set => cmd("SET").arg(rand("key:")).arg(value),
+            Op::Get => cmd("GET").arg(rand("key:")),
+            Op::Incr => cmd("INCR").arg(rand("counter:")),
+            Op::HSet => cmd("HSET").arg("myhash").arg(rand("element:")).arg(value),
+            Op::HGet => cmd("HGET").arg("myhash").arg(rand("element:")),
+            Op::SAdd => cmd("SADD").arg("myset").arg(rand("element:")),
+            Op::SIsMember => cmd("SISMEMBER").arg("myset").arg(rand("element:")),
+            Op::LPush => cmd("LPUSH").arg("mylist").arg(value),
+            Op::RPush => cmd("RPUSH").arg("mylist").arg(value),
+            Op::LPop => cmd("LPOP").arg("mylist"),
+            Op::RPop => cmd("RPOP").arg("mylist"),
+            Op::ZAdd => {
+                let score = rand("");
+                cmd("ZADD").arg("myzset").arg(score).arg(rand("element:"))
+            }
+            Op::MSet => (0..10).fold(cmd("MSET"), |mset, _| mset.arg(rand("key:")).arg(value)),
+        }
+    }
+}
+
+/// Commands picked at random, each as often as its weight says.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mix {
+    ops: Vec<(Op, u32)>,
+    total: u32,
+}
+
+impl Mix {
+    /// `get:9,set:1`, a missing weight counting as 1.
+    pub fn parse(spec: &str) -> Result<Mix, String> {
+        let mut ops = Vec::new();
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (name, weight) = match part.split_once(':') {
+                Some((name, weight)) => (
+                    name,
+                    weight
+                        .parse::<u32>()
+                        .map_err(|_| format!("Invalid weight in '{}'", part))?,
+                ),
+                None => (part, 1),
+            };
+            let op = Op::parse(name).ok_or_else(|| format!("Unknown command '{}'", name))?;
+            if weight > 0 {
+                ops.push((op, weight));
+            }
+        }
+        let total = ops.iter().map(|(_, w)| w).sum();
+        if total == 0 {
+            return Err("The mix has no command".to_string());
+        }
+        Ok(Mix { ops, total })
+    }
+
+    pub fn single(op: Op) -> Mix {
+        Mix {
+            ops: vec![(op, 1)],
+            total: 1,
+        }
+    }
+
+    pub fn ops(&self) -> impl Iterator<Item = Op> + '_ {
+        self.ops.iter().map(|(op, _)| *op)
+    }
+
+    pub fn pick(&self, rng: &mut Rng) -> Op {
+        if let [(op, _)] = self.ops.as_slice() {
+            return *op;
+        }
+        let mut n = rng.below(self.total as u64) as u32;
+        for (op, weight) in &self.ops {
+            if n < *weight {
+                return *op;
+            }
+            n -= weight;
+        }
+        self.ops[0].0
+    }
+
+    pub fn name(&self) -> String {
+        match self.ops.as_slice() {
+            [(op, _)] => op.name().to_string(),
+            ops => ops
+                .iter()
+                .map(|(op, w)| format!("{}:{}", op.name(), w))
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+}
+
+/// xorshift64*, plenty random for picking keys.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(stream: u64) -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        Self((nanos ^ stream.wrapping_mul(0x9e37_79b9_7f4a_7c15)) | 1)
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mix() {
+        let mix = Mix::parse("get:3, set").unwrap();
+        assert_eq!(mix.name(), "GET:3,SET:1");
+        let mut rng = Rng::new(1);
+        let gets = (0..4000).filter(|_| mix.pick(&mut rng) == Op::Get).count();
+        assert!((2700..3300).contains(&gets), "{}", gets);
+
+        assert!(Mix::parse("get:x").is_err());
+        assert!(Mix::parse("nope").is_err());
+        assert!(Mix::parse("get:0").is_err());
+        assert_eq!(Mix::single(Op::LPush).name(), "LPUSH");
+        assert_eq!(mix.ops().collect::<Vec<_>>(), [Op::Get, Op::Set]);
+        assert!(Op::LPush.redis_only());
+        assert!(!Op::Get.redis_only());
+    }
+
+    #[test]
+    fn test_op_cmd() {
+        let mut rng = Rng::new(1);
+        let set = Op::Set.cmd(&mut rng, 100, b"xxx");
+        let parts = set.parts();
+        assert_eq!(parts[0], "SET");
+        assert!(parts[1].starts_with(b"key:000000000"));
+        assert_eq!(parts[2], "xxx");
+
+        let get = Op::Get.cmd(&mut rng, 0, b"");
+        assert_eq!(get.parts()[1], "key:__rand_int__");
+        assert_eq!(Op::MSet.cmd(&mut rng, 10, b"v").parts().len(), 21);
+    }
+}
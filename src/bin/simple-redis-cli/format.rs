@@ -0,0 +1,221 @@
+use simple_redis::{BulkString, RespArray, RespFrame};
+
+/// A reply the way redis-cli shows it on a terminal: quoted strings,
+/// `(integer)`, `(nil)`, nested elements numbered and indented.
+pub fn format_tty(frame: &RespFrame) -> String {
+    let mut out = String::new();
+    tty(&mut out, frame, "");
+    out
+}
+
+fn tty(out: &mut String, frame: &RespFrame, prefix: &str) {
+    match frame {
+        RespFrame::SimpleString(s) => line(out, s),
+        RespFrame::SimpleError(e) => line(out, &format!("(error) {}", e.as_str())),
+        RespFrame::BulkError(e) => line(
+            out,
+            &format!("(error) {}", String::from_utf8_lossy(e.as_ref())),
+        ),
+        RespFrame::Integer(n) => line(out, &format!("(integer) {}", n)),
+        RespFrame::Double(d) => line(out, &format!("(double) {}", **d)),
+        RespFrame::Boolean(b) => line(out, if *b { "(true)" } else { "(false)" }),
+        RespFrame::BigNumber(n) => line(out, &format!("(big number) {}", n.as_str())),
+        RespFrame::BulkString(BulkString::String(s)) => line(out, &quote(s)),
+        RespFrame::BulkString(BulkString::Null)
+        | RespFrame::Array(RespArray::Null)
+        | RespFrame::Null(_) => line(out, "(nil)"),
+        // shown as it is, INFO and such are meant to be read
+        RespFrame::VerbatimString(v) => line(
+            out,
+            String::from_utf8_lossy(v.data()).trim_end_matches('\n'),
+        ),
+        RespFrame::Array(RespArray::Array(items)) => elements(out, items, ')', "array", prefix),
+        RespFrame::Push(items) => elements(out, items, ')', "push", prefix),
+        RespFrame::Set(items) => elements(out, items, '~', "set", prefix),
+        RespFrame::Map(map) => entries(out, map, '#', "hash", prefix),
+        RespFrame::Attribute(attribute) => {
+            entries(out, attribute.attributes(), '|', "attributes", prefix);
+            out.push_str(prefix);
+            tty(out, &attribute.clone().into_frame(), prefix);
+        }
+    }
+}
+
+fn line(out: &mut String, s: &str) {
+    out.push_str(s);
+    out.push('\n');
+}
+
+fn elements(out: &mut String, items: &[RespFrame], mark: char, kind: &str, prefix: &str) {
+    if items.is_empty() {
+        return line(out, &format!("(empty {})", kind));
+    }
+    let width = items.len().to_string().len();
+    let nested = format!("{}{}", prefix, " ".repeat(width + 2));
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.push_str(prefix);
+        }
+        out.push_str(&format!("{:>width$}{} ", i + 1, mark));
+        tty(out, item, &nested);
+    }
+}
+
+fn entries(
+    out: &mut String,
+    entries: &[(RespFrame, RespFrame)],
+    mark: char,
+    kind: &str,
+    prefix: &str,
+) {
+    if entries.is_empty() {
+        return line(out, &format!("(empty {})", kind));
+    }
+    let width = entries.len().to_string().len();
+    let nested = format!("{}{}", prefix, " ".repeat(width + 2));
+    for (i, (key, value)) in entries.iter().enumerate() {
+        if i > 0 {
+            out.push_str(prefix);
+        }
+        out.push_str(&format!("{:>width$}{} ", i + 1, mark));
+        tty(out, key, &nested);
+        out.pop();
+        out.push_str(" => ");
+        tty(out, value, &nested);
+    }
+}
+
+/// A reply as plain text, for when the output isn't a terminal: strings as
+/// they are, one element per line, nil as an empty line.
+pub fn format_raw(frame: &RespFrame) -> Vec<u8> {
+    let mut out = Vec::new();
+    raw(&mut out, frame);
+    out.push(b'\n');
+    out
+}
+
+fn raw(out: &mut Vec<u8>, frame: &RespFrame) {
+    let items: &[RespFrame] = match frame {
+        RespFrame::SimpleString(s) => return out.extend_from_slice(s.as_bytes()),
+        RespFrame::SimpleError(e) => return out.extend_from_slice(e.as_bytes()),
+        RespFrame::BulkError(e) => return out.extend_from_slice(e.as_ref()),
+        RespFrame::Integer(n) => return out.extend_from_slice(n.to_string().as_bytes()),
+        RespFrame::Double(d) => return out.extend_from_slice(d.to_string().as_bytes()),
+        RespFrame::Boolean(b) => {
+            return out.extend_from_slice(if *b { b"(true)" } else { b"(false)" })
+        }
+        RespFrame::BigNumber(n) => return out.extend_from_slice(n.as_bytes()),
+        RespFrame::BulkString(BulkString::String(s)) => return out.extend_from_slice(s),
+        RespFrame::VerbatimString(v) => return out.extend_from_slice(v.data()),
+        RespFrame::BulkString(BulkString::Null)
+        | RespFrame::Array(RespArray::Null)
+        | RespFrame::Null(_) => return,
+        RespFrame::Array(RespArray::Array(items)) => items,
+        RespFrame::Push(items) => items,
+        RespFrame::Set(items) => items,
+        RespFrame::Map(map) => {
+            let flat = map
+                .iter()
+                .flat_map(|(k, v)| [k.clone(), v.clone()])
+                .collect::<Vec<_>>();
+            return raw(out, &RespArray::new(flat).into());
+        }
+        RespFrame::Attribute(attribute) => return raw(out, &attribute.clone().into_frame()),
+    };
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.push(b'\n');
+        }
+        raw(out, item);
+    }
+}
+
+/// A string quoted and escaped the way redis-cli shows it (`sdscatrepr`).
+pub fn quote(s: &[u8]) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for &c in s {
+        match c {
+            b'\\' => out.push_str("\\\\"),
+            b'"' => out.push_str("\\\""),
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            b'\t' => out.push_str("\\t"),
+            0x07 => out.push_str("\\a"),
+            0x08 => out.push_str("\\b"),
+            c if c.is_ascii_graphic() || c == b' ' => out.push(c as char),
+            c => out.push_str(&format!("\\x{:02x}", c)),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use simple_redis::{RespMap, RespNull, RespSet, SimpleError, SimpleString};
+
+    use super::*;
+
+    fn bulk(s: &str) -> RespFrame {
+        BulkString::from(s).into()
+    }
+
+    #[test]
+    fn test_format_tty_scalars() {
+        assert_eq!(format_tty(&SimpleString::new("OK").into()), "OK\n");
+        assert_eq!(
+            format_tty(&SimpleError::new("ERR nope").into()),
+            "(error) ERR nope\n"
+        );
+        assert_eq!(format_tty(&RespFrame::Integer(-3)), "(integer) -3\n");
+        assert_eq!(format_tty(&RespFrame::Double(1.5.into())), "(double) 1.5\n");
+        assert_eq!(format_tty(&RespFrame::Boolean(true)), "(true)\n");
+        assert_eq!(format_tty(&RespNull.into()), "(nil)\n");
+        assert_eq!(format_tty(&BulkString::Null.into()), "(nil)\n");
+        assert_eq!(format_tty(&bulk("say \"hi\"\n")), "\"say \\\"hi\\\"\\n\"\n");
+        assert_eq!(quote(b"\x00\xff"), "\"\\x00\\xff\"");
+    }
+
+    #[test]
+    fn test_format_tty_aggregates() {
+        let nested: RespFrame = RespArray::new(vec![
+            RespArray::new(vec![bulk("a"), bulk("b")]).into(),
+            bulk("c"),
+            RespArray::new(vec![]).into(),
+        ])
+        .into();
+        assert_eq!(
+            format_tty(&nested),
+            "1) 1) \"a\"\n   2) \"b\"\n2) \"c\"\n3) (empty array)\n"
+        );
+
+        let ten = RespArray::new((0..10).map(RespFrame::Integer).collect::<Vec<_>>());
+        let out = format_tty(&ten.into());
+        assert!(out.starts_with(" 1) (integer) 0\n 2) (integer) 1\n"));
+        assert!(out.ends_with("10) (integer) 9\n"));
+
+        let mut map = RespMap::new();
+        map.insert(bulk("name"), bulk("alice"));
+        map.insert(
+            bulk("tags"),
+            RespSet::new(vec![bulk("x"), bulk("y")]).into(),
+        );
+        assert_eq!(
+            format_tty(&map.into()),
+            "1# \"name\" => \"alice\"\n2# \"tags\" => 1~ \"x\"\n   2~ \"y\"\n"
+        );
+    }
+
+    #[test]
+    fn test_format_raw() {
+        let reply: RespFrame = RespArray::new(vec![
+            bulk("a"),
+            BulkString::Null.into(),
+            RespFrame::Integer(1),
+        ])
+        .into();
+        assert_eq!(format_raw(&reply), b"a\n\n1\n");
+        assert_eq!(format_raw(&SimpleString::new("OK").into()), b"OK\n");
+    }
+}
@@ -0,0 +1,311 @@
+//! simple-redis-cli: an interactive client for simple-redis, or any Redis,
+//! in the manner of redis-cli.
+
+mod format;
+mod pipe;
+mod scan;
+
+use std::{
+    io::{self, IsTerminal, Read, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use anyhow::Result;
+use clap::{ArgAction, Parser};
+use rustyline::{error::ReadlineError, DefaultEditor};
+use simple_redis::{
+    client::{ClientOptions, Cmd, ConnectAddr, Connection},
+    split_inline_args, RespFrame,
+};
+
+use crate::format::{format_raw, format_tty};
+
+const HISTORY_FILE: &str = ".simple_rediscli_history";
+
+#[derive(Debug, Parser)]
+#[command(name = "simple-redis-cli", version, about, disable_help_flag = true)]
+struct Args {
+    /// Server hostname.
+    #[arg(short = 'h', long, default_value = "127.0.0.1")]
+    host: String,
+    /// Server port.
+    #[arg(short, long, default_value_t = 6379)]
+    port: u16,
+    /// Server socket, overrides hostname and port.
+    #[arg(short, long)]
+    socket: Option<PathBuf>,
+    /// Server URI, redis://[user:password@]host[:port].
+    #[arg(short, long)]
+    uri: Option<String>,
+    /// Username to authenticate with, with -a.
+    #[arg(long)]
+    user: Option<String>,
+    /// Password to authenticate with.
+    #[arg(short = 'a', long = "pass")]
+    password: Option<String>,
+    /// Talk RESP3.
+    #[arg(short = '3')]
+    resp3: bool,
+    /// Print replies as they are, the default when stdout isn't a terminal.
+    #[arg(long)]
+    raw: bool,
+    /// Format replies even when stdout isn't a terminal.
+    #[arg(long, conflicts_with = "raw")]
+    no_raw: bool,
+    /// Read the last argument from stdin.
+    #[arg(short = 'x')]
+    stdin_arg: bool,
+    /// Run the command this many times.
+    #[arg(short = 'r', default_value_t = 1)]
+    repeat: u64,
+    /// Send the RESP protocol read from stdin, for mass insertion.
+    #[arg(long)]
+    pipe: bool,
+    /// List the keys with SCAN (a Redis command this server lacks).
+    #[arg(long)]
+    scan: bool,
+    /// Keys to list with --scan.
+    #[arg(long, requires = "scan")]
+    pattern: Option<String>,
+    /// Keys asked for with each SCAN.
+    #[arg(long, default_value_t = 100)]
+    count: usize,
+    /// Find the biggest key of each type, with SCAN and TYPE (Redis only).
+    #[arg(long)]
+    bigkeys: bool,
+    /// Print help.
+    #[arg(long, action = ArgAction::Help)]
+    help: Option<bool>,
+    /// The command to run, instead of starting a prompt.
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    command: Vec<String>,
+}
+
+impl Args {
+    fn addr(&self) -> ConnectAddr {
+        #[cfg(unix)]
+        if let Some(path) = &self.socket {
+            return ConnectAddr::Unix(path.clone());
+        }
+        ConnectAddr::Tcp(format!("{}:{}", self.host, self.port))
+    }
+
+    fn options(&self) -> ClientOptions {
+        let auth = self.password.clone().map(|pass| {
+            let user = self.user.clone().unwrap_or_else(|| "default".to_string());
+            (user, pass)
+        });
+        ClientOptions {
+            protocol: if self.resp3 { 3 } else { 2 },
+            auth,
+            ..Default::default()
+        }
+    }
+
+    fn raw_output(&self) -> bool {
+        self.raw || (!self.no_raw && !io::stdout().is_terminal())
+    }
+
+    // redis://[user:password@]host[:port]
+    fn apply_uri(&mut self) {
+        let Some(uri) = self.uri.take() else {
+            return;
+        };
+        let rest = uri.strip_prefix("redis://").unwrap_or(&uri);
+        let rest = rest.split('/').next().unwrap_or(rest);
+        let hostport = match rest.rsplit_once('@') {
+            Some((auth, hostport)) => {
+                match auth.split_once(':') {
+                    Some((user, pass)) => {
+                        if !user.is_empty() {
+                            self.user = Some(user.to_string());
+                        }
+                        self.password = Some(pass.to_string());
+                    }
+                    None => self.password = Some(auth.to_string()),
+                }
+                hostport
+            }
+            None => rest,
+        };
+        match hostport.rsplit_once(':') {
+            Some((host, port)) => {
+                self.host = host.to_string();
+                self.port = port.parse().unwrap_or(self.port);
+            }
+            None if !hostport.is_empty() => self.host = hostport.to_string(),
+            None => {}
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let mut args = Args::parse();
+    args.apply_uri();
+    match run(args).await {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(args: Args) -> Result<ExitCode> {
+    let addr = args.addr();
+    if args.pipe {
+        let errors = pipe::run(&addr)?;
+        return Ok(if errors > 0 {
+            ExitCode::FAILURE
+        } else {
+            ExitCode::SUCCESS
+        });
+    }
+
+    let interactive =
+        args.command.is_empty() && !args.scan && !args.bigkeys && io::stdin().is_terminal();
+    let mut conn = match Connection::connect(addr.clone(), &args.options()).await {
+        Ok(conn) => Some(conn),
+        // the prompt starts anyway and tries again with each command
+        Err(e) if interactive => {
+            eprintln!("Could not connect to Redis at {}: {}", addr, e);
+            None
+        }
+        Err(e) => {
+            return Err(anyhow::anyhow!(
+                "Could not connect to Redis at {}: {}",
+                addr,
+                e
+            ))
+        }
+    };
+
+    if interactive {
+        repl(&args, &addr, conn).await?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    let conn = conn.as_mut().expect("connected");
+    if args.scan {
+        scan::scan(conn, args.pattern.as_deref(), args.count).await?;
+    } else if args.bigkeys {
+        scan::bigkeys(conn, args.count).await?;
+    } else if !args.command.is_empty() {
+        let mut parts = args
+            .command
+            .iter()
+            .map(|a| a.as_bytes().to_vec())
+            .collect::<Vec<_>>();
+        if args.stdin_arg {
+            let mut last = Vec::new();
+            io::stdin().read_to_end(&mut last)?;
+            parts.push(last);
+        }
+        for _ in 0..args.repeat {
+            run_command(&args, conn, parts.clone()).await?;
+        }
+    } else {
+        // one command per line from stdin
+        for line in io::stdin().lines() {
+            let line = line?;
+            match split_inline_args(line.as_bytes()) {
+                Ok(parts) if parts.is_empty() => {}
+                Ok(parts) => run_command(&args, conn, parts).await?,
+                Err(_) => eprintln!("Invalid argument(s)"),
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn repl(args: &Args, addr: &ConnectAddr, mut conn: Option<Connection>) -> Result<()> {
+    let mut editor = DefaultEditor::new()?;
+    let history = std::env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+    if let Some(history) = &history {
+        let _ = editor.load_history(history);
+    }
+
+    loop {
+        let prompt = match &conn {
+            Some(_) => format!("{}> ", addr),
+            None => "not connected> ".to_string(),
+        };
+        let line = match tokio::task::block_in_place(|| editor.readline(&prompt)) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+        let parts = match split_inline_args(line.as_bytes()) {
+            Ok(parts) => parts,
+            Err(_) => {
+                println!("Invalid argument(s)");
+                continue;
+            }
+        };
+        match parts[0].to_ascii_lowercase().as_slice() {
+            b"quit" | b"exit" => break,
+            b"clear" => {
+                let _ = editor.clear_screen();
+                continue;
+            }
+            _ => {}
+        }
+
+        if conn.as_ref().is_none_or(Connection::is_broken) {
+            conn = match Connection::connect(addr.clone(), &args.options()).await {
+                Ok(conn) => Some(conn),
+                Err(e) => {
+                    println!("Could not connect to Redis at {}: {}", addr, e);
+                    None
+                }
+            };
+        }
+        if let Some(c) = conn.as_mut() {
+            if let Err(e) = run_command(args, c, parts).await {
+                println!("Error: {}", e);
+            }
+        }
+    }
+
+    if let Some(history) = &history {
+        let _ = editor.save_history(history);
+    }
+    Ok(())
+}
+
+async fn run_command(args: &Args, conn: &mut Connection, parts: Vec<Vec<u8>>) -> Result<()> {
+    let name = parts[0].to_ascii_lowercase();
+    let cmd = Cmd::new(&String::from_utf8_lossy(&parts[0])).args(parts.into_iter().skip(1));
+    if matches!(
+        name.as_slice(),
+        b"subscribe" | b"psubscribe" | b"ssubscribe"
+    ) {
+        // confirmations and messages alike, until the connection goes
+        conn.write(cmd).await?;
+        if !args.raw_output() {
+            println!("Reading messages... (press Ctrl-C to quit)");
+        }
+        loop {
+            print_reply(args, &conn.read().await?)?;
+        }
+    }
+    let reply = conn.send(cmd).await?;
+    print_reply(args, &reply)
+}
+
+fn print_reply(args: &Args, reply: &RespFrame) -> Result<()> {
+    let mut out = io::stdout().lock();
+    if args.raw_output() {
+        out.write_all(&format_raw(reply))?;
+    } else {
+        out.write_all(format_tty(reply).as_bytes())?;
+    }
+    out.flush()?;
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use std::{
+    io::{self, Read, Write},
+    net::TcpStream,
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use bytes::BytesMut;
+use simple_redis::{
+    client::cmd, client::ConnectAddr, BulkString, RespDecoder, RespEncode, RespFrame,
+};
+
+/// Mass insertion: copy the RESP commands on stdin to the server as they
+/// come, reading the replies meanwhile, and report how many were errors.
+/// Returns the number of errors.
+pub fn run(addr: &ConnectAddr) -> Result<u64> {
+    let (reader, writer) = connect(addr)?;
+    let (errors, replies) = transfer(io::stdin(), reader, writer)?;
+    println!("Last reply received from server.");
+    println!("errors: {}, replies: {}", errors, replies);
+    Ok(errors)
+}
+
+// An ECHO of a random marker after the last command says when all the
+// replies are in. Returns the number of errors and of replies.
+fn transfer(
+    mut input: impl Read + Send + 'static,
+    mut reader: Reader,
+    mut writer: Writer,
+) -> Result<(u64, u64)> {
+    let marker = marker();
+    let echo = cmd("ECHO").arg(marker.as_str()).into_frame().encode();
+    let sender = thread::spawn(move || -> io::Result<()> {
+        io::copy(&mut input, &mut writer)?;
+        writer.write_all(&echo)?;
+        writer.flush()?;
+        eprintln!("All data transferred. Waiting for the last reply...");
+        Ok(())
+    });
+
+    let mut decoder = RespDecoder::new();
+    let mut buf = BytesMut::with_capacity(64 * 1024);
+    let mut chunk = vec![0; 64 * 1024];
+    let (mut replies, mut errors) = (0u64, 0u64);
+    'read: loop {
+        let n = reader.read(&mut chunk)?;
+        if n == 0 {
+            return Err(anyhow!("Connection closed before the last reply"));
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        while let Some(frame) = decoder.decode(&mut buf)? {
+            match frame {
+                RespFrame::BulkString(BulkString::String(s)) if s == marker.as_bytes() => {
+                    break 'read
+                }
+                RespFrame::SimpleError(e) => {
+                    errors += 1;
+                    eprintln!("{}", e.as_str());
+                }
+                RespFrame::BulkError(e) => {
+                    errors += 1;
+                    eprintln!("{}", String::from_utf8_lossy(e.as_ref()));
+                }
+                _ => {}
+            }
+            replies += 1;
+        }
+    }
+    sender
+        .join()
+        .map_err(|_| anyhow!("Writing to the server failed"))??;
+    Ok((errors, replies))
+}
+
+type Reader = Box<dyn Read + Send>;
+type Writer = Box<dyn Write + Send>;
+
+// a plain blocking socket, replies are read while stdin is still being sent
+fn connect(addr: &ConnectAddr) -> Result<(Reader, Writer)> {
+    match addr {
+        ConnectAddr::Tcp(addr) => {
+            let stream = TcpStream::connect(addr)?;
+            Ok((Box::new(stream.try_clone()?), Box::new(stream)))
+        }
+        #[cfg(unix)]
+        ConnectAddr::Unix(path) => {
+            let stream = std::os::unix::net::UnixStream::connect(path)?;
+            Ok((Box::new(stream.try_clone()?), Box::new(stream)))
+        }
+    }
+}
+
+// 20 bytes that can't be mistaken for a real reply, in hex
+fn marker() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let seed = nanos ^ ((std::process::id() as u128) << 64);
+    format!("{:040x}", seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, net::SocketAddr};
+
+    use simple_redis::{network, Backend};
+
+    use super::*;
+
+    // a server on a free port, on a runtime of its own since --pipe is
+    // blocking
+    fn start_server() -> SocketAddr {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        listener.set_nonblocking(true).unwrap();
+        thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(async move {
+                let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                let backend = Backend::new();
+                while let Ok((stream, _)) = listener.accept().await {
+                    tokio::spawn(network::stream_handler(stream, backend.clone()));
+                }
+            });
+        });
+        addr
+    }
+
+    #[test]
+    fn test_pipe_counts_replies_and_errors() -> Result<()> {
+        let addr = ConnectAddr::Tcp(start_server().to_string());
+        let (reader, writer) = connect(&addr)?;
+        let input = [cmd("SET").arg("a").arg(1), cmd("GET"), cmd("GET").arg("a")]
+            .into_iter()
+            .flat_map(|c| c.into_frame().encode())
+            .collect::<Vec<u8>>();
+
+        assert_eq!(transfer(Cursor::new(input), reader, writer)?, (1, 3));
+        Ok(())
+    }
+}
@@ -0,0 +1,241 @@
+use std::io::Write;
+
+use anyhow::{anyhow, Result};
+use bytes::Bytes;
+use simple_redis::{
+    client::{cmd, ClientError, Cmd, Connection},
+    FromRespFrame, RespFrame,
+};
+
+use crate::format::{format_tty, quote};
+
+// SCAN and TYPE, and the STRLEN, HLEN, SCARD... --bigkeys sizes keys with,
+// aren't commands of this server: --scan and --bigkeys are for real Redis.
+
+/// Every key matching the pattern, one per line, with SCAN.
+pub async fn scan(conn: &mut Connection, pattern: Option<&str>, count: usize) -> Result<()> {
+    let mut out = std::io::stdout().lock();
+    let mut cursor = Bytes::from_static(b"0");
+    loop {
+        let keys;
+        (cursor, keys) = scan_step(conn, &cursor, pattern, count).await?;
+        for key in keys {
+            out.write_all(&key)?;
+            out.write_all(b"\n")?;
+        }
+        if &cursor[..] == b"0" {
+            return Ok(());
+        }
+    }
+}
+
+async fn scan_step(
+    conn: &mut Connection,
+    cursor: &Bytes,
+    pattern: Option<&str>,
+    count: usize,
+) -> Result<(Bytes, Vec<Bytes>)> {
+    let mut scan = cmd("SCAN").arg(cursor);
+    if let Some(pattern) = pattern {
+        scan = scan.arg("MATCH").arg(pattern);
+    }
+    let reply = conn
+        .query::<RespFrame>(scan.arg("COUNT").arg(count))
+        .await?;
+    parse_scan_reply(reply)
+}
+
+// the next cursor and a page of keys
+fn parse_scan_reply(reply: RespFrame) -> Result<(Bytes, Vec<Bytes>)> {
+    <(Bytes, Vec<Bytes>)>::from_resp_frame(reply.clone()).map_err(|_| {
+        anyhow!(
+            "Unexpected SCAN reply, does the server support it? {}",
+            format_tty(&reply).trim_end()
+        )
+    })
+}
+
+#[derive(Debug)]
+struct TypeStats {
+    name: &'static str,
+    size_cmd: &'static str,
+    unit: &'static str,
+    keys: u64,
+    total: u64,
+    biggest: Option<(Bytes, u64)>,
+}
+
+/// Scan the whole keyspace and report the biggest key of each type, by
+/// length for strings and element count for the others.
+pub async fn bigkeys(conn: &mut Connection, count: usize) -> Result<()> {
+    let mut stats = [
+        ("string", "STRLEN", "bytes"),
+        ("list", "LLEN", "items"),
+        ("set", "SCARD", "members"),
+        ("hash", "HLEN", "fields"),
+        ("zset", "ZCARD", "members"),
+        ("stream", "XLEN", "entries"),
+    ]
+    .map(|(name, size_cmd, unit)| TypeStats {
+        name,
+        size_cmd,
+        unit,
+        keys: 0,
+        total: 0,
+        biggest: None,
+    });
+
+    println!();
+    println!("# Scanning the entire keyspace to find biggest keys as well as");
+    println!("# average sizes per key type.");
+    println!();
+    let (mut sampled, mut key_bytes) = (0u64, 0u64);
+    let mut cursor = Bytes::from_static(b"0");
+    loop {
+        let keys;
+        (cursor, keys) = scan_step(conn, &cursor, None, count).await?;
+        let types = pipeline(conn, keys.iter().map(|k| cmd("TYPE").arg(k))).await?;
+        let typed = keys
+            .into_iter()
+            .zip(types)
+            .filter_map(|(key, t)| {
+                let t = String::from_resp_frame(t).ok()?;
+                Some((key, stats.iter().position(|s| s.name == t)?))
+            })
+            .collect::<Vec<_>>();
+        let sizes = pipeline(
+            conn,
+            typed.iter().map(|(k, t)| cmd(stats[*t].size_cmd).arg(k)),
+        )
+        .await?;
+        for ((key, t), size) in typed.into_iter().zip(sizes) {
+            let size = u64::from_resp_frame(size).unwrap_or(0);
+            let stat = &mut stats[t];
+            sampled += 1;
+            key_bytes += key.len() as u64;
+            if stat.record(key, size) {
+                println!(
+                    "Biggest {:>6} found so far '{}' with {} {}",
+                    stat.name,
+                    quote(stat.biggest_key()),
+                    size,
+                    stat.unit
+                );
+            }
+        }
+        if &cursor[..] == b"0" {
+            break;
+        }
+    }
+
+    println!();
+    println!("-------- summary -------");
+    println!();
+    println!("Sampled {} keys in the keyspace!", sampled);
+    println!(
+        "Total key length in bytes is {} (avg len {:.2})",
+        key_bytes,
+        ratio(key_bytes, sampled)
+    );
+    println!();
+    for stat in &stats {
+        if let Some((key, size)) = &stat.biggest {
+            println!(
+                "Biggest {:>6} found '{}' has {} {}",
+                stat.name,
+                quote(key),
+                size,
+                stat.unit
+            );
+        }
+    }
+    println!();
+    for stat in &stats {
+        println!(
+            "{} {}s with {} {} ({:.2}% of keys, avg size {:.2})",
+            stat.keys,
+            stat.name,
+            stat.total,
+            stat.unit,
+            ratio(stat.keys * 100, sampled),
+            ratio(stat.total, stat.keys)
+        );
+    }
+    Ok(())
+}
+
+impl TypeStats {
+    // count a key in, telling whether it's the biggest so far
+    fn record(&mut self, key: Bytes, size: u64) -> bool {
+        self.keys += 1;
+        self.total += size;
+        if self.biggest.as_ref().is_some_and(|(_, s)| size <= *s) {
+            return false;
+        }
+        self.biggest = Some((key, size));
+        true
+    }
+
+    fn biggest_key(&self) -> &[u8] {
+        self.biggest.as_ref().map_or(&[], |(key, _)| key)
+    }
+}
+
+async fn pipeline(
+    conn: &mut Connection,
+    cmds: impl Iterator<Item = Cmd>,
+) -> Result<Vec<RespFrame>, ClientError> {
+    conn.pipeline(cmds.collect()).await
+}
+
+fn ratio(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use simple_redis::{BulkString, RespArray, SimpleString};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_scan_reply() {
+        let reply = RespArray::new([
+            BulkString::from("17").into(),
+            RespArray::new([BulkString::from("a").into(), BulkString::from("b").into()]).into(),
+        ]);
+        let (cursor, keys) = parse_scan_reply(reply.into()).unwrap();
+        assert_eq!(cursor, "17");
+        assert_eq!(keys, ["a", "b"]);
+
+        // what this server answers a command it doesn't know
+        let err = parse_scan_reply(SimpleString::new("OK").into()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unexpected SCAN reply, does the server support it? OK"
+        );
+    }
+
+    #[test]
+    fn test_type_stats_keeps_the_biggest_key() {
+        let mut stat = TypeStats {
+            name: "hash",
+            size_cmd: "HLEN",
+            unit: "fields",
+            keys: 0,
+            total: 0,
+            biggest: None,
+        };
+        assert!(stat.record(Bytes::from("a"), 3));
+        assert!(!stat.record(Bytes::from("b"), 3));
+        assert!(stat.record(Bytes::from("c"), 5));
+        assert!(!stat.record(Bytes::from("d"), 1));
+        assert_eq!((stat.keys, stat.total), (4, 12));
+        assert_eq!(stat.biggest_key(), b"c");
+        assert_eq!(ratio(stat.total, stat.keys), 3.0);
+    }
+}
@@ -0,0 +1,864 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Duration,
+};
+
+use bytes::Bytes;
+use tokio::time;
+use tracing::{info, warn};
+
+use crate::{
+    key_hash_slot, BulkString, FromRespFrame, RespArray, RespError, RespFrame, SimpleError,
+    SimpleString, SLOT_COUNT,
+};
+
+use super::{check, cmd, Client, ClientError, ClientOptions, Cmd, ConnectAddr};
+
+const UNASSIGNED: u16 = u16::MAX;
+
+#[derive(Debug, Clone)]
+pub struct ClusterOptions {
+    /// Options for the connection to each node.
+    pub client: ClientOptions,
+    /// Send read-only commands to replicas, round robin, with READONLY
+    /// said on every connection.
+    pub read_from_replicas: bool,
+    /// MOVED and ASK redirections followed for a command before its last
+    /// reply is returned as is.
+    pub max_redirects: usize,
+}
+
+impl Default for ClusterOptions {
+    fn default() -> Self {
+        Self {
+            client: ClientOptions::default(),
+            read_from_replicas: false,
+            max_redirects: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Shard {
+    master: String,
+    replicas: Vec<String>,
+}
+
+/// Which shard serves each slot.
+#[derive(Debug, Clone)]
+struct SlotMap {
+    shards: Vec<Shard>,
+    slots: Vec<u16>,
+}
+
+impl Default for SlotMap {
+    fn default() -> Self {
+        Self {
+            shards: Vec::new(),
+            slots: vec![UNASSIGNED; SLOT_COUNT],
+        }
+    }
+}
+
+impl SlotMap {
+    fn shard(&self, slot: u16) -> Option<&Shard> {
+        self.shards.get(self.slots[slot as usize] as usize)
+    }
+
+    fn assign(&mut self, start: u16, end: u16, shard: Shard) {
+        let index = match self.shards.iter().position(|s| *s == shard) {
+            Some(index) => index,
+            None => {
+                self.shards.push(shard);
+                self.shards.len() - 1
+            }
+        };
+        let end = end.min(SLOT_COUNT as u16 - 1);
+        for slot in start..=end {
+            self.slots[slot as usize] = index as u16;
+        }
+    }
+
+    // what a MOVED says until the map is refreshed
+    fn moved(&mut self, slot: u16, addr: &str) {
+        let shard = match self.shards.iter().find(|s| s.master == addr) {
+            Some(shard) => shard.clone(),
+            None => Shard {
+                master: addr.to_string(),
+                replicas: Vec::new(),
+            },
+        };
+        self.assign(slot, slot, shard);
+    }
+}
+
+/// A client for a Redis Cluster. It learns which node serves which slot
+/// from CLUSTER SHARDS (CLUSTER SLOTS on older servers), sends each command
+/// to the node serving its key, follows MOVED and ASK redirections, and
+/// splits MGET, MSET, DEL, EXISTS, UNLINK and TOUCH over keys in different
+/// slots. Commands without keys go to any master. Cheap to clone.
+#[derive(Debug, Clone)]
+pub struct ClusterClient {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    seeds: Vec<ConnectAddr>,
+    options: ClusterOptions,
+    map: RwLock<SlotMap>,
+    nodes: Mutex<HashMap<String, Client>>,
+    refreshing: AtomicBool,
+    next_replica: AtomicUsize,
+}
+
+enum Redirect {
+    Moved(u16, String),
+    Ask(String),
+    TryAgain,
+}
+
+impl ClusterClient {
+    pub async fn connect<A: Into<ConnectAddr>>(
+        seeds: impl IntoIterator<Item = A>,
+    ) -> Result<ClusterClient, ClientError> {
+        Self::connect_with(seeds, ClusterOptions::default()).await
+    }
+
+    /// Connect to the first seed that answers and load the slot map from it.
+    pub async fn connect_with<A: Into<ConnectAddr>>(
+        seeds: impl IntoIterator<Item = A>,
+        mut options: ClusterOptions,
+    ) -> Result<ClusterClient, ClientError> {
+        options.client.readonly = options.read_from_replicas;
+        let client = ClusterClient {
+            inner: Arc::new(Inner {
+                seeds: seeds.into_iter().map(Into::into).collect(),
+                options,
+                map: RwLock::new(SlotMap::default()),
+                nodes: Mutex::new(HashMap::new()),
+                refreshing: AtomicBool::new(false),
+                next_replica: AtomicUsize::new(0),
+            }),
+        };
+        client.refresh_slots().await?;
+        Ok(client)
+    }
+
+    /// Send a command to the node serving its keys and return its reply
+    /// as is, error replies included.
+    pub async fn send(&self, cmd: Cmd) -> Result<RespFrame, ClientError> {
+        let keys = key_positions(cmd.parts());
+        let mut groups = group_by_slot(cmd.parts(), &keys);
+        if groups.len() > 1 {
+            if let Some(name) = splittable(cmd.name()) {
+                return self.send_split(name, cmd, groups).await;
+            }
+        }
+        let slot = groups.pop().map(|(slot, _)| slot);
+        self.route(slot, cmd).await
+    }
+
+    /// Send a command and convert its reply, an error reply is returned as
+    /// `ClientError::Server`.
+    pub async fn query<T: FromRespFrame>(&self, cmd: Cmd) -> Result<T, ClientError> {
+        let reply = check(self.send(cmd).await?)?;
+        Ok(T::from_resp_frame(reply)?)
+    }
+
+    /// Reload the slot map from the first node that answers, known masters
+    /// first and then the seeds.
+    pub async fn refresh_slots(&self) -> Result<(), ClientError> {
+        let inner = &self.inner;
+        let mut candidates: Vec<ConnectAddr> = {
+            let map = inner.map.read().unwrap();
+            map.shards
+                .iter()
+                .map(|s| ConnectAddr::from(s.master.as_str()))
+                .collect()
+        };
+        candidates.extend(inner.seeds.iter().cloned());
+        let mut last = ClientError::Disconnected("no seed node".to_string());
+        for addr in candidates {
+            match self.load_slots(&addr).await {
+                Ok(map) => {
+                    *inner.map.write().unwrap() = map;
+                    return Ok(());
+                }
+                Err(e) => {
+                    warn!("Loading the slot map from {} failed: {}", addr, e);
+                    last = e;
+                }
+            }
+        }
+        Err(last)
+    }
+
+    async fn load_slots(&self, addr: &ConnectAddr) -> Result<SlotMap, ClientError> {
+        let client = self.node(&addr.to_string()).await?;
+        let host = match addr {
+            ConnectAddr::Tcp(addr) => addr.rsplit_once(':').map_or(addr.as_str(), |(h, _)| h),
+            #[cfg(unix)]
+            ConnectAddr::Unix(_) => "",
+        };
+        match client.send(cmd("CLUSTER").arg("SHARDS")).await? {
+            RespFrame::SimpleError(_) | RespFrame::BulkError(_) => {
+                let reply = client.query(cmd("CLUSTER").arg("SLOTS")).await?;
+                parse_slots(reply, host)
+            }
+            reply => parse_shards(reply, host),
+        }
+    }
+
+    // in the background, unless a refresh is already going on
+    fn schedule_refresh(&self) {
+        if self.inner.refreshing.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let client = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = client.refresh_slots().await {
+                warn!("Refreshing the slot map failed: {}", e);
+            }
+            client.inner.refreshing.store(false, Ordering::Release);
+        });
+    }
+
+    async fn route(&self, slot: Option<u16>, cmd: Cmd) -> Result<RespFrame, ClientError> {
+        let read = self.inner.options.read_from_replicas && is_read_only(cmd.name());
+        let mut addr = self.pick(slot, read)?;
+        let mut asking = false;
+        let mut reply = None;
+        for _ in 0..=self.inner.options.max_redirects {
+            let client = self.node(&addr).await?;
+            let frame = if asking {
+                let mut replies = client
+                    .pipeline(vec![super::cmd("ASKING"), cmd.clone()])
+                    .await?;
+                replies.pop().ok_or(ClientError::Closed)?
+            } else {
+                match client.send(cmd.clone()).await {
+                    Ok(frame) => frame,
+                    Err(e @ (ClientError::Disconnected(_) | ClientError::Io(_))) => {
+                        // the node may be gone for good, another may have its slots
+                        self.schedule_refresh();
+                        return Err(e);
+                    }
+                    Err(e) => return Err(e),
+                }
+            };
+            asking = false;
+            match redirect(&frame) {
+                Some(Redirect::Moved(slot, to)) => {
+                    info!("Slot {} moved to {}", slot, to);
+                    self.inner.map.write().unwrap().moved(slot, &to);
+                    self.schedule_refresh();
+                    addr = to;
+                }
+                Some(Redirect::Ask(to)) => {
+                    addr = to;
+                    asking = true;
+                }
+                Some(Redirect::TryAgain) => time::sleep(Duration::from_millis(10)).await,
+                None => return Ok(frame),
+            }
+            reply = Some(frame);
+        }
+        reply.ok_or(ClientError::Closed)
+    }
+
+    // the node for a slot, a replica for reads when asked to
+    fn pick(&self, slot: Option<u16>, read: bool) -> Result<String, ClientError> {
+        let map = self.inner.map.read().unwrap();
+        let shard = match slot {
+            Some(slot) => map.shard(slot),
+            None => map.shards.first(),
+        };
+        let Some(shard) = shard else {
+            return Err(ClientError::Server(match slot {
+                Some(slot) => format!("CLUSTERDOWN Hash slot {} not served", slot),
+                None => "CLUSTERDOWN No node known".to_string(),
+            }));
+        };
+        if read && !shard.replicas.is_empty() {
+            let next = self.inner.next_replica.fetch_add(1, Ordering::Relaxed);
+            return Ok(shard.replicas[next % shard.replicas.len()].clone());
+        }
+        Ok(shard.master.clone())
+    }
+
+    async fn node(&self, addr: &str) -> Result<Client, ClientError> {
+        if let Some(client) = self.inner.nodes.lock().unwrap().get(addr) {
+            return Ok(client.clone());
+        }
+        let client = Client::connect_with(addr, self.inner.options.client.clone()).await?;
+        let mut nodes = self.inner.nodes.lock().unwrap();
+        Ok(nodes.entry(addr.to_string()).or_insert(client).clone())
+    }
+
+    // one command per slot, run concurrently, and their replies merged
+    async fn send_split(
+        &self,
+        name: &'static str,
+        cmd: Cmd,
+        groups: Vec<(u16, Vec<usize>)>,
+    ) -> Result<RespFrame, ClientError> {
+        let parts = cmd.parts();
+        // MSET takes a value after each key, there's nothing to split without
+        if name == "MSET" && parts.len().is_multiple_of(2) {
+            return Ok(SimpleError::new("ERR wrong number of arguments for 'mset' command").into());
+        }
+        let tasks = groups
+            .iter()
+            .map(|(slot, keys)| {
+                let mut sub = super::cmd(name);
+                for &i in keys {
+                    sub = sub.arg(&parts[i]);
+                    if name == "MSET" {
+                        sub = sub.arg(&parts[i + 1]);
+                    }
+                }
+                let (client, slot) = (self.clone(), *slot);
+                tokio::spawn(async move { client.route(Some(slot), sub).await })
+            })
+            .collect::<Vec<_>>();
+
+        let mut values = vec![RespFrame::from(BulkString::Null); parts.len() - 1];
+        let mut count = 0;
+        for ((_, keys), task) in groups.iter().zip(tasks) {
+            let reply = task
+                .await
+                .map_err(|e| ClientError::Disconnected(e.to_string()))??;
+            match reply {
+                RespFrame::SimpleError(_) | RespFrame::BulkError(_) => return Ok(reply),
+                RespFrame::Integer(n) => count += n,
+                RespFrame::Array(RespArray::Array(replies)) => {
+                    for (&i, value) in keys.iter().zip(replies) {
+                        values[i - 1] = value;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(match name {
+            "MGET" => RespArray::new(values).into(),
+            "MSET" => SimpleString::new("OK").into(),
+            _ => RespFrame::Integer(count),
+        })
+    }
+}
+
+// where the keys are among a command's parts, the name at 0
+fn key_positions(parts: &[Bytes]) -> Vec<usize> {
+    let name = parts[0].to_ascii_uppercase();
+    match name.as_slice() {
+        b"MGET" | b"DEL" | b"UNLINK" | b"EXISTS" | b"TOUCH" | b"WATCH" => {
+            (1..parts.len()).collect()
+        }
+        b"MSET" | b"MSETNX" => (1..parts.len()).step_by(2).collect(),
+        b"EVAL" | b"EVALSHA" | b"EVAL_RO" | b"EVALSHA_RO" | b"FCALL" | b"FCALL_RO" => {
+            let numkeys = parts
+                .get(2)
+                .and_then(|n| std::str::from_utf8(n).ok()?.parse::<usize>().ok())
+                .unwrap_or(0);
+            (3..(3 + numkeys).min(parts.len())).collect()
+        }
+        b"PING" | b"ECHO" | b"HELLO" | b"AUTH" | b"CLUSTER" | b"CONFIG" | b"SCRIPT"
+        | b"FUNCTION" | b"CLIENT" | b"COMMAND" | b"PUBLISH" | b"PUBSUB" | b"INFO" | b"WASM"
+        | b"MULTI" | b"EXEC" | b"DISCARD" | b"UNWATCH" | b"READONLY" | b"READWRITE" => Vec::new(),
+        _ if parts.len() > 1 => vec![1],
+        _ => Vec::new(),
+    }
+}
+
+// the key positions by slot, in the order the slots first appear
+fn group_by_slot(parts: &[Bytes], keys: &[usize]) -> Vec<(u16, Vec<usize>)> {
+    let mut groups: Vec<(u16, Vec<usize>)> = Vec::new();
+    for &i in keys {
+        let slot = key_hash_slot(&parts[i]);
+        match groups.iter_mut().find(|(s, _)| *s == slot) {
+            Some((_, keys)) => keys.push(i),
+            None => groups.push((slot, vec![i])),
+        }
+    }
+    groups
+}
+
+fn splittable(name: &[u8]) -> Option<&'static str> {
+    let name = name.to_ascii_uppercase();
+    ["MGET", "MSET", "DEL", "EXISTS", "UNLINK", "TOUCH"]
+        .into_iter()
+        .find(|n| n.as_bytes() == name.as_slice())
+}
+
+fn is_read_only(name: &[u8]) -> bool {
+    let name = name.to_ascii_uppercase();
+    matches!(
+        name.as_slice(),
+        b"GET"
+            | b"MGET"
+            | b"HGET"
+            | b"HMGET"
+            | b"HGETALL"
+            | b"SISMEMBER"
+            | b"EXISTS"
+            | b"STRLEN"
+            | b"TTL"
+            | b"TYPE"
+            | b"EVAL_RO"
+            | b"EVALSHA_RO"
+            | b"FCALL_RO"
+    )
+}
+
+fn redirect(frame: &RespFrame) -> Option<Redirect> {
+    let RespFrame::SimpleError(e) = frame else {
+        return None;
+    };
+    let mut words = e.0.split_whitespace();
+    match words.next()? {
+        "MOVED" => {
+            let slot = words.next()?.parse().ok()?;
+            Some(Redirect::Moved(slot, words.next()?.to_string()))
+        }
+        "ASK" => Some(Redirect::Ask(words.nth(1)?.to_string())),
+        "TRYAGAIN" => Some(Redirect::TryAgain),
+        _ => None,
+    }
+}
+
+// an empty host means the node that answered
+fn node_addr(host: &str, port: i64, default_host: &str) -> String {
+    let host = match host {
+        "" | "?" => default_host,
+        host => host,
+    };
+    format!("{}:{}", host, port)
+}
+
+// [[start, end, [host, port, id, ...], replica...], ...]
+fn parse_slots(reply: Vec<Vec<RespFrame>>, default_host: &str) -> Result<SlotMap, ClientError> {
+    let mut map = SlotMap::default();
+    for range in reply {
+        let mut range = range.into_iter();
+        let (Some(start), Some(end)) = (range.next(), range.next()) else {
+            continue;
+        };
+        let mut nodes = range.map(|node| -> Result<String, ClientError> {
+            let mut node = Vec::<RespFrame>::from_resp_frame(node)?.into_iter();
+            let (Some(host), Some(port)) = (node.next(), node.next()) else {
+                return Err(RespError::Conversion("node without an address".to_string()).into());
+            };
+            let (host, port) = (String::from_resp_frame(host)?, i64::from_resp_frame(port)?);
+            Ok(node_addr(&host, port, default_host))
+        });
+        let Some(master) = nodes.next() else {
+            continue;
+        };
+        let shard = Shard {
+            master: master?,
+            replicas: nodes.collect::<Result<_, _>>()?,
+        };
+        map.assign(
+            u16::from_resp_frame(start)?,
+            u16::from_resp_frame(end)?,
+            shard,
+        );
+    }
+    Ok(map)
+}
+
+// [{slots: [start, end, ...], nodes: [{endpoint, ip, port, role, health}, ...]}, ...]
+fn parse_shards(reply: RespFrame, default_host: &str) -> Result<SlotMap, ClientError> {
+    let mut map = SlotMap::default();
+    for shard in Vec::<HashMap<String, RespFrame>>::from_resp_frame(reply)? {
+        let field = |name: &str| {
+            shard
+                .get(name)
+                .cloned()
+                .unwrap_or(RespFrame::Array(RespArray::Null))
+        };
+        let slots = Vec::<u16>::from_resp_frame(field("slots"))?;
+        let mut master = None;
+        let mut replicas = Vec::new();
+        for node in Vec::<HashMap<String, RespFrame>>::from_resp_frame(field("nodes"))? {
+            let text = |name: &str| {
+                node.get(name)
+                    .cloned()
+                    .and_then(|f| Option::<String>::from_resp_frame(f).ok().flatten())
+                    .unwrap_or_default()
+            };
+            let Some(port) = node
+                .get("port")
+                .and_then(|p| i64::from_resp_frame(p.clone()).ok())
+            else {
+                continue;
+            };
+            let host = match text("endpoint") {
+                host if host.is_empty() || host == "?" => text("ip"),
+                host => host,
+            };
+            let addr = node_addr(&host, port, default_host);
+            match text("role").as_str() {
+                "master" => master = Some(addr),
+                _ if text("health") == "online" => replicas.push(addr),
+                _ => {}
+            }
+        }
+        let Some(master) = master else {
+            continue;
+        };
+        let shard = Shard { master, replicas };
+        for range in slots.chunks_exact(2) {
+            map.assign(range[0], range[1], shard.clone());
+        }
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use futures::SinkExt;
+    use tokio::net::TcpListener;
+    use tokio_stream::StreamExt;
+    use tokio_util::codec::Framed;
+
+    use super::*;
+    use crate::{client::ClientCodec, RespMap, SimpleError};
+
+    // slots 0-8191 on node 0, the rest on node 1, node 2 replicates node 0
+    #[derive(Debug, Default)]
+    struct FakeCluster {
+        addrs: Vec<String>,
+        // master for slots below 8192, and for the others
+        owners: Mutex<[usize; 2]>,
+        stores: Mutex<HashMap<usize, HashMap<Bytes, Bytes>>>,
+        // (slot, from, to) for a slot being migrated
+        migrating: Mutex<Option<(u16, usize, usize)>>,
+        replica_reads: AtomicUsize,
+    }
+
+    impl FakeCluster {
+        async fn start() -> Arc<FakeCluster> {
+            let mut listeners = Vec::new();
+            let mut addrs = Vec::new();
+            for _ in 0..3 {
+                let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+                addrs.push(listener.local_addr().unwrap().to_string());
+                listeners.push(listener);
+            }
+            let cluster = Arc::new(FakeCluster {
+                addrs,
+                owners: Mutex::new([0, 1]),
+                ..Default::default()
+            });
+            for (node, listener) in listeners.into_iter().enumerate() {
+                let cluster = cluster.clone();
+                tokio::spawn(async move {
+                    while let Ok((stream, _)) = listener.accept().await {
+                        let cluster = cluster.clone();
+                        tokio::spawn(async move {
+                            let mut framed = Framed::new(stream, ClientCodec::new());
+                            let (mut asking, mut readonly) = (false, false);
+                            while let Some(Ok(RespFrame::Array(RespArray::Array(args)))) =
+                                framed.next().await
+                            {
+                                let args = args
+                                    .into_iter()
+                                    .map(|a| Bytes::from_resp_frame(a).unwrap())
+                                    .collect::<Vec<_>>();
+                                let reply = cluster.handle(node, &args, &mut asking, &mut readonly);
+                                framed.send(reply).await.unwrap();
+                            }
+                        });
+                    }
+                });
+            }
+            cluster
+        }
+
+        fn owner(&self, slot: u16) -> usize {
+            self.owners.lock().unwrap()[(slot >= 8192) as usize]
+        }
+
+        fn store(&self, node: usize) -> HashMap<Bytes, Bytes> {
+            self.stores
+                .lock()
+                .unwrap()
+                .get(&node)
+                .cloned()
+                .unwrap_or_default()
+        }
+
+        fn handle(
+            &self,
+            node: usize,
+            args: &[Bytes],
+            asking: &mut bool,
+            readonly: &mut bool,
+        ) -> RespFrame {
+            let ok = || SimpleString::new("OK").into();
+            let name = args[0].to_ascii_uppercase();
+            match name.as_slice() {
+                b"HELLO" => return ok(),
+                b"READONLY" => {
+                    *readonly = true;
+                    return ok();
+                }
+                b"ASKING" => {
+                    *asking = true;
+                    return ok();
+                }
+                b"CLUSTER" if args[1].eq_ignore_ascii_case(b"SLOTS") => {
+                    let owners = *self.owners.lock().unwrap();
+                    let node = |i: usize| {
+                        let (host, port) = self.addrs[i].rsplit_once(':').unwrap();
+                        RespArray::new(vec![
+                            BulkString::from(host).into(),
+                            RespFrame::Integer(port.parse().unwrap()),
+                        ])
+                        .into()
+                    };
+                    let mut low = vec![
+                        RespFrame::Integer(0),
+                        RespFrame::Integer(8191),
+                        node(owners[0]),
+                    ];
+                    if owners[0] == 0 {
+                        low.push(node(2));
+                    }
+                    let high = vec![
+                        RespFrame::Integer(8192),
+                        RespFrame::Integer(16383),
+                        node(owners[1]),
+                    ];
+                    return RespArray::new(vec![
+                        RespArray::new(low).into(),
+                        RespArray::new(high).into(),
+                    ])
+                    .into();
+                }
+                b"CLUSTER" => return SimpleError::new("ERR unknown subcommand").into(),
+                _ => {}
+            }
+
+            let slot = key_hash_slot(&args[1]);
+            let asked = std::mem::take(asking);
+            let owner = self.owner(slot);
+            let migrating = *self.migrating.lock().unwrap();
+            let serving = match migrating {
+                Some((s, from, to))
+                    if s == slot && node == from && !self.store(from).contains_key(&args[1]) =>
+                {
+                    return SimpleError::new(format!("ASK {} {}", slot, self.addrs[to])).into();
+                }
+                Some((s, _, to)) if s == slot && node == to && asked => to,
+                _ if node == owner => owner,
+                _ if node == 2 && owner == 0 && *readonly && name == b"GET" => {
+                    self.replica_reads.fetch_add(1, Ordering::Relaxed);
+                    0
+                }
+                _ => {
+                    return SimpleError::new(format!("MOVED {} {}", slot, self.addrs[owner])).into()
+                }
+            };
+            let mut stores = self.stores.lock().unwrap();
+            let store = stores.entry(serving).or_default();
+            match name.as_slice() {
+                b"GET" => match store.get(&args[1]) {
+                    Some(v) => BulkString::new(v.clone()).into(),
+                    None => BulkString::Null.into(),
+                },
+                b"SET" => {
+                    store.insert(args[1].clone(), args[2].clone());
+                    ok()
+                }
+                b"MGET" => RespArray::new(
+                    args[1..]
+                        .iter()
+                        .map(|k| match store.get(k) {
+                            Some(v) => BulkString::new(v.clone()).into(),
+                            None => BulkString::Null.into(),
+                        })
+                        .collect::<Vec<_>>(),
+                )
+                .into(),
+                b"MSET" => {
+                    for pair in args[1..].chunks(2) {
+                        store.insert(pair[0].clone(), pair[1].clone());
+                    }
+                    ok()
+                }
+                b"DEL" => RespFrame::Integer(
+                    args[1..]
+                        .iter()
+                        .filter(|k| store.remove(*k).is_some())
+                        .count() as i64,
+                ),
+                _ => SimpleError::new("ERR unknown command").into(),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cluster_routes_and_splits_by_slot() -> Result<(), ClientError> {
+        let cluster = FakeCluster::start().await;
+        let client = ClusterClient::connect([cluster.addrs[0].as_str()]).await?;
+
+        // "foo" is in slot 12182, "bar" in 5061
+        client
+            .query::<String>(cmd("MSET").args(["foo", "1", "bar", "2"]))
+            .await?;
+        assert_eq!(cluster.store(1).get(&b"foo"[..]), Some(&Bytes::from("1")));
+        assert_eq!(cluster.store(0).get(&b"bar"[..]), Some(&Bytes::from("2")));
+
+        let values: Vec<Option<String>> = client
+            .query(cmd("MGET").args(["bar", "missing", "foo", "{bar}x"]))
+            .await?;
+        assert_eq!(
+            values,
+            [Some("2".to_string()), None, Some("1".to_string()), None]
+        );
+        assert_eq!(
+            client.query::<i64>(cmd("DEL").args(["foo", "bar"])).await?,
+            2
+        );
+
+        // a key without its value, across slots
+        let err = client
+            .query::<String>(cmd("MSET").args(["foo", "1", "bar"]))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ClientError::Server(ref e) if e.contains("wrong number of arguments")),
+            "{:?}",
+            err
+        );
+        assert_eq!(cluster.store(0).get(&b"bar"[..]), None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cluster_follows_redirections() -> Result<(), ClientError> {
+        let cluster = FakeCluster::start().await;
+        let client = ClusterClient::connect([cluster.addrs[0].as_str()]).await?;
+        client
+            .query::<String>(cmd("SET").arg("foo").arg("v"))
+            .await?;
+
+        // the upper slots move to node 0, the client still thinks node 1
+        let moved = cluster.stores.lock().unwrap().remove(&1).unwrap();
+        cluster
+            .stores
+            .lock()
+            .unwrap()
+            .entry(0)
+            .or_default()
+            .extend(moved);
+        cluster.owners.lock().unwrap()[1] = 0;
+        assert_eq!(client.query::<String>(cmd("GET").arg("foo")).await?, "v");
+        time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(client.pick(Some(12182), false)?, cluster.addrs[0]);
+
+        // "bar" half way to node 1: asked there, without the map changing
+        let slot = key_hash_slot(b"bar");
+        cluster
+            .stores
+            .lock()
+            .unwrap()
+            .entry(1)
+            .or_default()
+            .insert("bar".into(), "w".into());
+        *cluster.migrating.lock().unwrap() = Some((slot, 0, 1));
+        assert_eq!(client.query::<String>(cmd("GET").arg("bar")).await?, "w");
+        assert_eq!(client.pick(Some(slot), false)?, cluster.addrs[0]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cluster_reads_from_replicas() -> Result<(), ClientError> {
+        let cluster = FakeCluster::start().await;
+        let options = ClusterOptions {
+            read_from_replicas: true,
+            ..Default::default()
+        };
+        let client = ClusterClient::connect_with([cluster.addrs[0].as_str()], options).await?;
+        client
+            .query::<String>(cmd("SET").arg("bar").arg("v"))
+            .await?;
+        assert_eq!(client.query::<String>(cmd("GET").arg("bar")).await?, "v");
+        assert_eq!(cluster.replica_reads.load(Ordering::Relaxed), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_shards() -> Result<(), ClientError> {
+        let node = |endpoint: &str, port: i64, role: &str| -> RespFrame {
+            let mut node = RespMap::new();
+            node.insert(
+                BulkString::from("endpoint").into(),
+                BulkString::from(endpoint).into(),
+            );
+            node.insert(
+                BulkString::from("ip").into(),
+                BulkString::from("10.0.0.1").into(),
+            );
+            node.insert(BulkString::from("port").into(), RespFrame::Integer(port));
+            node.insert(
+                BulkString::from("role").into(),
+                BulkString::from(role).into(),
+            );
+            node.insert(
+                BulkString::from("health").into(),
+                BulkString::from("online").into(),
+            );
+            node.into()
+        };
+        let mut shard = RespMap::new();
+        shard.insert(
+            BulkString::from("slots").into(),
+            RespArray::new(vec![
+                RespFrame::Integer(0),
+                RespFrame::Integer(99),
+                RespFrame::Integer(200),
+                RespFrame::Integer(299),
+            ])
+            .into(),
+        );
+        shard.insert(
+            BulkString::from("nodes").into(),
+            RespArray::new(vec![
+                node("", 7001, "replica"),
+                node("a.example", 7000, "master"),
+            ])
+            .into(),
+        );
+        let map = parse_shards(RespArray::new(vec![shard.into()]).into(), "seed")?;
+        let expected = Shard {
+            master: "a.example:7000".to_string(),
+            replicas: vec!["10.0.0.1:7001".to_string()],
+        };
+        assert_eq!(map.shard(50), Some(&expected));
+        assert_eq!(map.shard(250), Some(&expected));
+        assert_eq!(map.shard(150), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_positions() {
+        let parts = |cmd: Cmd| key_positions(cmd.parts());
+        assert_eq!(parts(cmd("GET").arg("k")), [1]);
+        assert_eq!(parts(cmd("MSET").args(["a", "1", "b", "2"])), [1, 3]);
+        assert_eq!(
+            parts(cmd("EVAL").args(["return 1", "2", "a", "b", "x"])),
+            [3, 4]
+        );
+        assert!(parts(cmd("PING")).is_empty());
+    }
+}
@@ -0,0 +1,161 @@
+use bytes::Bytes;
+
+use crate::{BulkString, RespArray, RespFrame};
+
+/// A command to send, its name and arguments as bulk strings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cmd {
+    args: Vec<Bytes>,
+}
+
+/// Start a command, `cmd("SET").arg("key").arg(1)`.
+pub fn cmd(name: &str) -> Cmd {
+    Cmd::new(name)
+}
+
+impl Cmd {
+    pub fn new(name: &str) -> Self {
+        Self {
+            args: vec![Bytes::copy_from_slice(name.as_bytes())],
+        }
+    }
+
+    pub fn arg(mut self, arg: impl IntoArg) -> Self {
+        self.args.push(arg.into_arg());
+        self
+    }
+
+    pub fn args<T: IntoArg>(mut self, args: impl IntoIterator<Item = T>) -> Self {
+        self.args.extend(args.into_iter().map(IntoArg::into_arg));
+        self
+    }
+
+    /// The command name, as given.
+    pub fn name(&self) -> &[u8] {
+        &self.args[0]
+    }
+
+    /// The name and arguments.
+    pub fn parts(&self) -> &[Bytes] {
+        &self.args
+    }
+
+    pub fn into_frame(self) -> RespFrame {
+        RespArray::new(
+            self.args
+                .into_iter()
+                .map(|arg| BulkString::new(arg).into())
+                .collect::<Vec<_>>(),
+        )
+        .into()
+    }
+}
+
+impl From<Cmd> for RespFrame {
+    fn from(cmd: Cmd) -> Self {
+        cmd.into_frame()
+    }
+}
+
+/// What can be sent as a command argument. Everything goes out as a bulk
+/// string, numbers in their decimal form.
+pub trait IntoArg {
+    fn into_arg(self) -> Bytes;
+}
+
+impl IntoArg for Bytes {
+    fn into_arg(self) -> Bytes {
+        self
+    }
+}
+
+impl IntoArg for &Bytes {
+    fn into_arg(self) -> Bytes {
+        self.clone()
+    }
+}
+
+impl IntoArg for Vec<u8> {
+    fn into_arg(self) -> Bytes {
+        self.into()
+    }
+}
+
+impl IntoArg for &[u8] {
+    fn into_arg(self) -> Bytes {
+        Bytes::copy_from_slice(self)
+    }
+}
+
+impl<const N: usize> IntoArg for &[u8; N] {
+    fn into_arg(self) -> Bytes {
+        Bytes::copy_from_slice(self)
+    }
+}
+
+impl IntoArg for String {
+    fn into_arg(self) -> Bytes {
+        self.into()
+    }
+}
+
+impl IntoArg for &String {
+    fn into_arg(self) -> Bytes {
+        Bytes::copy_from_slice(self.as_bytes())
+    }
+}
+
+impl IntoArg for &str {
+    fn into_arg(self) -> Bytes {
+        Bytes::copy_from_slice(self.as_bytes())
+    }
+}
+
+macro_rules! integer_arg {
+    ($($t:ty),*) => {
+        $(
+            impl IntoArg for $t {
+                fn into_arg(self) -> Bytes {
+                    Bytes::copy_from_slice(itoa::Buffer::new().format(self).as_bytes())
+                }
+            }
+        )*
+    };
+}
+
+integer_arg!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+// infinities the way Redis spells them
+impl IntoArg for f64 {
+    fn into_arg(self) -> Bytes {
+        match self {
+            f64::INFINITY => Bytes::from_static(b"+inf"),
+            f64::NEG_INFINITY => Bytes::from_static(b"-inf"),
+            f => f.to_string().into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespEncode;
+
+    #[test]
+    fn test_cmd_into_frame() {
+        let cmd = cmd("HSET")
+            .arg("user:1")
+            .args(
+                [("age", 30)]
+                    .iter()
+                    .flat_map(|(f, v)| [f.into_arg(), v.into_arg()]),
+            )
+            .arg(b"\xff")
+            .arg(f64::NEG_INFINITY);
+        assert_eq!(cmd.name(), b"HSET");
+        assert_eq!(
+            cmd.into_frame().encode(),
+            b"*6\r\n$4\r\nHSET\r\n$6\r\nuser:1\r\n$3\r\nage\r\n$2\r\n30\r\n$1\r\n\xff\r\n$4\r\n-inf\r\n"
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use bytes::BytesMut;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{DecodeLimits, RespDecoder, RespEncode, RespError, RespFrame};
+
+use super::ClientError;
+
+/// The client side of the RESP codec: commands out, replies in. Unlike the
+/// server's, it never reads inline commands, every reply is a RESP frame.
+#[derive(Debug, Default)]
+pub struct ClientCodec {
+    decoder: RespDecoder,
+}
+
+impl ClientCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limits(limits: DecodeLimits) -> Self {
+        Self {
+            decoder: RespDecoder::with_limits(limits),
+        }
+    }
+}
+
+impl Encoder<RespFrame> for ClientCodec {
+    type Error = ClientError;
+
+    fn encode(&mut self, item: RespFrame, dst: &mut BytesMut) -> Result<(), ClientError> {
+        dst.reserve(item.encoded_len());
+        item.encode_to(dst);
+        Ok(())
+    }
+}
+
+impl Decoder for ClientCodec {
+    type Item = RespFrame;
+    type Error = ClientError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<RespFrame>, ClientError> {
+        match self.decoder.decode(src) {
+            Err(RespError::NotComplete) => Ok(None),
+            frame => Ok(frame?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BulkString, RespArray, SimpleString};
+
+    #[test]
+    fn test_client_codec() -> Result<(), ClientError> {
+        let mut codec = ClientCodec::new();
+        let mut buf = BytesMut::new();
+        codec.encode(
+            RespArray::new([BulkString::from("ping").into()]).into(),
+            &mut buf,
+        )?;
+        assert_eq!(&buf[..], b"*1\r\n$4\r\nping\r\n");
+
+        // replies that would be inline commands on the server side
+        let mut buf = BytesMut::from(&b"+PONG\r\n:1"[..]);
+        assert_eq!(
+            codec.decode(&mut buf)?,
+            Some(SimpleString::new("PONG").into())
+        );
+        assert_eq!(codec.decode(&mut buf)?, None);
+        buf.extend_from_slice(b"\r\n");
+        assert_eq!(codec.decode(&mut buf)?, Some(RespFrame::Integer(1)));
+
+        // a malformed reply is an error, not a panic in the reader task
+        let mut buf = BytesMut::from(&b"*1\r\n\r\n"[..]);
+        assert!(codec.decode(&mut buf).is_err());
+        Ok(())
+    }
+}
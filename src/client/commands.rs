@@ -0,0 +1,604 @@
+use bytes::Bytes;
+
+use crate::{FromRespFrame, RespFrame};
+
+use super::{check, cmd, Client, ClientError, Cmd, Connection, IntoArg};
+
+/// What FUNCTION RESTORE does with the libraries already loaded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RestorePolicy {
+    /// Fail if a restored library is already loaded.
+    #[default]
+    Append,
+    /// Replace the loaded libraries of the same name.
+    Replace,
+    /// Delete every loaded library first.
+    Flush,
+}
+
+// typed helpers for the commands the server supports, anything else goes
+// through `query` or `send`; those changing the state of the connection are
+// on `Connection` below
+impl Client {
+    pub async fn ping(&self) -> Result<String, ClientError> {
+        self.query(cmd("PING")).await
+    }
+
+    pub async fn echo<T: FromRespFrame>(&self, message: impl IntoArg) -> Result<T, ClientError> {
+        self.query(cmd("ECHO").arg(message)).await
+    }
+
+    pub async fn get<T: FromRespFrame>(&self, key: impl IntoArg) -> Result<T, ClientError> {
+        self.query(cmd("GET").arg(key)).await
+    }
+
+    pub async fn set(&self, key: impl IntoArg, value: impl IntoArg) -> Result<(), ClientError> {
+        self.ok(cmd("SET").arg(key).arg(value)).await
+    }
+
+    pub async fn hget<T: FromRespFrame>(
+        &self,
+        key: impl IntoArg,
+        field: impl IntoArg,
+    ) -> Result<T, ClientError> {
+        self.query(cmd("HGET").arg(key).arg(field)).await
+    }
+
+    pub async fn hset(
+        &self,
+        key: impl IntoArg,
+        field: impl IntoArg,
+        value: impl IntoArg,
+    ) -> Result<(), ClientError> {
+        self.ok(cmd("HSET").arg(key).arg(field).arg(value)).await
+    }
+
+    pub async fn hgetall<T: FromRespFrame>(&self, key: impl IntoArg) -> Result<T, ClientError> {
+        self.query(cmd("HGETALL").arg(key)).await
+    }
+
+    pub async fn hmget<F: IntoArg, T: FromRespFrame>(
+        &self,
+        key: impl IntoArg,
+        fields: impl IntoIterator<Item = F>,
+    ) -> Result<T, ClientError> {
+        self.query(cmd("HMGET").arg(key).args(fields)).await
+    }
+
+    /// Returns the number of members added.
+    pub async fn sadd<M: IntoArg>(
+        &self,
+        key: impl IntoArg,
+        members: impl IntoIterator<Item = M>,
+    ) -> Result<i64, ClientError> {
+        self.query(cmd("SADD").arg(key).args(members)).await
+    }
+
+    pub async fn sismember(
+        &self,
+        key: impl IntoArg,
+        member: impl IntoArg,
+    ) -> Result<bool, ClientError> {
+        self.query(cmd("SISMEMBER").arg(key).arg(member)).await
+    }
+
+    /// Returns the number of subscribers that got the message.
+    pub async fn publish(
+        &self,
+        channel: impl IntoArg,
+        message: impl IntoArg,
+    ) -> Result<i64, ClientError> {
+        self.query(cmd("PUBLISH").arg(channel).arg(message)).await
+    }
+
+    pub async fn spublish(
+        &self,
+        channel: impl IntoArg,
+        message: impl IntoArg,
+    ) -> Result<i64, ClientError> {
+        self.query(cmd("SPUBLISH").arg(channel).arg(message)).await
+    }
+
+    /// Active channels matching the pattern, `*` for all of them.
+    pub async fn pubsub_channels<T: FromRespFrame>(
+        &self,
+        pattern: impl IntoArg,
+    ) -> Result<T, ClientError> {
+        self.query(cmd("PUBSUB").arg("CHANNELS").arg(pattern)).await
+    }
+
+    /// Each channel with its number of subscribers.
+    pub async fn pubsub_numsub<C: IntoArg, T: FromRespFrame>(
+        &self,
+        channels: impl IntoIterator<Item = C>,
+    ) -> Result<T, ClientError> {
+        self.query(cmd("PUBSUB").arg("NUMSUB").args(channels)).await
+    }
+
+    /// The number of patterns subscribed to, by all clients.
+    pub async fn pubsub_numpat(&self) -> Result<i64, ClientError> {
+        self.query(cmd("PUBSUB").arg("NUMPAT")).await
+    }
+
+    pub async fn pubsub_shardchannels<T: FromRespFrame>(
+        &self,
+        pattern: impl IntoArg,
+    ) -> Result<T, ClientError> {
+        self.query(cmd("PUBSUB").arg("SHARDCHANNELS").arg(pattern))
+            .await
+    }
+
+    pub async fn pubsub_shardnumsub<C: IntoArg, T: FromRespFrame>(
+        &self,
+        channels: impl IntoIterator<Item = C>,
+    ) -> Result<T, ClientError> {
+        self.query(cmd("PUBSUB").arg("SHARDNUMSUB").args(channels))
+            .await
+    }
+
+    pub async fn cluster_keyslot(&self, key: impl IntoArg) -> Result<u16, ClientError> {
+        self.query(cmd("CLUSTER").arg("KEYSLOT").arg(key)).await
+    }
+
+    pub async fn cluster_addslots(
+        &self,
+        slots: impl IntoIterator<Item = u16>,
+    ) -> Result<(), ClientError> {
+        self.ok(cmd("CLUSTER").arg("ADDSLOTS").args(slots)).await
+    }
+
+    pub async fn cluster_delslots(
+        &self,
+        slots: impl IntoIterator<Item = u16>,
+    ) -> Result<(), ClientError> {
+        self.ok(cmd("CLUSTER").arg("DELSLOTS").args(slots)).await
+    }
+
+    /// Parameters matching the pattern, with their values.
+    pub async fn config_get<T: FromRespFrame>(
+        &self,
+        pattern: impl IntoArg,
+    ) -> Result<T, ClientError> {
+        self.query(cmd("CONFIG").arg("GET").arg(pattern)).await
+    }
+
+    pub async fn config_set(
+        &self,
+        parameter: impl IntoArg,
+        value: impl IntoArg,
+    ) -> Result<(), ClientError> {
+        self.ok(cmd("CONFIG").arg("SET").arg(parameter).arg(value))
+            .await
+    }
+
+    pub async fn eval<K: IntoArg, A: IntoArg, T: FromRespFrame>(
+        &self,
+        script: impl IntoArg,
+        keys: Vec<K>,
+        args: impl IntoIterator<Item = A>,
+    ) -> Result<T, ClientError> {
+        self.query(with_keys(cmd("EVAL").arg(script), keys, args))
+            .await
+    }
+
+    pub async fn evalsha<K: IntoArg, A: IntoArg, T: FromRespFrame>(
+        &self,
+        sha: impl IntoArg,
+        keys: Vec<K>,
+        args: impl IntoIterator<Item = A>,
+    ) -> Result<T, ClientError> {
+        self.query(with_keys(cmd("EVALSHA").arg(sha), keys, args))
+            .await
+    }
+
+    pub async fn eval_ro<K: IntoArg, A: IntoArg, T: FromRespFrame>(
+        &self,
+        script: impl IntoArg,
+        keys: Vec<K>,
+        args: impl IntoIterator<Item = A>,
+    ) -> Result<T, ClientError> {
+        self.query(with_keys(cmd("EVAL_RO").arg(script), keys, args))
+            .await
+    }
+
+    pub async fn evalsha_ro<K: IntoArg, A: IntoArg, T: FromRespFrame>(
+        &self,
+        sha: impl IntoArg,
+        keys: Vec<K>,
+        args: impl IntoIterator<Item = A>,
+    ) -> Result<T, ClientError> {
+        self.query(with_keys(cmd("EVALSHA_RO").arg(sha), keys, args))
+            .await
+    }
+
+    /// Returns the script's SHA1.
+    pub async fn script_load(&self, script: impl IntoArg) -> Result<String, ClientError> {
+        self.query(cmd("SCRIPT").arg("LOAD").arg(script)).await
+    }
+
+    pub async fn script_exists<S: IntoArg>(
+        &self,
+        shas: impl IntoIterator<Item = S>,
+    ) -> Result<Vec<bool>, ClientError> {
+        self.query(cmd("SCRIPT").arg("EXISTS").args(shas)).await
+    }
+
+    pub async fn script_flush(&self) -> Result<(), ClientError> {
+        self.ok(cmd("SCRIPT").arg("FLUSH")).await
+    }
+
+    /// Stop the running script. It has to come from another `Client` than
+    /// the one that ran the script, whose replies wait on it.
+    pub async fn script_kill(&self) -> Result<(), ClientError> {
+        self.ok(cmd("SCRIPT").arg("KILL")).await
+    }
+
+    /// Returns the library name.
+    pub async fn function_load(
+        &self,
+        code: impl IntoArg,
+        replace: bool,
+    ) -> Result<String, ClientError> {
+        let mut load = cmd("FUNCTION").arg("LOAD");
+        if replace {
+            load = load.arg("REPLACE");
+        }
+        self.query(load.arg(code)).await
+    }
+
+    pub async fn function_delete(&self, library: impl IntoArg) -> Result<(), ClientError> {
+        self.ok(cmd("FUNCTION").arg("DELETE").arg(library)).await
+    }
+
+    pub async fn function_flush(&self) -> Result<(), ClientError> {
+        self.ok(cmd("FUNCTION").arg("FLUSH")).await
+    }
+
+    /// The libraries whose name matches the pattern, all of them without
+    /// one, with their functions and optionally their code.
+    pub async fn function_list<T: FromRespFrame>(
+        &self,
+        library_pattern: Option<&str>,
+        with_code: bool,
+    ) -> Result<T, ClientError> {
+        let mut list = cmd("FUNCTION").arg("LIST");
+        if let Some(pattern) = library_pattern {
+            list = list.arg("LIBRARYNAME").arg(pattern);
+        }
+        if with_code {
+            list = list.arg("WITHCODE");
+        }
+        self.query(list).await
+    }
+
+    /// Every library, serialized for `function_restore`.
+    pub async fn function_dump(&self) -> Result<Bytes, ClientError> {
+        self.query(cmd("FUNCTION").arg("DUMP")).await
+    }
+
+    pub async fn function_restore(
+        &self,
+        payload: impl IntoArg,
+        policy: RestorePolicy,
+    ) -> Result<(), ClientError> {
+        let policy = match policy {
+            RestorePolicy::Append => "APPEND",
+            RestorePolicy::Replace => "REPLACE",
+            RestorePolicy::Flush => "FLUSH",
+        };
+        self.ok(cmd("FUNCTION").arg("RESTORE").arg(payload).arg(policy))
+            .await
+    }
+
+    pub async fn fcall<K: IntoArg, A: IntoArg, T: FromRespFrame>(
+        &self,
+        function: impl IntoArg,
+        keys: Vec<K>,
+        args: impl IntoIterator<Item = A>,
+    ) -> Result<T, ClientError> {
+        self.query(with_keys(cmd("FCALL").arg(function), keys, args))
+            .await
+    }
+
+    pub async fn fcall_ro<K: IntoArg, A: IntoArg, T: FromRespFrame>(
+        &self,
+        function: impl IntoArg,
+        keys: Vec<K>,
+        args: impl IntoIterator<Item = A>,
+    ) -> Result<T, ClientError> {
+        self.query(with_keys(cmd("FCALL_RO").arg(function), keys, args))
+            .await
+    }
+
+    /// Load a WebAssembly module under `name`, its exports becoming
+    /// commands.
+    pub async fn wasm_load(
+        &self,
+        name: impl IntoArg,
+        module: impl IntoArg,
+    ) -> Result<(), ClientError> {
+        self.ok(cmd("WASM").arg("LOAD").arg(name).arg(module)).await
+    }
+
+    pub async fn wasm_list<T: FromRespFrame>(&self) -> Result<T, ClientError> {
+        self.query(cmd("WASM").arg("LIST")).await
+    }
+
+    pub async fn wasm_unload(&self, name: impl IntoArg) -> Result<(), ClientError> {
+        self.ok(cmd("WASM").arg("UNLOAD").arg(name)).await
+    }
+
+    pub async fn command_count(&self) -> Result<i64, ClientError> {
+        self.query(cmd("COMMAND").arg("COUNT")).await
+    }
+
+    /// The specs of the named commands, null for unknown ones.
+    pub async fn command_info<C: IntoArg, T: FromRespFrame>(
+        &self,
+        names: impl IntoIterator<Item = C>,
+    ) -> Result<T, ClientError> {
+        self.query(cmd("COMMAND").arg("INFO").args(names)).await
+    }
+
+    pub async fn command_list(&self) -> Result<Vec<String>, ClientError> {
+        self.query(cmd("COMMAND").arg("LIST")).await
+    }
+
+    pub async fn client_id(&self) -> Result<i64, ClientError> {
+        self.query(cmd("CLIENT").arg("ID")).await
+    }
+
+    pub async fn client_setname(&self, name: impl IntoArg) -> Result<(), ClientError> {
+        self.ok(cmd("CLIENT").arg("SETNAME").arg(name)).await
+    }
+
+    pub async fn client_getname(&self) -> Result<Option<String>, ClientError> {
+        self.query(cmd("CLIENT").arg("GETNAME")).await
+    }
+
+    // commands whose reply only matters when it's an error
+    async fn ok(&self, cmd: Cmd) -> Result<(), ClientError> {
+        check(self.send(cmd).await?).map(|_: RespFrame| ())
+    }
+}
+
+// confirmations of the subscription commands, and the messages after them,
+// come back through `read`
+impl Connection {
+    pub async fn subscribe<C: IntoArg>(
+        &mut self,
+        channels: impl IntoIterator<Item = C>,
+    ) -> Result<(), ClientError> {
+        self.write(cmd("SUBSCRIBE").args(channels)).await
+    }
+
+    /// Unsubscribe from the channels, from all of them if none are given.
+    pub async fn unsubscribe<C: IntoArg>(
+        &mut self,
+        channels: impl IntoIterator<Item = C>,
+    ) -> Result<(), ClientError> {
+        self.write(cmd("UNSUBSCRIBE").args(channels)).await
+    }
+
+    pub async fn psubscribe<P: IntoArg>(
+        &mut self,
+        patterns: impl IntoIterator<Item = P>,
+    ) -> Result<(), ClientError> {
+        self.write(cmd("PSUBSCRIBE").args(patterns)).await
+    }
+
+    pub async fn punsubscribe<P: IntoArg>(
+        &mut self,
+        patterns: impl IntoIterator<Item = P>,
+    ) -> Result<(), ClientError> {
+        self.write(cmd("PUNSUBSCRIBE").args(patterns)).await
+    }
+
+    pub async fn ssubscribe<C: IntoArg>(
+        &mut self,
+        channels: impl IntoIterator<Item = C>,
+    ) -> Result<(), ClientError> {
+        self.write(cmd("SSUBSCRIBE").args(channels)).await
+    }
+
+    pub async fn sunsubscribe<C: IntoArg>(
+        &mut self,
+        channels: impl IntoIterator<Item = C>,
+    ) -> Result<(), ClientError> {
+        self.write(cmd("SUNSUBSCRIBE").args(channels)).await
+    }
+
+    /// Make the next MULTI/EXEC on this connection fail if any of the keys
+    /// changes meanwhile.
+    pub async fn watch<K: IntoArg>(
+        &mut self,
+        keys: impl IntoIterator<Item = K>,
+    ) -> Result<(), ClientError> {
+        self.ok(cmd("WATCH").args(keys)).await
+    }
+
+    pub async fn unwatch(&mut self) -> Result<(), ClientError> {
+        self.ok(cmd("UNWATCH")).await
+    }
+
+    /// Say HELLO again, switching to `protocol`; returns what the server
+    /// says about itself.
+    pub async fn hello<T: FromRespFrame>(&mut self, protocol: u8) -> Result<T, ClientError> {
+        let reply = self.query(cmd("HELLO").arg(protocol)).await?;
+        self.set_protocol(protocol);
+        Ok(reply)
+    }
+
+    /// Turn client side caching on, with options as CLIENT TRACKING takes
+    /// them (`BCAST`, `PREFIX p`, `OPTIN`, `REDIRECT id`...), or off.
+    /// Invalidations come as push messages, or through `read` when
+    /// redirected.
+    pub async fn client_tracking<A: IntoArg>(
+        &mut self,
+        on: bool,
+        options: impl IntoIterator<Item = A>,
+    ) -> Result<(), ClientError> {
+        let on = if on { "ON" } else { "OFF" };
+        self.ok(cmd("CLIENT").arg("TRACKING").arg(on).args(options))
+            .await
+    }
+
+    /// Whether the next command's keys are tracked, in OPTIN or OPTOUT mode.
+    pub async fn client_caching(&mut self, yes: bool) -> Result<(), ClientError> {
+        let yes = if yes { "YES" } else { "NO" };
+        self.ok(cmd("CLIENT").arg("CACHING").arg(yes)).await
+    }
+
+    pub async fn client_trackinginfo<T: FromRespFrame>(&mut self) -> Result<T, ClientError> {
+        self.query(cmd("CLIENT").arg("TRACKINGINFO")).await
+    }
+
+    /// The client invalidations are redirected to, 0 for none and -1 when
+    /// tracking is off.
+    pub async fn client_getredir(&mut self) -> Result<i64, ClientError> {
+        self.query(cmd("CLIENT").arg("GETREDIR")).await
+    }
+
+    async fn ok(&mut self, cmd: Cmd) -> Result<(), ClientError> {
+        check(self.send(cmd).await?).map(|_: RespFrame| ())
+    }
+}
+
+fn with_keys<K: IntoArg, A: IntoArg>(
+    cmd: Cmd,
+    keys: Vec<K>,
+    args: impl IntoIterator<Item = A>,
+) -> Cmd {
+    cmd.arg(keys.len()).args(keys).args(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::client::tests::start_server;
+
+    #[tokio::test]
+    async fn test_client_typed_commands() -> Result<(), ClientError> {
+        let (addr, _) = start_server().await;
+        let client = Client::connect(addr).await?;
+        assert_eq!(client.ping().await?, "PONG");
+        assert_eq!(client.echo::<String>("hi").await?, "hi");
+
+        assert_eq!(client.sadd("s", ["a", "b", "a"]).await?, 2);
+        assert!(client.sismember("s", "a").await?);
+        assert!(!client.sismember("s", "c").await?);
+
+        client.hset("h", "f1", "v1").await?;
+        let values: Vec<Option<String>> = client.hmget("h", ["f1", "f2"]).await?;
+        assert_eq!(values, [Some("v1".to_string()), None]);
+
+        let n: i64 = client
+            .eval("return #KEYS + #ARGV", vec!["k1", "k2"], ["a"])
+            .await?;
+        assert_eq!(n, 3);
+        let sha = client.script_load("return 1").await?;
+        assert_eq!(
+            client.script_exists([sha.as_str(), "nope"]).await?,
+            [true, false]
+        );
+
+        client.client_setname("worker").await?;
+        assert_eq!(client.client_getname().await?.as_deref(), Some("worker"));
+        assert_eq!(
+            client.cluster_keyslot("{user}:1").await?,
+            client.cluster_keyslot("user").await?
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_admin_commands() -> Result<(), ClientError> {
+        let (addr, _) = start_server().await;
+        let client = Client::connect(addr).await?;
+
+        assert_eq!(client.pubsub_numpat().await?, 0);
+        let channels: Vec<String> = client.pubsub_channels("*").await?;
+        assert!(channels.is_empty());
+        let numsub: Vec<RespFrame> = client.pubsub_numsub(["news"]).await?;
+        assert_eq!(numsub.len(), 2);
+
+        client.cluster_delslots([0, 1]).await?;
+        client.cluster_addslots([0, 1]).await?;
+
+        client.set("k", "v").await?;
+        let v: String = client
+            .eval_ro(
+                "return redis.call('GET', KEYS[1])",
+                vec!["k"],
+                Vec::<&str>::new(),
+            )
+            .await?;
+        assert_eq!(v, "v");
+        let err = client
+            .eval_ro::<_, &str, RespFrame>(
+                "return redis.call('SET', 'k', 'w')",
+                Vec::<&str>::new(),
+                [],
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ClientError::Server(_)));
+        assert!(matches!(
+            client.script_kill().await,
+            Err(ClientError::Server(e)) if e.starts_with("NOTBUSY")
+        ));
+
+        client
+            .function_load(
+                "#!lua name=lib\nredis.register_function('f', function() return 1 end)",
+                false,
+            )
+            .await?;
+        let libraries: Vec<RespFrame> = client.function_list(Some("li*"), true).await?;
+        assert_eq!(libraries.len(), 1);
+        let payload = client.function_dump().await?;
+        client
+            .function_restore(payload.clone(), RestorePolicy::Replace)
+            .await?;
+        assert!(client
+            .function_restore(payload, RestorePolicy::Append)
+            .await
+            .is_err());
+
+        let count = client.command_count().await?;
+        assert_eq!(client.command_list().await?.len() as i64, count);
+        let info: Vec<Option<RespFrame>> = client.command_info(["get", "nope"]).await?;
+        assert!(info[0].is_some() && info[1].is_none());
+        let modules: Vec<RespFrame> = client.wasm_list().await?;
+        assert!(modules.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_connection_state_commands() -> Result<(), ClientError> {
+        let (addr, _) = start_server().await;
+        let mut conn = Connection::connect(addr, &Default::default()).await?;
+
+        conn.watch(["k"]).await?;
+        assert!(!conn.is_reusable());
+        conn.unwatch().await?;
+        assert!(conn.is_reusable());
+
+        assert_eq!(conn.client_getredir().await?, -1);
+        conn.client_tracking(true, ["OPTIN"]).await?;
+        conn.client_caching(true).await?;
+        let info: HashMap<String, RespFrame> = conn.client_trackinginfo().await?;
+        assert!(info.contains_key("flags"));
+        assert_eq!(conn.client_getredir().await?, 0);
+        conn.client_tracking(false, Vec::<&str>::new()).await?;
+
+        let _: RespFrame = conn.hello(2).await?;
+        assert_eq!(conn.protocol(), 2);
+        conn.subscribe(["news"]).await?;
+        let confirmation: Vec<RespFrame> = Vec::from_resp_frame(conn.read().await?)?;
+        assert_eq!(confirmation[1], crate::BulkString::from("news").into());
+        Ok(())
+    }
+}
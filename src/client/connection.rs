@@ -0,0 +1,439 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    future::Future,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use futures::SinkExt;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+    sync::{mpsc, oneshot},
+    time,
+};
+use tokio_stream::StreamExt;
+use tokio_util::codec::Framed;
+use tracing::{info, warn};
+
+use crate::{FromRespFrame, RespFrame, RespPush, SimpleError};
+
+use super::{check, cmd, ClientCodec, ClientError, ClientOptions, Cmd, ConnectAddr};
+
+// requests taken off the queue before a flush, so that concurrent callers
+// share a write
+const PIPELINE_BATCH: usize = 128;
+
+pub(super) trait AsyncStream: AsyncRead + AsyncWrite + Send + Unpin + fmt::Debug {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin + fmt::Debug> AsyncStream for T {}
+
+pub(super) type Transport = Framed<Box<dyn AsyncStream>, ClientCodec>;
+
+/// Frames to write back to back, and where their replies go.
+#[derive(Debug)]
+pub(super) struct Request {
+    pub(super) frames: Vec<RespFrame>,
+    pub(super) reply: oneshot::Sender<Result<Vec<RespFrame>, ClientError>>,
+}
+
+#[derive(Debug)]
+struct InFlight {
+    expected: usize,
+    replies: Vec<RespFrame>,
+    reply: oneshot::Sender<Result<Vec<RespFrame>, ClientError>>,
+}
+
+/// A connection of its own, for what can't share one: WATCH, blocking
+/// commands, subscriptions. Unlike `Client` it doesn't reconnect, once a
+/// request on it fails or times out it stays broken.
+#[derive(Debug)]
+pub struct Connection {
+    transport: Transport,
+    protocol: u8,
+    command_timeout: Option<Duration>,
+    pushes: Option<mpsc::Sender<RespPush>>,
+    broken: bool,
+    // left behind by the commands sent, see `is_reusable`
+    in_multi: bool,
+    watching: bool,
+    subscribed: bool,
+}
+
+impl Connection {
+    /// Connect and say HELLO. The reconnect options don't apply.
+    pub async fn connect(
+        addr: impl Into<ConnectAddr>,
+        options: &ClientOptions,
+    ) -> Result<Connection, ClientError> {
+        let (transport, protocol) = connect(&addr.into(), options).await?;
+        Ok(Connection {
+            transport,
+            protocol,
+            command_timeout: options.command_timeout,
+            pushes: options.pushes.clone(),
+            broken: false,
+            in_multi: false,
+            watching: false,
+            subscribed: false,
+        })
+    }
+
+    /// The protocol agreed on with HELLO.
+    pub fn protocol(&self) -> u8 {
+        self.protocol
+    }
+
+    pub(super) fn set_protocol(&mut self, protocol: u8) {
+        self.protocol = protocol;
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.broken
+    }
+
+    /// Whether the connection can be handed to someone else as it is: not
+    /// broken, and not left in a transaction, watching keys or subscribed.
+    pub fn is_reusable(&self) -> bool {
+        !(self.broken || self.in_multi || self.watching || self.subscribed)
+    }
+
+    // follow what the commands sent leave the server side of the connection
+    // in; subscriptions are assumed to last, unsubscribing from everything
+    // can't be told from here
+    fn track(&mut self, cmd: &Cmd) {
+        match cmd.name().to_ascii_uppercase().as_slice() {
+            b"MULTI" => self.in_multi = true,
+            b"EXEC" | b"DISCARD" => (self.in_multi, self.watching) = (false, false),
+            b"WATCH" => self.watching = true,
+            b"UNWATCH" => self.watching = false,
+            b"SUBSCRIBE" | b"PSUBSCRIBE" | b"SSUBSCRIBE" => self.subscribed = true,
+            b"RESET" => (self.in_multi, self.watching, self.subscribed) = (false, false, false),
+            _ => {}
+        }
+    }
+
+    /// Send a command and return its reply as is, error replies included.
+    pub async fn send(&mut self, cmd: Cmd) -> Result<RespFrame, ClientError> {
+        let mut replies = self.pipeline(vec![cmd]).await?;
+        replies.pop().ok_or(ClientError::Closed)
+    }
+
+    /// Send a command and convert its reply, an error reply is returned as
+    /// `ClientError::Server`.
+    pub async fn query<T: FromRespFrame>(&mut self, cmd: Cmd) -> Result<T, ClientError> {
+        let reply = check(self.send(cmd).await?)?;
+        Ok(T::from_resp_frame(reply)?)
+    }
+
+    /// Send commands in one write and return their replies as is. Push
+    /// messages read meanwhile go to `ClientOptions::pushes`.
+    pub async fn pipeline(&mut self, cmds: Vec<Cmd>) -> Result<Vec<RespFrame>, ClientError> {
+        if self.broken {
+            return Err(ClientError::Disconnected(
+                "connection is broken".to_string(),
+            ));
+        }
+        for cmd in &cmds {
+            self.track(cmd);
+        }
+        let (transport, pushes) = (&mut self.transport, &self.pushes);
+        let result = with_timeout(self.command_timeout, "waiting for a reply", async move {
+            let expected = cmds.len();
+            for cmd in cmds {
+                transport.feed(cmd.into_frame()).await?;
+            }
+            transport.flush().await?;
+            let mut replies = Vec::with_capacity(expected);
+            while replies.len() < expected {
+                match next(transport).await? {
+                    RespFrame::Push(push) => {
+                        if let Some(pushes) = pushes {
+                            let _ = pushes.try_send(push);
+                        }
+                    }
+                    frame => replies.push(frame),
+                }
+            }
+            Ok(replies)
+        })
+        .await;
+        // a reply may still be on its way, nothing after it could be trusted
+        self.broken = result.is_err();
+        result
+    }
+
+    /// Write a command without waiting for its reply, e.g. SUBSCRIBE.
+    pub async fn write(&mut self, cmd: Cmd) -> Result<(), ClientError> {
+        self.track(&cmd);
+        let result = self.transport.send(cmd.into_frame()).await;
+        self.broken |= result.is_err();
+        result
+    }
+
+    /// The next frame from the server, push messages included, waiting as
+    /// long as it takes: how a subscribed connection gets its messages.
+    pub async fn read(&mut self) -> Result<RespFrame, ClientError> {
+        let result = next(&mut self.transport).await;
+        self.broken |= result.is_err();
+        result
+    }
+}
+
+async fn next(transport: &mut Transport) -> Result<RespFrame, ClientError> {
+    match transport.next().await {
+        Some(frame) => frame,
+        None => Err(ClientError::Disconnected(
+            "connection closed by the server".to_string(),
+        )),
+    }
+}
+
+pub(super) async fn with_timeout<T>(
+    limit: Option<Duration>,
+    what: &'static str,
+    f: impl Future<Output = Result<T, ClientError>>,
+) -> Result<T, ClientError> {
+    match limit {
+        Some(limit) => time::timeout(limit, f)
+            .await
+            .map_err(|_| ClientError::Timeout(what))?,
+        None => f.await,
+    }
+}
+
+/// Open a connection and say HELLO, returning the protocol agreed on.
+pub(super) async fn connect(
+    addr: &ConnectAddr,
+    options: &ClientOptions,
+) -> Result<(Transport, u8), ClientError> {
+    with_timeout(options.connect_timeout, "connecting", open(addr, options)).await
+}
+
+async fn open(addr: &ConnectAddr, options: &ClientOptions) -> Result<(Transport, u8), ClientError> {
+    let stream: Box<dyn AsyncStream> = match addr {
+        ConnectAddr::Tcp(addr) => {
+            let stream = TcpStream::connect(addr.as_str()).await?;
+            stream.set_nodelay(true)?;
+            Box::new(stream)
+        }
+        #[cfg(unix)]
+        ConnectAddr::Unix(path) => Box::new(tokio::net::UnixStream::connect(path).await?),
+    };
+    let mut transport = Framed::new(stream, ClientCodec::with_limits(options.limits));
+    let protocol = handshake(&mut transport, options).await?;
+    Ok((transport, protocol))
+}
+
+// HELLO with the credentials and name, then READONLY if asked; falls back to
+// RESP2 with AUTH and CLIENT SETNAME for servers without HELLO or the protocol
+async fn handshake(transport: &mut Transport, options: &ClientOptions) -> Result<u8, ClientError> {
+    let mut hello = cmd("HELLO").arg(options.protocol as i64);
+    if let Some((user, pass)) = &options.auth {
+        hello = hello.arg("AUTH").arg(user).arg(pass);
+    }
+    if let Some(name) = &options.name {
+        hello = hello.arg("SETNAME").arg(name);
+    }
+    let protocol = match round_trip(transport, hello.into_frame()).await? {
+        RespFrame::SimpleError(e) if is_unsupported(&e) => fall_back(transport, options).await?,
+        RespFrame::SimpleError(e) => return Err(ClientError::Server(e.0)),
+        _ => options.protocol,
+    };
+    if options.readonly {
+        if let RespFrame::SimpleError(e) =
+            round_trip(transport, cmd("READONLY").into_frame()).await?
+        {
+            return Err(ClientError::Server(e.0));
+        }
+    }
+    Ok(protocol)
+}
+
+async fn fall_back(transport: &mut Transport, options: &ClientOptions) -> Result<u8, ClientError> {
+    if let Some((user, pass)) = &options.auth {
+        let auth = cmd("AUTH").arg(user).arg(pass);
+        if let RespFrame::SimpleError(e) = round_trip(transport, auth.into_frame()).await? {
+            return Err(ClientError::Server(e.0));
+        }
+    }
+    if let Some(name) = &options.name {
+        let setname = cmd("CLIENT").arg("SETNAME").arg(name);
+        if let RespFrame::SimpleError(e) = round_trip(transport, setname.into_frame()).await? {
+            return Err(ClientError::Server(e.0));
+        }
+    }
+    Ok(2)
+}
+
+fn is_unsupported(e: &SimpleError) -> bool {
+    e.0.starts_with("NOPROTO") || e.0.starts_with("ERR unknown command")
+}
+
+async fn round_trip(transport: &mut Transport, frame: RespFrame) -> Result<RespFrame, ClientError> {
+    transport.send(frame).await?;
+    loop {
+        match next(transport).await? {
+            RespFrame::Push(_) => continue,
+            frame => return Ok(frame),
+        }
+    }
+}
+
+/// Runs a client's connection: writes requests as they come, matches
+/// replies to them in order and reconnects when the connection drops.
+pub(super) async fn run(
+    mut transport: Transport,
+    addr: ConnectAddr,
+    options: ClientOptions,
+    protocol: Arc<AtomicU8>,
+    mut requests: mpsc::Receiver<Request>,
+) {
+    let mut held = None;
+    loop {
+        let e = match serve(&mut transport, &options, &mut requests, held.take()).await {
+            Ok(()) => return,
+            Err(e) => e,
+        };
+        warn!("Connection to {} lost: {}", addr, e);
+
+        let mut attempt = 0;
+        transport = loop {
+            attempt += 1;
+            if options.max_reconnect_attempts.is_some_and(|n| attempt > n) {
+                // fail whoever is waiting, and try again for the next one
+                warn!("Giving up reconnecting to {} for now", addr);
+                while let Ok(request) = requests.try_recv() {
+                    let _ = request.reply.send(Err(disconnected(&e)));
+                }
+                if let Some(request) = held.take() {
+                    let _ = request.reply.send(Err(disconnected(&e)));
+                }
+                match requests.recv().await {
+                    Some(request) => held = Some(request),
+                    None => return,
+                }
+                attempt = 1;
+            }
+            time::sleep(backoff(&options, attempt)).await;
+            if requests.is_closed() && held.is_none() {
+                return;
+            }
+            match connect(&addr, &options).await {
+                Ok((transport, agreed)) => {
+                    info!("Reconnected to {}", addr);
+                    protocol.store(agreed, Ordering::Relaxed);
+                    break transport;
+                }
+                Err(e) => warn!("Reconnecting to {} failed: {}", addr, e),
+            }
+        };
+    }
+}
+
+// exponential, from the initial delay up to the maximum
+fn backoff(options: &ClientOptions, attempt: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempt.saturating_sub(1).min(16));
+    options
+        .reconnect_delay
+        .saturating_mul(factor)
+        .min(options.max_reconnect_delay)
+}
+
+fn disconnected(e: &ClientError) -> ClientError {
+    ClientError::Disconnected(e.to_string())
+}
+
+// Ok once every client handle is gone, Err when the connection fails, in
+// which case the requests waiting for replies get the error
+async fn serve(
+    transport: &mut Transport,
+    options: &ClientOptions,
+    requests: &mut mpsc::Receiver<Request>,
+    held: Option<Request>,
+) -> Result<(), ClientError> {
+    let mut in_flight = VecDeque::new();
+    let result = async {
+        if let Some(request) = held {
+            write(transport, &mut in_flight, request).await?;
+            transport.flush().await?;
+        }
+        loop {
+            tokio::select! {
+                request = requests.recv() => {
+                    let Some(request) = request else {
+                        return Ok(());
+                    };
+                    write(transport, &mut in_flight, request).await?;
+                    for _ in 1..PIPELINE_BATCH {
+                        match requests.try_recv() {
+                            Ok(request) => write(transport, &mut in_flight, request).await?,
+                            Err(_) => break,
+                        }
+                    }
+                    transport.flush().await?;
+                }
+                frame = transport.next() => match frame {
+                    Some(Ok(RespFrame::Push(push))) => {
+                        if let Some(pushes) = &options.pushes {
+                            // a slow reader loses messages rather than stalling replies
+                            let _ = pushes.try_send(push);
+                        }
+                    }
+                    Some(Ok(frame)) => {
+                        let Some(front) = in_flight.front_mut() else {
+                            return Err(ClientError::Disconnected(
+                                "reply without a request".to_string(),
+                            ));
+                        };
+                        front.replies.push(frame);
+                        if front.replies.len() == front.expected {
+                            if let Some(done) = in_flight.pop_front() {
+                                let _ = done.reply.send(Ok(done.replies));
+                            }
+                        }
+                    }
+                    Some(Err(e)) => return Err(e),
+                    None => {
+                        return Err(ClientError::Disconnected(
+                            "connection closed by the server".to_string(),
+                        ))
+                    }
+                },
+            }
+        }
+    }
+    .await;
+    if let Err(e) = &result {
+        for request in in_flight {
+            let _ = request.reply.send(Err(disconnected(e)));
+        }
+    }
+    result
+}
+
+async fn write(
+    transport: &mut Transport,
+    in_flight: &mut VecDeque<InFlight>,
+    request: Request,
+) -> Result<(), ClientError> {
+    // an empty batch is answered right away
+    if request.frames.is_empty() {
+        let _ = request.reply.send(Ok(Vec::new()));
+        return Ok(());
+    }
+    // a caller that went away still gets its replies read off the wire
+    in_flight.push_back(InFlight {
+        expected: request.frames.len(),
+        replies: Vec::with_capacity(request.frames.len()),
+        reply: request.reply,
+    });
+    for frame in request.frames {
+        transport.feed(frame).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,400 @@
+//! An async client for this server or any Redis, built on the crate's RESP
+//! types. Requests from concurrent tasks share one connection and go out
+//! pipelined, the connection says HELLO first and is re-established with
+//! backoff when it drops.
+
+mod cluster;
+mod cmd;
+mod codec;
+mod commands;
+mod connection;
+mod pool;
+
+use std::{
+    fmt,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use thiserror::Error;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{DecodeLimits, FromRespFrame, RespError, RespFrame, RespPush};
+
+use self::connection::{with_timeout, Request};
+
+pub use self::{
+    cluster::{ClusterClient, ClusterOptions},
+    cmd::{cmd, Cmd, IntoArg},
+    codec::ClientCodec,
+    commands::RestorePolicy,
+    connection::Connection,
+    pool::{Pool, PoolMetrics, PoolOptions, PooledConnection},
+};
+
+// requests waiting for the connection task, callers wait beyond that
+const REQUEST_QUEUE_CAP: usize = 1024;
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A frame the client couldn't decode, or a reply it couldn't convert.
+    #[error("{0}")]
+    Resp(#[from] RespError),
+
+    /// An error reply, e.g. "ERR unknown command 'foo'".
+    #[error("{0}")]
+    Server(String),
+
+    /// The connection dropped with the request in flight, or couldn't be
+    /// re-established.
+    #[error("Connection lost: {0}")]
+    Disconnected(String),
+
+    #[error("Client closed")]
+    Closed,
+
+    #[error("Timed out {0}")]
+    Timeout(&'static str),
+
+    /// Every connection of a pool is in use and none came back in time.
+    #[error("No connection available in the pool")]
+    PoolExhausted,
+}
+
+/// Where to connect: `host:port` (an optional `redis://` is ignored), or a
+/// Unix socket given as `unix:/path` or an absolute path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectAddr {
+    Tcp(String),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl From<&str> for ConnectAddr {
+    fn from(addr: &str) -> Self {
+        #[cfg(unix)]
+        if let Some(path) = addr
+            .strip_prefix("unix:")
+            .or(addr.starts_with('/').then_some(addr))
+        {
+            return ConnectAddr::Unix(PathBuf::from(path.trim_start_matches("//")));
+        }
+        ConnectAddr::Tcp(addr.trim_start_matches("redis://").to_string())
+    }
+}
+
+impl From<String> for ConnectAddr {
+    fn from(addr: String) -> Self {
+        addr.as_str().into()
+    }
+}
+
+impl From<std::net::SocketAddr> for ConnectAddr {
+    fn from(addr: std::net::SocketAddr) -> Self {
+        ConnectAddr::Tcp(addr.to_string())
+    }
+}
+
+impl fmt::Display for ConnectAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectAddr::Tcp(addr) => write!(f, "{}", addr),
+            #[cfg(unix)]
+            ConnectAddr::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    /// The protocol asked for with HELLO, the client settles for RESP2 if
+    /// the server doesn't take it.
+    pub protocol: u8,
+    /// Username and password sent with HELLO.
+    pub auth: Option<(String, String)>,
+    /// Name sent with HELLO SETNAME.
+    pub name: Option<String>,
+    /// Send READONLY after HELLO, to read from a cluster replica.
+    pub readonly: bool,
+    /// Delay before the first reconnect attempt, doubled on each failure.
+    pub reconnect_delay: Duration,
+    pub max_reconnect_delay: Duration,
+    /// Attempts before the requests waiting for the connection fail, `None`
+    /// to keep them waiting until it's back.
+    pub max_reconnect_attempts: Option<u32>,
+    /// How long connecting and saying HELLO may take.
+    pub connect_timeout: Option<Duration>,
+    /// How long to wait for a reply, `None` for blocking commands.
+    pub command_timeout: Option<Duration>,
+    /// Limits on the replies, like the server's on requests.
+    pub limits: DecodeLimits,
+    /// Where RESP3 push messages (client side caching invalidations) go,
+    /// they're dropped without one or when it's full.
+    pub pushes: Option<mpsc::Sender<RespPush>>,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            protocol: 3,
+            auth: None,
+            name: None,
+            readonly: false,
+            reconnect_delay: Duration::from_millis(100),
+            max_reconnect_delay: Duration::from_secs(5),
+            max_reconnect_attempts: Some(10),
+            connect_timeout: Some(Duration::from_secs(5)),
+            command_timeout: None,
+            limits: DecodeLimits::default(),
+            pushes: None,
+        }
+    }
+}
+
+/// A handle on a connection, cheap to clone and share between tasks.
+///
+/// Commands that change the state of the connection (SUBSCRIBE, WATCH,
+/// CLIENT TRACKING, HELLO) have no helpers here, as every clone shares it,
+/// they're on `Connection`; MULTI and EXEC go out together through
+/// `transaction`.
+#[derive(Debug, Clone)]
+pub struct Client {
+    requests: mpsc::Sender<Request>,
+    protocol: Arc<AtomicU8>,
+    command_timeout: Option<Duration>,
+}
+
+impl Client {
+    pub async fn connect(addr: impl Into<ConnectAddr>) -> Result<Client, ClientError> {
+        Self::connect_with(addr, ClientOptions::default()).await
+    }
+
+    /// Connect and say HELLO, failing if either does. Later connection
+    /// losses are handled by reconnecting in the background.
+    pub async fn connect_with(
+        addr: impl Into<ConnectAddr>,
+        options: ClientOptions,
+    ) -> Result<Client, ClientError> {
+        let addr = addr.into();
+        let (transport, agreed) = connection::connect(&addr, &options).await?;
+        let (tx, rx) = mpsc::channel(REQUEST_QUEUE_CAP);
+        let protocol = Arc::new(AtomicU8::new(agreed));
+        let command_timeout = options.command_timeout;
+        tokio::spawn(connection::run(
+            transport,
+            addr,
+            options,
+            protocol.clone(),
+            rx,
+        ));
+        Ok(Client {
+            requests: tx,
+            protocol,
+            command_timeout,
+        })
+    }
+
+    /// The protocol the connection agreed on with HELLO.
+    pub fn protocol(&self) -> u8 {
+        self.protocol.load(Ordering::Relaxed)
+    }
+
+    /// Send a command and return its reply as is, error replies included.
+    pub async fn send(&self, cmd: Cmd) -> Result<RespFrame, ClientError> {
+        let mut replies = self.request(vec![cmd.into_frame()]).await?;
+        replies.pop().ok_or(ClientError::Closed)
+    }
+
+    /// Send a command and convert its reply, an error reply is returned as
+    /// `ClientError::Server`.
+    pub async fn query<T: FromRespFrame>(&self, cmd: Cmd) -> Result<T, ClientError> {
+        let reply = check(self.send(cmd).await?)?;
+        Ok(T::from_resp_frame(reply)?)
+    }
+
+    /// Send commands back to back and return their replies as is. Nothing
+    /// from other tasks gets written between them.
+    pub async fn pipeline(&self, cmds: Vec<Cmd>) -> Result<Vec<RespFrame>, ClientError> {
+        self.request(cmds.into_iter().map(Cmd::into_frame).collect())
+            .await
+    }
+
+    /// Run commands in a MULTI/EXEC block and return their replies. The
+    /// transaction fails with the server's error if any command is refused.
+    pub async fn transaction(&self, cmds: Vec<Cmd>) -> Result<Vec<RespFrame>, ClientError> {
+        let mut frames = vec![cmd("MULTI").into_frame()];
+        frames.extend(cmds.into_iter().map(Cmd::into_frame));
+        frames.push(cmd("EXEC").into_frame());
+        let mut replies = self.request(frames).await?;
+        let exec = replies.pop().ok_or(ClientError::Closed)?;
+        // a refused command makes EXEC fail, its own error says why
+        if let Some(refused) = replies
+            .into_iter()
+            .find(|r| matches!(r, RespFrame::SimpleError(_)))
+        {
+            check(refused)?;
+        }
+        Ok(Vec::from_resp_frame(check(exec)?)?)
+    }
+
+    async fn request(&self, frames: Vec<RespFrame>) -> Result<Vec<RespFrame>, ClientError> {
+        let (tx, rx) = oneshot::channel();
+        // a reply that comes too late is read and dropped by the connection
+        with_timeout(self.command_timeout, "waiting for a reply", async {
+            self.requests
+                .send(Request { frames, reply: tx })
+                .await
+                .map_err(|_| ClientError::Closed)?;
+            rx.await.map_err(|_| ClientError::Closed)?
+        })
+        .await
+    }
+}
+
+fn check(reply: RespFrame) -> Result<RespFrame, ClientError> {
+    match reply {
+        RespFrame::SimpleError(e) => Err(ClientError::Server(e.0)),
+        RespFrame::BulkError(e) => Err(ClientError::Server(
+            String::from_utf8_lossy(&e.0).into_owned(),
+        )),
+        reply => Ok(reply),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{collections::HashMap, net::SocketAddr};
+
+    use tokio::{net::TcpListener, task::JoinHandle};
+
+    use super::*;
+    use crate::{network, Backend, BulkString};
+
+    /// A server on a free port, and the tasks serving its connections so
+    /// that a test can drop them.
+    pub(crate) async fn start_server() -> (SocketAddr, Arc<std::sync::Mutex<Vec<JoinHandle<()>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let handles = connections.clone();
+        let backend = Backend::new();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let backend = backend.clone();
+                let handle = tokio::spawn(async move {
+                    let _ = network::stream_handler(stream, backend).await;
+                });
+                handles.lock().unwrap().push(handle);
+            }
+        });
+        (addr, connections)
+    }
+
+    #[tokio::test]
+    async fn test_client_commands() -> Result<(), ClientError> {
+        let (addr, _) = start_server().await;
+        let client = Client::connect(addr).await?;
+        assert_eq!(client.protocol(), 3);
+
+        client.set("greeting", "hello").await?;
+        assert_eq!(client.get::<String>("greeting").await?, "hello");
+        assert_eq!(client.get::<Option<String>>("missing").await?, None);
+
+        client.hset("user:1", "name", "alice").await?;
+        client.hset("user:1", "age", 30).await?;
+        let user: HashMap<String, String> = client.hgetall("user:1").await?;
+        assert_eq!(user["age"], "30");
+        assert_eq!(client.hget::<u8>("user:1", "age").await?, 30);
+
+        let err = client.query::<RespFrame>(cmd("GET")).await.unwrap_err();
+        assert!(matches!(err, ClientError::Server(_)));
+
+        let replies = client
+            .transaction(vec![cmd("SET").arg("a").arg(1), cmd("GET").arg("a")])
+            .await?;
+        assert_eq!(replies[1], BulkString::from("1").into());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_pipelines_concurrent_requests() -> Result<(), ClientError> {
+        let (addr, connections) = start_server().await;
+        let client = Client::connect(addr).await?;
+        let tasks = (0..200)
+            .map(|i| {
+                let client = client.clone();
+                tokio::spawn(async move {
+                    let key = format!("key:{}", i);
+                    client.set(&key, i).await?;
+                    client.get::<i64>(&key).await
+                })
+            })
+            .collect::<Vec<_>>();
+        for (i, task) in tasks.into_iter().enumerate() {
+            assert_eq!(task.await.unwrap()?, i as i64);
+        }
+        // all of it over the one connection
+        assert_eq!(connections.lock().unwrap().len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_reconnects() -> Result<(), ClientError> {
+        let (addr, connections) = start_server().await;
+        let options = ClientOptions {
+            protocol: 2,
+            reconnect_delay: Duration::from_millis(10),
+            ..Default::default()
+        };
+        let client = Client::connect_with(addr, options).await?;
+        assert_eq!(client.protocol(), 2);
+        client.set("k", "v").await?;
+
+        // drop the connection on the server side
+        for handle in connections.lock().unwrap().drain(..) {
+            handle.abort();
+        }
+        let mut reply = client.get::<String>("k").await;
+        for _ in 0..50 {
+            if reply.is_ok() {
+                break;
+            }
+            assert!(matches!(reply, Err(ClientError::Disconnected(_))));
+            time_out().await;
+            reply = client.get::<String>("k").await;
+        }
+        assert_eq!(reply?, "v");
+        assert_eq!(connections.lock().unwrap().len(), 1);
+        Ok(())
+    }
+
+    async fn time_out() {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    #[test]
+    fn test_connect_addr() {
+        assert_eq!(
+            ConnectAddr::from("redis://127.0.0.1:6379"),
+            ConnectAddr::Tcp("127.0.0.1:6379".to_string())
+        );
+        #[cfg(unix)]
+        {
+            assert_eq!(
+                ConnectAddr::from("unix:///tmp/redis.sock"),
+                ConnectAddr::Unix("/tmp/redis.sock".into())
+            );
+            assert_eq!(
+                ConnectAddr::from("/tmp/redis.sock"),
+                ConnectAddr::Unix("/tmp/redis.sock".into())
+            );
+        }
+    }
+}
@@ -0,0 +1,467 @@
+use std::{
+    collections::VecDeque,
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::Duration,
+};
+
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time::{self, Instant},
+};
+use tracing::warn;
+
+use super::{cmd, connection::with_timeout, ClientError, ClientOptions, ConnectAddr, Connection};
+
+#[derive(Debug, Clone)]
+pub struct PoolOptions {
+    /// Connections opened up front and kept open however idle.
+    pub min_size: usize,
+    /// Connections open at most, checked out or idle.
+    pub max_size: usize,
+    /// How long a checkout waits for a connection when all are in use,
+    /// `None` to wait as long as it takes.
+    pub checkout_timeout: Option<Duration>,
+    /// Idle connections unused for this long are closed, down to `min_size`.
+    pub idle_timeout: Option<Duration>,
+    /// How often idle connections are reaped and `min_size` restored.
+    pub reap_interval: Duration,
+    /// A connection idle for at least this long gets a PING before it's
+    /// handed out, `None` to never check.
+    pub health_check_after: Option<Duration>,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        Self {
+            min_size: 0,
+            max_size: 16,
+            checkout_timeout: Some(Duration::from_secs(5)),
+            idle_timeout: Some(Duration::from_secs(300)),
+            reap_interval: Duration::from_secs(30),
+            health_check_after: Some(Duration::ZERO),
+        }
+    }
+}
+
+/// How the pool has been doing since it was created.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PoolMetrics {
+    pub checkouts: u64,
+    /// Checkouts that found every connection in use and had to wait.
+    pub exhausted: u64,
+    /// Checkouts that gave up waiting.
+    pub timeouts: u64,
+    pub total_wait: Duration,
+    pub max_wait: Duration,
+    pub connections_opened: u64,
+    /// Idle connections found dead by the health check.
+    pub health_check_failures: u64,
+    pub idle: usize,
+    pub in_use: usize,
+}
+
+impl PoolMetrics {
+    pub fn average_wait(&self) -> Duration {
+        match self.checkouts {
+            0 => Duration::ZERO,
+            n => self.total_wait / n as u32,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    checkouts: AtomicU64,
+    exhausted: AtomicU64,
+    timeouts: AtomicU64,
+    wait_micros: AtomicU64,
+    max_wait_micros: AtomicU64,
+    connections_opened: AtomicU64,
+    health_check_failures: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Idle {
+    conn: Connection,
+    since: Instant,
+}
+
+#[derive(Debug)]
+struct Shared {
+    addr: ConnectAddr,
+    client: ClientOptions,
+    options: PoolOptions,
+    idle: Mutex<VecDeque<Idle>>,
+    // one permit per connection that may be checked out
+    permits: Arc<Semaphore>,
+    counters: Counters,
+}
+
+/// A pool of dedicated connections, for the commands a shared `Client`
+/// can't run: transactions with WATCH, blocking commands. Cheap to clone.
+#[derive(Debug, Clone)]
+pub struct Pool {
+    shared: Arc<Shared>,
+}
+
+/// A connection checked out of a pool, back in it when dropped unless a
+/// request on it failed or it was left in a transaction, watching keys or
+/// subscribed.
+#[derive(Debug)]
+pub struct PooledConnection {
+    conn: Option<Connection>,
+    shared: Arc<Shared>,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Pool {
+    /// Open `min_size` connections, failing if any can't be, and start
+    /// reaping idle ones in the background.
+    pub async fn new(
+        addr: impl Into<ConnectAddr>,
+        client: ClientOptions,
+        options: PoolOptions,
+    ) -> Result<Pool, ClientError> {
+        let max_size = options.max_size.max(1);
+        let shared = Arc::new(Shared {
+            addr: addr.into(),
+            client,
+            options: PoolOptions {
+                max_size,
+                ..options
+            },
+            idle: Mutex::new(VecDeque::new()),
+            permits: Arc::new(Semaphore::new(max_size)),
+            counters: Counters::default(),
+        });
+        shared.fill().await?;
+        tokio::spawn(reap(Arc::downgrade(&shared)));
+        Ok(Pool { shared })
+    }
+
+    /// Check a connection out, waiting up to `checkout_timeout` when all
+    /// are in use.
+    pub async fn get(&self) -> Result<PooledConnection, ClientError> {
+        let shared = &self.shared;
+        let start = Instant::now();
+        let permit = match shared.permits.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                shared.counters.exhausted.fetch_add(1, Ordering::Relaxed);
+                let acquire = shared.permits.clone().acquire_owned();
+                let permit = match shared.options.checkout_timeout {
+                    Some(limit) => time::timeout(limit, acquire).await.ok(),
+                    None => Some(acquire.await),
+                };
+                match permit {
+                    Some(permit) => permit.map_err(|_| ClientError::Closed)?,
+                    None => {
+                        shared.counters.timeouts.fetch_add(1, Ordering::Relaxed);
+                        return Err(ClientError::PoolExhausted);
+                    }
+                }
+            }
+        };
+        let conn = shared.checkout().await?;
+        shared.record_wait(start.elapsed());
+        Ok(PooledConnection {
+            conn: Some(conn),
+            shared: shared.clone(),
+            _permit: permit,
+        })
+    }
+
+    pub fn metrics(&self) -> PoolMetrics {
+        let shared = &self.shared;
+        let counters = &shared.counters;
+        PoolMetrics {
+            checkouts: counters.checkouts.load(Ordering::Relaxed),
+            exhausted: counters.exhausted.load(Ordering::Relaxed),
+            timeouts: counters.timeouts.load(Ordering::Relaxed),
+            total_wait: Duration::from_micros(counters.wait_micros.load(Ordering::Relaxed)),
+            max_wait: Duration::from_micros(counters.max_wait_micros.load(Ordering::Relaxed)),
+            connections_opened: counters.connections_opened.load(Ordering::Relaxed),
+            health_check_failures: counters.health_check_failures.load(Ordering::Relaxed),
+            idle: shared.idle_len(),
+            in_use: shared.in_use(),
+        }
+    }
+}
+
+impl Shared {
+    // an idle connection that passes the health check, or a new one
+    async fn checkout(&self) -> Result<Connection, ClientError> {
+        loop {
+            let Some(idle) = self.pop_idle() else {
+                return self.open().await;
+            };
+            if self.expired(&idle) {
+                continue;
+            }
+            let mut conn = idle.conn;
+            match self.options.health_check_after {
+                Some(after) if idle.since.elapsed() >= after => {
+                    let ping = with_timeout(
+                        self.client.command_timeout.or(self.client.connect_timeout),
+                        "checking a connection",
+                        conn.query::<String>(cmd("PING")),
+                    )
+                    .await;
+                    if let Err(e) = ping {
+                        warn!("Dropping a pooled connection to {}: {}", self.addr, e);
+                        self.counters
+                            .health_check_failures
+                            .fetch_add(1, Ordering::Relaxed);
+                        continue;
+                    }
+                    return Ok(conn);
+                }
+                _ => return Ok(conn),
+            }
+        }
+    }
+
+    async fn open(&self) -> Result<Connection, ClientError> {
+        let conn = Connection::connect(self.addr.clone(), &self.client).await?;
+        self.counters
+            .connections_opened
+            .fetch_add(1, Ordering::Relaxed);
+        Ok(conn)
+    }
+
+    // open connections until there are `min_size`
+    async fn fill(&self) -> Result<(), ClientError> {
+        while self.idle_len() + self.in_use() < self.options.min_size {
+            let conn = self.open().await?;
+            self.push_idle(conn);
+        }
+        Ok(())
+    }
+
+    // close the connections idle for too long, keeping `min_size` open
+    fn reap(&self) {
+        let in_use = self.in_use();
+        let mut idle = self.idle.lock().unwrap();
+        // the oldest are at the front
+        while idle.len() + in_use > self.options.min_size
+            && idle.front().is_some_and(|i| self.expired(i))
+        {
+            idle.pop_front();
+        }
+    }
+
+    fn expired(&self, idle: &Idle) -> bool {
+        self.options
+            .idle_timeout
+            .is_some_and(|timeout| idle.since.elapsed() >= timeout)
+    }
+
+    // the most recently used first, so that the others can expire
+    fn pop_idle(&self) -> Option<Idle> {
+        self.idle.lock().unwrap().pop_back()
+    }
+
+    fn push_idle(&self, conn: Connection) {
+        self.idle.lock().unwrap().push_back(Idle {
+            conn,
+            since: Instant::now(),
+        });
+    }
+
+    fn idle_len(&self) -> usize {
+        self.idle.lock().unwrap().len()
+    }
+
+    fn in_use(&self) -> usize {
+        self.options.max_size - self.permits.available_permits()
+    }
+
+    fn record_wait(&self, wait: Duration) {
+        let micros = wait.as_micros() as u64;
+        let counters = &self.counters;
+        counters.checkouts.fetch_add(1, Ordering::Relaxed);
+        counters.wait_micros.fetch_add(micros, Ordering::Relaxed);
+        counters
+            .max_wait_micros
+            .fetch_max(micros, Ordering::Relaxed);
+    }
+}
+
+// runs until the pool is dropped
+async fn reap(shared: Weak<Shared>) {
+    let period = shared
+        .upgrade()
+        .map(|shared| shared.options.reap_interval)
+        .unwrap_or_default()
+        .max(Duration::from_millis(1));
+    let mut interval = time::interval_at(Instant::now() + period, period);
+    loop {
+        interval.tick().await;
+        let Some(shared) = shared.upgrade() else {
+            return;
+        };
+        shared.reap();
+        if let Err(e) = shared.fill().await {
+            warn!("Refilling the pool for {} failed: {}", shared.addr, e);
+        }
+    }
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("connection taken")
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("connection taken")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            if conn.is_reusable() {
+                self.shared.push_idle(conn);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{client::tests::start_server, RespFrame};
+
+    fn options() -> PoolOptions {
+        PoolOptions {
+            min_size: 1,
+            max_size: 2,
+            checkout_timeout: Some(Duration::from_millis(50)),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pool_checkout_and_exhaustion() -> Result<(), ClientError> {
+        let (addr, _) = start_server().await;
+        let pool = Pool::new(addr, ClientOptions::default(), options()).await?;
+        assert_eq!(pool.metrics().idle, 1);
+
+        let mut a = pool.get().await?;
+        let mut b = pool.get().await?;
+        a.query::<String>(cmd("SET").arg("k").arg("v")).await?;
+        assert_eq!(b.query::<String>(cmd("GET").arg("k")).await?, "v");
+        assert!(matches!(pool.get().await, Err(ClientError::PoolExhausted)));
+
+        // a connection given back is there for the one waiting
+        let waiting = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.get().await.map(|_| ()) }
+        });
+        time::sleep(Duration::from_millis(10)).await;
+        drop(a);
+        waiting.await.unwrap()?;
+        drop(b);
+
+        let metrics = pool.metrics();
+        assert_eq!(metrics.checkouts, 3);
+        assert_eq!(metrics.exhausted, 2);
+        assert_eq!(metrics.timeouts, 1);
+        assert_eq!(metrics.connections_opened, 2);
+        assert_eq!((metrics.idle, metrics.in_use), (2, 0));
+        assert!(metrics.max_wait >= Duration::from_millis(10));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pool_drops_dead_connections() -> Result<(), ClientError> {
+        let (addr, connections) = start_server().await;
+        let pool = Pool::new(addr, ClientOptions::default(), options()).await?;
+        for handle in connections.lock().unwrap().drain(..) {
+            handle.abort();
+        }
+        time::sleep(Duration::from_millis(10)).await;
+
+        let mut conn = pool.get().await?;
+        assert_eq!(
+            conn.send(cmd("PING")).await?,
+            RespFrame::from(crate::SimpleString::new("PONG"))
+        );
+        assert_eq!(pool.metrics().health_check_failures, 1);
+        assert_eq!(pool.metrics().connections_opened, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pool_discards_connections_left_in_a_session_state() -> Result<(), ClientError> {
+        let (addr, _) = start_server().await;
+        let pool = Pool::new(addr, ClientOptions::default(), options()).await?;
+
+        let mut conn = pool.get().await?;
+        conn.query::<String>(cmd("MULTI")).await?;
+        drop(conn);
+        let mut conn = pool.get().await?;
+        conn.query::<String>(cmd("WATCH").arg("k")).await?;
+        drop(conn);
+        let mut conn = pool.get().await?;
+        conn.write(cmd("SUBSCRIBE").arg("news")).await?;
+        drop(conn);
+        assert_eq!(pool.metrics().idle, 0);
+        assert_eq!(pool.metrics().connections_opened, 3);
+
+        // done with the transaction, good to go back
+        let mut conn = pool.get().await?;
+        conn.pipeline(vec![cmd("WATCH").arg("k"), cmd("MULTI"), cmd("EXEC")])
+            .await?;
+        assert!(conn.is_reusable());
+        drop(conn);
+        assert_eq!(pool.metrics().idle, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pool_reaps_idle_connections() -> Result<(), ClientError> {
+        let (addr, _) = start_server().await;
+        let options = PoolOptions {
+            idle_timeout: Some(Duration::from_millis(20)),
+            reap_interval: Duration::from_millis(10),
+            ..options()
+        };
+        let pool = Pool::new(addr, ClientOptions::default(), options).await?;
+        let (a, b) = (pool.get().await?, pool.get().await?);
+        drop((a, b));
+        assert_eq!(pool.metrics().idle, 2);
+        time::sleep(Duration::from_millis(60)).await;
+        // down to min_size
+        assert_eq!(pool.metrics().idle, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_connect_timeout() -> Result<(), ClientError> {
+        // a server that never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            let mut streams = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                streams.push(stream);
+            }
+        });
+        let client = ClientOptions {
+            connect_timeout: Some(Duration::from_millis(20)),
+            ..Default::default()
+        };
+        let err = Connection::connect(addr, &client).await.unwrap_err();
+        assert!(matches!(err, ClientError::Timeout("connecting")));
+        Ok(())
+    }
+}
@@ -0,0 +1,329 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    backend::TrackingOptions, session::Session, Backend, BulkString, RespArray, RespFrame,
+    SimpleError,
+};
+
+use super::{
+    connection_only, extract_string_args, validate_command_for_more, CommandError, CommandExecutor,
+    RESP_OK,
+};
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Client {
+    Id,
+    Tracking(Option<TrackingOptions>),
+    Caching(bool),
+    TrackingInfo,
+    GetRedir,
+    SetName(String),
+    GetName,
+}
+
+impl CommandExecutor for Client {
+    fn execute(self, _: &Backend) -> RespFrame {
+        connection_only("CLIENT")
+    }
+
+    fn execute_with(self, backend: &Backend, session: &mut Session) -> RespFrame {
+        let current = backend.tracking.options(session.id);
+        match self {
+            Client::Id => RespFrame::Integer(session.id as i64),
+            Client::Tracking(Some(options)) => {
+                // the mode can only change by turning tracking off first
+                if let Some(current) = current {
+                    if current.bcast != options.bcast {
+                        return SimpleError::new("ERR You can't switch BCAST mode on/off before disabling tracking for this client, and then re-enabling it with a different mode.").into();
+                    }
+                    if current.optin != options.optin || current.optout != options.optout {
+                        return SimpleError::new("ERR You can't switch OPTIN/OPTOUT mode before disabling tracking for this client, and then re-enabling it with a different mode.").into();
+                    }
+                }
+                session.track(options);
+                RESP_OK.clone()
+            }
+            Client::Tracking(None) => {
+                session.untrack();
+                RESP_OK.clone()
+            }
+            Client::Caching(yes) => match current {
+                Some(options) if options.optin && yes || options.optout && !yes => {
+                    session.caching = Some(yes);
+                    RESP_OK.clone()
+                }
+                Some(options) if options.optin => SimpleError::new(
+                    "ERR CLIENT CACHING NO is only valid when tracking is enabled in OPTOUT mode.",
+                )
+                .into(),
+                Some(options) if options.optout => SimpleError::new(
+                    "ERR CLIENT CACHING YES is only valid when tracking is enabled in OPTIN mode.",
+                )
+                .into(),
+                _ => SimpleError::new("ERR CLIENT CACHING can be called only when the client is in tracking mode with OPTIN or OPTOUT mode enabled").into(),
+            },
+            Client::TrackingInfo => {
+                let mut flags = Vec::new();
+                let (redirect, prefixes) = match current {
+                    None => {
+                        flags.push("off");
+                        (-1, BTreeSet::new())
+                    }
+                    Some(options) => {
+                        flags.push("on");
+                        for (set, flag) in [
+                            (options.bcast, "bcast"),
+                            (options.optin, "optin"),
+                            (options.optout, "optout"),
+                            (session.caching == Some(true), "caching-yes"),
+                            (session.caching == Some(false), "caching-no"),
+                            (options.noloop, "noloop"),
+                        ] {
+                            if set {
+                                flags.push(flag);
+                            }
+                        }
+                        (options.redirect.map_or(0, |id| id as i64), options.prefixes)
+                    }
+                };
+                let flags = flags
+                    .into_iter()
+                    .map(|flag| BulkString::from(flag).into())
+                    .collect::<Vec<RespFrame>>();
+                let prefixes = prefixes
+                    .into_iter()
+                    .map(|prefix| BulkString::new(prefix).into())
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new([
+                    BulkString::from("flags").into(),
+                    RespArray::new(flags).into(),
+                    BulkString::from("redirect").into(),
+                    RespFrame::Integer(redirect),
+                    BulkString::from("prefixes").into(),
+                    RespArray::new(prefixes).into(),
+                ])
+                .into()
+            }
+            Client::GetRedir => RespFrame::Integer(match current {
+                None => -1,
+                Some(options) => options.redirect.map_or(0, |id| id as i64),
+            }),
+            Client::SetName(name) => match session.set_name(name) {
+                Ok(()) => RESP_OK.clone(),
+                Err(e) => e.into(),
+            },
+            Client::GetName => match &session.name {
+                Some(name) => BulkString::new(name.clone()).into(),
+                None => BulkString::new_null().into(),
+            },
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Client {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // client id | client tracking on|off [redirect id] [prefix prefix ...] [bcast]
+        // [optin] [optout] [noloop] | client caching yes|no | client trackinginfo
+        // | client getredir | client setname name | client getname
+        validate_command_for_more(&value, &["client"], 1)?;
+
+        let mut args = extract_string_args(value, 1)?.into_iter();
+        let sub = args.next().unwrap_or_default().to_ascii_lowercase();
+        let mut args = args.collect::<Vec<_>>();
+        match sub.as_str() {
+            "id" if args.is_empty() => Ok(Client::Id),
+            "tracking" if !args.is_empty() => parse_tracking(args).map(Client::Tracking),
+            "caching" if args.len() == 1 => match args[0].to_ascii_lowercase().as_str() {
+                "yes" => Ok(Client::Caching(true)),
+                "no" => Ok(Client::Caching(false)),
+                _ => Err(CommandError::InvalidArgument("syntax error".to_string())),
+            },
+            "trackinginfo" if args.is_empty() => Ok(Client::TrackingInfo),
+            "getredir" if args.is_empty() => Ok(Client::GetRedir),
+            "setname" if args.len() == 1 => Ok(Client::SetName(args.remove(0))),
+            "getname" if args.is_empty() => Ok(Client::GetName),
+            _ => Err(CommandError::InvalidArgument(format!(
+                "Unknown subcommand or wrong number of arguments for 'client {}'",
+                sub
+            ))),
+        }
+    }
+}
+
+fn parse_tracking(args: Vec<String>) -> Result<Option<TrackingOptions>, CommandError> {
+    let mut args = args.into_iter();
+    let on = match args
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "on" => true,
+        "off" => false,
+        _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+    };
+    let mut options = TrackingOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.to_ascii_lowercase().as_str() {
+            "redirect" => {
+                let id = args.next().and_then(|id| id.parse::<u64>().ok());
+                match id {
+                    Some(id) => options.redirect = Some(id),
+                    None => {
+                        return Err(CommandError::InvalidArgument(
+                            "value is not an integer or out of range".to_string(),
+                        ))
+                    }
+                }
+            }
+            "prefix" => match args.next() {
+                Some(prefix) => {
+                    options.prefixes.insert(prefix.into());
+                }
+                None => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+            },
+            "bcast" => options.bcast = true,
+            "optin" => options.optin = true,
+            "optout" => options.optout = true,
+            "noloop" => options.noloop = true,
+            _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+        }
+    }
+    if !on {
+        return Ok(None);
+    }
+    if !options.prefixes.is_empty() && !options.bcast {
+        return Err(CommandError::InvalidArgument(
+            "PREFIX option requires BCAST mode to be enabled".to_string(),
+        ));
+    }
+    if options.optin && options.optout {
+        return Err(CommandError::InvalidArgument(
+            "You can't use both OPTIN and OPTOUT".to_string(),
+        ));
+    }
+    if options.bcast && (options.optin || options.optout) {
+        return Err(CommandError::InvalidArgument(
+            "OPTIN and OPTOUT are not compatible with BCAST".to_string(),
+        ));
+    }
+    Ok(Some(options))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cmd::{Command, Get, Set},
+        RespDecode,
+    };
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::{Bytes, BytesMut};
+
+    #[test]
+    fn test_client_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*6\r\n$6\r\nclient\r\n$8\r\ntracking\r\n$2\r\non\r\n$5\r\nBCAST\r\n$6\r\nprefix\r\n$2\r\na:\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Client = frame.try_into()?;
+        assert_eq!(
+            result,
+            Client::Tracking(Some(TrackingOptions {
+                bcast: true,
+                prefixes: BTreeSet::from(["a:".into()]),
+                ..Default::default()
+            }))
+        );
+
+        buf.extend_from_slice(b"*3\r\n$6\r\nclient\r\n$7\r\ncaching\r\n$3\r\nYES\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Client = frame.try_into()?;
+        assert_eq!(result, Client::Caching(true));
+
+        buf.extend_from_slice(
+            b"*5\r\n$6\r\nclient\r\n$8\r\ntracking\r\n$2\r\non\r\n$6\r\nprefix\r\n$2\r\na:\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Client::try_from(frame).is_err());
+
+        buf.extend_from_slice(
+            b"*5\r\n$6\r\nclient\r\n$8\r\ntracking\r\n$2\r\non\r\n$5\r\noptin\r\n$6\r\noptout\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Client::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_client_tracking_optin() {
+        let backend = Backend::new();
+        let (mut session, mut push_rx) = Session::new(backend.clone());
+        session.protocol = 3;
+        let optin = TrackingOptions {
+            optin: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            Client::Caching(true).execute_with(&backend, &mut session),
+            SimpleError::new("ERR CLIENT CACHING can be called only when the client is in tracking mode with OPTIN or OPTOUT mode enabled").into()
+        );
+        assert_eq!(
+            Client::Tracking(Some(optin)).execute_with(&backend, &mut session),
+            RESP_OK.clone()
+        );
+
+        // not cached without CLIENT CACHING YES
+        let get = |key: &str| {
+            Command::Get(Get {
+                key: Bytes::copy_from_slice(key.as_bytes()),
+            })
+        };
+        session.caller().run(|| get("foo").execute(&backend));
+        Set {
+            key: "foo".into(),
+            value: BulkString::from("a").into(),
+        }
+        .execute(&backend);
+        assert!(push_rx.try_recv().is_err());
+
+        assert_eq!(
+            Client::Caching(true).execute_with(&backend, &mut session),
+            RESP_OK.clone()
+        );
+        session.caller().run(|| get("foo").execute(&backend));
+        Set {
+            key: "foo".into(),
+            value: BulkString::from("b").into(),
+        }
+        .execute(&backend);
+        let message = push_rx.try_recv().unwrap();
+        assert_eq!(message[0], BulkString::from("invalidate").into());
+
+        assert_eq!(
+            Client::GetRedir.execute_with(&backend, &mut session),
+            RespFrame::Integer(0)
+        );
+        assert_eq!(
+            Client::Tracking(None).execute_with(&backend, &mut session),
+            RESP_OK.clone()
+        );
+        assert_eq!(
+            Client::TrackingInfo.execute_with(&backend, &mut session),
+            RespArray::new([
+                BulkString::from("flags").into(),
+                RespArray::new([BulkString::from("off").into()]).into(),
+                BulkString::from("redirect").into(),
+                RespFrame::Integer(-1),
+                BulkString::from("prefixes").into(),
+                RespArray::new([]).into(),
+            ])
+            .into()
+        );
+    }
+}
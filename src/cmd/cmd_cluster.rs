@@ -0,0 +1,144 @@
+use bytes::Bytes;
+
+use crate::{key_hash_slot, Backend, RespArray, RespFrame, SimpleError, SLOT_COUNT};
+
+use super::{
+    extract_bytes_args, validate_command_for_more, CommandError, CommandExecutor, RESP_OK,
+};
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Cluster {
+    KeySlot(Bytes),
+    AddSlots(Vec<u16>),
+    DelSlots(Vec<u16>),
+}
+
+impl CommandExecutor for Cluster {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self {
+            Cluster::KeySlot(key) => RespFrame::Integer(key_hash_slot(&key) as i64),
+            Cluster::AddSlots(slots) => {
+                if let Some(slot) = slots.iter().find(|&&s| backend.slots.owns(s)) {
+                    return SimpleError::new(format!("ERR Slot {} is already busy", slot)).into();
+                }
+                for slot in slots {
+                    backend.slots.add(slot);
+                }
+                RESP_OK.clone()
+            }
+            Cluster::DelSlots(slots) => {
+                if let Some(slot) = slots.iter().find(|&&s| !backend.slots.owns(s)) {
+                    return SimpleError::new(format!("ERR Slot {} is already unassigned", slot))
+                        .into();
+                }
+                for slot in slots {
+                    backend.del_slot(slot);
+                }
+                RESP_OK.clone()
+            }
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Cluster {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // cluster keyslot key | cluster addslots slot [slot ...] | cluster delslots slot [slot ...]
+        validate_command_for_more(&value, &["cluster"], 1)?;
+
+        let sub = match value[1] {
+            RespFrame::BulkString(ref s) => s.as_ref().to_ascii_lowercase(),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid subcommand".to_string(),
+                ))
+            }
+        };
+
+        let mut args = extract_bytes_args(value, 2)?;
+        match sub.as_slice() {
+            b"keyslot" if args.len() == 1 => Ok(Cluster::KeySlot(args.remove(0))),
+            b"addslots" if !args.is_empty() => Ok(Cluster::AddSlots(parse_slots(&args)?)),
+            b"delslots" if !args.is_empty() => Ok(Cluster::DelSlots(parse_slots(&args)?)),
+            _ => Err(CommandError::InvalidArgument(format!(
+                "Unknown subcommand or wrong number of arguments for 'cluster {}'",
+                String::from_utf8_lossy(&sub)
+            ))),
+        }
+    }
+}
+
+fn parse_slots(args: &[Bytes]) -> Result<Vec<u16>, CommandError> {
+    args.iter()
+        .map(|s| match std::str::from_utf8(s).map(str::parse::<u16>) {
+            Ok(Ok(slot)) if (slot as usize) < SLOT_COUNT => Ok(slot),
+            _ => Err(CommandError::InvalidArgument(format!(
+                "Invalid or out of range slot: {}",
+                String::from_utf8_lossy(s)
+            ))),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RespDecode;
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_cluster_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$7\r\ncluster\r\n$7\r\nKEYSLOT\r\n$3\r\nfoo\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Cluster = frame.try_into()?;
+        assert_eq!(result, Cluster::KeySlot("foo".into()));
+
+        buf.extend_from_slice(b"*3\r\n$7\r\ncluster\r\n$7\r\nkeyslot\r\n$2\r\n\xff\xfe\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Cluster = frame.try_into()?;
+        assert_eq!(result, Cluster::KeySlot(Bytes::from_static(b"\xff\xfe")));
+
+        buf.extend_from_slice(b"*4\r\n$7\r\ncluster\r\n$8\r\ndelslots\r\n$1\r\n1\r\n$1\r\n2\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Cluster = frame.try_into()?;
+        assert_eq!(result, Cluster::DelSlots(vec![1, 2]));
+
+        buf.extend_from_slice(b"*3\r\n$7\r\ncluster\r\n$8\r\naddslots\r\n$5\r\n16384\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Cluster::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cluster_command() {
+        let backend = Backend::new();
+        assert_eq!(
+            Cluster::KeySlot("foo".into()).execute(&backend),
+            RespFrame::Integer(12182)
+        );
+        // keys are hashed as the bytes they are, UTF-8 or not
+        assert_eq!(
+            Cluster::KeySlot(Bytes::from_static(b"\xff{foo}\xfe")).execute(&backend),
+            RespFrame::Integer(12182)
+        );
+        assert_eq!(
+            Cluster::DelSlots(vec![7]).execute(&backend),
+            RESP_OK.clone()
+        );
+        assert!(!backend.slots.owns(7));
+        assert_eq!(
+            Cluster::DelSlots(vec![7]).execute(&backend),
+            SimpleError::new("ERR Slot 7 is already unassigned").into()
+        );
+        assert_eq!(
+            Cluster::AddSlots(vec![7]).execute(&backend),
+            RESP_OK.clone()
+        );
+        assert!(backend.slots.owns(7));
+    }
+}
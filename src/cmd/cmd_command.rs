@@ -0,0 +1,274 @@
+use crate::{Backend, BulkString, RespArray, RespFrame};
+
+use super::{extract_string_args, validate_command_for_more, CommandError, CommandExecutor};
+
+// name, arity, flags, first key, last key, step, acl categories
+type BuiltinSpec = (
+    &'static str,
+    i64,
+    &'static [&'static str],
+    i64,
+    i64,
+    i64,
+    &'static [&'static str],
+);
+
+#[rustfmt::skip]
+const BUILTIN_COMMANDS: &[BuiltinSpec] = &[
+    ("get", 2, &["readonly", "fast"], 1, 1, 1, &["read", "string", "fast"]),
+    ("set", -3, &["write", "denyoom"], 1, 1, 1, &["write", "string", "slow"]),
+    ("hget", 3, &["readonly", "fast"], 1, 1, 1, &["read", "hash", "fast"]),
+    ("hset", -4, &["write", "denyoom", "fast"], 1, 1, 1, &["write", "hash", "fast"]),
+    ("hgetall", 2, &["readonly"], 1, 1, 1, &["read", "hash", "slow"]),
+    ("hmget", -3, &["readonly", "fast"], 1, 1, 1, &["read", "hash", "fast"]),
+    ("echo", 2, &["fast"], 0, 0, 0, &["fast", "connection"]),
+    ("sadd", -3, &["write", "denyoom", "fast"], 1, 1, 1, &["write", "set", "fast"]),
+    ("sismember", 3, &["readonly", "fast"], 1, 1, 1, &["read", "set", "fast"]),
+    ("ping", -1, &["fast"], 0, 0, 0, &["fast", "connection"]),
+    ("subscribe", -2, &["pubsub", "noscript", "loading", "stale", "no_multi"], 0, 0, 0, &["pubsub", "slow"]),
+    ("unsubscribe", -1, &["pubsub", "noscript", "loading", "stale", "no_multi"], 0, 0, 0, &["pubsub", "slow"]),
+    ("psubscribe", -2, &["pubsub", "noscript", "loading", "stale", "no_multi"], 0, 0, 0, &["pubsub", "slow"]),
+    ("punsubscribe", -1, &["pubsub", "noscript", "loading", "stale", "no_multi"], 0, 0, 0, &["pubsub", "slow"]),
+    ("publish", 3, &["pubsub", "loading", "stale", "fast"], 0, 0, 0, &["pubsub", "fast"]),
+    ("pubsub", -2, &[], 0, 0, 0, &["slow"]),
+    ("ssubscribe", -2, &["pubsub", "noscript", "loading", "stale", "no_multi"], 1, -1, 1, &["pubsub", "slow"]),
+    ("sunsubscribe", -1, &["pubsub", "noscript", "loading", "stale", "no_multi"], 1, -1, 1, &["pubsub", "slow"]),
+    ("spublish", 3, &["pubsub", "loading", "stale", "fast"], 1, 1, 1, &["pubsub", "fast"]),
+    ("cluster", -2, &[], 0, 0, 0, &["slow"]),
+    ("config", -2, &["admin", "noscript", "loading", "stale"], 0, 0, 0, &["admin", "slow", "dangerous"]),
+    ("multi", 1, &["noscript", "loading", "stale", "fast"], 0, 0, 0, &["fast", "transaction"]),
+    ("exec", 1, &["noscript", "loading", "stale", "skip_slowlog"], 0, 0, 0, &["slow", "transaction"]),
+    ("discard", 1, &["noscript", "loading", "stale", "fast"], 0, 0, 0, &["fast", "transaction"]),
+    ("watch", -2, &["noscript", "loading", "stale", "fast"], 1, -1, 1, &["fast", "transaction"]),
+    ("unwatch", 1, &["noscript", "loading", "stale", "fast"], 0, 0, 0, &["fast", "transaction"]),
+    ("eval", -3, &["noscript", "stale", "movablekeys"], 0, 0, 0, &["slow", "scripting"]),
+    ("evalsha", -3, &["noscript", "stale", "movablekeys"], 0, 0, 0, &["slow", "scripting"]),
+    ("eval_ro", -3, &["readonly", "noscript", "stale", "movablekeys"], 0, 0, 0, &["slow", "scripting"]),
+    ("evalsha_ro", -3, &["readonly", "noscript", "stale", "movablekeys"], 0, 0, 0, &["slow", "scripting"]),
+    ("script", -2, &["noscript"], 0, 0, 0, &["slow", "scripting"]),
+    ("function", -2, &["noscript"], 0, 0, 0, &["slow", "scripting"]),
+    ("fcall", -3, &["noscript", "stale", "movablekeys"], 0, 0, 0, &["slow", "scripting"]),
+    ("fcall_ro", -3, &["readonly", "noscript", "stale", "movablekeys"], 0, 0, 0, &["slow", "scripting"]),
+    ("wasm", -2, &["admin", "noscript"], 0, 0, 0, &["admin", "slow", "dangerous"]),
+    ("command", -1, &["loading", "stale"], 0, 0, 0, &["slow", "connection"]),
+    ("hello", -1, &["noscript", "loading", "stale", "fast"], 0, 0, 0, &["fast", "connection"]),
+    ("client", -2, &["noscript", "loading", "stale"], 0, 0, 0, &["slow", "connection"]),
+];
+
+/// What COMMAND reports about a command.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CommandSpec {
+    pub(crate) name: String,
+    pub(crate) arity: i64,
+    pub(crate) flags: Vec<String>,
+    pub(crate) first_key: i64,
+    pub(crate) last_key: i64,
+    pub(crate) step: i64,
+    pub(crate) acl_categories: Vec<String>,
+}
+
+impl From<&BuiltinSpec> for CommandSpec {
+    fn from(
+        &(name, arity, flags, first_key, last_key, step, acl_categories): &BuiltinSpec,
+    ) -> Self {
+        CommandSpec {
+            name: name.to_string(),
+            arity,
+            flags: flags.iter().map(|s| s.to_string()).collect(),
+            first_key,
+            last_key,
+            step,
+            acl_categories: acl_categories.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+impl From<CommandSpec> for RespFrame {
+    fn from(spec: CommandSpec) -> Self {
+        let strings = |items: Vec<String>, prefix: &str| {
+            items
+                .into_iter()
+                .map(|s| BulkString::new(format!("{}{}", prefix, s)).into())
+                .collect::<Vec<RespFrame>>()
+        };
+        RespArray::new([
+            BulkString::new(spec.name).into(),
+            RespFrame::Integer(spec.arity),
+            RespArray::new(strings(spec.flags, "")).into(),
+            RespFrame::Integer(spec.first_key),
+            RespFrame::Integer(spec.last_key),
+            RespFrame::Integer(spec.step),
+            RespArray::new(strings(spec.acl_categories, "@")).into(),
+        ])
+        .into()
+    }
+}
+
+/// Built-in, plugin and WASM commands, in that order.
+fn command_specs(backend: &Backend) -> Vec<CommandSpec> {
+    // WASM modules don't declare anything beyond the name and flags
+    let wasm = backend
+        .wasm
+        .list()
+        .into_iter()
+        .flat_map(|(_, commands)| commands)
+        .map(|name| CommandSpec {
+            flags: backend.wasm.command_flags(&name).unwrap_or_default(),
+            name,
+            arity: -1,
+            first_key: 0,
+            last_key: 0,
+            step: 0,
+            acl_categories: vec!["slow".to_string()],
+        });
+    BUILTIN_COMMANDS
+        .iter()
+        .map(CommandSpec::from)
+        .chain(backend.plugins.specs())
+        .chain(wasm)
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Commands {
+    All,
+    Count,
+    Info(Vec<String>),
+    List,
+}
+
+impl CommandExecutor for Commands {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let specs = command_specs(backend);
+        match self {
+            Commands::All => RespArray::new(
+                specs
+                    .into_iter()
+                    .map(RespFrame::from)
+                    .collect::<Vec<RespFrame>>(),
+            )
+            .into(),
+            Commands::Count => RespFrame::Integer(specs.len() as i64),
+            Commands::Info(names) => {
+                // unknown names get a nil in their place
+                let data = names
+                    .iter()
+                    .map(|name| {
+                        let name = name.to_ascii_lowercase();
+                        match specs.iter().find(|spec| spec.name == name) {
+                            Some(spec) => spec.clone().into(),
+                            None => RespArray::new_null().into(),
+                        }
+                    })
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new(data).into()
+            }
+            Commands::List => RespArray::new(
+                specs
+                    .into_iter()
+                    .map(|spec| BulkString::new(spec.name).into())
+                    .collect::<Vec<RespFrame>>(),
+            )
+            .into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Commands {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // command | command count | command info [name ...] | command list
+        validate_command_for_more(&value, &["command"], 0)?;
+
+        let mut args = extract_string_args(value, 1)?;
+        if args.is_empty() {
+            return Ok(Commands::All);
+        }
+        let sub = args.remove(0).to_ascii_lowercase();
+        match sub.as_str() {
+            "count" if args.is_empty() => Ok(Commands::Count),
+            "info" => Ok(Commands::Info(args)),
+            "list" if args.is_empty() => Ok(Commands::List),
+            _ => Err(CommandError::InvalidArgument(format!(
+                "Unknown subcommand or wrong number of arguments for 'command {}'",
+                sub
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cmd::is_builtin, RespDecode};
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_commands_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$7\r\ncommand\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Commands = frame.try_into()?;
+        assert_eq!(result, Commands::All);
+
+        buf.extend_from_slice(b"*4\r\n$7\r\ncommand\r\n$4\r\nINFO\r\n$3\r\nget\r\n$3\r\nset\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Commands = frame.try_into()?;
+        assert_eq!(
+            result,
+            Commands::Info(vec!["get".to_string(), "set".to_string()])
+        );
+
+        buf.extend_from_slice(b"*3\r\n$7\r\ncommand\r\n$5\r\ncount\r\n$1\r\nx\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Commands::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_commands_command() {
+        let backend = Backend::new();
+        assert_eq!(
+            Commands::Info(vec!["GET".to_string(), "nope".to_string()]).execute(&backend),
+            RespArray::new([
+                RespArray::new([
+                    BulkString::from("get").into(),
+                    RespFrame::Integer(2),
+                    RespArray::new([
+                        BulkString::from("readonly").into(),
+                        BulkString::from("fast").into()
+                    ])
+                    .into(),
+                    RespFrame::Integer(1),
+                    RespFrame::Integer(1),
+                    RespFrame::Integer(1),
+                    RespArray::new([
+                        BulkString::from("@read").into(),
+                        BulkString::from("@string").into(),
+                        BulkString::from("@fast").into()
+                    ])
+                    .into(),
+                ])
+                .into(),
+                RespArray::new_null().into(),
+            ])
+            .into()
+        );
+        let RespFrame::Integer(count) = Commands::Count.execute(&backend) else {
+            panic!("COMMAND COUNT must reply with an integer");
+        };
+        assert!(count >= BUILTIN_COMMANDS.len() as i64);
+    }
+
+    #[test]
+    fn test_builtin_table_is_complete() {
+        // every name in the table must be served by the dispatcher
+        for (name, ..) in BUILTIN_COMMANDS {
+            assert!(is_builtin(name), "{} is not a command", name);
+        }
+    }
+}
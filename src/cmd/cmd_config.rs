@@ -0,0 +1,388 @@
+use crate::{
+    backend::{format_notify_flags, glob_match, parse_notify_flags},
+    Backend, BulkString, RespArray, RespFrame, SimpleError,
+};
+
+use super::{
+    extract_string_args, validate_command_for_more, CommandError, CommandExecutor, RESP_OK,
+};
+
+// parameters understood by CONFIG GET / CONFIG SET
+const PARAMS: &[&str] = &[
+    "busy-reply-threshold",
+    "client-query-buffer-limit",
+    "lua-time-limit",
+    "notify-keyspace-events",
+    "proto-inline-max-size",
+    "proto-max-bulk-len",
+    "proto-max-multibulk-len",
+    "proto-max-nesting-depth",
+    "pubsub-buffer-limit",
+    "wasm-fuel-limit",
+    "wasm-memory-limit",
+];
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Config {
+    Get(Vec<String>),
+    Set(Vec<(String, String)>),
+}
+
+impl CommandExecutor for Config {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self {
+            Config::Get(patterns) => {
+                let data = PARAMS
+                    .iter()
+                    .filter(|name| {
+                        patterns
+                            .iter()
+                            .any(|p| glob_match(p.as_bytes(), name.as_bytes(), true))
+                    })
+                    .flat_map(|name| {
+                        let value = get_param(backend, name).unwrap_or_default();
+                        vec![
+                            BulkString::from(*name).into(),
+                            BulkString::new(value).into(),
+                        ]
+                    })
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new(data).into()
+            }
+            Config::Set(pairs) => {
+                // validate everything first so a bad pair leaves nothing applied
+                for (name, value) in &pairs {
+                    if let Err(e) = check_param(name, value) {
+                        return SimpleError::new(e).into();
+                    }
+                }
+                for (name, value) in &pairs {
+                    set_param(backend, name, value);
+                }
+                RESP_OK.clone()
+            }
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Config {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // config get parameter [parameter ...] | config set parameter value [parameter value ...]
+        validate_command_for_more(&value, &["config"], 2)?;
+
+        let sub = match value[1] {
+            RespFrame::BulkString(ref s) => s.as_ref().to_ascii_lowercase(),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid subcommand".to_string(),
+                ))
+            }
+        };
+
+        let args = extract_string_args(value, 2)?;
+        match sub.as_slice() {
+            b"get" => Ok(Config::Get(args)),
+            b"set" if args.len() % 2 == 0 => {
+                let pairs = args
+                    .chunks(2)
+                    .map(|kv| (kv[0].to_ascii_lowercase(), kv[1].clone()))
+                    .collect();
+                Ok(Config::Set(pairs))
+            }
+            _ => Err(CommandError::InvalidArgument(format!(
+                "Unknown subcommand or wrong number of arguments for 'config {}'",
+                String::from_utf8_lossy(&sub)
+            ))),
+        }
+    }
+}
+
+fn get_param(backend: &Backend, name: &str) -> Option<String> {
+    match name {
+        "notify-keyspace-events" => Some(format_notify_flags(backend.notify_flags())),
+        "busy-reply-threshold" | "lua-time-limit" => {
+            Some(backend.scripts.busy_threshold().to_string())
+        }
+        "wasm-fuel-limit" => Some(backend.wasm.fuel_limit().to_string()),
+        "wasm-memory-limit" => Some(backend.wasm.memory_limit().to_string()),
+        "pubsub-buffer-limit" => Some(backend.subscriber_buffer_cap().to_string()),
+        "proto-inline-max-size" => Some(backend.decode_limits().max_inline_len.to_string()),
+        "proto-max-bulk-len" => Some(backend.decode_limits().max_bulk_len.to_string()),
+        "proto-max-multibulk-len" => Some(backend.decode_limits().max_multibulk_len.to_string()),
+        "proto-max-nesting-depth" => Some(backend.decode_limits().max_depth.to_string()),
+        "client-query-buffer-limit" => Some(backend.decode_limits().max_query_buffer.to_string()),
+        _ => None,
+    }
+}
+
+fn check_param(name: &str, value: &str) -> Result<(), String> {
+    match valid_param(name, value) {
+        Some(true) => Ok(()),
+        Some(false) => Err(format!(
+            "ERR CONFIG SET failed (possibly related to argument '{}') - Invalid argument '{}'",
+            name, value
+        )),
+        None => Err(format!(
+            "ERR Unknown option or number of arguments for CONFIG SET - '{}'",
+            name
+        )),
+    }
+}
+
+// whether `value` will do for the parameter, None when there's no such
+// parameter; the config file is checked with it too
+pub(crate) fn valid_param(name: &str, value: &str) -> Option<bool> {
+    let valid = match name {
+        "notify-keyspace-events" => parse_notify_flags(value).is_some(),
+        "busy-reply-threshold" | "lua-time-limit" | "wasm-fuel-limit" => {
+            value.parse::<u64>().is_ok()
+        }
+        "proto-max-bulk-len"
+        | "client-query-buffer-limit"
+        | "proto-inline-max-size"
+        | "wasm-memory-limit" => parse_memory(value).is_some_and(|n| n > 0),
+        "proto-max-multibulk-len" | "proto-max-nesting-depth" | "pubsub-buffer-limit" => {
+            value.parse::<usize>().is_ok_and(|n| n > 0)
+        }
+        _ => return None,
+    };
+    Some(valid)
+}
+
+pub(crate) fn set_param(backend: &Backend, name: &str, value: &str) {
+    match name {
+        "notify-keyspace-events" => {
+            if let Some(flags) = parse_notify_flags(value) {
+                backend.set_notify_flags(flags);
+            }
+        }
+        "busy-reply-threshold" | "lua-time-limit" => {
+            if let Ok(ms) = value.parse::<u64>() {
+                backend.scripts.set_busy_threshold(ms);
+            }
+        }
+        "wasm-fuel-limit" => {
+            if let Ok(fuel) = value.parse::<u64>() {
+                backend.wasm.set_fuel_limit(fuel);
+            }
+        }
+        "wasm-memory-limit" => {
+            if let Some(n) = parse_memory(value) {
+                backend.wasm.set_memory_limit(n);
+            }
+        }
+        "pubsub-buffer-limit" => {
+            if let Ok(n) = value.parse::<usize>() {
+                backend.set_subscriber_buffer_cap(n);
+            }
+        }
+        "proto-max-bulk-len" | "client-query-buffer-limit" | "proto-inline-max-size" => {
+            if let Some(n) = parse_memory(value) {
+                backend.update_decode_limits(|limits| match name {
+                    "proto-max-bulk-len" => limits.max_bulk_len = n,
+                    "proto-inline-max-size" => limits.max_inline_len = n,
+                    _ => limits.max_query_buffer = n,
+                });
+            }
+        }
+        "proto-max-multibulk-len" | "proto-max-nesting-depth" => {
+            if let Ok(n) = value.parse::<usize>() {
+                backend.update_decode_limits(|limits| match name {
+                    "proto-max-multibulk-len" => limits.max_multibulk_len = n,
+                    _ => limits.max_depth = n,
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
+// a byte count with an optional unit, "1gb" or "100k" as Redis takes them
+fn parse_memory(value: &str) -> Option<usize> {
+    let value = value.to_ascii_lowercase();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (digits, unit) = value.split_at(split);
+    let unit = match unit {
+        "" | "b" => 1,
+        "k" => 1000,
+        "kb" => 1024,
+        "m" => 1000 * 1000,
+        "mb" => 1024 * 1024,
+        "g" => 1000 * 1000 * 1000,
+        "gb" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    digits.parse::<usize>().ok()?.checked_mul(unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cmd::{Set, Subscribe},
+        session::Session,
+        RespDecode, RespPush,
+    };
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_config_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*4\r\n$6\r\nconfig\r\n$3\r\nSET\r\n$22\r\nnotify-keyspace-events\r\n$3\r\nKEA\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Config = frame.try_into()?;
+        assert_eq!(
+            result,
+            Config::Set(vec![(
+                "notify-keyspace-events".to_string(),
+                "KEA".to_string()
+            )])
+        );
+
+        buf.extend_from_slice(b"*3\r\n$6\r\nconfig\r\n$3\r\nset\r\n$1\r\na\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Config::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_get_set_command() {
+        let backend = Backend::new();
+        let cmd = Config::Set(vec![(
+            "notify-keyspace-events".to_string(),
+            "Ex$".to_string(),
+        )]);
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+
+        let cmd = Config::Get(vec!["notify-*".to_string()]);
+        assert_eq!(
+            cmd.execute(&backend),
+            RespArray::new([
+                BulkString::from("notify-keyspace-events").into(),
+                BulkString::from("$xE").into(),
+            ])
+            .into()
+        );
+
+        let cmd = Config::Set(vec![(
+            "notify-keyspace-events".to_string(),
+            "Q".to_string(),
+        )]);
+        assert!(matches!(cmd.execute(&backend), RespFrame::SimpleError(_)));
+    }
+
+    #[test]
+    fn test_config_decode_limits() {
+        let backend = Backend::new();
+        let cmd = Config::Set(vec![
+            ("proto-max-bulk-len".to_string(), "1mb".to_string()),
+            ("proto-max-nesting-depth".to_string(), "4".to_string()),
+        ]);
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.decode_limits().max_bulk_len, 1024 * 1024);
+        assert_eq!(backend.decode_limits().max_depth, 4);
+
+        let cmd = Config::Get(vec!["proto-max-*".to_string()]);
+        assert_eq!(
+            cmd.execute(&backend),
+            RespArray::new([
+                BulkString::from("proto-max-bulk-len").into(),
+                BulkString::from("1048576").into(),
+                BulkString::from("proto-max-multibulk-len").into(),
+                BulkString::from("1048576").into(),
+                BulkString::from("proto-max-nesting-depth").into(),
+                BulkString::from("4").into(),
+            ])
+            .into()
+        );
+
+        for (name, value) in [
+            ("client-query-buffer-limit", "1tb"),
+            ("proto-max-multibulk-len", "0"),
+        ] {
+            let cmd = Config::Set(vec![(name.to_string(), value.to_string())]);
+            assert!(matches!(cmd.execute(&backend), RespFrame::SimpleError(_)));
+        }
+        assert_eq!(parse_memory("100K"), Some(100_000));
+    }
+
+    #[test]
+    fn test_config_buffer_limits() {
+        let backend = Backend::new();
+        let cmd = Config::Set(vec![
+            ("proto-inline-max-size".to_string(), "1k".to_string()),
+            ("pubsub-buffer-limit".to_string(), "2".to_string()),
+            ("wasm-memory-limit".to_string(), "1kb".to_string()),
+        ]);
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.decode_limits().max_inline_len, 1000);
+
+        let cmd = Config::Get(vec!["pubsub-*".to_string(), "wasm-memory-*".to_string()]);
+        assert_eq!(
+            cmd.execute(&backend),
+            RespArray::new([
+                BulkString::from("pubsub-buffer-limit").into(),
+                BulkString::from("2").into(),
+                BulkString::from("wasm-memory-limit").into(),
+                BulkString::from("1024").into(),
+            ])
+            .into()
+        );
+
+        // connections accepted from now on get the smaller buffer
+        let (session, _rx) = Session::new(backend.clone());
+        let subscriber = session.subscriber();
+        assert!(subscriber.send(RespPush::new([])));
+        assert!(subscriber.send(RespPush::new([])));
+        assert!(!subscriber.send(RespPush::new([])));
+        assert!(session.is_overflowed());
+
+        // the module asks for a 64KB page
+        assert!(backend
+            .wasm
+            .load(&backend, "copy", crate::wasm::tests::MODULE.as_bytes())
+            .is_err());
+    }
+
+    #[test]
+    fn test_keyevent_notification_on_set() {
+        let backend = Backend::new();
+        let (mut session, mut rx) = Session::new(backend.clone());
+        let cmd = Subscribe {
+            channels: vec!["__keyevent@0__:set".into()],
+        };
+        cmd.execute_with(&backend, &mut session);
+
+        Config::Set(vec![(
+            "notify-keyspace-events".to_string(),
+            "E$".to_string(),
+        )])
+        .execute(&backend);
+
+        let cmd = Set {
+            key: "hello".into(),
+            value: BulkString::from("world").into(),
+        };
+        cmd.execute(&backend);
+
+        let message = rx.try_recv().unwrap();
+        assert_eq!(
+            session.push_message(message),
+            RespArray::new([
+                BulkString::from("message").into(),
+                BulkString::from("__keyevent@0__:set").into(),
+                BulkString::from("hello").into(),
+            ])
+            .into()
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{session::Session, Backend, RespArray, RespFrame, SimpleError};
+
+use super::{connection_only, validate_command, CommandError, CommandExecutor, RESP_OK};
+
+#[derive(Debug)]
+pub(crate) struct Discard;
+
+impl CommandExecutor for Discard {
+    fn execute(self, _: &Backend) -> RespFrame {
+        connection_only("DISCARD")
+    }
+
+    fn execute_with(self, _: &Backend, session: &mut Session) -> RespFrame {
+        if session.multi.take().is_none() {
+            return SimpleError::new("ERR DISCARD without MULTI").into();
+        }
+        session.multi_error = false;
+        session.unwatch();
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for Discard {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["discard"], 0)?;
+        Ok(Discard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cmd::Multi, RespDecode};
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_discard_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$7\r\ndiscard\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let _: Discard = frame.try_into()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_discard_command() {
+        let backend = Backend::new();
+        let (mut session, _rx) = Session::new(backend.clone());
+
+        assert_eq!(
+            Discard.execute_with(&backend, &mut session),
+            SimpleError::new("ERR DISCARD without MULTI").into()
+        );
+
+        Multi.execute_with(&backend, &mut session);
+        assert_eq!(
+            Discard.execute_with(&backend, &mut session),
+            RESP_OK.clone()
+        );
+        assert!(!session.in_multi());
+    }
+}
@@ -0,0 +1,150 @@
+use bytes::Bytes;
+
+use crate::{script::Script, Backend, RespArray, RespFrame};
+
+use super::{
+    extract_bytes_args, split_keys, validate_command_for_more, CommandError, CommandExecutor,
+};
+
+/// EVAL, EVALSHA and their read-only variants EVAL_RO / EVALSHA_RO.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Eval {
+    pub(crate) script: Script,
+    pub(crate) keys: Vec<Bytes>,
+    pub(crate) args: Vec<Bytes>,
+    pub(crate) read_only: bool,
+}
+
+impl CommandExecutor for Eval {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        backend
+            .scripts
+            .eval(backend, &self.script, self.keys, self.args, self.read_only)
+    }
+}
+
+impl TryFrom<RespArray> for Eval {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // eval script numkeys [key [key ...]] [arg [arg ...]]
+        let name = match value.first() {
+            Some(RespFrame::BulkString(s)) => match s.as_ref().to_ascii_lowercase().as_slice() {
+                b"eval" => "eval",
+                b"evalsha" => "evalsha",
+                b"eval_ro" => "eval_ro",
+                b"evalsha_ro" => "evalsha_ro",
+                _ => "eval",
+            },
+            _ => "eval",
+        };
+        validate_command_for_more(&value, &[name], 2)?;
+
+        let mut args = extract_bytes_args(value, 1)?;
+        let script = String::from_utf8(args.remove(0).into())?;
+        let (keys, args) = split_keys(args)?;
+
+        Ok(Eval {
+            script: if name.starts_with("evalsha") {
+                Script::Sha(script)
+            } else {
+                Script::Body(script)
+            },
+            keys,
+            args,
+            read_only: name.ends_with("_ro"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BulkString, RespDecode};
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_eval_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$4\r\nEVAL\r\n$8\r\nreturn 1\r\n$1\r\n1\r\n$3\r\nfoo\r\n$3\r\nbar\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Eval = frame.try_into()?;
+        assert_eq!(
+            result,
+            Eval {
+                script: Script::Body("return 1".to_string()),
+                keys: vec!["foo".into()],
+                args: vec!["bar".into()],
+                read_only: false,
+            }
+        );
+
+        buf.extend_from_slice(b"*3\r\n$10\r\nevalsha_ro\r\n$3\r\nabc\r\n$1\r\n0\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Eval = frame.try_into()?;
+        assert_eq!(result.script, Script::Sha("abc".to_string()));
+        assert!(result.read_only);
+
+        buf.extend_from_slice(b"*3\r\n$4\r\neval\r\n$8\r\nreturn 1\r\n$1\r\n1\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Eval::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_eval_command() {
+        let backend = Backend::new();
+        let cmd = Eval {
+            script: Script::Body(
+                "redis.call('sadd', KEYS[1], ARGV[1]) return redis.call('sismember', KEYS[1], ARGV[1])"
+                    .to_string(),
+            ),
+            keys: vec!["myset".into()],
+            args: vec!["a".into()],
+            read_only: false,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let cmd = Eval {
+            script: Script::Body("return redis.call('get', 'missing')".to_string()),
+            keys: vec![],
+            args: vec![],
+            read_only: true,
+        };
+        assert_eq!(cmd.execute(&backend), BulkString::new_null().into());
+    }
+
+    #[test]
+    fn test_eval_binary_keys_and_args() -> Result<()> {
+        let backend = Backend::new();
+        backend.set(
+            Bytes::from_static(b"\xff\x01"),
+            BulkString::from("bar").into(),
+        );
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*4\r\n$4\r\nEVAL\r\n$33\r\nreturn redis.call('GET', KEYS[1])\r\n$1\r\n1\r\n$2\r\n\xff\x01\r\n",
+        );
+        let cmd = Eval::try_from(RespArray::decode(&mut buf)?)?;
+        assert_eq!(cmd.execute(&backend), BulkString::from("bar").into());
+
+        let cmd = Eval {
+            script: Script::Body("return ARGV[1]".to_string()),
+            keys: vec![],
+            args: vec![Bytes::from_static(b"\x00\xfe")],
+            read_only: false,
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            BulkString::new(b"\x00\xfe".to_vec()).into()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{session::Session, Backend, RespArray, RespFrame, SimpleError};
+
+use super::{connection_only, validate_command, CommandError, CommandExecutor};
+
+#[derive(Debug)]
+pub(crate) struct Exec;
+
+impl CommandExecutor for Exec {
+    fn execute(self, _: &Backend) -> RespFrame {
+        connection_only("EXEC")
+    }
+
+    // runs with the backend locked exclusively, see `Command::needs_exclusive`
+    fn execute_with(self, backend: &Backend, session: &mut Session) -> RespFrame {
+        let Some(queued) = session.multi.take() else {
+            return SimpleError::new("ERR EXEC without MULTI").into();
+        };
+        let aborted = std::mem::take(&mut session.multi_error);
+        let changed = session.watched_keys_changed();
+        session.unwatch();
+
+        if aborted {
+            return SimpleError::new("EXECABORT Transaction discarded because of previous errors.")
+                .into();
+        }
+        if changed {
+            return RespArray::new_null().into();
+        }
+
+        let replies = queued
+            .into_iter()
+            .map(|cmd| cmd.execute_with(backend, session))
+            .collect::<Vec<RespFrame>>();
+        RespArray::new(replies).into()
+    }
+}
+
+impl TryFrom<RespArray> for Exec {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["exec"], 0)?;
+        Ok(Exec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cmd::{Command, Get, Multi, Set, Watch, RESP_OK},
+        BulkString, RespDecode,
+    };
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_exec_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$4\r\nexec\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let _: Exec = frame.try_into()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_exec_commands() {
+        let backend = Backend::new();
+        let (mut session, _rx) = Session::new(backend.clone());
+
+        Multi.execute_with(&backend, &mut session);
+        let queue = session.multi.as_mut().unwrap();
+        queue.push(Command::Set(Set {
+            key: "hello".into(),
+            value: BulkString::from("world").into(),
+        }));
+        queue.push(Command::Get(Get {
+            key: "hello".into(),
+        }));
+
+        let result = Exec.execute_with(&backend, &mut session);
+        assert_eq!(
+            result,
+            RespArray::new([RESP_OK.clone(), BulkString::from("world").into()]).into()
+        );
+        assert!(!session.in_multi());
+    }
+
+    #[test]
+    fn test_exec_with_modified_watched_key() {
+        let backend = Backend::new();
+        let (mut session, _rx) = Session::new(backend.clone());
+
+        let cmd = Watch {
+            keys: vec!["stock".into()],
+        };
+        cmd.execute_with(&backend, &mut session);
+
+        // another client changes the key between WATCH and EXEC
+        let cmd = Set {
+            key: "stock".into(),
+            value: RespFrame::Integer(9),
+        };
+        cmd.execute(&backend);
+
+        Multi.execute_with(&backend, &mut session);
+        session.multi.as_mut().unwrap().push(Command::Set(Set {
+            key: "stock".into(),
+            value: RespFrame::Integer(8),
+        }));
+
+        let result = Exec.execute_with(&backend, &mut session);
+        assert_eq!(result, RespArray::new_null().into());
+        assert_eq!(backend.get(b"stock"), Some(RespFrame::Integer(9)));
+    }
+
+    #[test]
+    fn test_exec_abort() {
+        let backend = Backend::new();
+        let (mut session, _rx) = Session::new(backend.clone());
+
+        Multi.execute_with(&backend, &mut session);
+        session.multi_error = true;
+
+        let result = Exec.execute_with(&backend, &mut session);
+        assert_eq!(
+            result,
+            SimpleError::new("EXECABORT Transaction discarded because of previous errors.").into()
+        );
+        assert!(!session.in_multi());
+    }
+}
@@ -0,0 +1,101 @@
+use bytes::Bytes;
+
+use crate::{Backend, RespArray, RespFrame};
+
+use super::{
+    extract_bytes_args, split_keys, validate_command_for_more, CommandError, CommandExecutor,
+};
+
+/// FCALL and its read-only variant FCALL_RO.
+#[derive(Debug, PartialEq)]
+pub(crate) struct FCall {
+    pub(crate) function: String,
+    pub(crate) keys: Vec<Bytes>,
+    pub(crate) args: Vec<Bytes>,
+    pub(crate) read_only: bool,
+}
+
+impl CommandExecutor for FCall {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        backend.scripts.fcall(
+            backend,
+            &self.function,
+            self.keys,
+            self.args,
+            self.read_only,
+        )
+    }
+}
+
+impl TryFrom<RespArray> for FCall {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // fcall function numkeys [key [key ...]] [arg [arg ...]]
+        let read_only = matches!(value.first(), Some(RespFrame::BulkString(s)) if s.as_ref().eq_ignore_ascii_case(b"fcall_ro"));
+        let name = if read_only { "fcall_ro" } else { "fcall" };
+        validate_command_for_more(&value, &[name], 2)?;
+
+        let mut args = extract_bytes_args(value, 1)?;
+        let function = String::from_utf8(args.remove(0).into())?;
+        let (keys, args) = split_keys(args)?;
+        Ok(FCall {
+            function,
+            keys,
+            args,
+            read_only,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{RespDecode, SimpleError};
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_fcall_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$8\r\nFCALL_RO\r\n$1\r\nf\r\n$1\r\n1\r\n$3\r\nfoo\r\n$3\r\nbar\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: FCall = frame.try_into()?;
+        assert_eq!(
+            result,
+            FCall {
+                function: "f".to_string(),
+                keys: vec!["foo".into()],
+                args: vec!["bar".into()],
+                read_only: true,
+            }
+        );
+
+        buf.extend_from_slice(b"*3\r\n$5\r\nfcall\r\n$1\r\nf\r\n$2\r\n-1\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(FCall::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fcall_command() {
+        let backend = Backend::new();
+        let code = "#!lua name=lib\nredis.register_function{function_name='ro', callback=function() return redis.call('set', 'k', 'v') end, flags={'no-writes'}}";
+        backend.scripts.function_load(code, false).unwrap();
+
+        let cmd = FCall {
+            function: "ro".to_string(),
+            keys: vec![],
+            args: vec![],
+            read_only: false,
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR Write commands are not allowed from read-only scripts.").into()
+        );
+    }
+}
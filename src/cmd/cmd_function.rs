@@ -0,0 +1,285 @@
+use crate::{glob_match, script::RestorePolicy, Backend, BulkString, RespArray, RespFrame};
+
+use super::{
+    extract_string_args, validate_command_for_more, CommandError, CommandExecutor, RESP_OK,
+};
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Function {
+    Load {
+        code: String,
+        replace: bool,
+    },
+    List {
+        pattern: Option<String>,
+        with_code: bool,
+    },
+    Delete(String),
+    Dump,
+    Restore {
+        payload: String,
+        policy: RestorePolicy,
+    },
+    Flush,
+}
+
+impl CommandExecutor for Function {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let scripts = &backend.scripts;
+        match self {
+            Function::Load { code, replace } => match scripts.function_load(&code, replace) {
+                Ok(name) => BulkString::new(name).into(),
+                Err(e) => e.into(),
+            },
+            Function::List { pattern, with_code } => {
+                let data = scripts
+                    .libraries()
+                    .into_iter()
+                    .filter(|library| {
+                        pattern.as_ref().is_none_or(|p| {
+                            glob_match(p.as_bytes(), library.name.as_bytes(), false)
+                        })
+                    })
+                    .map(|library| {
+                        let functions = library
+                            .functions
+                            .into_iter()
+                            .map(|(name, info)| {
+                                let description = match info.description {
+                                    Some(description) => BulkString::new(description),
+                                    None => BulkString::new_null(),
+                                };
+                                let flags = info
+                                    .flags
+                                    .into_iter()
+                                    .map(|flag| BulkString::new(flag).into())
+                                    .collect::<Vec<RespFrame>>();
+                                RespArray::new([
+                                    BulkString::from("name").into(),
+                                    BulkString::new(name).into(),
+                                    BulkString::from("description").into(),
+                                    description.into(),
+                                    BulkString::from("flags").into(),
+                                    RespArray::new(flags).into(),
+                                ])
+                                .into()
+                            })
+                            .collect::<Vec<RespFrame>>();
+                        let mut fields = vec![
+                            BulkString::from("library_name").into(),
+                            BulkString::new(library.name).into(),
+                            BulkString::from("engine").into(),
+                            BulkString::from("LUA").into(),
+                            BulkString::from("functions").into(),
+                            RespArray::new(functions).into(),
+                        ];
+                        if with_code {
+                            fields.push(BulkString::from("library_code").into());
+                            fields.push(BulkString::new(library.code).into());
+                        }
+                        RespArray::new(fields).into()
+                    })
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new(data).into()
+            }
+            Function::Delete(name) => match scripts.function_delete(&name) {
+                Ok(()) => RESP_OK.clone(),
+                Err(e) => e.into(),
+            },
+            Function::Dump => BulkString::new(scripts.function_dump()).into(),
+            Function::Restore { payload, policy } => {
+                match scripts.function_restore(&payload, policy) {
+                    Ok(()) => RESP_OK.clone(),
+                    Err(e) => e.into(),
+                }
+            }
+            Function::Flush => {
+                scripts.function_flush();
+                RESP_OK.clone()
+            }
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Function {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // function load [replace] code | function list [libraryname pattern] [withcode]
+        // | function delete name | function dump | function restore payload [flush|append|replace]
+        // | function flush [async|sync]
+        validate_command_for_more(&value, &["function"], 1)?;
+
+        let sub = match value[1] {
+            RespFrame::BulkString(ref s) => s.as_ref().to_ascii_lowercase(),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid subcommand".to_string(),
+                ))
+            }
+        };
+
+        let mut args = extract_string_args(value, 2)?;
+        let options = args
+            .iter()
+            .map(|arg| arg.to_ascii_lowercase())
+            .collect::<Vec<String>>();
+        let options = options.iter().map(String::as_str).collect::<Vec<&str>>();
+        match (sub.as_slice(), options.as_slice()) {
+            (b"load", [_]) => Ok(Function::Load {
+                code: args.remove(0),
+                replace: false,
+            }),
+            (b"load", ["replace", _]) => Ok(Function::Load {
+                code: args.remove(1),
+                replace: true,
+            }),
+            (b"list", _) => {
+                let mut pattern = None;
+                let mut with_code = false;
+                let mut i = 0;
+                while i < options.len() {
+                    match options[i] {
+                        "withcode" => with_code = true,
+                        "libraryname" if i + 1 < options.len() => {
+                            i += 1;
+                            pattern = Some(args[i].clone());
+                        }
+                        _ => return Err(unknown_subcommand(&sub)),
+                    }
+                    i += 1;
+                }
+                Ok(Function::List { pattern, with_code })
+            }
+            (b"delete", [_]) => Ok(Function::Delete(args.remove(0))),
+            (b"dump", []) => Ok(Function::Dump),
+            (b"restore", [_, rest @ ..]) => {
+                let policy = match rest {
+                    [] | ["append"] => RestorePolicy::Append,
+                    ["replace"] => RestorePolicy::Replace,
+                    ["flush"] => RestorePolicy::Flush,
+                    _ => return Err(unknown_subcommand(&sub)),
+                };
+                Ok(Function::Restore {
+                    payload: args.remove(0),
+                    policy,
+                })
+            }
+            // the flush is always synchronous, the mode is accepted for compatibility
+            (b"flush", [] | ["async"] | ["sync"]) => Ok(Function::Flush),
+            _ => Err(unknown_subcommand(&sub)),
+        }
+    }
+}
+
+fn unknown_subcommand(sub: &[u8]) -> CommandError {
+    CommandError::InvalidArgument(format!(
+        "Unknown subcommand or wrong number of arguments for 'function {}'",
+        String::from_utf8_lossy(sub)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RespDecode;
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_function_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*4\r\n$8\r\nfunction\r\n$4\r\nLOAD\r\n$7\r\nREPLACE\r\n$4\r\ncode\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Function = frame.try_into()?;
+        assert_eq!(
+            result,
+            Function::Load {
+                code: "code".to_string(),
+                replace: true
+            }
+        );
+
+        buf.extend_from_slice(
+            b"*5\r\n$8\r\nfunction\r\n$4\r\nlist\r\n$8\r\nWITHCODE\r\n$11\r\nlibraryname\r\n$2\r\nm*\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Function = frame.try_into()?;
+        assert_eq!(
+            result,
+            Function::List {
+                pattern: Some("m*".to_string()),
+                with_code: true
+            }
+        );
+
+        buf.extend_from_slice(
+            b"*4\r\n$8\r\nfunction\r\n$7\r\nrestore\r\n$1\r\np\r\n$7\r\nREPLACE\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Function = frame.try_into()?;
+        assert_eq!(
+            result,
+            Function::Restore {
+                payload: "p".to_string(),
+                policy: RestorePolicy::Replace
+            }
+        );
+
+        buf.extend_from_slice(b"*3\r\n$8\r\nfunction\r\n$4\r\ndump\r\n$1\r\nx\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Function::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_function_list_command() {
+        let backend = Backend::new();
+        let code = "#!lua name=mylib\nredis.register_function('f', function() return 1 end)";
+        assert_eq!(
+            Function::Load {
+                code: code.to_string(),
+                replace: false
+            }
+            .execute(&backend),
+            BulkString::from("mylib").into()
+        );
+
+        let cmd = Function::List {
+            pattern: Some("my*".to_string()),
+            with_code: false,
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            RespArray::new([RespArray::new([
+                BulkString::from("library_name").into(),
+                BulkString::from("mylib").into(),
+                BulkString::from("engine").into(),
+                BulkString::from("LUA").into(),
+                BulkString::from("functions").into(),
+                RespArray::new([RespArray::new([
+                    BulkString::from("name").into(),
+                    BulkString::from("f").into(),
+                    BulkString::from("description").into(),
+                    BulkString::new_null().into(),
+                    BulkString::from("flags").into(),
+                    RespArray::new([]).into(),
+                ])
+                .into()])
+                .into(),
+            ])
+            .into()])
+            .into()
+        );
+
+        let cmd = Function::List {
+            pattern: Some("other*".to_string()),
+            with_code: false,
+        };
+        assert_eq!(cmd.execute(&backend), RespArray::new([]).into());
+    }
+}
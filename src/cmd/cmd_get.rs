@@ -1,17 +1,22 @@
-use crate::{Backend, BulkString, RespArray, RespFrame};
+use bytes::Bytes;
+
+use crate::{Backend, BulkString, RespArray, RespFrame, NOTIFY_KEY_MISS};
 
 use super::{extract_args, validate_command, CommandError, CommandExecutor};
 
 #[derive(Debug)]
 pub(crate) struct Get {
-    pub(crate) key: String,
+    pub(crate) key: Bytes,
 }
 
 impl CommandExecutor for Get {
     fn execute(self, backend: &Backend) -> RespFrame {
         match backend.get(&self.key) {
             Some(value) => value,
-            None => RespFrame::BulkString(BulkString::new_null()),
+            None => {
+                backend.notify_keyspace_event(NOTIFY_KEY_MISS, "keymiss", &self.key);
+                RespFrame::BulkString(BulkString::new_null())
+            }
         }
     }
 }
@@ -26,7 +31,7 @@ impl TryFrom<RespArray> for Get {
 
         match args.next() {
             Some(RespFrame::BulkString(key)) => Ok(Get {
-                key: String::try_from(key)?,
+                key: Bytes::try_from(key)?,
             }),
             _ => Err(CommandError::InvalidCommand("Invalid key".to_string())),
         }
@@ -74,14 +79,14 @@ mod tests {
     fn test_set_get_command() -> Result<()> {
         let backend = Backend::new();
         let cmd = Set {
-            key: "hello".to_string(),
+            key: "hello".into(),
             value: RespFrame::BulkString(b"world".into()),
         };
         let result = cmd.execute(&backend);
         assert_eq!(result, RESP_OK.clone());
 
         let cmd = Get {
-            key: "hello".to_string(),
+            key: "hello".into(),
         };
         let result = cmd.execute(&backend);
         assert_eq!(result, RespFrame::BulkString(b"world".into()));
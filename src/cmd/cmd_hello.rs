@@ -0,0 +1,204 @@
+use crate::{session::Session, Backend, BulkString, RespArray, RespFrame, RespMap, SimpleError};
+
+use super::{
+    connection_only, extract_string_args, validate_command_for_more, CommandError, CommandExecutor,
+};
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Hello {
+    protocol: Option<i64>,
+    auth: Option<(String, String)>,
+    name: Option<String>,
+}
+
+impl CommandExecutor for Hello {
+    fn execute(self, _: &Backend) -> RespFrame {
+        connection_only("HELLO")
+    }
+
+    fn execute_with(self, _: &Backend, session: &mut Session) -> RespFrame {
+        if let Some(protocol) = self.protocol {
+            if !(2..=3).contains(&protocol) {
+                return SimpleError::new("NOPROTO unsupported protocol version").into();
+            }
+        }
+        // there are no ACL users yet, only the passwordless default one
+        if let Some((user, _)) = &self.auth {
+            if user != "default" {
+                return SimpleError::new(
+                    "WRONGPASS invalid username-password pair or user is disabled.",
+                )
+                .into();
+            }
+        }
+        if let Some(name) = self.name {
+            if let Err(e) = session.set_name(name) {
+                return e.into();
+            }
+        }
+        if let Some(protocol) = self.protocol {
+            session.set_protocol(protocol as u8);
+        }
+
+        let mut info = RespMap::new();
+        info.insert(
+            BulkString::from("server").into(),
+            BulkString::from("redis").into(),
+        );
+        info.insert(
+            BulkString::from("version").into(),
+            BulkString::from(env!("CARGO_PKG_VERSION")).into(),
+        );
+        info.insert(
+            BulkString::from("proto").into(),
+            RespFrame::Integer(session.protocol as i64),
+        );
+        info.insert(
+            BulkString::from("id").into(),
+            RespFrame::Integer(session.id as i64),
+        );
+        info.insert(
+            BulkString::from("mode").into(),
+            BulkString::from("standalone").into(),
+        );
+        info.insert(
+            BulkString::from("role").into(),
+            BulkString::from("master").into(),
+        );
+        info.insert(
+            BulkString::from("modules").into(),
+            RespArray::new([]).into(),
+        );
+        info.into()
+    }
+}
+
+impl TryFrom<RespArray> for Hello {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // hello [protover [auth username password] [setname clientname]]
+        validate_command_for_more(&value, &["hello"], 0)?;
+
+        let mut args = extract_string_args(value, 1)?.into_iter();
+        let mut hello = Hello {
+            protocol: None,
+            auth: None,
+            name: None,
+        };
+        let Some(protocol) = args.next() else {
+            return Ok(hello);
+        };
+        match protocol.parse::<i64>() {
+            Ok(protocol) => hello.protocol = Some(protocol),
+            Err(_) => {
+                return Err(CommandError::InvalidArgument(
+                    "Protocol version is not an integer or out of range".to_string(),
+                ))
+            }
+        }
+        while let Some(option) = args.next() {
+            match option.to_ascii_lowercase().as_str() {
+                "auth" => match (args.next(), args.next()) {
+                    (Some(user), Some(pass)) => hello.auth = Some((user, pass)),
+                    _ => {
+                        return Err(CommandError::InvalidArgument(
+                            "Syntax error in HELLO option 'auth'".to_string(),
+                        ))
+                    }
+                },
+                "setname" => match args.next() {
+                    Some(name) => hello.name = Some(name),
+                    None => {
+                        return Err(CommandError::InvalidArgument(
+                            "Syntax error in HELLO option 'setname'".to_string(),
+                        ))
+                    }
+                },
+                _ => {
+                    return Err(CommandError::InvalidArgument(format!(
+                        "Syntax error in HELLO option '{}'",
+                        option
+                    )))
+                }
+            }
+        }
+        Ok(hello)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RespDecode;
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_hello_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$5\r\nhello\r\n$1\r\n3\r\n$7\r\nSETNAME\r\n$3\r\napp\r\n$4\r\nAUTH\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Hello::try_from(frame).is_err());
+
+        buf.extend_from_slice(b"*4\r\n$5\r\nhello\r\n$1\r\n3\r\n$7\r\nSETNAME\r\n$3\r\napp\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Hello = frame.try_into()?;
+        assert_eq!(
+            result,
+            Hello {
+                protocol: Some(3),
+                auth: None,
+                name: Some("app".to_string()),
+            }
+        );
+
+        buf.extend_from_slice(b"*2\r\n$5\r\nhello\r\n$5\r\nthree\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Hello::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hello_command() {
+        let backend = Backend::new();
+        let (mut session, _) = Session::new(backend.clone());
+        let hello = Hello {
+            protocol: Some(3),
+            auth: Some(("default".to_string(), "secret".to_string())),
+            name: Some("app".to_string()),
+        };
+        let RespFrame::Map(info) = hello.execute_with(&backend, &mut session) else {
+            panic!("HELLO must reply with a map");
+        };
+        assert_eq!(info["proto"], RespFrame::Integer(3));
+        assert_eq!(info["id"], RespFrame::Integer(session.id as i64));
+        assert_eq!(session.protocol, 3);
+        assert_eq!(session.name.as_deref(), Some("app"));
+
+        let hello = Hello {
+            protocol: Some(2),
+            auth: Some(("admin".to_string(), "secret".to_string())),
+            name: None,
+        };
+        assert!(matches!(
+            hello.execute_with(&backend, &mut session),
+            RespFrame::SimpleError(_)
+        ));
+        assert_eq!(session.protocol, 3);
+
+        let hello = Hello {
+            protocol: Some(4),
+            auth: None,
+            name: None,
+        };
+        assert_eq!(
+            hello.execute_with(&backend, &mut session),
+            SimpleError::new("NOPROTO unsupported protocol version").into()
+        );
+    }
+}
@@ -1,18 +1,27 @@
-use crate::{BulkString, RespArray, RespFrame};
+use bytes::Bytes;
+
+use crate::{Backend, BulkString, RespArray, RespFrame, NOTIFY_KEY_MISS};
 
 use super::{extract_args, validate_command, CommandError, CommandExecutor};
 
 #[derive(Debug)]
 pub(crate) struct HGet {
-    pub(crate) key: String,
-    pub(crate) field: String,
+    pub(crate) key: Bytes,
+    pub(crate) field: Bytes,
 }
 
 impl CommandExecutor for HGet {
-    fn execute(self, backend: &crate::Backend) -> RespFrame {
-        match backend.hget(&self.key, &self.field) {
-            Some(value) => value,
-            None => RespFrame::BulkString(BulkString::new_null()),
+    fn execute(self, backend: &Backend) -> RespFrame {
+        backend.track_read(&self.key);
+        match backend.hmap.get(&self.key) {
+            Some(hmap) => match hmap.get(&self.field) {
+                Some(value) => value.value().clone(),
+                None => RespFrame::BulkString(BulkString::new_null()),
+            },
+            None => {
+                backend.notify_keyspace_event(NOTIFY_KEY_MISS, "keymiss", &self.key);
+                RespFrame::BulkString(BulkString::new_null())
+            }
         }
     }
 }
@@ -26,8 +35,8 @@ impl TryFrom<RespArray> for HGet {
         let mut args = extract_args(value, 1)?.into_iter();
         match (args.next(), args.next()) {
             (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(field))) => Ok(HGet {
-                key: String::try_from(key)?,
-                field: String::try_from(field)?,
+                key: Bytes::try_from(key)?,
+                field: Bytes::try_from(field)?,
             }),
             _ => Err(CommandError::InvalidArgument(
                 "Invalid key or field".to_string(),
@@ -38,7 +47,11 @@ impl TryFrom<RespArray> for HGet {
 
 #[cfg(test)]
 mod tests {
-    use crate::RespDecode;
+    use crate::{
+        cmd::{Client, HSet},
+        session::Session,
+        RespDecode, TrackingOptions,
+    };
 
     use super::*;
     use anyhow::Result;
@@ -57,4 +70,31 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_hget_is_tracked() {
+        let backend = Backend::new();
+        let (mut session, mut push_rx) = Session::new(backend.clone());
+        session.protocol = 3;
+        Client::Tracking(Some(TrackingOptions::default())).execute_with(&backend, &mut session);
+
+        let hget = HGet {
+            key: "map".into(),
+            field: "hello".into(),
+        };
+        session.caller().run(|| hget.execute(&backend));
+        HSet {
+            key: "map".into(),
+            field: "hello".into(),
+            value: BulkString::from("world").into(),
+        }
+        .execute(&backend);
+
+        let message = push_rx.try_recv().unwrap();
+        assert_eq!(message[0], BulkString::from("invalidate").into());
+        assert_eq!(
+            message[1],
+            RespArray::new([BulkString::from("map").into()]).into()
+        );
+    }
 }
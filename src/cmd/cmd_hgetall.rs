@@ -1,15 +1,18 @@
-use crate::{Backend, BulkString, RespArray, RespFrame};
+use bytes::Bytes;
+
+use crate::{Backend, BulkString, RespArray, RespFrame, NOTIFY_KEY_MISS};
 
 use super::{extract_args, validate_command, CommandError, CommandExecutor};
 
 #[derive(Debug)]
 pub(crate) struct HGetAll {
-    pub(crate) key: String,
+    pub(crate) key: Bytes,
     pub(crate) sort: bool,
 }
 
 impl CommandExecutor for HGetAll {
     fn execute(self, backend: &Backend) -> RespFrame {
+        backend.track_read(&self.key);
         let hmap = backend.hmap.get(&self.key);
         match hmap {
             Some(hmap) => {
@@ -30,7 +33,10 @@ impl CommandExecutor for HGetAll {
 
                 RespArray::new(ret).into()
             }
-            None => RespArray::new([]).into(),
+            None => {
+                backend.notify_keyspace_event(NOTIFY_KEY_MISS, "keymiss", &self.key);
+                RespArray::new([]).into()
+            }
         }
     }
 }
@@ -44,7 +50,7 @@ impl TryFrom<RespArray> for HGetAll {
         let mut args = extract_args(value, 1)?.into_iter();
         match args.next() {
             Some(RespFrame::BulkString(key)) => Ok(HGetAll {
-                key: String::try_from(key)?,
+                key: Bytes::try_from(key)?,
                 sort: false,
             }),
             _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
@@ -80,29 +86,29 @@ mod tests {
     fn test_hset_hget_hgetall_commands() -> Result<()> {
         let backend = crate::Backend::new();
         let cmd = HSet {
-            key: "map".to_string(),
-            field: "hello".to_string(),
+            key: "map".into(),
+            field: "hello".into(),
             value: RespFrame::BulkString(b"world".into()),
         };
         let result = cmd.execute(&backend);
         assert_eq!(result, RESP_OK.clone());
 
         let cmd = HSet {
-            key: "map".to_string(),
-            field: "hello1".to_string(),
+            key: "map".into(),
+            field: "hello1".into(),
             value: RespFrame::BulkString(b"world1".into()),
         };
         cmd.execute(&backend);
 
         let cmd = HGet {
-            key: "map".to_string(),
-            field: "hello".to_string(),
+            key: "map".into(),
+            field: "hello".into(),
         };
         let result = cmd.execute(&backend);
         assert_eq!(result, RespFrame::BulkString(b"world".into()));
 
         let cmd = HGetAll {
-            key: "map".to_string(),
+            key: "map".into(),
             sort: true,
         };
         let result = cmd.execute(&backend);
@@ -1,15 +1,18 @@
-use crate::{Backend, BulkString, RespArray, RespFrame};
+use bytes::Bytes;
+
+use crate::{Backend, BulkString, RespArray, RespFrame, NOTIFY_KEY_MISS};
 
 use super::{extract_args, validate_command_for_more, CommandError, CommandExecutor};
 
 #[derive(Debug)]
 pub(crate) struct HMGet {
-    key: String,
-    fields: Vec<String>,
+    key: Bytes,
+    fields: Vec<Bytes>,
 }
 
 impl CommandExecutor for HMGet {
     fn execute(self, backend: &Backend) -> RespFrame {
+        backend.track_read(&self.key);
         let hmap = backend.hmap.get(&self.key);
         match hmap {
             Some(hmap) => {
@@ -25,7 +28,10 @@ impl CommandExecutor for HMGet {
 
                 RespArray::new(data).into()
             }
-            None => RespArray::new([]).into(),
+            None => {
+                backend.notify_keyspace_event(NOTIFY_KEY_MISS, "keymiss", &self.key);
+                RespArray::new([]).into()
+            }
         }
     }
 }
@@ -40,7 +46,7 @@ impl TryFrom<RespArray> for HMGet {
         let args = extract_args(value, 1)?.into_iter();
 
         let mut ret = HMGet {
-            key: String::from(""),
+            key: Bytes::new(),
             fields: vec![],
         };
 
@@ -48,9 +54,9 @@ impl TryFrom<RespArray> for HMGet {
             match frame {
                 RespFrame::BulkString(field) => {
                     if i == 0 {
-                        ret.key = String::try_from(field)?;
+                        ret.key = Bytes::try_from(field)?;
                     } else {
-                        ret.fields.push(String::try_from(field)?);
+                        ret.fields.push(Bytes::try_from(field)?);
                     }
                 }
                 _ => {
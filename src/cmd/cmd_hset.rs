@@ -1,11 +1,13 @@
+use bytes::Bytes;
+
 use crate::{RespArray, RespFrame};
 
 use super::{extract_args, validate_command, CommandError, CommandExecutor, RESP_OK};
 
 #[derive(Debug)]
 pub(crate) struct HSet {
-    pub(crate) key: String,
-    pub(crate) field: String,
+    pub(crate) key: Bytes,
+    pub(crate) field: Bytes,
     pub(crate) value: RespFrame,
 }
 
@@ -27,8 +29,8 @@ impl TryFrom<RespArray> for HSet {
         match (args.next(), args.next(), args.next()) {
             (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(field)), Some(value)) => {
                 Ok(HSet {
-                    key: String::try_from(key)?,
-                    field: String::try_from(field)?,
+                    key: Bytes::try_from(key)?,
+                    field: Bytes::try_from(field)?,
                     value,
                 })
             }
@@ -61,4 +63,24 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_hset_binary_key_and_field() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*4\r\n$4\r\nhset\r\n$2\r\n\xff\x00\r\n$2\r\n\xc3\x28\r\n$1\r\nv\r\n",
+        );
+
+        let cmd: HSet = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(cmd.key, &b"\xff\x00"[..]);
+
+        let backend = crate::Backend::new();
+        cmd.execute(&backend);
+        assert_eq!(
+            backend.hget(b"\xff\x00", b"\xc3\x28"),
+            Some(RespFrame::BulkString(b"v".into()))
+        );
+
+        Ok(())
+    }
 }
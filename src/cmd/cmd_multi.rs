@@ -0,0 +1,64 @@
+use crate::{session::Session, Backend, RespArray, RespFrame, SimpleError};
+
+use super::{connection_only, validate_command, CommandError, CommandExecutor, RESP_OK};
+
+#[derive(Debug)]
+pub(crate) struct Multi;
+
+impl CommandExecutor for Multi {
+    fn execute(self, _: &Backend) -> RespFrame {
+        connection_only("MULTI")
+    }
+
+    fn execute_with(self, _: &Backend, session: &mut Session) -> RespFrame {
+        if session.in_multi() {
+            return SimpleError::new("ERR MULTI calls can not be nested").into();
+        }
+        session.multi = Some(Vec::new());
+        session.multi_error = false;
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for Multi {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["multi"], 0)?;
+        Ok(Multi)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RespDecode;
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_multi_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$5\r\nMULTI\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let _: Multi = frame.try_into()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_multi() {
+        let backend = Backend::new();
+        let (mut session, _rx) = Session::new(backend.clone());
+
+        assert_eq!(Multi.execute_with(&backend, &mut session), RESP_OK.clone());
+        assert!(session.in_multi());
+        assert_eq!(
+            Multi.execute_with(&backend, &mut session),
+            SimpleError::new("ERR MULTI calls can not be nested").into()
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{session::Session, Backend, BulkString, RespArray, RespFrame, SimpleString};
+
+use super::{extract_args, validate_command_for_more, CommandError, CommandExecutor};
+
+#[derive(Debug)]
+pub(crate) struct Ping {
+    pub(crate) message: Option<BulkString>,
+}
+
+impl CommandExecutor for Ping {
+    fn execute(self, _: &Backend) -> RespFrame {
+        match self.message {
+            Some(message) => message.into(),
+            None => SimpleString::new("PONG").into(),
+        }
+    }
+
+    fn execute_with(self, backend: &Backend, session: &mut Session) -> RespFrame {
+        // a RESP2 subscriber gets its pong shaped like a pushed message
+        if session.in_subscriber_mode() {
+            let message = self.message.unwrap_or_else(|| BulkString::from(""));
+            return RespArray::new([BulkString::from("pong").into(), message.into()]).into();
+        }
+        self.execute(backend)
+    }
+}
+
+impl TryFrom<RespArray> for Ping {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // ping [message]
+        validate_command_for_more(&value, &["ping"], 0)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (None, None) => Ok(Ping { message: None }),
+            (Some(RespFrame::BulkString(message)), None) => Ok(Ping {
+                message: Some(message),
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "ping command accepts at most 1 argument".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cmd::Subscribe, RespDecode};
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_ping_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$4\r\nPING\r\n$5\r\nhello\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: Ping = frame.try_into()?;
+        assert_eq!(result.message, Some(BulkString::from("hello")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ping_in_subscriber_mode() {
+        let backend = Backend::new();
+        let (mut session, _rx) = Session::new(backend.clone());
+
+        let result = Ping { message: None }.execute_with(&backend, &mut session);
+        assert_eq!(result, SimpleString::new("PONG").into());
+
+        let cmd = Subscribe {
+            channels: vec!["news".into()],
+        };
+        cmd.execute_with(&backend, &mut session);
+
+        let result = Ping { message: None }.execute_with(&backend, &mut session);
+        assert_eq!(
+            result,
+            RespArray::new([BulkString::from("pong").into(), BulkString::from("").into()]).into()
+        );
+    }
+}
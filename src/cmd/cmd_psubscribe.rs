@@ -0,0 +1,65 @@
+use bytes::Bytes;
+
+use crate::{session::Session, Backend, BulkString, RespArray, RespFrame, RespPush};
+
+use super::{
+    connection_only, extract_bytes_args, validate_command_for_more, CommandError, CommandExecutor,
+};
+
+#[derive(Debug)]
+pub(crate) struct PSubscribe {
+    pub(crate) patterns: Vec<Bytes>,
+}
+
+impl CommandExecutor for PSubscribe {
+    fn execute(self, _: &Backend) -> RespFrame {
+        connection_only("PSUBSCRIBE")
+    }
+
+    fn execute_with(self, _: &Backend, session: &mut Session) -> RespFrame {
+        let mut frames = Vec::with_capacity(self.patterns.len());
+        for pattern in self.patterns {
+            let count = session.psubscribe(&pattern);
+            frames.push(session.push_frame(RespPush::new([
+                BulkString::from("psubscribe").into(),
+                BulkString::new(pattern).into(),
+                RespFrame::Integer(count as i64),
+            ])));
+        }
+        session.reply_many(frames)
+    }
+}
+
+impl TryFrom<RespArray> for PSubscribe {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // psubscribe pattern [pattern ...]
+        validate_command_for_more(&value, &["psubscribe"], 1)?;
+        Ok(PSubscribe {
+            patterns: extract_bytes_args(value, 1)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RespDecode;
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_psubscribe_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$10\r\npsubscribe\r\n$6\r\nnews.*\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: PSubscribe = frame.try_into()?;
+        assert_eq!(result.patterns, vec!["news.*"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use bytes::Bytes;
+
+use crate::{Backend, BulkString, RespArray, RespFrame};
+
+use super::{extract_args, validate_command, CommandError, CommandExecutor};
+
+#[derive(Debug)]
+pub(crate) struct Publish {
+    pub(crate) channel: Bytes,
+    pub(crate) message: BulkString,
+}
+
+impl CommandExecutor for Publish {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let receivers = backend.pubsub.publish(&self.channel, self.message);
+        RespFrame::Integer(receivers as i64)
+    }
+}
+
+impl TryFrom<RespArray> for Publish {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // publish channel message
+        validate_command(&value, &["publish"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(channel)), Some(RespFrame::BulkString(message))) => {
+                Ok(Publish {
+                    channel: Bytes::try_from(channel)?,
+                    message,
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid channel or message".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cmd::Subscribe, session::Session, RespDecode};
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_publish_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$7\r\npublish\r\n$4\r\nnews\r\n$5\r\nhello\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: Publish = frame.try_into()?;
+        assert_eq!(result.channel, "news");
+        assert_eq!(result.message, BulkString::from("hello"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_subscribe_publish_commands() {
+        let backend = Backend::new();
+        let (mut session, mut rx) = Session::new(backend.clone());
+
+        let cmd = Subscribe {
+            channels: vec!["news".into()],
+        };
+        cmd.execute_with(&backend, &mut session);
+
+        let cmd = Publish {
+            channel: "news".into(),
+            message: BulkString::from("hello"),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let message = rx.try_recv().unwrap();
+        assert_eq!(
+            session.push_frame(message),
+            RespArray::new([
+                BulkString::from("message").into(),
+                BulkString::from("news").into(),
+                BulkString::from("hello").into(),
+            ])
+            .into()
+        );
+
+        drop(session);
+        let cmd = Publish {
+            channel: "news".into(),
+            message: BulkString::from("hello"),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+    }
+}
@@ -0,0 +1,180 @@
+use bytes::Bytes;
+
+use crate::{Backend, BulkString, RespArray, RespFrame};
+
+use super::{extract_bytes_args, validate_command_for_more, CommandError, CommandExecutor};
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum PubSub {
+    Channels(Option<Bytes>),
+    NumSub(Vec<Bytes>),
+    NumPat,
+    ShardChannels(Option<Bytes>),
+    ShardNumSub(Vec<Bytes>),
+}
+
+impl CommandExecutor for PubSub {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self {
+            PubSub::Channels(pattern) => {
+                let channels = backend
+                    .pubsub
+                    .channels(pattern.as_deref())
+                    .into_iter()
+                    .map(|c| BulkString::new(c).into())
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new(channels).into()
+            }
+            PubSub::NumSub(channels) => {
+                let data = channels
+                    .into_iter()
+                    .flat_map(|c| {
+                        let n = backend.pubsub.numsub(&c) as i64;
+                        vec![BulkString::new(c).into(), RespFrame::Integer(n)]
+                    })
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new(data).into()
+            }
+            PubSub::NumPat => RespFrame::Integer(backend.pubsub.numpat() as i64),
+            PubSub::ShardChannels(pattern) => {
+                let channels = backend
+                    .pubsub
+                    .shard_channels(pattern.as_deref())
+                    .into_iter()
+                    .map(|c| BulkString::new(c).into())
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new(channels).into()
+            }
+            PubSub::ShardNumSub(channels) => {
+                let data = channels
+                    .into_iter()
+                    .flat_map(|c| {
+                        let n = backend.pubsub.shard_numsub(&c) as i64;
+                        vec![BulkString::new(c).into(), RespFrame::Integer(n)]
+                    })
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new(data).into()
+            }
+        }
+    }
+}
+
+impl TryFrom<RespArray> for PubSub {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // pubsub channels [pattern] | pubsub numsub [channel ...] | pubsub numpat
+        // pubsub shardchannels [pattern] | pubsub shardnumsub [shardchannel ...]
+        validate_command_for_more(&value, &["pubsub"], 1)?;
+
+        let sub = match value[1] {
+            RespFrame::BulkString(ref s) => s.as_ref().to_ascii_lowercase(),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid subcommand".to_string(),
+                ))
+            }
+        };
+
+        let mut args = extract_bytes_args(value, 2)?;
+        match sub.as_slice() {
+            b"channels" if args.len() <= 1 => Ok(PubSub::Channels(args.pop())),
+            b"numsub" => Ok(PubSub::NumSub(args)),
+            b"numpat" if args.is_empty() => Ok(PubSub::NumPat),
+            b"shardchannels" if args.len() <= 1 => Ok(PubSub::ShardChannels(args.pop())),
+            b"shardnumsub" => Ok(PubSub::ShardNumSub(args)),
+            _ => Err(CommandError::InvalidArgument(format!(
+                "Unknown subcommand or wrong number of arguments for 'pubsub {}'",
+                String::from_utf8_lossy(&sub)
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cmd::{SSubscribe, Subscribe},
+        session::Session,
+        RespDecode,
+    };
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_pubsub_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\npubsub\r\n$8\r\nchannels\r\n$1\r\n*\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: PubSub = frame.try_into()?;
+        assert_eq!(result, PubSub::Channels(Some("*".into())));
+
+        buf.extend_from_slice(b"*2\r\n$6\r\npubsub\r\n$6\r\nNUMPAT\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: PubSub = frame.try_into()?;
+        assert_eq!(result, PubSub::NumPat);
+
+        buf.extend_from_slice(b"*3\r\n$6\r\npubsub\r\n$6\r\nnumpat\r\n$1\r\nx\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(PubSub::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pubsub_command() {
+        let backend = Backend::new();
+        let (mut session, _rx) = Session::new(backend.clone());
+        let cmd = Subscribe {
+            channels: vec!["news.tech".into(), "sport".into()],
+        };
+        cmd.execute_with(&backend, &mut session);
+
+        let result = PubSub::Channels(Some("news.*".into())).execute(&backend);
+        assert_eq!(
+            result,
+            RespArray::new([BulkString::from("news.tech").into()]).into()
+        );
+
+        let result = PubSub::NumSub(vec!["sport".into(), "none".into()]).execute(&backend);
+        assert_eq!(
+            result,
+            RespArray::new([
+                BulkString::from("sport").into(),
+                RespFrame::Integer(1),
+                BulkString::from("none").into(),
+                RespFrame::Integer(0),
+            ])
+            .into()
+        );
+
+        assert_eq!(PubSub::NumPat.execute(&backend), RespFrame::Integer(0));
+    }
+
+    #[test]
+    fn test_pubsub_shard_commands() {
+        let backend = Backend::new();
+        let (mut session, _rx) = Session::new(backend.clone());
+        let cmd = SSubscribe {
+            channels: vec!["orders".into()],
+        };
+        cmd.execute_with(&backend, &mut session);
+
+        let result = PubSub::Channels(None).execute(&backend);
+        assert_eq!(result, RespArray::new([]).into());
+
+        let result = PubSub::ShardChannels(None).execute(&backend);
+        assert_eq!(
+            result,
+            RespArray::new([BulkString::from("orders").into()]).into()
+        );
+
+        let result = PubSub::ShardNumSub(vec!["orders".into()]).execute(&backend);
+        assert_eq!(
+            result,
+            RespArray::new([BulkString::from("orders").into(), RespFrame::Integer(1)]).into()
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use bytes::Bytes;
+
+use crate::{session::Session, Backend, BulkString, RespArray, RespFrame, RespPush};
+
+use super::{
+    connection_only, extract_bytes_args, validate_command_for_more, CommandError, CommandExecutor,
+};
+
+#[derive(Debug)]
+pub(crate) struct PUnsubscribe {
+    pub(crate) patterns: Vec<Bytes>,
+}
+
+impl CommandExecutor for PUnsubscribe {
+    fn execute(self, _: &Backend) -> RespFrame {
+        connection_only("PUNSUBSCRIBE")
+    }
+
+    fn execute_with(self, _: &Backend, session: &mut Session) -> RespFrame {
+        // without arguments, unsubscribe from every pattern
+        let patterns = if self.patterns.is_empty() {
+            session.patterns.iter().cloned().collect()
+        } else {
+            self.patterns
+        };
+
+        if patterns.is_empty() {
+            return session.push_frame(RespPush::new([
+                BulkString::from("punsubscribe").into(),
+                BulkString::new_null().into(),
+                RespFrame::Integer(session.subscription_count() as i64),
+            ]));
+        }
+
+        let mut frames = Vec::with_capacity(patterns.len());
+        for pattern in patterns {
+            let count = session.punsubscribe(&pattern);
+            frames.push(session.push_frame(RespPush::new([
+                BulkString::from("punsubscribe").into(),
+                BulkString::new(pattern).into(),
+                RespFrame::Integer(count as i64),
+            ])));
+        }
+        session.reply_many(frames)
+    }
+}
+
+impl TryFrom<RespArray> for PUnsubscribe {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // punsubscribe [pattern [pattern ...]]
+        validate_command_for_more(&value, &["punsubscribe"], 0)?;
+        Ok(PUnsubscribe {
+            patterns: extract_bytes_args(value, 1)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cmd::PSubscribe, RespDecode};
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_punsubscribe_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$12\r\npunsubscribe\r\n$6\r\nnews.*\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: PUnsubscribe = frame.try_into()?;
+        assert_eq!(result.patterns, vec!["news.*"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_psubscribe_punsubscribe_commands() {
+        let backend = Backend::new();
+        let (mut session, _rx) = Session::new(backend.clone());
+
+        let cmd = PSubscribe {
+            patterns: vec!["news.*".into()],
+        };
+        cmd.execute_with(&backend, &mut session);
+        assert_eq!(backend.pubsub.numpat(), 1);
+
+        let cmd = PUnsubscribe {
+            patterns: vec!["news.*".into()],
+        };
+        let result = cmd.execute_with(&backend, &mut session);
+        assert_eq!(
+            result,
+            RespArray::new([
+                BulkString::from("punsubscribe").into(),
+                BulkString::from("news.*").into(),
+                RespFrame::Integer(0),
+            ])
+            .into()
+        );
+        assert_eq!(backend.pubsub.numpat(), 0);
+    }
+}
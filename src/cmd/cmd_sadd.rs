@@ -1,10 +1,12 @@
-use crate::{Backend, RespArray, RespFrame};
+use bytes::Bytes;
+
+use crate::{Backend, RespArray, RespFrame, NOTIFY_SET};
 
 use super::{extract_args, validate_command_for_more, CommandError, CommandExecutor};
 
 #[derive(Debug)]
 pub(crate) struct SAdd {
-    key: String,
+    key: Bytes,
     members: Vec<RespFrame>,
 }
 
@@ -16,6 +18,9 @@ impl CommandExecutor for SAdd {
                 count += 1;
             }
         }
+        if count > 0 {
+            backend.notify_keyspace_event(NOTIFY_SET, "sadd", &self.key);
+        }
         RespFrame::Integer(count)
     }
 }
@@ -28,7 +33,7 @@ impl TryFrom<RespArray> for SAdd {
         validate_command_for_more(&value, &["sadd"], 2)?;
         let args = extract_args(value, 1)?.into_iter();
         let mut ret = SAdd {
-            key: String::from(""),
+            key: Bytes::new(),
             members: vec![],
         };
 
@@ -36,7 +41,7 @@ impl TryFrom<RespArray> for SAdd {
             match frame {
                 RespFrame::BulkString(bs) => {
                     if i == 0 {
-                        ret.key = String::try_from(bs)?;
+                        ret.key = Bytes::try_from(bs)?;
                     } else {
                         ret.members.push(bs.into());
                     }
@@ -71,7 +76,7 @@ mod tests {
         assert_eq!(result.key, "key");
         assert_eq!(
             result.members,
-            vec![RespFrame::BulkString(crate::BulkString::new(b"hello"))]
+            vec![RespFrame::BulkString(crate::BulkString::from(b"hello"))]
         );
 
         Ok(())
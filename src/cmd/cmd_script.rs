@@ -0,0 +1,132 @@
+use crate::{Backend, BulkString, RespArray, RespFrame};
+
+use super::{
+    extract_string_args, validate_command_for_more, CommandError, CommandExecutor, RESP_OK,
+};
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Script {
+    Load(String),
+    Exists(Vec<String>),
+    Flush,
+    Kill,
+}
+
+impl CommandExecutor for Script {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self {
+            Script::Load(body) => match backend.scripts.load(&body) {
+                Ok(sha) => BulkString::new(sha).into(),
+                Err(e) => e.into(),
+            },
+            Script::Exists(shas) => {
+                let data = shas
+                    .iter()
+                    .map(|sha| RespFrame::Integer(backend.scripts.exists(sha) as i64))
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new(data).into()
+            }
+            Script::Flush => {
+                backend.scripts.flush();
+                RESP_OK.clone()
+            }
+            Script::Kill => match backend.scripts.kill() {
+                Ok(()) => RESP_OK.clone(),
+                Err(e) => e.into(),
+            },
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Script {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // script load script | script exists sha1 [sha1 ...] | script flush [async|sync] | script kill
+        validate_command_for_more(&value, &["script"], 1)?;
+
+        let sub = match value[1] {
+            RespFrame::BulkString(ref s) => s.as_ref().to_ascii_lowercase(),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid subcommand".to_string(),
+                ))
+            }
+        };
+
+        let mut args = extract_string_args(value, 2)?;
+        match sub.as_slice() {
+            b"load" if args.len() == 1 => Ok(Script::Load(args.remove(0))),
+            b"exists" if !args.is_empty() => Ok(Script::Exists(args)),
+            // the flush is always synchronous, the mode is accepted for compatibility
+            b"flush"
+                if args.is_empty()
+                    || (args.len() == 1
+                        && ["async", "sync"].contains(&args[0].to_ascii_lowercase().as_str())) =>
+            {
+                Ok(Script::Flush)
+            }
+            b"kill" if args.is_empty() => Ok(Script::Kill),
+            _ => Err(CommandError::InvalidArgument(format!(
+                "Unknown subcommand or wrong number of arguments for 'script {}'",
+                String::from_utf8_lossy(&sub)
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{RespDecode, SimpleError};
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_script_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nscript\r\n$4\r\nLOAD\r\n$8\r\nreturn 1\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Script = frame.try_into()?;
+        assert_eq!(result, Script::Load("return 1".to_string()));
+
+        buf.extend_from_slice(b"*3\r\n$6\r\nscript\r\n$5\r\nflush\r\n$5\r\nASYNC\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Script = frame.try_into()?;
+        assert_eq!(result, Script::Flush);
+
+        buf.extend_from_slice(b"*3\r\n$6\r\nscript\r\n$4\r\nkill\r\n$3\r\nnow\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Script::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_script_command() {
+        let backend = Backend::new();
+        let sha = "e0e1f9fabfc9d4800c877a703b823ac0578ff8db";
+        assert_eq!(
+            Script::Load("return 1".to_string()).execute(&backend),
+            BulkString::from(sha).into()
+        );
+        assert_eq!(
+            Script::Exists(vec![sha.to_uppercase(), "nope".to_string()]).execute(&backend),
+            RespArray::new([RespFrame::Integer(1), RespFrame::Integer(0)]).into()
+        );
+        assert_eq!(Script::Flush.execute(&backend), RESP_OK.clone());
+        assert_eq!(
+            Script::Exists(vec![sha.to_string()]).execute(&backend),
+            RespArray::new([RespFrame::Integer(0)]).into()
+        );
+        assert_eq!(
+            Script::Kill.execute(&backend),
+            SimpleError::new("NOTBUSY No scripts in execution right now.").into()
+        );
+        assert!(matches!(
+            Script::Load("return (".to_string()).execute(&backend),
+            RespFrame::SimpleError(_)
+        ));
+    }
+}
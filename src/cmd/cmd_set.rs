@@ -1,9 +1,11 @@
+use bytes::Bytes;
+
 use super::{extract_args, validate_command, CommandError, CommandExecutor, RESP_OK};
 use crate::{RespArray, RespFrame};
 
 #[derive(Debug)]
 pub(crate) struct Set {
-    pub(crate) key: String,
+    pub(crate) key: Bytes,
     pub(crate) value: RespFrame,
 }
 
@@ -22,7 +24,7 @@ impl TryFrom<RespArray> for Set {
         let mut args = extract_args(value, 1)?.into_iter();
         match (args.next(), args.next()) {
             (Some(RespFrame::BulkString(key)), Some(value)) => Ok(Set {
-                key: String::try_from(key)?,
+                key: Bytes::try_from(key)?,
                 value,
             }),
             _ => Err(CommandError::InvalidArgument(
@@ -57,14 +59,14 @@ mod tests {
     fn test_set_get_command() -> Result<()> {
         let backend = Backend::new();
         let cmd = Set {
-            key: "hello".to_string(),
+            key: "hello".into(),
             value: RespFrame::BulkString(b"world".into()),
         };
         let result = cmd.execute(&backend);
         assert_eq!(result, RESP_OK.clone());
 
         let cmd = Get {
-            key: "hello".to_string(),
+            key: "hello".into(),
         };
         let result = cmd.execute(&backend);
         assert_eq!(result, RespFrame::BulkString(b"world".into()));
@@ -1,22 +1,28 @@
-use crate::{Backend, RespArray, RespFrame};
+use bytes::Bytes;
+
+use crate::{Backend, RespArray, RespFrame, NOTIFY_KEY_MISS};
 
 use super::{extract_args, validate_command, CommandError, CommandExecutor};
 
 #[derive(Debug)]
 pub(crate) struct SIsMember {
-    key: String,
+    key: Bytes,
     member: RespFrame,
 }
 
 impl CommandExecutor for SIsMember {
     fn execute(self, backend: &Backend) -> RespFrame {
+        backend.track_read(&self.key);
         let set = backend.set.get(&self.key);
         match set {
             Some(set) => {
                 let ret = set.contains(&self.member);
                 RespFrame::Integer(if ret { 1 } else { 0 })
             }
-            None => RespFrame::Integer(0),
+            None => {
+                backend.notify_keyspace_event(NOTIFY_KEY_MISS, "keymiss", &self.key);
+                RespFrame::Integer(0)
+            }
         }
     }
 }
@@ -29,7 +35,7 @@ impl TryFrom<RespArray> for SIsMember {
         validate_command(&value, &["sismember"], 2)?;
         let mut args = extract_args(value, 1)?.into_iter();
         let key = match args.next() {
-            Some(RespFrame::BulkString(bs)) => String::try_from(bs)?,
+            Some(RespFrame::BulkString(bs)) => Bytes::try_from(bs)?,
             _ => {
                 return Err(CommandError::InvalidArgument(
                     "Invalid key or member".to_string(),
@@ -62,7 +68,7 @@ mod tests {
         assert_eq!(result.key, "key");
         assert_eq!(
             result.member,
-            RespFrame::BulkString(crate::BulkString::new(b"hello"))
+            RespFrame::BulkString(crate::BulkString::from(b"hello"))
         );
 
         Ok(())
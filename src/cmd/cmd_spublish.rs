@@ -0,0 +1,81 @@
+use bytes::Bytes;
+
+use crate::{Backend, BulkString, RespArray, RespFrame};
+
+use super::{check_shard_slot, extract_args, validate_command, CommandError, CommandExecutor};
+
+#[derive(Debug)]
+pub(crate) struct SPublish {
+    pub(crate) channel: Bytes,
+    pub(crate) message: BulkString,
+}
+
+impl CommandExecutor for SPublish {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if let Some(err) = check_shard_slot(backend, [&self.channel]) {
+            return err;
+        }
+        let receivers = backend.pubsub.spublish(&self.channel, self.message);
+        RespFrame::Integer(receivers as i64)
+    }
+}
+
+impl TryFrom<RespArray> for SPublish {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // spublish shardchannel message
+        validate_command(&value, &["spublish"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(channel)), Some(RespFrame::BulkString(message))) => {
+                Ok(SPublish {
+                    channel: Bytes::try_from(channel)?,
+                    message,
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid channel or message".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{key_hash_slot, RespDecode, SimpleError};
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_spublish_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$8\r\nspublish\r\n$6\r\norders\r\n$2\r\nhi\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SPublish = frame.try_into()?;
+        assert_eq!(result.channel, "orders");
+        assert_eq!(result.message, BulkString::from("hi"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_spublish_to_unserved_slot() {
+        let backend = Backend::new();
+        backend.del_slot(key_hash_slot(b"orders"));
+
+        let cmd = SPublish {
+            channel: "orders".into(),
+            message: BulkString::from("hi"),
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("CLUSTERDOWN Hash slot not served").into()
+        );
+    }
+}
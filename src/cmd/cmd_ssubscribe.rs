@@ -0,0 +1,100 @@
+use bytes::Bytes;
+
+use crate::{session::Session, Backend, BulkString, RespArray, RespFrame, RespPush};
+
+use super::{
+    check_shard_slot, connection_only, extract_bytes_args, validate_command_for_more, CommandError,
+    CommandExecutor,
+};
+
+#[derive(Debug)]
+pub(crate) struct SSubscribe {
+    pub(crate) channels: Vec<Bytes>,
+}
+
+impl CommandExecutor for SSubscribe {
+    fn execute(self, _: &Backend) -> RespFrame {
+        connection_only("SSUBSCRIBE")
+    }
+
+    fn execute_with(self, backend: &Backend, session: &mut Session) -> RespFrame {
+        if let Some(err) = check_shard_slot(backend, &self.channels) {
+            return err;
+        }
+
+        let mut frames = Vec::with_capacity(self.channels.len());
+        for channel in self.channels {
+            let count = session.ssubscribe(&channel);
+            frames.push(session.push_frame(RespPush::new([
+                BulkString::from("ssubscribe").into(),
+                BulkString::new(channel).into(),
+                RespFrame::Integer(count as i64),
+            ])));
+        }
+        session.reply_many(frames)
+    }
+}
+
+impl TryFrom<RespArray> for SSubscribe {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // ssubscribe shardchannel [shardchannel ...]
+        validate_command_for_more(&value, &["ssubscribe"], 1)?;
+        Ok(SSubscribe {
+            channels: extract_bytes_args(value, 1)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{RespDecode, SimpleError};
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_ssubscribe_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$10\r\nssubscribe\r\n$6\r\norders\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SSubscribe = frame.try_into()?;
+        assert_eq!(result.channels, vec!["orders"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ssubscribe_cross_slot() {
+        let backend = Backend::new();
+        let (mut session, _rx) = Session::new(backend.clone());
+
+        let cmd = SSubscribe {
+            channels: vec!["foo".into(), "bar".into()],
+        };
+        let result = cmd.execute_with(&backend, &mut session);
+        assert_eq!(
+            result,
+            SimpleError::new("CROSSSLOT Keys in request don't hash to the same slot").into()
+        );
+
+        let cmd = SSubscribe {
+            channels: vec!["{user}.a".into(), "{user}.b".into()],
+        };
+        let result = cmd.execute_with(&backend, &mut session);
+        assert_eq!(
+            result,
+            RespArray::new([
+                BulkString::from("ssubscribe").into(),
+                BulkString::from("{user}.b").into(),
+                RespFrame::Integer(2),
+            ])
+            .into()
+        );
+        assert!(session.in_subscriber_mode());
+    }
+}
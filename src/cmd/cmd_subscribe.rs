@@ -0,0 +1,98 @@
+use bytes::Bytes;
+
+use crate::{session::Session, Backend, BulkString, RespArray, RespFrame, RespPush};
+
+use super::{
+    connection_only, extract_bytes_args, validate_command_for_more, CommandError, CommandExecutor,
+};
+
+#[derive(Debug)]
+pub(crate) struct Subscribe {
+    pub(crate) channels: Vec<Bytes>,
+}
+
+impl CommandExecutor for Subscribe {
+    fn execute(self, _: &Backend) -> RespFrame {
+        connection_only("SUBSCRIBE")
+    }
+
+    fn execute_with(self, _: &Backend, session: &mut Session) -> RespFrame {
+        let mut frames = Vec::with_capacity(self.channels.len());
+        for channel in self.channels {
+            let count = session.subscribe(&channel);
+            frames.push(session.push_frame(RespPush::new([
+                BulkString::from("subscribe").into(),
+                BulkString::new(channel).into(),
+                RespFrame::Integer(count as i64),
+            ])));
+        }
+        session.reply_many(frames)
+    }
+}
+
+impl TryFrom<RespArray> for Subscribe {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // subscribe channel [channel ...]
+        validate_command_for_more(&value, &["subscribe"], 1)?;
+        Ok(Subscribe {
+            channels: extract_bytes_args(value, 1)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RespDecode;
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_subscribe_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$9\r\nsubscribe\r\n$3\r\nfoo\r\n$3\r\nbar\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: Subscribe = frame.try_into()?;
+        assert_eq!(result.channels, vec!["foo", "bar"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_subscribe_command() {
+        let backend = Backend::new();
+        let (mut session, _rx) = Session::new(backend.clone());
+
+        let cmd = Subscribe {
+            channels: vec!["foo".into(), "bar".into()],
+        };
+        let result = cmd.execute_with(&backend, &mut session);
+
+        let replies = session.take_replies();
+        assert_eq!(
+            replies,
+            vec![RespArray::new([
+                BulkString::from("subscribe").into(),
+                BulkString::from("foo").into(),
+                RespFrame::Integer(1),
+            ])
+            .into()]
+        );
+        assert_eq!(
+            result,
+            RespArray::new([
+                BulkString::from("subscribe").into(),
+                BulkString::from("bar").into(),
+                RespFrame::Integer(2),
+            ])
+            .into()
+        );
+        assert!(session.in_subscriber_mode());
+        assert_eq!(backend.pubsub.numsub(b"foo"), 1);
+    }
+}
@@ -0,0 +1,132 @@
+use bytes::Bytes;
+
+use crate::{session::Session, Backend, BulkString, RespArray, RespFrame, RespPush};
+
+use super::{
+    connection_only, extract_bytes_args, validate_command_for_more, CommandError, CommandExecutor,
+};
+
+#[derive(Debug)]
+pub(crate) struct SUnsubscribe {
+    pub(crate) channels: Vec<Bytes>,
+}
+
+impl CommandExecutor for SUnsubscribe {
+    fn execute(self, _: &Backend) -> RespFrame {
+        connection_only("SUNSUBSCRIBE")
+    }
+
+    fn execute_with(self, _: &Backend, session: &mut Session) -> RespFrame {
+        session.forget_evicted_shard_channels();
+        // without arguments, unsubscribe from every shard channel
+        let channels = if self.channels.is_empty() {
+            session.shard_channels.iter().cloned().collect()
+        } else {
+            self.channels
+        };
+
+        if channels.is_empty() {
+            return session.push_frame(RespPush::new([
+                BulkString::from("sunsubscribe").into(),
+                BulkString::new_null().into(),
+                RespFrame::Integer(session.subscription_count() as i64),
+            ]));
+        }
+
+        let mut frames = Vec::with_capacity(channels.len());
+        for channel in channels {
+            let count = session.sunsubscribe(&channel);
+            frames.push(session.push_frame(RespPush::new([
+                BulkString::from("sunsubscribe").into(),
+                BulkString::new(channel).into(),
+                RespFrame::Integer(count as i64),
+            ])));
+        }
+        session.reply_many(frames)
+    }
+}
+
+impl TryFrom<RespArray> for SUnsubscribe {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // sunsubscribe [shardchannel [shardchannel ...]]
+        validate_command_for_more(&value, &["sunsubscribe"], 0)?;
+        Ok(SUnsubscribe {
+            channels: extract_bytes_args(value, 1)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cmd::SSubscribe, key_hash_slot, RespDecode};
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_sunsubscribe_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$12\r\nsunsubscribe\r\n$6\r\norders\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SUnsubscribe = frame.try_into()?;
+        assert_eq!(result.channels, vec!["orders"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sunsubscribe_when_slot_moves_away() {
+        let backend = Backend::new();
+        let (mut session, mut rx) = Session::new(backend.clone());
+
+        let cmd = SSubscribe {
+            channels: vec!["orders".into()],
+        };
+        cmd.execute_with(&backend, &mut session);
+        assert!(session.in_subscriber_mode());
+
+        assert!(backend.del_slot(key_hash_slot(b"orders")));
+
+        let push = rx.try_recv().unwrap();
+        assert_eq!(
+            session.push_message(push),
+            RespArray::new([
+                BulkString::from("sunsubscribe").into(),
+                BulkString::from("orders").into(),
+                RespFrame::Integer(0),
+            ])
+            .into()
+        );
+        assert!(!session.in_subscriber_mode());
+        assert_eq!(backend.pubsub.shard_numsub(b"orders"), 0);
+    }
+
+    #[test]
+    fn test_slot_eviction_without_the_push() {
+        let backend = Backend::new();
+        let (mut session, _rx) = Session::new(backend.clone());
+
+        let cmd = SSubscribe {
+            channels: vec!["orders".into()],
+        };
+        cmd.execute_with(&backend, &mut session);
+
+        // the client stopped reading, so the sunsubscribe push is lost
+        let subscriber = session.subscriber();
+        while subscriber.send(RespPush::new([BulkString::from("message").into()])) {}
+        assert!(backend.del_slot(key_hash_slot(b"orders")));
+        assert!(backend.slots.add(key_hash_slot(b"orders")));
+
+        let cmd = SSubscribe {
+            channels: vec!["orders".into()],
+        };
+        cmd.execute_with(&backend, &mut session);
+        assert_eq!(backend.pubsub.shard_numsub(b"orders"), 1);
+        assert_eq!(session.subscription_count(), 1);
+    }
+}
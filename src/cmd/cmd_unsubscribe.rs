@@ -0,0 +1,119 @@
+use bytes::Bytes;
+
+use crate::{session::Session, Backend, BulkString, RespArray, RespFrame, RespPush};
+
+use super::{
+    connection_only, extract_bytes_args, validate_command_for_more, CommandError, CommandExecutor,
+};
+
+#[derive(Debug)]
+pub(crate) struct Unsubscribe {
+    pub(crate) channels: Vec<Bytes>,
+}
+
+impl CommandExecutor for Unsubscribe {
+    fn execute(self, _: &Backend) -> RespFrame {
+        connection_only("UNSUBSCRIBE")
+    }
+
+    fn execute_with(self, _: &Backend, session: &mut Session) -> RespFrame {
+        // without arguments, unsubscribe from every channel
+        let channels = if self.channels.is_empty() {
+            session.channels.iter().cloned().collect()
+        } else {
+            self.channels
+        };
+
+        if channels.is_empty() {
+            return session.push_frame(RespPush::new([
+                BulkString::from("unsubscribe").into(),
+                BulkString::new_null().into(),
+                RespFrame::Integer(session.subscription_count() as i64),
+            ]));
+        }
+
+        let mut frames = Vec::with_capacity(channels.len());
+        for channel in channels {
+            let count = session.unsubscribe(&channel);
+            frames.push(session.push_frame(RespPush::new([
+                BulkString::from("unsubscribe").into(),
+                BulkString::new(channel).into(),
+                RespFrame::Integer(count as i64),
+            ])));
+        }
+        session.reply_many(frames)
+    }
+}
+
+impl TryFrom<RespArray> for Unsubscribe {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // unsubscribe [channel [channel ...]]
+        validate_command_for_more(&value, &["unsubscribe"], 0)?;
+        Ok(Unsubscribe {
+            channels: extract_bytes_args(value, 1)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cmd::Subscribe, RespDecode};
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_unsubscribe_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$11\r\nunsubscribe\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: Unsubscribe = frame.try_into()?;
+        assert!(result.channels.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsubscribe_all_command() {
+        let backend = Backend::new();
+        let (mut session, _rx) = Session::new(backend.clone());
+
+        let cmd = Subscribe {
+            channels: vec!["foo".into(), "bar".into()],
+        };
+        cmd.execute_with(&backend, &mut session);
+        session.take_replies();
+
+        let cmd = Unsubscribe { channels: vec![] };
+        let result = cmd.execute_with(&backend, &mut session);
+        assert_eq!(session.take_replies().len(), 1);
+        assert_eq!(
+            result,
+            RespArray::new([
+                BulkString::from("unsubscribe").into(),
+                BulkString::from("foo").into(),
+                RespFrame::Integer(0),
+            ])
+            .into()
+        );
+        assert!(!session.in_subscriber_mode());
+        assert!(backend.pubsub.channels(None).is_empty());
+
+        let cmd = Unsubscribe { channels: vec![] };
+        let result = cmd.execute_with(&backend, &mut session);
+        assert_eq!(
+            result,
+            RespArray::new([
+                BulkString::from("unsubscribe").into(),
+                BulkString::new_null().into(),
+                RespFrame::Integer(0),
+            ])
+            .into()
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use crate::{session::Session, Backend, RespArray, RespFrame};
+
+use super::{connection_only, validate_command, CommandError, CommandExecutor, RESP_OK};
+
+#[derive(Debug)]
+pub(crate) struct Unwatch;
+
+impl CommandExecutor for Unwatch {
+    fn execute(self, _: &Backend) -> RespFrame {
+        connection_only("UNWATCH")
+    }
+
+    fn execute_with(self, _: &Backend, session: &mut Session) -> RespFrame {
+        session.unwatch();
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for Unwatch {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["unwatch"], 0)?;
+        Ok(Unwatch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RespDecode;
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_unwatch_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$7\r\nunwatch\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let _: Unwatch = frame.try_into()?;
+
+        Ok(())
+    }
+}
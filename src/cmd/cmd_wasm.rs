@@ -0,0 +1,196 @@
+use crate::{Backend, BulkString, RespArray, RespFrame};
+
+use super::{extract_args, validate_command_for_more, CommandError, CommandExecutor, RESP_OK};
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Wasm {
+    Load { name: String, module: Vec<u8> },
+    List,
+    Unload(String),
+}
+
+impl CommandExecutor for Wasm {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self {
+            Wasm::Load { name, module } => match backend.wasm.load(backend, &name, &module) {
+                Ok(commands) => RespArray::new(
+                    commands
+                        .into_iter()
+                        .map(|c| BulkString::new(c).into())
+                        .collect::<Vec<RespFrame>>(),
+                )
+                .into(),
+                Err(e) => e.into(),
+            },
+            Wasm::List => {
+                let data = backend
+                    .wasm
+                    .list()
+                    .into_iter()
+                    .map(|(name, commands)| {
+                        let commands = commands
+                            .into_iter()
+                            .map(|c| BulkString::new(c).into())
+                            .collect::<Vec<RespFrame>>();
+                        RespArray::new([
+                            BulkString::from("name").into(),
+                            BulkString::new(name).into(),
+                            BulkString::from("commands").into(),
+                            RespArray::new(commands).into(),
+                        ])
+                        .into()
+                    })
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new(data).into()
+            }
+            Wasm::Unload(name) => match backend.wasm.unload(&name) {
+                Ok(()) => RESP_OK.clone(),
+                Err(e) => e.into(),
+            },
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Wasm {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // wasm load name module | wasm list | wasm unload name
+        validate_command_for_more(&value, &["wasm"], 1)?;
+
+        let sub = match value[1] {
+            RespFrame::BulkString(ref s) => s.as_ref().to_ascii_lowercase(),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid subcommand".to_string(),
+                ))
+            }
+        };
+
+        // the module is binary, only its name has to be a string
+        let mut args = extract_args(value, 2)?
+            .into_iter()
+            .map(|frame| match frame {
+                RespFrame::BulkString(BulkString::String(s)) => Ok(Vec::from(s)),
+                _ => Err(CommandError::InvalidArgument(
+                    "Arguments must be BulkString".to_string(),
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        match sub.as_slice() {
+            b"load" if args.len() == 2 => {
+                let module = args.pop().unwrap_or_default();
+                let name = String::from_utf8(args.remove(0))?;
+                Ok(Wasm::Load { name, module })
+            }
+            b"list" if args.is_empty() => Ok(Wasm::List),
+            b"unload" if args.len() == 1 => Ok(Wasm::Unload(String::from_utf8(args.remove(0))?)),
+            _ => Err(CommandError::InvalidArgument(format!(
+                "Unknown subcommand or wrong number of arguments for 'wasm {}'",
+                String::from_utf8_lossy(&sub)
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{wasm::tests::MODULE, RespDecode, SimpleError};
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_wasm_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$4\r\nwasm\r\n$4\r\nLOAD\r\n$1\r\nm\r\n$2\r\n\x00\xff\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Wasm = frame.try_into()?;
+        assert_eq!(
+            result,
+            Wasm::Load {
+                name: "m".to_string(),
+                module: vec![0, 255]
+            }
+        );
+
+        buf.extend_from_slice(b"*2\r\n$4\r\nwasm\r\n$4\r\nlist\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Wasm = frame.try_into()?;
+        assert_eq!(result, Wasm::List);
+
+        buf.extend_from_slice(b"*2\r\n$4\r\nwasm\r\n$6\r\nunload\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Wasm::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wasm_command() -> Result<()> {
+        let backend = Backend::new();
+        let load = Wasm::Load {
+            name: "copy".to_string(),
+            module: MODULE.as_bytes().to_vec(),
+        };
+        assert_eq!(
+            load.execute(&backend),
+            RespArray::new([
+                BulkString::from("wcopy").into(),
+                BulkString::from("wspin").into()
+            ])
+            .into()
+        );
+        assert_eq!(
+            Wasm::List.execute(&backend),
+            RespArray::new([RespArray::new([
+                BulkString::from("name").into(),
+                BulkString::from("copy").into(),
+                BulkString::from("commands").into(),
+                RespArray::new([
+                    BulkString::from("wcopy").into(),
+                    BulkString::from("wspin").into()
+                ])
+                .into(),
+            ])
+            .into()])
+            .into()
+        );
+
+        // registered commands dispatch like any other
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$5\r\nWCOPY\r\n$3\r\nfoo\r\n$3\r\nbar\r\n");
+        let cmd = crate::cmd::Command::try_from(RespArray::decode(&mut buf)?)?;
+        assert!(cmd.needs_exclusive(&backend));
+        assert!(cmd.is_write(&backend));
+        assert!(!cmd.is_noscript(&backend));
+        assert_eq!(cmd.execute(&backend), BulkString::from("bar").into());
+
+        buf.extend_from_slice(b"*1\r\n$5\r\nwspin\r\n");
+        let cmd = crate::cmd::Command::try_from(RespArray::decode(&mut buf)?)?;
+        assert!(!cmd.is_write(&backend));
+        assert!(cmd.is_noscript(&backend));
+
+        // unknown commands don't hold up everyone else
+        buf.extend_from_slice(b"*1\r\n$9\r\nnosuchcmd\r\n");
+        let cmd = crate::cmd::Command::try_from(RespArray::decode(&mut buf)?)?;
+        assert!(!cmd.needs_exclusive(&backend));
+        assert_eq!(backend.get(b"foo"), Some(BulkString::from("bar").into()));
+
+        assert_eq!(
+            Wasm::Unload("copy".to_string()).execute(&backend),
+            RESP_OK.clone()
+        );
+        assert_eq!(
+            Wasm::Unload("copy".to_string()).execute(&backend),
+            SimpleError::new("ERR no such module 'copy'").into()
+        );
+        let load = Wasm::Load {
+            name: "bad".to_string(),
+            module: b"not wasm".to_vec(),
+        };
+        assert!(matches!(load.execute(&backend), RespFrame::SimpleError(_)));
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use bytes::Bytes;
+
+use crate::{session::Session, Backend, RespArray, RespFrame, SimpleError};
+
+use super::{
+    connection_only, extract_bytes_args, validate_command_for_more, CommandError, CommandExecutor,
+    RESP_OK,
+};
+
+#[derive(Debug)]
+pub(crate) struct Watch {
+    pub(crate) keys: Vec<Bytes>,
+}
+
+impl CommandExecutor for Watch {
+    fn execute(self, _: &Backend) -> RespFrame {
+        connection_only("WATCH")
+    }
+
+    fn execute_with(self, _: &Backend, session: &mut Session) -> RespFrame {
+        if session.in_multi() {
+            return SimpleError::new("ERR WATCH inside MULTI is not allowed").into();
+        }
+        for key in self.keys {
+            session.watch(key);
+        }
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for Watch {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // watch key [key ...]
+        validate_command_for_more(&value, &["watch"], 1)?;
+        Ok(Watch {
+            keys: extract_bytes_args(value, 1)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RespDecode;
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_watch_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$5\r\nwatch\r\n$3\r\nfoo\r\n$3\r\nbar\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: Watch = frame.try_into()?;
+        assert_eq!(result.keys, vec!["foo", "bar"]);
+
+        Ok(())
+    }
+}
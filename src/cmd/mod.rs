@@ -1,24 +1,92 @@
+mod cmd_client;
+mod cmd_cluster;
+mod cmd_command;
+mod cmd_config;
+mod cmd_discard;
 mod cmd_echo;
+mod cmd_eval;
+mod cmd_exec;
+mod cmd_fcall;
+mod cmd_function;
 mod cmd_get;
+mod cmd_hello;
 mod cmd_hget;
 mod cmd_hgetall;
 mod cmd_hmget;
 mod cmd_hset;
+mod cmd_multi;
+mod cmd_ping;
+mod cmd_psubscribe;
+mod cmd_publish;
+mod cmd_pubsub;
+mod cmd_punsubscribe;
 mod cmd_sadd;
+mod cmd_script;
 mod cmd_set;
 mod cmd_sismember;
+mod cmd_spublish;
+mod cmd_ssubscribe;
+mod cmd_subscribe;
+mod cmd_sunsubscribe;
+mod cmd_unsubscribe;
+mod cmd_unwatch;
+mod cmd_wasm;
+mod cmd_watch;
+mod registry;
 
+use bytes::Bytes;
 use enum_dispatch::enum_dispatch;
 use lazy_static::lazy_static;
 use thiserror::Error;
 
-use crate::{Backend, RespArray, RespError, RespFrame, SimpleString};
+use crate::{
+    key_hash_slot, session::Session, Backend, BulkString, RespArray, RespError, RespFrame,
+    SimpleError, SimpleString,
+};
 
 use self::{
-    cmd_echo::Echo, cmd_get::Get, cmd_hget::HGet, cmd_hgetall::HGetAll, cmd_hmget::HMGet,
-    cmd_hset::HSet, cmd_sadd::SAdd, cmd_set::Set, cmd_sismember::SIsMember,
+    cmd_client::Client,
+    cmd_cluster::Cluster,
+    cmd_command::{CommandSpec, Commands},
+    cmd_config::Config,
+    cmd_discard::Discard,
+    cmd_echo::Echo,
+    cmd_eval::Eval,
+    cmd_exec::Exec,
+    cmd_fcall::FCall,
+    cmd_function::Function,
+    cmd_get::Get,
+    cmd_hello::Hello,
+    cmd_hget::HGet,
+    cmd_hgetall::HGetAll,
+    cmd_hmget::HMGet,
+    cmd_hset::HSet,
+    cmd_multi::Multi,
+    cmd_ping::Ping,
+    cmd_psubscribe::PSubscribe,
+    cmd_publish::Publish,
+    cmd_pubsub::PubSub,
+    cmd_punsubscribe::PUnsubscribe,
+    cmd_sadd::SAdd,
+    cmd_script::Script,
+    cmd_set::Set,
+    cmd_sismember::SIsMember,
+    cmd_spublish::SPublish,
+    cmd_ssubscribe::SSubscribe,
+    cmd_subscribe::Subscribe,
+    cmd_sunsubscribe::SUnsubscribe,
+    cmd_unsubscribe::Unsubscribe,
+    cmd_unwatch::Unwatch,
+    cmd_wasm::Wasm,
+    cmd_watch::Watch,
+    registry::Plugin,
 };
 
+pub(crate) use self::registry::PluginRegistry;
+
+pub(crate) use self::cmd_config::{set_param, valid_param};
+pub use self::registry::{CommandPlugin, PluginCommand};
+
 lazy_static! {
     static ref RESP_OK: RespFrame = SimpleString::new("OK").into();
 }
@@ -26,6 +94,15 @@ lazy_static! {
 #[enum_dispatch]
 pub(crate) trait CommandExecutor {
     fn execute(self, backend: &Backend) -> RespFrame;
+
+    // commands that depend on the calling connection (subscriptions,
+    // protocol version...) override this, the rest only need the backend
+    fn execute_with(self, backend: &Backend, _session: &mut Session) -> RespFrame
+    where
+        Self: Sized,
+    {
+        self.execute(backend)
+    }
 }
 
 #[derive(Error, Debug)]
@@ -55,15 +132,202 @@ pub enum Command {
     Echo(Echo),
     SAdd(SAdd),
     SIsMember(SIsMember),
+    Ping(Ping),
+    Subscribe(Subscribe),
+    Unsubscribe(Unsubscribe),
+    PSubscribe(PSubscribe),
+    PUnsubscribe(PUnsubscribe),
+    Publish(Publish),
+    PubSub(PubSub),
+    SSubscribe(SSubscribe),
+    SUnsubscribe(SUnsubscribe),
+    SPublish(SPublish),
+    Cluster(Cluster),
+    Config(Config),
+    Multi(Multi),
+    Exec(Exec),
+    Discard(Discard),
+    Watch(Watch),
+    Unwatch(Unwatch),
+    Eval(Eval),
+    Script(Script),
+    Function(Function),
+    FCall(FCall),
+    Wasm(Wasm),
+    Commands(Commands),
+    Client(Client),
+    Hello(Hello),
+    Plugin(Plugin),
     Unrecognized(Unrecognized),
 }
 
+// also how commands registered by WASM modules reach the backend
 #[derive(Debug)]
-pub(crate) struct Unrecognized;
+pub(crate) struct Unrecognized {
+    name: String,
+    args: Vec<RespFrame>,
+}
 
 impl CommandExecutor for Unrecognized {
-    fn execute(self, _: &Backend) -> RespFrame {
-        RESP_OK.clone()
+    fn execute(self, backend: &Backend) -> RespFrame {
+        backend
+            .wasm
+            .call(backend, &self.name, self.args)
+            .unwrap_or_else(|| RESP_OK.clone())
+    }
+}
+
+impl Unrecognized {
+    /// Whether a loaded WASM module serves this command.
+    pub(crate) fn is_registered(&self, backend: &Backend) -> bool {
+        backend.wasm.has_command(&self.name)
+    }
+
+    fn has_flag(&self, backend: &Backend, flag: &str) -> bool {
+        backend
+            .wasm
+            .command_flags(&self.name)
+            .is_some_and(|flags| flags.iter().any(|f| f == flag))
+    }
+
+    fn into_array(self) -> RespArray {
+        let mut frames = vec![BulkString::new(self.name).into()];
+        frames.extend(self.args);
+        RespArray::new(frames)
+    }
+}
+
+/// Whether `name` (lowercase) is a built-in command.
+pub(crate) fn is_builtin(name: &str) -> bool {
+    let frame = RespArray::new([BulkString::from(name).into()]);
+    !matches!(Command::try_from(frame), Ok(Command::Unrecognized(_)))
+}
+
+/// Whether `name` (lowercase) is a built-in or one of `backend`'s plugin
+/// commands.
+pub(crate) fn command_exists(backend: &Backend, name: &str) -> bool {
+    is_builtin(name) || backend.plugins.lookup(name).is_some()
+}
+
+impl Command {
+    /// Commands a RESP2 client may still issue once it has subscribed to a
+    /// channel or pattern.
+    pub(crate) fn allowed_in_subscriber_mode(&self) -> bool {
+        matches!(
+            self,
+            Command::Ping(_)
+                | Command::Subscribe(_)
+                | Command::Unsubscribe(_)
+                | Command::PSubscribe(_)
+                | Command::PUnsubscribe(_)
+                | Command::SSubscribe(_)
+                | Command::SUnsubscribe(_)
+        )
+    }
+}
+
+impl Command {
+    /// Commands that run right away inside MULTI instead of being queued.
+    pub(crate) fn is_transaction_control(&self) -> bool {
+        matches!(
+            self,
+            Command::Multi(_) | Command::Exec(_) | Command::Discard(_) | Command::Watch(_)
+        )
+    }
+
+    /// Commands refused inside MULTI, they answer with a frame per channel
+    /// which doesn't fit in EXEC's reply.
+    pub(crate) fn is_no_multi(&self) -> bool {
+        matches!(
+            self,
+            Command::Subscribe(_)
+                | Command::Unsubscribe(_)
+                | Command::PSubscribe(_)
+                | Command::PUnsubscribe(_)
+                | Command::SSubscribe(_)
+                | Command::SUnsubscribe(_)
+        )
+    }
+
+    /// Commands that must not interleave with any other client's command.
+    pub(crate) fn needs_exclusive(&self, backend: &Backend) -> bool {
+        if let Command::Unrecognized(unrecognized) = self {
+            // unknown commands only reply OK, they don't need the lock
+            return unrecognized.is_registered(backend);
+        }
+        matches!(
+            self,
+            Command::Exec(_)
+                | Command::Eval(_)
+                | Command::FCall(_)
+                // these run the libraries' top-level code
+                | Command::Function(
+                    Function::Load { .. } | Function::Delete(_) | Function::Restore { .. }
+                )
+        )
+    }
+
+    /// Commands served while a script holds the backend, SCRIPT KILL being
+    /// the way to get it back.
+    pub(crate) fn runs_while_busy(&self) -> bool {
+        matches!(self, Command::Script(Script::Kill))
+    }
+
+    /// Commands that modify the dataset.
+    pub(crate) fn is_write(&self, backend: &Backend) -> bool {
+        match self {
+            Command::Plugin(plugin) => plugin.has_flag("write"),
+            Command::Unrecognized(unrecognized) => unrecognized.has_flag(backend, "write"),
+            _ => matches!(self, Command::Set(_) | Command::HSet(_) | Command::SAdd(_)),
+        }
+    }
+
+    /// Commands a script can't call through `redis.call`.
+    pub(crate) fn is_noscript(&self, backend: &Backend) -> bool {
+        match self {
+            Command::Plugin(plugin) => return plugin.has_flag("noscript"),
+            Command::Unrecognized(unrecognized) => {
+                return unrecognized.has_flag(backend, "noscript")
+            }
+            _ => {}
+        }
+        matches!(
+            self,
+            Command::Subscribe(_)
+                | Command::Unsubscribe(_)
+                | Command::PSubscribe(_)
+                | Command::PUnsubscribe(_)
+                | Command::SSubscribe(_)
+                | Command::SUnsubscribe(_)
+                | Command::Multi(_)
+                | Command::Exec(_)
+                | Command::Discard(_)
+                | Command::Watch(_)
+                | Command::Unwatch(_)
+                | Command::Eval(_)
+                | Command::Script(_)
+                | Command::Function(_)
+                | Command::FCall(_)
+                | Command::Wasm(_)
+                | Command::Client(_)
+                | Command::Hello(_)
+        )
+    }
+}
+
+impl Command {
+    /// Parse a request, looking up the commands `backend` has plugins for.
+    pub(crate) fn parse(value: RespFrame, backend: &Backend) -> Result<Self, CommandError> {
+        match Command::try_from(value)? {
+            Command::Unrecognized(unrecognized) => match backend.plugins.lookup(&unrecognized.name)
+            {
+                Some(plugin) => {
+                    Plugin::parse(plugin, unrecognized.into_array()).map(Command::Plugin)
+                }
+                None => Ok(Command::Unrecognized(unrecognized)),
+            },
+            cmd => Ok(cmd),
+        }
     }
 }
 
@@ -85,7 +349,7 @@ impl TryFrom<RespArray> for Command {
 
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
         match value.first() {
-            Some(RespFrame::BulkString(s)) => match s.as_ref() {
+            Some(RespFrame::BulkString(s)) => match s.as_ref().to_ascii_lowercase().as_slice() {
                 b"get" => Get::try_from(value).map(Command::Get),
                 b"set" => Set::try_from(value).map(Command::Set),
                 b"hget" => HGet::try_from(value).map(Command::HGet),
@@ -95,7 +359,42 @@ impl TryFrom<RespArray> for Command {
                 b"hmget" => HMGet::try_from(value).map(Command::HMGet),
                 b"sadd" => SAdd::try_from(value).map(Command::SAdd),
                 b"sismember" => SIsMember::try_from(value).map(Command::SIsMember),
-                _ => Ok(Command::Unrecognized(Unrecognized)),
+                b"ping" => Ping::try_from(value).map(Command::Ping),
+                b"subscribe" => Subscribe::try_from(value).map(Command::Subscribe),
+                b"unsubscribe" => Unsubscribe::try_from(value).map(Command::Unsubscribe),
+                b"psubscribe" => PSubscribe::try_from(value).map(Command::PSubscribe),
+                b"punsubscribe" => PUnsubscribe::try_from(value).map(Command::PUnsubscribe),
+                b"publish" => Publish::try_from(value).map(Command::Publish),
+                b"pubsub" => PubSub::try_from(value).map(Command::PubSub),
+                b"ssubscribe" => SSubscribe::try_from(value).map(Command::SSubscribe),
+                b"sunsubscribe" => SUnsubscribe::try_from(value).map(Command::SUnsubscribe),
+                b"spublish" => SPublish::try_from(value).map(Command::SPublish),
+                b"cluster" => Cluster::try_from(value).map(Command::Cluster),
+                b"config" => Config::try_from(value).map(Command::Config),
+                b"multi" => Multi::try_from(value).map(Command::Multi),
+                b"exec" => Exec::try_from(value).map(Command::Exec),
+                b"discard" => Discard::try_from(value).map(Command::Discard),
+                b"watch" => Watch::try_from(value).map(Command::Watch),
+                b"unwatch" => Unwatch::try_from(value).map(Command::Unwatch),
+                b"eval" | b"evalsha" | b"eval_ro" | b"evalsha_ro" => {
+                    Eval::try_from(value).map(Command::Eval)
+                }
+                b"script" => Script::try_from(value).map(Command::Script),
+                b"function" => Function::try_from(value).map(Command::Function),
+                b"fcall" | b"fcall_ro" => FCall::try_from(value).map(Command::FCall),
+                b"wasm" => Wasm::try_from(value).map(Command::Wasm),
+                b"command" => Commands::try_from(value).map(Command::Commands),
+                b"client" => Client::try_from(value).map(Command::Client),
+                b"hello" => Hello::try_from(value).map(Command::Hello),
+                // plugins are looked up by `Command::parse`
+                _ => {
+                    let name: String =
+                        String::from_utf8_lossy(&s.as_ref().to_ascii_lowercase()).into();
+                    Ok(Command::Unrecognized(Unrecognized {
+                        name,
+                        args: extract_args(value, 1)?,
+                    }))
+                }
             },
             _ => Err(CommandError::InvalidCommand(
                 "Command must have a BulkString as the first argument".to_string(),
@@ -177,6 +476,78 @@ fn extract_args(value: RespArray, start: usize) -> Result<Vec<RespFrame>, Comman
     }
 }
 
+fn extract_string_args(value: RespArray, start: usize) -> Result<Vec<String>, CommandError> {
+    extract_args(value, start)?
+        .into_iter()
+        .map(|frame| match frame {
+            RespFrame::BulkString(s) => Ok(String::try_from(s)?),
+            _ => Err(CommandError::InvalidArgument(
+                "Arguments must be BulkString".to_string(),
+            )),
+        })
+        .collect()
+}
+
+fn extract_bytes_args(value: RespArray, start: usize) -> Result<Vec<Bytes>, CommandError> {
+    extract_args(value, start)?
+        .into_iter()
+        .map(|frame| match frame {
+            RespFrame::BulkString(s) => Ok(Bytes::try_from(s)?),
+            _ => Err(CommandError::InvalidArgument(
+                "Arguments must be BulkString".to_string(),
+            )),
+        })
+        .collect()
+}
+
+// numkeys key [key ...] arg [arg ...], as taken by EVAL and FCALL
+fn split_keys(mut args: Vec<Bytes>) -> Result<(Vec<Bytes>, Vec<Bytes>), CommandError> {
+    let numkeys = std::str::from_utf8(&args.remove(0))
+        .ok()
+        .and_then(|n| n.parse::<i64>().ok())
+        .ok_or_else(|| {
+            CommandError::InvalidArgument("value is not an integer or out of range".to_string())
+        })?;
+    if numkeys < 0 {
+        return Err(CommandError::InvalidArgument(
+            "Number of keys can't be negative".to_string(),
+        ));
+    }
+    if numkeys as usize > args.len() {
+        return Err(CommandError::InvalidArgument(
+            "Number of keys can't be greater than number of args".to_string(),
+        ));
+    }
+    let rest = args.split_off(numkeys as usize);
+    Ok((args, rest))
+}
+
+// shard channels of one command must live in a single slot served here
+fn check_shard_slot<'a>(
+    backend: &Backend,
+    channels: impl IntoIterator<Item = &'a Bytes>,
+) -> Option<RespFrame> {
+    let mut slots = channels.into_iter().map(|c| key_hash_slot(c));
+    let slot = slots.next()?;
+    if slots.any(|s| s != slot) {
+        return Some(
+            SimpleError::new("CROSSSLOT Keys in request don't hash to the same slot").into(),
+        );
+    }
+    if !backend.slots.owns(slot) {
+        return Some(SimpleError::new("CLUSTERDOWN Hash slot not served").into());
+    }
+    None
+}
+
+fn connection_only(name: &str) -> RespFrame {
+    SimpleError::new(format!(
+        "ERR {} is only supported on a client connection",
+        name
+    ))
+    .into()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,299 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use crate::{Backend, RespArray, RespFrame};
+
+use super::{is_builtin, CommandError, CommandExecutor, CommandSpec};
+
+/// A command implemented outside of this crate. Once registered with
+/// [`Backend::register_command`] it is dispatched, queued by MULTI, called
+/// from scripts and listed by COMMAND like a built-in.
+pub trait CommandPlugin: Send + Sync + 'static {
+    /// Lowercase command name.
+    fn name(&self) -> &str;
+
+    /// Number of arguments including the command name, negative meaning
+    /// "at least" as in Redis' COMMAND output.
+    fn arity(&self) -> i64;
+
+    /// COMMAND flags. `write` marks it as modifying the dataset and
+    /// `noscript` keeps it out of scripts.
+    fn flags(&self) -> &[&str] {
+        &[]
+    }
+
+    /// ACL categories, without the leading '@'.
+    fn acl_categories(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Position of the first key, the last key (negative counts from the
+    /// end) and the step between keys, all 0 for commands without keys.
+    fn key_positions(&self) -> (i64, i64, i64) {
+        (0, 0, 0)
+    }
+
+    /// Parse a call whose arity has already been checked. `value` still
+    /// starts with the command name.
+    fn parse(&self, value: RespArray) -> Result<Box<dyn PluginCommand>, CommandError>;
+}
+
+/// A parsed call of a [`CommandPlugin`].
+pub trait PluginCommand: fmt::Debug + Send {
+    fn execute(self: Box<Self>, backend: &Backend) -> RespFrame;
+}
+
+/// The plugin commands of one backend, by lowercase name.
+#[derive(Default)]
+pub(crate) struct PluginRegistry {
+    plugins: RwLock<BTreeMap<String, Arc<dyn CommandPlugin>>>,
+}
+
+impl fmt::Debug for PluginRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.read().keys()).finish()
+    }
+}
+
+impl PluginRegistry {
+    pub(crate) fn lookup(&self, name: &str) -> Option<Arc<dyn CommandPlugin>> {
+        self.read().get(name).cloned()
+    }
+
+    pub(crate) fn specs(&self) -> Vec<CommandSpec> {
+        self.read()
+            .values()
+            .map(|plugin| spec(plugin.as_ref()))
+            .collect()
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, BTreeMap<String, Arc<dyn CommandPlugin>>> {
+        self.plugins.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, BTreeMap<String, Arc<dyn CommandPlugin>>> {
+        self.plugins.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Backend {
+    /// Make a plugin command available to every connection of this
+    /// backend. Fails if the name is taken by a built-in, another plugin or
+    /// a WASM module's command.
+    pub fn register_command(&self, plugin: impl CommandPlugin) -> Result<(), CommandError> {
+        let name = plugin.name().to_ascii_lowercase();
+        // loading a module looks plugins up with its modules locked, so
+        // don't hold the plugins while asking it
+        let exists = is_builtin(&name) || self.wasm.has_command(&name);
+        let mut plugins = self.plugins.write();
+        if exists || plugins.contains_key(&name) {
+            return Err(CommandError::InvalidCommand(format!(
+                "command '{}' already exists",
+                name
+            )));
+        }
+        plugins.insert(name, Arc::new(plugin));
+        Ok(())
+    }
+
+    /// Remove a plugin command, returns false if it wasn't registered.
+    pub fn unregister_command(&self, name: &str) -> bool {
+        let mut plugins = self.plugins.write();
+        plugins.remove(&name.to_ascii_lowercase()).is_some()
+    }
+}
+
+fn spec(plugin: &dyn CommandPlugin) -> CommandSpec {
+    let (first_key, last_key, step) = plugin.key_positions();
+    CommandSpec {
+        name: plugin.name().to_ascii_lowercase(),
+        arity: plugin.arity(),
+        flags: plugin.flags().iter().map(|s| s.to_string()).collect(),
+        first_key,
+        last_key,
+        step,
+        acl_categories: plugin
+            .acl_categories()
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+    }
+}
+
+pub(crate) struct Plugin {
+    plugin: Arc<dyn CommandPlugin>,
+    call: Box<dyn PluginCommand>,
+}
+
+impl fmt::Debug for Plugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Plugin")
+            .field("name", &self.plugin.name())
+            .field("call", &self.call)
+            .finish()
+    }
+}
+
+impl CommandExecutor for Plugin {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.call.execute(backend)
+    }
+}
+
+impl Plugin {
+    pub(crate) fn parse(
+        plugin: Arc<dyn CommandPlugin>,
+        value: RespArray,
+    ) -> Result<Self, CommandError> {
+        let arity = plugin.arity();
+        let len = value.len() as i64;
+        if (arity >= 0 && len != arity) || len < arity.abs() {
+            return Err(CommandError::InvalidArgument(format!(
+                "wrong number of arguments for '{}' command",
+                plugin.name()
+            )));
+        }
+        let call = plugin.parse(value)?;
+        Ok(Plugin { plugin, call })
+    }
+
+    pub(crate) fn has_flag(&self, flag: &str) -> bool {
+        self.plugin.flags().contains(&flag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cmd::{extract_string_args, Command},
+        wasm::tests::MODULE,
+        BulkString, RespDecode, SimpleError,
+    };
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    struct Append;
+
+    #[derive(Debug)]
+    struct AppendCall {
+        key: String,
+        suffix: String,
+    }
+
+    impl CommandPlugin for Append {
+        fn name(&self) -> &str {
+            "plugin.append"
+        }
+        fn arity(&self) -> i64 {
+            3
+        }
+        fn flags(&self) -> &[&str] {
+            &["write"]
+        }
+        fn parse(&self, value: RespArray) -> Result<Box<dyn PluginCommand>, CommandError> {
+            let mut args = extract_string_args(value, 1)?.into_iter();
+            match (args.next(), args.next()) {
+                (Some(key), Some(suffix)) => Ok(Box::new(AppendCall { key, suffix })),
+                _ => unreachable!("arity is checked before parsing"),
+            }
+        }
+    }
+
+    impl PluginCommand for AppendCall {
+        fn execute(self: Box<Self>, backend: &Backend) -> RespFrame {
+            let mut value = match backend.get(self.key.as_bytes()) {
+                Some(RespFrame::BulkString(BulkString::String(s))) => Vec::from(s),
+                _ => Vec::new(),
+            };
+            value.extend_from_slice(self.suffix.as_bytes());
+            let len = value.len() as i64;
+            backend.set(self.key.into(), BulkString::new(value).into());
+            RespFrame::Integer(len)
+        }
+    }
+
+    struct Get;
+
+    impl CommandPlugin for Get {
+        fn name(&self) -> &str {
+            "GET"
+        }
+        fn arity(&self) -> i64 {
+            2
+        }
+        fn parse(&self, _: RespArray) -> Result<Box<dyn PluginCommand>, CommandError> {
+            unreachable!("never registered")
+        }
+    }
+
+    struct Named(&'static str);
+
+    impl CommandPlugin for Named {
+        fn name(&self) -> &str {
+            self.0
+        }
+        fn arity(&self) -> i64 {
+            2
+        }
+        fn parse(&self, _: RespArray) -> Result<Box<dyn PluginCommand>, CommandError> {
+            unreachable!("never called")
+        }
+    }
+
+    #[test]
+    fn test_plugin_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.register_command(Append)?;
+        assert!(backend.register_command(Append).is_err());
+        assert!(backend.register_command(Get).is_err());
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$13\r\nPLUGIN.APPEND\r\n$3\r\nkey\r\n$2\r\nab\r\n");
+        let cmd = Command::parse(RespArray::decode(&mut buf)?.into(), &backend)?;
+        assert!(cmd.is_write(&backend));
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert_eq!(backend.get(b"key"), Some(BulkString::from("ab").into()));
+
+        buf.extend_from_slice(b"*2\r\n$13\r\nplugin.append\r\n$3\r\nkey\r\n");
+        let result = Command::parse(RespArray::decode(&mut buf)?.into(), &backend);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Invalid argument: wrong number of arguments for 'plugin.append' command"
+        );
+
+        // plugins belong to the backend they were registered with
+        let other = Backend::new();
+        buf.extend_from_slice(b"*3\r\n$13\r\nplugin.append\r\n$3\r\nkey\r\n$2\r\nab\r\n");
+        let frame: RespFrame = RespArray::decode(&mut buf)?.into();
+        assert!(matches!(
+            Command::parse(frame, &other)?,
+            Command::Unrecognized(_)
+        ));
+
+        assert!(backend.unregister_command("plugin.append"));
+        assert!(!backend.unregister_command("plugin.append"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_plugin_and_wasm_names_clash() -> Result<()> {
+        let backend = Backend::new();
+        assert!(backend
+            .wasm
+            .load(&backend, "copy", MODULE.as_bytes())
+            .is_ok());
+        assert!(backend.register_command(Named("WCOPY")).is_err());
+
+        assert!(backend.wasm.unload("copy").is_ok());
+        backend.register_command(Named("wcopy"))?;
+        assert_eq!(
+            backend.wasm.load(&backend, "copy", MODULE.as_bytes()),
+            Err(SimpleError::new("ERR command 'wcopy' already exists"))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,363 @@
+use std::{
+    fmt, fs, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+};
+
+use thiserror::Error;
+
+use crate::{
+    cmd::{set_param, valid_param},
+    split_inline_args, Backend,
+};
+
+/// What the server is started with: a redis.conf style file, one
+/// `directive arg ...` per line, with `--directive arg ...` options on the
+/// command line after it taking precedence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    /// Addresses to listen on, `bind`.
+    pub bind: Vec<IpAddr>,
+    /// `port`.
+    pub port: u16,
+    /// `loglevel`.
+    pub loglevel: LogLevel,
+    /// The CONFIG SET parameters given, in order, `proto-max-bulk-len`,
+    /// `notify-keyspace-events` and the like.
+    pub params: Vec<(String, String)>,
+}
+
+/// The Redis log levels, from the chattiest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    Debug,
+    Verbose,
+    Notice,
+    Warning,
+    Nothing,
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Can't open the config file '{path}': {source}")]
+    Io { path: String, source: io::Error },
+    #[error(
+        "*** FATAL CONFIG FILE ERROR ***\nReading {origin}, at line {line}\n>>> '{text}'\n{reason}"
+    )]
+    Invalid {
+        origin: Origin,
+        line: usize,
+        text: String,
+        reason: String,
+    },
+}
+
+/// Where a bad directive came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    File(String),
+    CommandLine,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: vec![IpAddr::V4(Ipv4Addr::UNSPECIFIED)],
+            port: 6379,
+            loglevel: LogLevel::Notice,
+            params: Vec::new(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// `[config-file] [--directive arg ...] ...`, as redis-server takes them.
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<Self, ConfigError> {
+        let mut args = args.into_iter().peekable();
+        let mut config = Self::default();
+        if let Some(path) = args.next_if(|a| !a.starts_with("--")) {
+            let text = fs::read_to_string(&path).map_err(|source| ConfigError::Io {
+                path: path.clone(),
+                source,
+            })?;
+            config.load(&text, Origin::File(path))?;
+        }
+
+        // each option becomes a line of its own
+        let mut lines: Vec<String> = Vec::new();
+        for arg in args {
+            match (arg.strip_prefix("--"), lines.last_mut()) {
+                (Some(name), _) => lines.push(name.to_string()),
+                (None, Some(line)) => {
+                    line.push(' ');
+                    line.push_str(&quote(&arg));
+                }
+                (None, None) => {
+                    return Err(ConfigError::Invalid {
+                        origin: Origin::CommandLine,
+                        line: 1,
+                        text: arg,
+                        reason: "Options must start with --".to_string(),
+                    })
+                }
+            }
+        }
+        config.load(&lines.join("\n"), Origin::CommandLine)?;
+        Ok(config)
+    }
+
+    /// Apply the directives in `text` on top of what is set already.
+    pub fn load(&mut self, text: &str, origin: Origin) -> Result<(), ConfigError> {
+        for (i, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            self.directive(trimmed)
+                .map_err(|reason| ConfigError::Invalid {
+                    origin: origin.clone(),
+                    line: i + 1,
+                    text: trimmed.to_string(),
+                    reason,
+                })?;
+        }
+        Ok(())
+    }
+
+    fn directive(&mut self, line: &str) -> Result<(), String> {
+        let args = split_inline_args(line.as_bytes())
+            .map_err(|_| "Unbalanced quotes in configuration line".to_string())?
+            .into_iter()
+            .map(String::from_utf8)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| "Invalid UTF-8 in configuration line".to_string())?;
+        let name = args[0].to_ascii_lowercase();
+        match (name.as_str(), &args[1..]) {
+            ("bind", addrs) if !addrs.is_empty() => {
+                self.bind = addrs
+                    .iter()
+                    .map(|addr| parse_bind(addr))
+                    .collect::<Result<_, _>>()?;
+            }
+            ("port", [port]) => {
+                self.port = port
+                    .parse()
+                    .ok()
+                    .filter(|&port| port > 0)
+                    .ok_or_else(|| "Invalid port".to_string())?;
+            }
+            ("loglevel", [level]) => {
+                self.loglevel = LogLevel::parse(level).ok_or_else(|| {
+                    "Invalid log level. Must be one of debug, verbose, notice, warning, nothing"
+                        .to_string()
+                })?;
+            }
+            (name, [value]) => match valid_param(name, value) {
+                Some(true) => self.params.push((name.to_string(), value.clone())),
+                Some(false) => return Err(format!("Invalid argument '{}' for '{}'", value, name)),
+                None => return Err("Bad directive or wrong number of arguments".to_string()),
+            },
+            _ => return Err("Bad directive or wrong number of arguments".to_string()),
+        }
+        Ok(())
+    }
+
+    /// Every address to listen on.
+    pub fn addrs(&self) -> Vec<SocketAddr> {
+        self.bind
+            .iter()
+            .map(|ip| SocketAddr::new(*ip, self.port))
+            .collect()
+    }
+
+    /// Set the CONFIG SET parameters on the backend, later ones winning.
+    pub fn apply(&self, backend: &Backend) {
+        for (name, value) in &self.params {
+            set_param(backend, name, value);
+        }
+    }
+}
+
+impl LogLevel {
+    pub fn parse(level: &str) -> Option<Self> {
+        match level.to_ascii_lowercase().as_str() {
+            "debug" => Some(Self::Debug),
+            "verbose" => Some(Self::Verbose),
+            "notice" => Some(Self::Notice),
+            "warning" => Some(Self::Warning),
+            "nothing" => Some(Self::Nothing),
+            _ => None,
+        }
+    }
+
+    /// The tracing filter for the level. Every request is logged at info,
+    /// which is what verbose means in Redis, so notice starts from warn.
+    pub fn filter(&self) -> &'static str {
+        match self {
+            Self::Debug => "debug",
+            Self::Verbose => "info",
+            Self::Notice => "warn",
+            Self::Warning => "error",
+            Self::Nothing => "off",
+        }
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "the configuration file '{}'", path),
+            Self::CommandLine => write!(f, "the command line options"),
+        }
+    }
+}
+
+// "*" and "::*" are every IPv4 and IPv6 address, as in redis.conf
+fn parse_bind(addr: &str) -> Result<IpAddr, String> {
+    match addr {
+        "*" => Ok(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+        "::*" => Ok(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        addr => addr
+            .parse()
+            .map_err(|_| format!("Invalid bind address '{}'", addr)),
+    }
+}
+
+// a command line argument as a config file would have it
+fn quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || c == '"' || c == '\'') {
+        return arg.to_string();
+    }
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        match c {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_load_config_file() {
+        let mut config = ServerConfig::default();
+        let text = "# a comment\n\nbind 127.0.0.1 ::1\nPORT 6380\nloglevel verbose\n\
+                    proto-max-bulk-len 1mb\nnotify-keyspace-events \"KEA\"\n";
+        config.load(text, Origin::CommandLine).unwrap();
+        assert_eq!(
+            config.addrs(),
+            vec![
+                "127.0.0.1:6380".parse::<SocketAddr>().unwrap(),
+                "[::1]:6380".parse().unwrap()
+            ]
+        );
+        assert_eq!(config.loglevel, LogLevel::Verbose);
+        assert_eq!(config.loglevel.filter(), "info");
+
+        let backend = Backend::new();
+        config.apply(&backend);
+        assert_eq!(backend.decode_limits().max_bulk_len, 1024 * 1024);
+        assert_ne!(backend.notify_flags(), 0);
+    }
+
+    #[test]
+    fn test_config_errors_point_to_the_line() {
+        for (text, line, reason) in [
+            ("port 6380\nport abc\n", 2, "Invalid port"),
+            ("\n\nbind 127.0.0.1 nope", 3, "Invalid bind address 'nope'"),
+            (
+                "daemonize yes",
+                1,
+                "Bad directive or wrong number of arguments",
+            ),
+            ("port", 1, "Bad directive or wrong number of arguments"),
+            (
+                "proto-max-nesting-depth 0",
+                1,
+                "Invalid argument '0' for 'proto-max-nesting-depth'",
+            ),
+            (
+                "loglevel \"notice",
+                1,
+                "Unbalanced quotes in configuration line",
+            ),
+        ] {
+            let err = ServerConfig::default()
+                .load(text, Origin::File("redis.conf".to_string()))
+                .unwrap_err();
+            match &err {
+                ConfigError::Invalid {
+                    line: l, reason: r, ..
+                } => {
+                    assert_eq!((*l, r.as_str()), (line, reason), "{}", text);
+                }
+                e => panic!("unexpected {:?}", e),
+            }
+            assert!(err.to_string().contains(&format!(
+                "the configuration file 'redis.conf', at line {}",
+                line
+            )));
+        }
+    }
+
+    #[test]
+    fn test_command_line_overrides_file() {
+        let path = std::env::temp_dir().join(format!("simple-redis-{}.conf", std::process::id()));
+        fs::write(&path, "port 7000\nbind 127.0.0.1\nlua-time-limit 100\n").unwrap();
+        let config = ServerConfig::from_args(args(&[
+            path.to_str().unwrap(),
+            "--port",
+            "7001",
+            "--notify-keyspace-events",
+            "",
+            "--lua-time-limit",
+            "200",
+        ]));
+        fs::remove_file(&path).unwrap();
+
+        let config = config.unwrap();
+        assert_eq!(config.port, 7001);
+        assert_eq!(config.bind, vec![IpAddr::V4(Ipv4Addr::LOCALHOST)]);
+        assert_eq!(
+            config.params,
+            vec![
+                ("lua-time-limit".to_string(), "100".to_string()),
+                ("notify-keyspace-events".to_string(), "".to_string()),
+                ("lua-time-limit".to_string(), "200".to_string()),
+            ]
+        );
+
+        assert_eq!(
+            ServerConfig::from_args(vec![]).unwrap(),
+            ServerConfig::default()
+        );
+        let err =
+            ServerConfig::from_args(args(&["--port", "6380", "--loglevel", "loud"])).unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigError::Invalid {
+                origin: Origin::CommandLine,
+                line: 2,
+                ..
+            }
+        ));
+        assert!(matches!(
+            ServerConfig::from_args(args(&["/no/such/file.conf"])),
+            Err(ConfigError::Io { .. })
+        ));
+    }
+}
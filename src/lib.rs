@@ -1,7 +1,14 @@
 mod backend;
+pub mod client;
 mod cmd;
+mod config;
 pub mod network;
 mod resp;
+mod script;
+mod session;
+mod wasm;
 
 pub use backend::*;
+pub use cmd::{CommandError, CommandPlugin, PluginCommand};
+pub use config::{ConfigError, LogLevel, Origin, ServerConfig};
 pub use resp::*;
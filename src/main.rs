@@ -1,17 +1,76 @@
-use anyhow::Result;
-use simple_redis::{network, Backend};
-use tokio::net::TcpListener;
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use simple_redis::{network, Backend, ServerConfig};
+use tokio::{net::TcpListener, task::JoinSet};
 use tracing::{info, warn};
+use tracing_subscriber::EnvFilter;
+
+const USAGE: &str = "\
+Usage: simple-redis [/path/to/redis.conf] [--directive value ...]
+       simple-redis -v or --version
+       simple-redis -h or --help
+
+Examples:
+       simple-redis (run the server with the default configuration)
+       simple-redis /etc/simple-redis/6379.conf
+       simple-redis --port 7777 --bind 127.0.0.1 ::1
+       simple-redis /etc/myredis.conf --loglevel verbose --proto-max-bulk-len 64mb";
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
+async fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(String::as_str) {
+        Some("-h" | "--help") => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Some("-v" | "--version") => {
+            println!("simple-redis v={}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        _ => {}
+    }
+    let config = match ServerConfig::from_args(args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // RUST_LOG still wins over loglevel when it's set
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(config.loglevel.filter()));
+    tracing_subscriber::fmt().with_env_filter(filter).init();
 
-    let addr = "0.0.0.0:6379";
-    info!("Simple-Redis-Server is listening on {}", addr);
-    let listener = TcpListener::bind(addr).await?;
+    match run(config).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
 
+async fn run(config: ServerConfig) -> Result<()> {
     let backend = Backend::new();
+    config.apply(&backend);
+
+    let mut listeners = JoinSet::new();
+    for addr in config.addrs() {
+        let listener = network::bind(addr)
+            .with_context(|| format!("Could not create server TCP listening socket {}", addr))?;
+        info!("Simple-Redis-Server is listening on {}", addr);
+        listeners.spawn(serve(listener, backend.clone()));
+    }
+    while let Some(result) = listeners.join_next().await {
+        result??;
+    }
+    Ok(())
+}
+
+async fn serve(listener: TcpListener, backend: Backend) -> Result<()> {
     loop {
         let (stream, raddr) = listener.accept().await?;
         info!("Accepted connection from: {}", raddr);
@@ -1,16 +1,30 @@
 use crate::{
     cmd::{Command, CommandExecutor},
-    Backend, RespDecode, RespEncode, RespError, RespFrame,
+    decode_inline,
+    session::Session,
+    Backend, RespDecoder, RespEncode, RespError, RespFrame, SimpleError, SimpleString,
 };
+use std::{io, net::SocketAddr};
+
 use anyhow::Result;
 use futures::SinkExt;
-use tokio::net::TcpStream;
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    runtime::{Handle, RuntimeFlavor},
+    task,
+};
 use tokio_stream::StreamExt;
 use tokio_util::codec::{Decoder, Encoder, Framed};
-use tracing::info;
+use tracing::{info, warn};
 
+// encodes in the protocol the connection negotiated with HELLO, and keeps
+// the progress on a partially received frame between reads
 #[derive(Debug)]
-struct RespFrameCodec;
+struct RespFrameCodec {
+    protocol: u8,
+    decoder: RespDecoder,
+}
 
 #[derive(Debug)]
 struct RedisRequest {
@@ -23,38 +37,147 @@ struct RedisResponse {
     frame: RespFrame,
 }
 
+// Redis' default tcp-backlog
+const LISTEN_BACKLOG: i32 = 511;
+
+/// Listen on `addr` the way Redis does: an IPv6 socket only takes IPv6, so
+/// that `*` and `::*` can both be bound on the same port even where IPv6
+/// sockets are dual-stack by default.
+pub fn bind(addr: SocketAddr) -> io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(LISTEN_BACKLOG)?;
+    TcpListener::from_std(socket.into())
+}
+
 pub async fn stream_handler(stream: TcpStream, backend: Backend) -> Result<()> {
+    // each reply is its own write, and with Nagle on, one written while the
+    // previous is still unacknowledged waits out the client's delayed ACK:
+    // about 40ms for every pipelined command after the first
+    if let Err(e) = stream.set_nodelay(true) {
+        warn!("Failed to set TCP_NODELAY: {}", e);
+    }
     // how to get a frame from the stream?
-    let mut framed = Framed::new(stream, RespFrameCodec);
+    let mut framed = Framed::new(
+        stream,
+        RespFrameCodec {
+            protocol: 2,
+            decoder: RespDecoder::with_limits(backend.decode_limits()),
+        },
+    );
+    // pub/sub messages arrive on `push_rx` at any time, interleaved with
+    // regular request/response traffic
+    let (mut session, mut push_rx) = Session::new(backend.clone());
     loop {
-        match framed.next().await {
-            Some(Ok(frame)) => {
-                info!("Received frame: {:?}", frame);
-                let request = RedisRequest {
-                    frame,
-                    backend: backend.clone(),
-                };
-                let response = request_handler(request).await?;
-                info!("Sending response: {:?}", response.frame);
-                framed.send(response.frame).await?;
+        tokio::select! {
+            frame = framed.next() => match frame {
+                Some(Ok(frame)) => {
+                    info!("Received frame: {:?}", frame);
+                    let request = RedisRequest {
+                        frame,
+                        backend: backend.clone(),
+                    };
+                    let response = request_handler(request, &mut session).await?;
+                    let codec = framed.codec_mut();
+                    codec.protocol = session.protocol;
+                    codec.decoder.set_limits(backend.decode_limits());
+                    for frame in session.take_replies() {
+                        framed.feed(frame).await?;
+                    }
+                    info!("Sending response: {:?}", response.frame);
+                    framed.send(response.frame).await?;
+                }
+                Some(Err(e)) => {
+                    let e = e.downcast::<RespError>()?;
+                    // like Redis, tell the client what was wrong with its
+                    // request before closing the connection
+                    warn!("Protocol error from client {}: {}, closing", session.id, e);
+                    backend.reject_client();
+                    framed.send(protocol_error(e)).await?;
+                    return Ok(());
+                }
+                None => return Ok(()),
+            },
+            Some(push) = push_rx.recv() => {
+                framed.send(session.push_message(push)).await?;
+                if session.is_overflowed() {
+                    warn!("Client {} exceeded its pub/sub output buffer, closing", session.id);
+                    return Ok(());
+                }
             }
-            Some(Err(e)) => return Err(e),
-            None => return Ok(()),
         }
     }
 }
 
-async fn request_handler(request: RedisRequest) -> Result<RedisResponse> {
+async fn request_handler(request: RedisRequest, session: &mut Session) -> Result<RedisResponse> {
     let (frame, backend) = (request.frame, request.backend);
+    let name = command_name(&frame);
 
-    match Command::try_from(frame) {
+    match Command::parse(frame, &backend) {
         Ok(cmd) => {
+            session.forget_evicted_shard_channels();
+            if session.in_subscriber_mode() && !cmd.allowed_in_subscriber_mode() {
+                let msg = format!(
+                    "ERR Can't execute '{}': only (P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING are allowed in this context",
+                    name
+                );
+                return Ok(RedisResponse {
+                    frame: SimpleError::new(msg).into(),
+                });
+            }
+            if session.in_multi()
+                && matches!(cmd, Command::Unrecognized(ref u) if !u.is_registered(&backend))
+            {
+                session.multi_error = true;
+                return Ok(RedisResponse {
+                    frame: SimpleError::new(format!("ERR unknown command '{}'", name)).into(),
+                });
+            }
+            if session.in_multi() && cmd.is_no_multi() {
+                session.multi_error = true;
+                return Ok(RedisResponse {
+                    frame: SimpleError::new("ERR Command not allowed inside a transaction").into(),
+                });
+            }
+            if session.in_multi() && !cmd.is_transaction_control() {
+                info!("Queuing command: {:?}", cmd);
+                if let Some(queued) = session.multi.as_mut() {
+                    queued.push(cmd);
+                }
+                return Ok(RedisResponse {
+                    frame: SimpleString::new("QUEUED").into(),
+                });
+            }
             info!("Executing command: {:?}", cmd);
-            let frame = cmd.execute(&backend);
+            let caller = session.caller();
+            let frame = if cmd.runs_while_busy() {
+                caller.run(|| cmd.execute_with(&backend, session))
+            } else if cmd.needs_exclusive(&backend) {
+                match backend.lock_exclusive().await {
+                    Some(_guard) => {
+                        run_blocking(|| caller.run(|| cmd.execute_with(&backend, session)))
+                    }
+                    None => busy_error(),
+                }
+            } else {
+                match backend.lock_shared().await {
+                    Some(_guard) => caller.run(|| cmd.execute_with(&backend, session)),
+                    None => busy_error(),
+                }
+            };
             Ok(RedisResponse { frame })
         }
         Err(e) => {
             info!("Failed to parse command: {:?}", e);
+            // a command that can't be queued dooms the whole transaction
+            if session.in_multi() {
+                session.multi_error = true;
+            }
             Ok(RedisResponse {
                 frame: RespFrame::SimpleError(crate::SimpleError(e.to_string())),
             })
@@ -67,12 +190,52 @@ async fn request_handler(request: RedisRequest) -> Result<RedisResponse> {
     // Ok(RedisResponse { frame })
 }
 
+// a script can hold its worker thread for a long time, let the runtime
+// move the other connections elsewhere meanwhile so they can get BUSY
+// replies and send SCRIPT KILL
+fn run_blocking<R>(f: impl FnOnce() -> R) -> R {
+    match Handle::current().runtime_flavor() {
+        RuntimeFlavor::MultiThread => task::block_in_place(f),
+        _ => f(),
+    }
+}
+
+fn busy_error() -> RespFrame {
+    SimpleError::new(
+        "BUSY Redis is busy running a script. You can only call SCRIPT KILL or SHUTDOWN NOSAVE.",
+    )
+    .into()
+}
+
+fn protocol_error(e: RespError) -> RespFrame {
+    match e {
+        RespError::Protocol(_) => SimpleError::new(format!("ERR {}", e)),
+        _ => SimpleError::new(format!("ERR Protocol error: {}", e)),
+    }
+    .into()
+}
+
+fn command_name(frame: &RespFrame) -> String {
+    match frame {
+        RespFrame::Array(array) => match array.first() {
+            Some(RespFrame::BulkString(s)) => String::from_utf8_lossy(s.as_ref()).to_lowercase(),
+            _ => String::new(),
+        },
+        _ => String::new(),
+    }
+}
+
 impl Encoder<RespFrame> for RespFrameCodec {
     type Error = anyhow::Error;
 
     fn encode(&mut self, item: RespFrame, dst: &mut bytes::BytesMut) -> Result<()> {
-        let encoded = item.encode();
-        dst.extend_from_slice(&encoded);
+        let item = if self.protocol < 3 {
+            item.into_resp2()
+        } else {
+            item
+        };
+        dst.reserve(item.encoded_len());
+        item.encode_to(dst);
         Ok(())
     }
 }
@@ -82,10 +245,77 @@ impl Decoder for RespFrameCodec {
     type Error = anyhow::Error;
 
     fn decode(&mut self, src: &mut bytes::BytesMut) -> Result<Option<RespFrame>> {
-        match RespFrame::decode(src) {
-            Ok(frame) => Ok(Some(frame)),
-            Err(RespError::NotComplete) => Ok(None),
-            Err(e) => Err(e.into()),
+        loop {
+            // like Redis, anything that isn't a RESP array is an inline command
+            let frame = match src.first() {
+                None => return Ok(None),
+                Some(b) if *b != b'*' && self.decoder.is_idle() => {
+                    match decode_inline(src, self.decoder.limits().max_inline_len) {
+                        // blank lines are skipped without a reply
+                        Ok(args) if args.is_empty() => continue,
+                        result => result.map(|args| Some(args.into())),
+                    }
+                }
+                Some(_) => self.decoder.decode(src),
+            };
+            return match frame {
+                Err(RespError::NotComplete) => Ok(None),
+                frame => Ok(frame?),
+            };
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+    use crate::{BulkString, RespArray};
+
+    #[tokio::test]
+    async fn test_bind_both_wildcards_on_one_port() -> Result<()> {
+        let v4 = bind(SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0))?;
+        let port = v4.local_addr()?.port();
+        let v6 = bind(SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), port))?;
+        assert_eq!(v6.local_addr()?.port(), port);
+
+        TcpStream::connect((Ipv4Addr::LOCALHOST, port)).await?;
+        TcpStream::connect((Ipv6Addr::LOCALHOST, port)).await?;
+        assert!(v4.accept().await?.1.is_ipv4());
+        assert!(v6.accept().await?.1.is_ipv6());
+        Ok(())
+    }
+
+    async fn send(backend: &Backend, session: &mut Session, args: &[&str]) -> Result<RespFrame> {
+        let frame = RespArray::new(
+            args.iter()
+                .map(|arg| BulkString::from(*arg).into())
+                .collect::<Vec<RespFrame>>(),
+        );
+        let request = RedisRequest {
+            frame: frame.into(),
+            backend: backend.clone(),
+        };
+        Ok(request_handler(request, session).await?.frame)
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_refused_in_multi() -> Result<()> {
+        let backend = Backend::new();
+        let (mut session, _rx) = Session::new(backend.clone());
+
+        send(&backend, &mut session, &["multi"]).await?;
+        assert_eq!(
+            send(&backend, &mut session, &["subscribe", "a", "b"]).await?,
+            SimpleError::new("ERR Command not allowed inside a transaction").into()
+        );
+        assert_eq!(
+            send(&backend, &mut session, &["exec"]).await?,
+            SimpleError::new("EXECABORT Transaction discarded because of previous errors.").into()
+        );
+        assert!(session.take_replies().is_empty());
+        assert!(!session.in_subscriber_mode());
+        Ok(())
+    }
+}
@@ -1,10 +1,10 @@
 use std::ops::Deref;
 
-use bytes::Buf;
+use bytes::{BufMut, BytesMut};
 
-use crate::{RespDecode, RespEncode, RespError, RespFrame};
+use crate::{RespDecode, RespDecoder, RespEncode, RespError, RespFrame};
 
-use super::{calc_total_length, parse_length2, BUF_CAP, CRLF_LEN};
+use super::{decode_aggregate, encode_header, header_len};
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord)]
 pub enum RespArray {
@@ -13,17 +13,24 @@ pub enum RespArray {
 }
 
 impl RespEncode for RespArray {
-    fn encode(self) -> Vec<u8> {
+    fn encode_to<B: BufMut>(&self, buf: &mut B) {
         match self {
             RespArray::Array(v) => {
-                let mut buf = Vec::with_capacity(BUF_CAP);
-                buf.extend_from_slice(&format!("*{}\r\n", v.len()).into_bytes());
+                encode_header(buf, b'*', v.len());
                 for frame in v {
-                    buf.extend_from_slice(&frame.encode());
+                    frame.encode_to(buf);
                 }
-                buf
             }
-            RespArray::Null => b"*-1\r\n".to_vec(),
+            RespArray::Null => buf.put_slice(b"*-1\r\n"),
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        match self {
+            RespArray::Array(v) => {
+                header_len(v.len()) + v.iter().map(RespEncode::encoded_len).sum::<usize>()
+            }
+            RespArray::Null => 5,
         }
     }
 }
@@ -31,40 +38,15 @@ impl RespEncode for RespArray {
 impl RespDecode for RespArray {
     const PREFIX: &'static str = "*";
 
-    fn decode(buf: &mut bytes::BytesMut) -> Result<Self, crate::RespError>
-    where
-        Self: Sized,
-    {
-        let (end, len) = parse_length2(buf, Self::PREFIX)?;
-        if len == -1 {
-            return Ok(RespArray::new_null());
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        match decode_aggregate(buf, Self::PREFIX)? {
+            RespFrame::Array(frame) => Ok(frame),
+            _ => unreachable!(),
         }
-
-        let len = len as usize;
-
-        let total_len = calc_total_length(buf, end, len, Self::PREFIX)?;
-
-        if buf.len() < total_len {
-            return Err(RespError::NotComplete);
-        }
-
-        buf.advance(end + CRLF_LEN);
-
-        let mut frames = Vec::with_capacity(len);
-        for _ in 0..len {
-            frames.push(RespFrame::decode(buf)?);
-        }
-
-        Ok(RespArray::new(frames))
     }
 
-    fn expect_length(buf: &[u8]) -> Result<usize, crate::RespError> {
-        let (end, len) = parse_length2(buf, Self::PREFIX)?;
-        if len == -1 {
-            return Ok(end + CRLF_LEN);
-        }
-        let len = len as usize;
-        calc_total_length(buf, end, len, Self::PREFIX)
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        RespDecoder::frame_len(buf)
     }
 }
 
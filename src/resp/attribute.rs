@@ -0,0 +1,105 @@
+use bytes::{BufMut, BytesMut};
+
+use crate::{RespDecode, RespDecoder, RespEncode, RespError, RespFrame, RespMap};
+
+use super::decode_aggregate;
+
+/// Out-of-band metadata (key popularity and such) sent ahead of a reply,
+/// kept together with the reply it describes.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RespAttribute {
+    pub(crate) attributes: RespMap,
+    pub(crate) frame: Box<RespFrame>,
+}
+
+// - attribute: "|<number-of-entries>\r\n<key-1><value-1>...<key-n><value-n>"
+//   followed by the reply itself
+impl RespEncode for RespAttribute {
+    fn encode_to<B: BufMut>(&self, buf: &mut B) {
+        self.attributes.encode_with_prefix(buf, b'|');
+        self.frame.encode_to(buf);
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.attributes.encoded_len() + self.frame.encoded_len()
+    }
+}
+
+impl RespDecode for RespAttribute {
+    const PREFIX: &'static str = "|";
+
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        match decode_aggregate(buf, Self::PREFIX)? {
+            RespFrame::Attribute(frame) => Ok(frame),
+            _ => unreachable!(),
+        }
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        RespDecoder::frame_len(buf)
+    }
+}
+
+impl RespAttribute {
+    pub fn new(attributes: RespMap, frame: impl Into<RespFrame>) -> Self {
+        Self {
+            attributes,
+            frame: Box::new(frame.into()),
+        }
+    }
+
+    pub fn attributes(&self) -> &RespMap {
+        &self.attributes
+    }
+
+    /// The reply the attributes were sent with.
+    pub fn into_frame(self) -> RespFrame {
+        *self.frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BulkString, Double, RespArray};
+
+    use super::*;
+    use anyhow::Result;
+
+    fn attribute() -> RespAttribute {
+        let mut popularity = RespMap::new();
+        popularity.insert(BulkString::from("a").into(), Double::from(0.1923).into());
+        let mut attributes = RespMap::new();
+        attributes.insert(BulkString::from("key-popularity").into(), popularity.into());
+        RespAttribute::new(attributes, RespArray::new([RespFrame::Integer(2039123)]))
+    }
+
+    #[test]
+    fn test_attribute_encode() {
+        let frame: RespFrame = attribute().into();
+        assert_eq!(
+            frame.encode(),
+            b"|1\r\n$14\r\nkey-popularity\r\n%1\r\n$1\r\na\r\n,0.1923\r\n*1\r\n:2039123\r\n"
+        );
+    }
+
+    #[test]
+    fn test_attribute_decode() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"|1\r\n$14\r\nkey-popularity\r\n%1\r\n$1\r\na\r\n,0.1923\r\n");
+        assert_eq!(
+            RespAttribute::decode(&mut buf).unwrap_err(),
+            RespError::NotComplete
+        );
+
+        buf.extend_from_slice(b"*1\r\n:2039123\r\n");
+        let frame = RespAttribute::decode(&mut buf)?;
+        assert_eq!(frame, attribute());
+        assert!(buf.is_empty());
+
+        buf.extend_from_slice(b"|1\r\n+ttl\r\n:3600\r\n$1\r\nv\r\n");
+        let frame = RespAttribute::decode(&mut buf)?;
+        assert_eq!(frame.attributes()["ttl"], RespFrame::Integer(3600));
+        assert_eq!(frame.into_frame(), BulkString::from("v").into());
+        Ok(())
+    }
+}
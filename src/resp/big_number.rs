@@ -0,0 +1,97 @@
+use std::ops::Deref;
+
+use bytes::{BufMut, BytesMut};
+
+use crate::{extract_simple_frame_data, RespDecode, RespEncode, RespError};
+
+use super::{encode_line, CRLF_LEN};
+
+/// An integer outside of the i64 range, kept as its decimal digits.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BigNumber(pub(crate) String);
+
+// - big number: "(<big number>\r\n"
+impl RespEncode for BigNumber {
+    fn encode_to<B: BufMut>(&self, buf: &mut B) {
+        encode_line(buf, b'(', self.0.as_bytes());
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.0.len() + 3
+    }
+}
+
+impl RespDecode for BigNumber {
+    const PREFIX: &'static str = "(";
+
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        let end = extract_simple_frame_data(buf, Self::PREFIX)?;
+        let data = buf.split_to(end + CRLF_LEN);
+        let s = String::from_utf8(data[Self::PREFIX.len()..end].to_vec())?;
+        BigNumber::new(s)
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        let end = extract_simple_frame_data(buf, Self::PREFIX)?;
+        Ok(end + CRLF_LEN)
+    }
+}
+
+impl BigNumber {
+    pub fn new(s: impl Into<String>) -> Result<Self, RespError> {
+        let s = s.into();
+        let digits = s.strip_prefix('-').unwrap_or(&s);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(RespError::InvalidFrame(format!(
+                "Invalid big number: {}",
+                s
+            )));
+        }
+        Ok(Self(s))
+    }
+}
+
+impl From<i64> for BigNumber {
+    fn from(i: i64) -> Self {
+        Self(i.to_string())
+    }
+}
+
+impl Deref for BigNumber {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RespFrame;
+
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_big_number_encode() -> Result<()> {
+        let frame: RespFrame =
+            BigNumber::new("3492890328409238509324850943850943825024385")?.into();
+        assert_eq!(
+            frame.encode(),
+            b"(3492890328409238509324850943850943825024385\r\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_big_number_decode() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"(-3492890328409238509324850943850943825024385\r\n");
+        let frame = BigNumber::decode(&mut buf)?;
+        assert_eq!(*frame, "-3492890328409238509324850943850943825024385");
+
+        buf.extend_from_slice(b"(12a\r\n");
+        assert!(BigNumber::decode(&mut buf).is_err());
+        Ok(())
+    }
+}
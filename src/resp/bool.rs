@@ -1,10 +1,16 @@
+use bytes::BufMut;
+
 use crate::{RespDecode, RespEncode, RespError};
 
 use super::extract_fixed_data;
 
 impl RespEncode for bool {
-    fn encode(self) -> Vec<u8> {
-        format!("#{}\r\n", if self { "t" } else { "f" }).into_bytes()
+    fn encode_to<B: BufMut>(&self, buf: &mut B) {
+        buf.put_slice(if *self { b"#t\r\n" } else { b"#f\r\n" });
+    }
+
+    fn encoded_len(&self) -> usize {
+        4
     }
 }
 
@@ -0,0 +1,83 @@
+use bytes::{Buf, BufMut, BytesMut};
+
+use crate::{RespDecode, RespEncode, RespError};
+
+use super::{bulk_len, encode_bulk, parse_length, CRLF_LEN};
+
+/// An error that may hold any bytes, CRLF included.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BulkError(pub(crate) Vec<u8>);
+
+// - bulk error: "!<length>\r\n<error>\r\n"
+impl RespEncode for BulkError {
+    fn encode_to<B: BufMut>(&self, buf: &mut B) {
+        encode_bulk(buf, b'!', &self.0);
+    }
+
+    fn encoded_len(&self) -> usize {
+        bulk_len(self.0.len())
+    }
+}
+
+impl RespDecode for BulkError {
+    const PREFIX: &'static str = "!";
+
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        let (end, len) = parse_length(buf, Self::PREFIX)?;
+        if buf.len() < end + CRLF_LEN + len + CRLF_LEN {
+            return Err(RespError::NotComplete);
+        }
+
+        buf.advance(end + CRLF_LEN);
+        let data = buf.split_to(len);
+        buf.advance(CRLF_LEN);
+
+        Ok(BulkError::new(data.to_vec()))
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        let (end, len) = parse_length(buf, Self::PREFIX)?;
+        Ok(end + CRLF_LEN + len + CRLF_LEN)
+    }
+}
+
+impl BulkError {
+    pub fn new(s: impl Into<Vec<u8>>) -> Self {
+        Self(s.into())
+    }
+}
+
+impl AsRef<[u8]> for BulkError {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RespFrame;
+
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_bulk_error_encode() {
+        let frame: RespFrame = BulkError::new("SYNTAX invalid\r\nsyntax").into();
+        assert_eq!(frame.encode(), b"!22\r\nSYNTAX invalid\r\nsyntax\r\n");
+    }
+
+    #[test]
+    fn test_bulk_error_decode() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"!21\r\nSYNTAX invalid syntax\r\n");
+        let frame = BulkError::decode(&mut buf)?;
+        assert_eq!(frame, BulkError::new("SYNTAX invalid syntax"));
+
+        buf.extend_from_slice(b"!21\r\nSYNTAX");
+        assert_eq!(
+            BulkError::decode(&mut buf).unwrap_err(),
+            RespError::NotComplete
+        );
+        Ok(())
+    }
+}
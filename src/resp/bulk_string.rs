@@ -1,28 +1,29 @@
-use bytes::{Buf, BytesMut};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 
 use crate::{RespDecode, RespEncode, RespError};
 
-use super::{parse_length2, CRLF_LEN};
+use super::{bulk_len, decoder::take_payload, encode_bulk, parse_length2, CRLF_LEN};
 
+// the payload is reference counted, decoding hands out slices of the read
+// buffer rather than copies
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum BulkString {
-    String(Vec<u8>),
+    String(Bytes),
     Null,
 }
 
 impl RespEncode for BulkString {
-    fn encode(self) -> Vec<u8> {
+    fn encode_to<B: BufMut>(&self, buf: &mut B) {
         match self {
-            BulkString::String(s) => {
-                let mut buf = Vec::new();
-                buf.extend_from_slice(b"$");
-                buf.extend_from_slice(s.len().to_string().as_bytes());
-                buf.extend_from_slice(b"\r\n");
-                buf.extend_from_slice(&s);
-                buf.extend_from_slice(b"\r\n");
-                buf
-            }
-            BulkString::Null => b"$-1\r\n".to_vec(),
+            BulkString::String(s) => encode_bulk(buf, b'$', s),
+            BulkString::Null => buf.put_slice(b"$-1\r\n"),
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        match self {
+            BulkString::String(s) => bulk_len(s.len()),
+            BulkString::Null => 5,
         }
     }
 }
@@ -33,6 +34,7 @@ impl RespDecode for BulkString {
     fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
         let (end, len) = parse_length2(buf, Self::PREFIX)?;
         if len == -1 {
+            buf.advance(end + CRLF_LEN);
             return Ok(BulkString::new_null());
         }
 
@@ -44,11 +46,7 @@ impl RespDecode for BulkString {
         }
 
         buf.advance(end + CRLF_LEN);
-
-        let s = buf.split_to(len);
-        buf.advance(CRLF_LEN);
-
-        Ok(BulkString::new(s.to_vec()))
+        Ok(BulkString::String(take_payload(buf, len)))
     }
 
     fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
@@ -62,7 +60,7 @@ impl RespDecode for BulkString {
 }
 
 impl BulkString {
-    pub fn new(s: impl Into<Vec<u8>>) -> Self {
+    pub fn new(s: impl Into<Bytes>) -> Self {
         BulkString::String(s.into())
     }
 
@@ -82,25 +80,42 @@ impl AsRef<[u8]> for BulkString {
 
 impl From<Vec<u8>> for BulkString {
     fn from(s: Vec<u8>) -> Self {
+        BulkString::String(s.into())
+    }
+}
+
+impl From<Bytes> for BulkString {
+    fn from(s: Bytes) -> Self {
         BulkString::String(s)
     }
 }
 
 impl From<&str> for BulkString {
     fn from(s: &str) -> Self {
-        BulkString::String(s.as_bytes().to_vec())
+        BulkString::String(Bytes::copy_from_slice(s.as_bytes()))
     }
 }
 
 impl From<&[u8]> for BulkString {
     fn from(s: &[u8]) -> Self {
-        BulkString::String(s.to_vec())
+        BulkString::String(Bytes::copy_from_slice(s))
     }
 }
 
 impl<const N: usize> From<&[u8; N]> for BulkString {
     fn from(s: &[u8; N]) -> Self {
-        BulkString::String(s.to_vec())
+        BulkString::String(Bytes::copy_from_slice(s))
+    }
+}
+
+impl TryFrom<BulkString> for Bytes {
+    type Error = RespError;
+
+    fn try_from(value: BulkString) -> Result<Self, Self::Error> {
+        match value {
+            BulkString::String(s) => Ok(s),
+            BulkString::Null => Err(RespError::InvalidFrame("Null BulkString".to_string())),
+        }
     }
 }
 
@@ -109,7 +124,7 @@ impl TryFrom<BulkString> for String {
 
     fn try_from(value: BulkString) -> Result<Self, Self::Error> {
         match value {
-            BulkString::String(s) => Ok(String::from_utf8(s)?),
+            BulkString::String(s) => Ok(String::from_utf8(s.into())?),
             BulkString::Null => Err(RespError::InvalidFrame("Null BulkString".to_string())),
         }
     }
@@ -140,7 +155,7 @@ mod tests {
         buf.extend_from_slice(b"$5\r\nhello\r\n");
 
         let frame = BulkString::decode(&mut buf)?;
-        assert_eq!(frame, BulkString::new(b"hello"));
+        assert_eq!(frame, BulkString::from(b"hello"));
 
         buf.extend_from_slice(b"$5\r\nhello");
         let ret = BulkString::decode(&mut buf);
@@ -148,7 +163,7 @@ mod tests {
 
         buf.extend_from_slice(b"\r\n");
         let frame = BulkString::decode(&mut buf)?;
-        assert_eq!(frame, BulkString::new(b"hello"));
+        assert_eq!(frame, BulkString::from(b"hello"));
 
         Ok(())
     }
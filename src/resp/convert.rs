@@ -0,0 +1,386 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    hash::{BuildHasher, Hash},
+};
+
+use bytes::Bytes;
+
+use crate::{BulkString, Double, RespArray, RespError, RespFrame, RespMap, RespNull, RespSet};
+
+use super::{
+    de::{error_or_mismatch, from_frame, is_null, FrameDeserializer},
+    ser::integer,
+};
+
+/// Conversion of a reply into a Rust value. Scalars are read the way the
+/// serde `FrameDeserializer` reads them, and a reply of the wrong shape
+/// fails with an error saying what was expected and what came instead.
+pub trait FromRespFrame: Sized {
+    fn from_resp_frame(frame: RespFrame) -> Result<Self, RespError>;
+}
+
+/// Conversion of a Rust value into a frame to send, strings and bytes as
+/// bulk strings.
+pub trait IntoRespFrame {
+    fn into_resp_frame(self) -> RespFrame;
+}
+
+impl FromRespFrame for RespFrame {
+    fn from_resp_frame(frame: RespFrame) -> Result<Self, RespError> {
+        Ok(frame)
+    }
+}
+
+impl IntoRespFrame for RespFrame {
+    fn into_resp_frame(self) -> RespFrame {
+        self
+    }
+}
+
+macro_rules! scalar {
+    ($($t:ty),*) => {
+        $(
+            impl FromRespFrame for $t {
+                fn from_resp_frame(frame: RespFrame) -> Result<Self, RespError> {
+                    from_frame(frame)
+                }
+            }
+        )*
+    };
+}
+
+scalar!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64, bool, String);
+
+macro_rules! into_integer {
+    ($($t:ty),*) => {
+        $(
+            impl IntoRespFrame for $t {
+                fn into_resp_frame(self) -> RespFrame {
+                    integer(self)
+                }
+            }
+        )*
+    };
+}
+
+into_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl IntoRespFrame for f32 {
+    fn into_resp_frame(self) -> RespFrame {
+        Double::from(self as f64).into()
+    }
+}
+
+impl IntoRespFrame for f64 {
+    fn into_resp_frame(self) -> RespFrame {
+        Double::from(self).into()
+    }
+}
+
+impl IntoRespFrame for bool {
+    fn into_resp_frame(self) -> RespFrame {
+        self.into()
+    }
+}
+
+impl IntoRespFrame for String {
+    fn into_resp_frame(self) -> RespFrame {
+        BulkString::new(self).into()
+    }
+}
+
+impl IntoRespFrame for &str {
+    fn into_resp_frame(self) -> RespFrame {
+        BulkString::from(self).into()
+    }
+}
+
+impl IntoRespFrame for &[u8] {
+    fn into_resp_frame(self) -> RespFrame {
+        BulkString::from(self).into()
+    }
+}
+
+impl FromRespFrame for Bytes {
+    fn from_resp_frame(frame: RespFrame) -> Result<Self, RespError> {
+        match frame {
+            RespFrame::BulkString(BulkString::String(s)) => Ok(s),
+            frame => Ok(FrameDeserializer::new(frame).into_text()?.into()),
+        }
+    }
+}
+
+impl IntoRespFrame for Bytes {
+    fn into_resp_frame(self) -> RespFrame {
+        BulkString::new(self).into()
+    }
+}
+
+impl<T: FromRespFrame> FromRespFrame for Option<T> {
+    fn from_resp_frame(frame: RespFrame) -> Result<Self, RespError> {
+        if is_null(&frame) {
+            Ok(None)
+        } else {
+            T::from_resp_frame(frame).map(Some)
+        }
+    }
+}
+
+impl<T: IntoRespFrame> IntoRespFrame for Option<T> {
+    fn into_resp_frame(self) -> RespFrame {
+        match self {
+            Some(value) => value.into_resp_frame(),
+            None => RespNull.into(),
+        }
+    }
+}
+
+fn elements<T: FromRespFrame, C: FromIterator<T>>(frame: RespFrame) -> Result<C, RespError> {
+    FrameDeserializer::new(frame)
+        .into_seq()?
+        .into_iter()
+        .map(T::from_resp_frame)
+        .collect()
+}
+
+fn entries<K: FromRespFrame, V: FromRespFrame, C: FromIterator<(K, V)>>(
+    frame: RespFrame,
+) -> Result<C, RespError> {
+    FrameDeserializer::new(frame)
+        .into_entries()?
+        .into_iter()
+        .map(|(k, v)| Ok((K::from_resp_frame(k)?, V::from_resp_frame(v)?)))
+        .collect()
+}
+
+// a null array reads as an empty one
+impl<T: FromRespFrame> FromRespFrame for Vec<T> {
+    fn from_resp_frame(frame: RespFrame) -> Result<Self, RespError> {
+        elements(frame)
+    }
+}
+
+impl<T: IntoRespFrame> IntoRespFrame for Vec<T> {
+    fn into_resp_frame(self) -> RespFrame {
+        RespArray::new(
+            self.into_iter()
+                .map(IntoRespFrame::into_resp_frame)
+                .collect::<Vec<_>>(),
+        )
+        .into()
+    }
+}
+
+impl<T: FromRespFrame + Ord> FromRespFrame for BTreeSet<T> {
+    fn from_resp_frame(frame: RespFrame) -> Result<Self, RespError> {
+        elements(frame)
+    }
+}
+
+impl<T: IntoRespFrame> IntoRespFrame for BTreeSet<T> {
+    fn into_resp_frame(self) -> RespFrame {
+        RespSet::new(
+            self.into_iter()
+                .map(IntoRespFrame::into_resp_frame)
+                .collect::<Vec<_>>(),
+        )
+        .into()
+    }
+}
+
+impl<T: FromRespFrame + Eq + Hash, S: BuildHasher + Default> FromRespFrame for HashSet<T, S> {
+    fn from_resp_frame(frame: RespFrame) -> Result<Self, RespError> {
+        elements(frame)
+    }
+}
+
+impl<T: IntoRespFrame, S> IntoRespFrame for HashSet<T, S> {
+    fn into_resp_frame(self) -> RespFrame {
+        RespSet::new(
+            self.into_iter()
+                .map(IntoRespFrame::into_resp_frame)
+                .collect::<Vec<_>>(),
+        )
+        .into()
+    }
+}
+
+// from a map, or a flat [field, value, ...] array over RESP2
+impl<K, V, S> FromRespFrame for HashMap<K, V, S>
+where
+    K: FromRespFrame + Eq + Hash,
+    V: FromRespFrame,
+    S: BuildHasher + Default,
+{
+    fn from_resp_frame(frame: RespFrame) -> Result<Self, RespError> {
+        entries(frame)
+    }
+}
+
+impl<K: IntoRespFrame, V: IntoRespFrame, S> IntoRespFrame for HashMap<K, V, S> {
+    fn into_resp_frame(self) -> RespFrame {
+        self.into_iter()
+            .map(|(k, v)| (k.into_resp_frame(), v.into_resp_frame()))
+            .collect::<RespMap>()
+            .into()
+    }
+}
+
+impl<K: FromRespFrame + Ord, V: FromRespFrame> FromRespFrame for BTreeMap<K, V> {
+    fn from_resp_frame(frame: RespFrame) -> Result<Self, RespError> {
+        entries(frame)
+    }
+}
+
+impl<K: IntoRespFrame, V: IntoRespFrame> IntoRespFrame for BTreeMap<K, V> {
+    fn into_resp_frame(self) -> RespFrame {
+        self.into_iter()
+            .map(|(k, v)| (k.into_resp_frame(), v.into_resp_frame()))
+            .collect::<RespMap>()
+            .into()
+    }
+}
+
+macro_rules! tuple {
+    ($len:literal: $($name:ident),+) => {
+        impl<$($name: FromRespFrame),+> FromRespFrame for ($($name,)+) {
+            fn from_resp_frame(frame: RespFrame) -> Result<Self, RespError> {
+                let frames = match frame {
+                    frame @ (RespFrame::Array(RespArray::Array(_))
+                    | RespFrame::Set(_)
+                    | RespFrame::Push(_)) => FrameDeserializer::new(frame).into_seq()?,
+                    frame => return Err(error_or_mismatch("array", frame)),
+                };
+                if frames.len() != $len {
+                    return Err(RespError::Conversion(format!(
+                        "expected array of {} elements, got {}",
+                        $len,
+                        frames.len()
+                    )));
+                }
+                let mut frames = frames.into_iter();
+                Ok(($($name::from_resp_frame(frames.next().expect("length checked above"))?,)+))
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<$($name: IntoRespFrame),+> IntoRespFrame for ($($name,)+) {
+            fn into_resp_frame(self) -> RespFrame {
+                let ($($name,)+) = self;
+                RespArray::new([$($name.into_resp_frame()),+]).into()
+            }
+        }
+    };
+}
+
+tuple!(1: A);
+tuple!(2: A, B);
+tuple!(3: A, B, C);
+tuple!(4: A, B, C, D);
+tuple!(5: A, B, C, D, E);
+tuple!(6: A, B, C, D, E, F);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BigNumber, SimpleError, SimpleString};
+
+    fn bulk(s: &str) -> RespFrame {
+        BulkString::from(s).into()
+    }
+
+    #[test]
+    fn test_from_resp_frame() -> Result<(), RespError> {
+        assert_eq!(i64::from_resp_frame(RespFrame::Integer(-3))?, -3);
+        assert_eq!(u16::from_resp_frame(bulk("65535"))?, u16::MAX);
+        assert_eq!(f64::from_resp_frame(bulk("2.5"))?, 2.5);
+        assert_eq!(
+            String::from_resp_frame(SimpleString::new("PONG").into())?,
+            "PONG"
+        );
+        assert_eq!(
+            Bytes::from_resp_frame(BulkString::from(b"\xff").into())?,
+            Bytes::from_static(b"\xff")
+        );
+        assert_eq!(
+            Option::<String>::from_resp_frame(BulkString::new_null().into())?,
+            None
+        );
+        assert_eq!(
+            Vec::<i64>::from_resp_frame(RespArray::new_null().into())?,
+            Vec::<i64>::new()
+        );
+        assert_eq!(
+            BTreeSet::<String>::from_resp_frame(RespSet::new([bulk("b"), bulk("a")]).into())?,
+            BTreeSet::from(["a".to_string(), "b".to_string()])
+        );
+
+        // HGETALL, over RESP2 and RESP3
+        let flat = RespArray::new([bulk("f"), bulk("1")]);
+        let expected = HashMap::from([("f".to_string(), 1)]);
+        assert_eq!(HashMap::from_resp_frame(flat.into())?, expected);
+        let mut map = RespMap::new();
+        map.insert(bulk("f"), RespFrame::Integer(1));
+        assert_eq!(HashMap::from_resp_frame(map.into())?, expected);
+
+        let frame = RespArray::new([bulk("a"), RespFrame::Integer(1), RespNull.into()]);
+        assert_eq!(
+            <(String, i64, Option<bool>)>::from_resp_frame(frame.into())?,
+            ("a".to_string(), 1, None)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_resp_frame_errors() {
+        fn err<T: std::fmt::Debug>(result: Result<T, RespError>) -> String {
+            result.unwrap_err().to_string()
+        }
+        assert_eq!(
+            err(Vec::<i64>::from_resp_frame(RespFrame::Integer(1))),
+            "Conversion error: expected array, got integer"
+        );
+        assert_eq!(
+            err(Vec::<i64>::from_resp_frame(
+                RespArray::new([bulk("x")]).into()
+            )),
+            "Conversion error: expected integer, got bulk string"
+        );
+        assert_eq!(
+            err(<(i64, i64)>::from_resp_frame(RespArray::new([]).into())),
+            "Conversion error: expected array of 2 elements, got 0"
+        );
+        assert_eq!(
+            err(HashMap::<String, String>::from_resp_frame(
+                SimpleError::new("ERR x").into()
+            )),
+            "Conversion error: error reply: ERR x"
+        );
+    }
+
+    #[test]
+    fn test_into_resp_frame() {
+        assert_eq!(42u8.into_resp_frame(), RespFrame::Integer(42));
+        assert_eq!(
+            u64::MAX.into_resp_frame(),
+            BigNumber(u64::MAX.to_string()).into()
+        );
+        assert_eq!("a".into_resp_frame(), bulk("a"));
+        assert_eq!(None::<i64>.into_resp_frame(), RespNull.into());
+        assert_eq!(
+            ("k", 1.5, vec![true]).into_resp_frame(),
+            RespArray::new([
+                bulk("k"),
+                Double::from(1.5).into(),
+                RespArray::new([true.into()]).into(),
+            ])
+            .into()
+        );
+        let mut map = RespMap::new();
+        map.insert(bulk("a"), RespSet::new([RespFrame::Integer(1)]).into());
+        assert_eq!(
+            BTreeMap::from([("a", BTreeSet::from([1]))]).into_resp_frame(),
+            map.into()
+        );
+    }
+}
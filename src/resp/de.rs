@@ -0,0 +1,672 @@
+use std::fmt::Display;
+
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+
+use crate::{BulkString, RespArray, RespError, RespFrame, RespMap};
+
+/// Reads a reply into any `DeserializeOwned` type. Replies are taken the
+/// way Redis sends them: numbers may come as bulk strings, maps as flat
+/// `[field, value, ...]` arrays over RESP2, and nulls of any kind make
+/// `None`. An error reply fails the conversion with its message.
+#[derive(Debug)]
+pub struct FrameDeserializer {
+    frame: RespFrame,
+}
+
+pub fn from_frame<T: DeserializeOwned>(frame: RespFrame) -> Result<T, RespError> {
+    T::deserialize(FrameDeserializer::new(frame))
+}
+
+impl FrameDeserializer {
+    pub fn new(frame: RespFrame) -> Self {
+        // attributes only describe the reply they come with
+        let frame = match frame {
+            RespFrame::Attribute(a) => a.into_frame(),
+            frame => frame,
+        };
+        Self { frame }
+    }
+
+    fn mismatch(&self, expected: &str) -> RespError {
+        mismatch(expected, &self.frame)
+    }
+
+    // the text of a string-like frame, numbers are read from it
+    fn text(&self) -> Option<&[u8]> {
+        match &self.frame {
+            RespFrame::SimpleString(s) => Some(s.as_bytes()),
+            RespFrame::BulkString(BulkString::String(s)) => Some(s),
+            RespFrame::VerbatimString(s) => Some(&s.data),
+            RespFrame::BigNumber(n) => Some(n.as_bytes()),
+            _ => None,
+        }
+    }
+
+    fn parse<T: std::str::FromStr>(&self, expected: &str) -> Result<T, RespError> {
+        self.text()
+            .and_then(|s| std::str::from_utf8(s).ok())
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| self.mismatch(expected))
+    }
+
+    fn integer<T: TryFrom<i64> + std::str::FromStr>(&self) -> Result<T, RespError> {
+        match self.frame {
+            RespFrame::Integer(i) => T::try_from(i)
+                .map_err(|_| RespError::Conversion(format!("integer {} out of range", i))),
+            _ => self.parse("integer"),
+        }
+    }
+
+    fn float(&self) -> Result<f64, RespError> {
+        match &self.frame {
+            RespFrame::Double(d) => Ok(d.0),
+            RespFrame::Integer(i) => Ok(*i as f64),
+            // Redis spells them like this in bulk strings
+            _ => match self.text() {
+                Some(b"inf" | b"+inf") => Ok(f64::INFINITY),
+                Some(b"-inf") => Ok(f64::NEG_INFINITY),
+                _ => self.parse("double"),
+            },
+        }
+    }
+
+    fn is_null(&self) -> bool {
+        is_null(&self.frame)
+    }
+
+    pub(super) fn into_text(self) -> Result<Vec<u8>, RespError> {
+        match self.frame {
+            RespFrame::SimpleString(s) => Ok(s.0.into_bytes()),
+            RespFrame::BulkString(BulkString::String(s)) => Ok(s.into()),
+            RespFrame::VerbatimString(s) => Ok(s.data),
+            RespFrame::BigNumber(n) => Ok(n.0.into_bytes()),
+            RespFrame::Integer(i) => Ok(i.to_string().into_bytes()),
+            RespFrame::Double(d) => Ok(d.0.to_string().into_bytes()),
+            frame => Err(error_or_mismatch("string", frame)),
+        }
+    }
+
+    fn into_string(self) -> Result<String, RespError> {
+        String::from_utf8(self.into_text()?)
+            .map_err(|_| RespError::Conversion("expected string, got non UTF-8 bytes".to_string()))
+    }
+
+    pub(super) fn into_seq(self) -> Result<Vec<RespFrame>, RespError> {
+        match self.frame {
+            RespFrame::Array(RespArray::Array(frames))
+            | RespFrame::Set(crate::RespSet(frames))
+            | RespFrame::Push(crate::RespPush(frames)) => Ok(frames),
+            RespFrame::Array(RespArray::Null) => Ok(Vec::new()),
+            // pairs, for a Vec<(K, V)> out of a map reply
+            RespFrame::Map(map) => Ok(map
+                .into_iter()
+                .map(|(k, v)| RespArray::new([k, v]).into())
+                .collect()),
+            frame => Err(error_or_mismatch("array", frame)),
+        }
+    }
+
+    pub(super) fn into_entries(self) -> Result<Vec<(RespFrame, RespFrame)>, RespError> {
+        match self.frame {
+            RespFrame::Map(RespMap(entries)) => Ok(entries),
+            frame @ (RespFrame::Array(_) | RespFrame::Set(_) | RespFrame::Push(_)) => {
+                flat_entries(FrameDeserializer { frame }.into_seq()?)
+            }
+            frame => Err(error_or_mismatch("map", frame)),
+        }
+    }
+}
+
+pub(super) fn is_null(frame: &RespFrame) -> bool {
+    matches!(
+        frame,
+        RespFrame::Null(_)
+            | RespFrame::BulkString(BulkString::Null)
+            | RespFrame::Array(RespArray::Null)
+    )
+}
+
+// what a frame is, for errors about replies of the wrong shape
+pub(super) fn kind(frame: &RespFrame) -> &'static str {
+    match frame {
+        RespFrame::SimpleString(_) => "simple string",
+        RespFrame::SimpleError(_) => "error",
+        RespFrame::Integer(_) => "integer",
+        RespFrame::Double(_) => "double",
+        RespFrame::Boolean(_) => "boolean",
+        RespFrame::BulkString(BulkString::String(_)) => "bulk string",
+        RespFrame::BulkString(BulkString::Null) | RespFrame::Null(_) => "null",
+        RespFrame::Map(_) => "map",
+        RespFrame::Array(RespArray::Array(_)) => "array",
+        RespFrame::Array(RespArray::Null) => "null",
+        RespFrame::Set(_) => "set",
+        RespFrame::Push(_) => "push",
+        RespFrame::VerbatimString(_) => "verbatim string",
+        RespFrame::BigNumber(_) => "big number",
+        RespFrame::BulkError(_) => "error",
+        RespFrame::Attribute(_) => "attribute",
+    }
+}
+
+pub(super) fn mismatch(expected: &str, frame: &RespFrame) -> RespError {
+    RespError::Conversion(format!("expected {}, got {}", expected, kind(frame)))
+}
+
+// an error reply is reported as such rather than as the wrong type
+pub(super) fn error_or_mismatch(expected: &str, frame: RespFrame) -> RespError {
+    match frame {
+        RespFrame::SimpleError(e) => RespError::Conversion(format!("error reply: {}", e.0)),
+        RespFrame::BulkError(e) => {
+            RespError::Conversion(format!("error reply: {}", String::from_utf8_lossy(&e.0)))
+        }
+        frame => mismatch(expected, &frame),
+    }
+}
+
+// [field, value, ...] as sent by HGETALL or CONFIG GET over RESP2
+pub(super) fn flat_entries(
+    frames: Vec<RespFrame>,
+) -> Result<Vec<(RespFrame, RespFrame)>, RespError> {
+    if !frames.len().is_multiple_of(2) {
+        return Err(RespError::Conversion(format!(
+            "expected map, got array of odd length {}",
+            frames.len()
+        )));
+    }
+    let mut entries = Vec::with_capacity(frames.len() / 2);
+    let mut frames = frames.into_iter();
+    while let (Some(key), Some(value)) = (frames.next(), frames.next()) {
+        entries.push((key, value));
+    }
+    Ok(entries)
+}
+
+impl de::Error for RespError {
+    fn custom<T: Display>(msg: T) -> Self {
+        RespError::Conversion(msg.to_string())
+    }
+}
+
+impl IntoDeserializer<'_, RespError> for RespFrame {
+    type Deserializer = FrameDeserializer;
+
+    fn into_deserializer(self) -> FrameDeserializer {
+        FrameDeserializer::new(self)
+    }
+}
+
+macro_rules! deserialize_integer {
+    ($($method:ident => $visit:ident: $t:ty),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+                visitor.$visit(self.integer::<$t>()?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for FrameDeserializer {
+    type Error = RespError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        match self.frame {
+            RespFrame::SimpleString(s) => visitor.visit_string(s.0),
+            RespFrame::BulkString(BulkString::String(s)) => match String::from_utf8(s.into()) {
+                Ok(s) => visitor.visit_string(s),
+                Err(e) => visitor.visit_byte_buf(e.into_bytes()),
+            },
+            RespFrame::Integer(i) => visitor.visit_i64(i),
+            RespFrame::Double(d) => visitor.visit_f64(d.0),
+            RespFrame::Boolean(b) => visitor.visit_bool(b),
+            RespFrame::BigNumber(n) => match n.parse::<i128>() {
+                Ok(i) => visitor.visit_i128(i),
+                Err(_) => visitor.visit_string(n.0),
+            },
+            RespFrame::VerbatimString(s) => match String::from_utf8(s.data) {
+                Ok(s) => visitor.visit_string(s),
+                Err(e) => visitor.visit_byte_buf(e.into_bytes()),
+            },
+            RespFrame::Map(RespMap(entries)) => visitor.visit_map(Entries::new(entries)),
+            ref frame if is_null(frame) => visitor.visit_unit(),
+            frame @ (RespFrame::Array(_) | RespFrame::Set(_) | RespFrame::Push(_)) => {
+                visitor.visit_seq(Frames::new(FrameDeserializer { frame }.into_seq()?))
+            }
+            frame => Err(error_or_mismatch("value", frame)),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        match self.frame {
+            RespFrame::Boolean(b) => visitor.visit_bool(b),
+            // RESP2 replies with 0 or 1
+            RespFrame::Integer(i @ (0 | 1)) => visitor.visit_bool(i == 1),
+            _ => match self.text() {
+                Some(b"0") => visitor.visit_bool(false),
+                Some(b"1") => visitor.visit_bool(true),
+                _ => Err(error_or_mismatch("boolean", self.frame)),
+            },
+        }
+    }
+
+    deserialize_integer! {
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_i128 => visit_i128: i128,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64,
+        deserialize_u128 => visit_u128: u128,
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        visitor.visit_f32(self.float()? as f32)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        visitor.visit_f64(self.float()?)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        let s = self.into_string()?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(RespError::Conversion(format!(
+                "expected a single character, got {:?}",
+                s
+            ))),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        visitor.visit_string(self.into_string()?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        visitor.visit_string(self.into_string()?)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        visitor.visit_byte_buf(self.into_text()?)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        visitor.visit_byte_buf(self.into_text()?)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        if self.is_null() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    // a null, or a status reply like OK
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        match self.frame {
+            RespFrame::SimpleString(_) => visitor.visit_unit(),
+            ref frame if is_null(frame) => visitor.visit_unit(),
+            frame => Err(error_or_mismatch("null", frame)),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, RespError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, RespError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        visitor.visit_seq(Frames::new(self.into_seq()?))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, RespError> {
+        let frames = self.into_seq()?;
+        if frames.len() != len {
+            return Err(RespError::Conversion(format!(
+                "expected array of {} elements, got {}",
+                len,
+                frames.len()
+            )));
+        }
+        visitor.visit_seq(Frames::new(frames))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, RespError> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        visitor.visit_map(Entries::new(self.into_entries()?))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RespError> {
+        self.deserialize_map(visitor)
+    }
+
+    // a variant name, or a single entry map of the name to its data
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RespError> {
+        match self.frame {
+            RespFrame::Map(RespMap(mut entries)) if entries.len() == 1 => {
+                let (name, value) = entries.remove(0);
+                visitor.visit_enum(Variant {
+                    name,
+                    value: Some(value),
+                })
+            }
+            frame @ (RespFrame::SimpleString(_) | RespFrame::BulkString(BulkString::String(_))) => {
+                visitor.visit_enum(Variant {
+                    name: frame,
+                    value: None,
+                })
+            }
+            frame => Err(error_or_mismatch("enum variant", frame)),
+        }
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        visitor.visit_unit()
+    }
+}
+
+struct Frames {
+    frames: std::vec::IntoIter<RespFrame>,
+}
+
+impl Frames {
+    fn new(frames: Vec<RespFrame>) -> Self {
+        Self {
+            frames: frames.into_iter(),
+        }
+    }
+}
+
+impl<'de> SeqAccess<'de> for Frames {
+    type Error = RespError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, RespError> {
+        self.frames
+            .next()
+            .map(|frame| seed.deserialize(FrameDeserializer::new(frame)))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.frames.len())
+    }
+}
+
+struct Entries {
+    entries: std::vec::IntoIter<(RespFrame, RespFrame)>,
+    value: Option<RespFrame>,
+}
+
+impl Entries {
+    fn new(entries: Vec<(RespFrame, RespFrame)>) -> Self {
+        Self {
+            entries: entries.into_iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for Entries {
+    type Error = RespError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, RespError> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(FrameDeserializer::new(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, RespError> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| RespError::Conversion("map value without a key".to_string()))?;
+        seed.deserialize(FrameDeserializer::new(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct Variant {
+    name: RespFrame,
+    value: Option<RespFrame>,
+}
+
+impl<'de> EnumAccess<'de> for Variant {
+    type Error = RespError;
+    type Variant = VariantData;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantData), RespError> {
+        let name = seed.deserialize(FrameDeserializer::new(self.name))?;
+        Ok((name, VariantData(self.value)))
+    }
+}
+
+struct VariantData(Option<RespFrame>);
+
+impl VariantData {
+    fn value(self) -> Result<FrameDeserializer, RespError> {
+        self.0
+            .map(FrameDeserializer::new)
+            .ok_or_else(|| RespError::Conversion("expected enum variant data".to_string()))
+    }
+}
+
+impl<'de> VariantAccess<'de> for VariantData {
+    type Error = RespError;
+
+    fn unit_variant(self) -> Result<(), RespError> {
+        match self.0 {
+            None => Ok(()),
+            Some(frame) if is_null(&frame) => Ok(()),
+            Some(frame) => Err(mismatch("unit variant", &frame)),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, RespError> {
+        seed.deserialize(self.value()?)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, RespError> {
+        de::Deserializer::deserialize_tuple(self.value()?, len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RespError> {
+        de::Deserializer::deserialize_map(self.value()?, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::{Double, RespAttribute, RespNull, SimpleError, SimpleString};
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct User {
+        name: String,
+        age: u8,
+        email: Option<String>,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Event {
+        Ping,
+        Move(i32, i32),
+        Rename { to: String },
+    }
+
+    fn bulk(s: &str) -> RespFrame {
+        BulkString::from(s).into()
+    }
+
+    #[test]
+    fn test_deserialize_struct() -> Result<(), RespError> {
+        // a RESP3 map
+        let mut map = RespMap::new();
+        map.insert(bulk("name"), bulk("alice"));
+        map.insert(bulk("age"), RespFrame::Integer(30));
+        map.insert(bulk("email"), RespNull.into());
+        let expected = User {
+            name: "alice".to_string(),
+            age: 30,
+            email: None,
+            tags: vec![],
+        };
+        assert_eq!(from_frame::<User>(map.into())?, expected);
+
+        // HGETALL over RESP2: a flat array with numbers in bulk strings
+        let frame = RespArray::new([
+            bulk("age"),
+            bulk("30"),
+            bulk("name"),
+            bulk("alice"),
+            bulk("email"),
+            BulkString::new_null().into(),
+        ]);
+        assert_eq!(from_frame::<User>(frame.into())?, expected);
+
+        let attribute = RespAttribute::new(RespMap::new(), RespFrame::Integer(1));
+        assert_eq!(from_frame::<i64>(attribute.into())?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_values() -> Result<(), RespError> {
+        assert_eq!(from_frame::<f64>(bulk("1.5"))?, 1.5);
+        assert_eq!(from_frame::<f64>(bulk("-inf"))?, f64::NEG_INFINITY);
+        assert_eq!(from_frame::<f32>(Double::from(0.5).into())?, 0.5);
+        assert!(from_frame::<bool>(RespFrame::Integer(1))?);
+        assert_eq!(from_frame::<String>(RespFrame::Integer(7))?, "7");
+        assert_eq!(from_frame::<Option<i64>>(BulkString::Null.into())?, None);
+        from_frame::<()>(SimpleString::new("OK").into())?;
+        assert_eq!(
+            from_frame::<(String, i64)>(RespArray::new([bulk("a"), RespFrame::Integer(1)]).into())?,
+            ("a".to_string(), 1)
+        );
+        let mut map = RespMap::new();
+        map.insert(RespFrame::Integer(1), bulk("one"));
+        assert_eq!(
+            from_frame::<HashMap<i64, String>>(map.clone().into())?,
+            HashMap::from([(1, "one".to_string())])
+        );
+        assert_eq!(
+            from_frame::<Vec<(i64, String)>>(map.into())?,
+            vec![(1, "one".to_string())]
+        );
+
+        assert_eq!(from_frame::<Event>(bulk("Ping"))?, Event::Ping);
+        let mut map = RespMap::new();
+        map.insert(
+            bulk("Move"),
+            RespArray::new([RespFrame::Integer(1), RespFrame::Integer(2)]).into(),
+        );
+        assert_eq!(from_frame::<Event>(map.into())?, Event::Move(1, 2));
+        let mut to = RespMap::new();
+        to.insert(bulk("to"), bulk("b"));
+        let mut map = RespMap::new();
+        map.insert(bulk("Rename"), to.into());
+        assert_eq!(
+            from_frame::<Event>(map.into())?,
+            Event::Rename { to: "b".into() }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_errors() {
+        fn err<T: std::fmt::Debug>(result: Result<T, RespError>) -> String {
+            result.unwrap_err().to_string()
+        }
+        assert_eq!(
+            err(from_frame::<i64>(RespArray::new([]).into())),
+            "Conversion error: expected integer, got array"
+        );
+        assert_eq!(
+            err(from_frame::<u8>(RespFrame::Integer(300))),
+            "Conversion error: integer 300 out of range"
+        );
+        assert_eq!(
+            err(from_frame::<BTreeMap<String, i64>>(
+                RespArray::new([bulk("a")]).into()
+            )),
+            "Conversion error: expected map, got array of odd length 1"
+        );
+        assert_eq!(
+            err(from_frame::<String>(SimpleError::new("ERR nope").into())),
+            "Conversion error: error reply: ERR nope"
+        );
+        assert_eq!(
+            err(from_frame::<User>(bulk("alice"))),
+            "Conversion error: expected map, got bulk string"
+        );
+        assert!(err(from_frame::<User>(RespMap::new().into())).contains("missing field `name`"));
+    }
+}
@@ -1,8 +1,10 @@
-use bytes::BytesMut;
+use std::{fmt, io::Write, ops::Deref};
+
+use bytes::{BufMut, BytesMut};
 
 use crate::{extract_simple_frame_data, RespDecode, RespEncode, RespError};
 
-use super::CRLF_LEN;
+use super::{CRLF, CRLF_LEN};
 
 #[derive(Debug, Clone, PartialOrd)]
 pub struct Double(pub(crate) f64);
@@ -31,8 +33,25 @@ impl Ord for Double {
 impl Eq for Double {}
 
 impl RespEncode for Double {
-    fn encode(self) -> Vec<u8> {
-        format!(",{}\r\n", self.0).into_bytes()
+    fn encode_to<B: BufMut>(&self, buf: &mut B) {
+        buf.put_u8(b',');
+        // writing into a BufMut can't fail
+        let _ = write!(buf.writer(), "{}", self.0);
+        buf.put_slice(CRLF);
+    }
+
+    fn encoded_len(&self) -> usize {
+        // formatting into a counter rather than a string
+        struct Counter(usize);
+        impl fmt::Write for Counter {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                self.0 += s.len();
+                Ok(())
+            }
+        }
+        let mut counter = Counter(0);
+        let _ = fmt::Write::write_fmt(&mut counter, format_args!("{}", self.0));
+        counter.0 + 3
     }
 }
 
@@ -42,11 +61,20 @@ impl From<f64> for Double {
     }
 }
 
+impl Deref for Double {
+    type Target = f64;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
 impl RespDecode for Double {
     const PREFIX: &'static str = ",";
     fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
         let end = extract_simple_frame_data(buf, Self::PREFIX)?;
-        let s = String::from_utf8_lossy(&buf[Self::PREFIX.len()..end]);
+        let data = buf.split_to(end + CRLF_LEN);
+        let s = String::from_utf8_lossy(&data[Self::PREFIX.len()..end]);
         Ok(s.parse::<f64>()?.into())
     }
 
@@ -2,8 +2,10 @@ use bytes::BytesMut;
 use enum_dispatch::enum_dispatch;
 
 use super::{
-    array::RespArray, bulk_string::BulkString, double::Double, map::RespMap, null::RespNull,
-    set::RespSet, simple_error::SimpleError, simple_string::SimpleString, RespDecode, RespError,
+    array::RespArray, attribute::RespAttribute, big_number::BigNumber, bulk_error::BulkError,
+    bulk_string::BulkString, decoder::RespDecoder, double::Double, map::RespMap, null::RespNull,
+    push::RespPush, set::RespSet, simple_error::SimpleError, simple_string::SimpleString,
+    verbatim_string::VerbatimString, RespDecode, RespError,
 };
 
 #[enum_dispatch(RespEncode)]
@@ -19,6 +21,11 @@ pub enum RespFrame {
     Map(RespMap),
     Array(RespArray),
     Set(RespSet),
+    Push(RespPush),
+    VerbatimString(VerbatimString),
+    BigNumber(BigNumber),
+    BulkError(BulkError),
+    Attribute(RespAttribute),
 }
 
 impl RespDecode for RespFrame {
@@ -28,71 +35,18 @@ impl RespDecode for RespFrame {
     where
         Self: Sized,
     {
-        let mut iter = buf.iter().peekable();
-        match iter.peek() {
-            Some(b'+') => {
-                let frame = SimpleString::decode(buf)?;
-                Ok(frame.into())
-            }
-            Some(b'-') => {
-                let frame = SimpleError::decode(buf)?;
-                Ok(frame.into())
-            }
-            Some(b':') => {
-                let frame = i64::decode(buf)?;
-                Ok(RespFrame::Integer(frame))
-            }
-            Some(b'$') => {
-                let frame = BulkString::decode(buf)?;
-                Ok(frame.into())
-            }
-            Some(b'*') => {
-                let frame = RespArray::decode(buf)?;
-                Ok(frame.into())
-            }
-            Some(b'#') => {
-                let frame = bool::decode(buf)?;
-                Ok(frame.into())
-            }
-            Some(b',') => {
-                let frame = Double::decode(buf)?;
-                Ok(frame.into())
-            }
-            Some(b'%') => {
-                let frame = RespMap::decode(buf)?;
-                Ok(frame.into())
-            }
-            Some(b'_') => {
-                let frame = RespNull::decode(buf)?;
-                Ok(frame.into())
-            }
-            Some(b'~') => {
-                let frame = RespSet::decode(buf)?;
-                Ok(frame.into())
-            }
-            None => Err(RespError::NotComplete),
-            _ => Err(RespError::InvalidFrameType(format!(
-                "expect: frame type, got: {:?}",
-                buf
-            ))),
-        }
+        // decode a copy, so nothing is consumed if the frame isn't all there;
+        // copying is cheaper than scanning the headers a second time
+        let mut rest = buf.clone();
+        let frame = RespDecoder::new()
+            .decode(&mut rest)?
+            .ok_or(RespError::NotComplete)?;
+        *buf = rest;
+        Ok(frame)
     }
 
     fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
-        let mut iter = buf.iter().peekable();
-        match iter.peek() {
-            Some(b'*') => RespArray::expect_length(buf),
-            Some(b'~') => RespSet::expect_length(buf),
-            Some(b'%') => RespMap::expect_length(buf),
-            Some(b'$') => BulkString::expect_length(buf),
-            Some(b':') => i64::expect_length(buf),
-            Some(b'+') => SimpleString::expect_length(buf),
-            Some(b'-') => SimpleError::expect_length(buf),
-            Some(b'#') => bool::expect_length(buf),
-            Some(b',') => Double::expect_length(buf),
-            Some(b'_') => RespNull::expect_length(buf),
-            _ => Err(RespError::NotComplete),
-        }
+        RespDecoder::frame_len(buf)
     }
 }
 
@@ -113,3 +67,133 @@ impl<const N: usize> From<&[u8; N]> for RespFrame {
         BulkString::new(s.to_vec()).into()
     }
 }
+
+impl RespFrame {
+    /// The closest RESP2 equivalent of this frame, for connections that
+    /// didn't switch to RESP3 with HELLO. Commands always build the RESP3
+    /// reply and leave the downgrade to the codec.
+    pub fn into_resp2(self) -> RespFrame {
+        match self {
+            RespFrame::Double(d) => BulkString::new(d.0.to_string()).into(),
+            RespFrame::Boolean(b) => RespFrame::Integer(b as i64),
+            RespFrame::Null(_) => BulkString::new_null().into(),
+            RespFrame::Map(map) => {
+                let mut data = Vec::with_capacity(map.len() * 2);
+                for (key, value) in map {
+                    data.push(key.into_resp2());
+                    data.push(value.into_resp2());
+                }
+                RespArray::new(data).into()
+            }
+            RespFrame::Array(RespArray::Array(frames)) => RespArray::new(
+                frames
+                    .into_iter()
+                    .map(RespFrame::into_resp2)
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            RespFrame::Set(RespSet(frames)) | RespFrame::Push(RespPush(frames)) => RespArray::new(
+                frames
+                    .into_iter()
+                    .map(RespFrame::into_resp2)
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            RespFrame::VerbatimString(s) => BulkString::new(s.data).into(),
+            RespFrame::BigNumber(n) => BulkString::new(n.0).into(),
+            RespFrame::BulkError(e) => {
+                SimpleError::new(String::from_utf8_lossy(&e.0).replace(['\r', '\n'], " ")).into()
+            }
+            // RESP2 has no way to carry attributes, the reply is all that's left
+            RespFrame::Attribute(a) => a.frame.into_resp2(),
+            frame => frame,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespEncode;
+
+    #[test]
+    fn test_into_resp2() {
+        let mut map = RespMap::new();
+        map.insert(BulkString::from("proto").into(), RespFrame::Integer(2));
+        map.insert(Double::from(0.5).into(), true.into());
+        assert_eq!(
+            RespFrame::from(map).into_resp2(),
+            RespArray::new([
+                BulkString::from("proto").into(),
+                RespFrame::Integer(2),
+                BulkString::from("0.5").into(),
+                RespFrame::Integer(1),
+            ])
+            .into()
+        );
+
+        let set = RespSet::new([Double::from(1.5).into(), RespNull.into()]);
+        assert_eq!(
+            RespFrame::from(set).into_resp2(),
+            RespArray::new([
+                BulkString::from("1.5").into(),
+                BulkString::new_null().into()
+            ])
+            .into()
+        );
+
+        let push = RespPush::new([BulkString::from("invalidate").into()]);
+        assert_eq!(
+            RespFrame::from(push).into_resp2(),
+            RespArray::new([BulkString::from("invalidate").into()]).into()
+        );
+        assert_eq!(
+            RespFrame::from(SimpleString::new("OK")).into_resp2(),
+            SimpleString::new("OK").into()
+        );
+
+        let attribute = RespAttribute::new(RespMap::new(), VerbatimString::text("a\r\nb"));
+        assert_eq!(
+            RespFrame::from(attribute).into_resp2(),
+            BulkString::from("a\r\nb").into()
+        );
+        assert_eq!(
+            RespFrame::from(BulkError::new("ERR bad\r\nthing")).into_resp2(),
+            SimpleError::new("ERR bad  thing").into()
+        );
+    }
+
+    #[test]
+    fn test_encode_to() {
+        let mut map = RespMap::new();
+        map.insert("k".into(), Double::from(-1.25e-7).into());
+        let frame: RespFrame = RespArray::new([
+            SimpleString::new("OK").into(),
+            SimpleError::new("ERR x").into(),
+            RespFrame::Integer(-42),
+            Double::from(f64::INFINITY).into(),
+            false.into(),
+            BulkString::from("hello").into(),
+            BulkString::new_null().into(),
+            RespNull.into(),
+            map.clone().into(),
+            RespArray::new_null().into(),
+            RespSet::new([RespFrame::Integer(1)]).into(),
+            RespPush::new([]).into(),
+            VerbatimString::text("txt").into(),
+            BigNumber::from(i64::MIN).into(),
+            BulkError::new("ERR\r\n").into(),
+            RespAttribute::new(map, RespFrame::Integer(0)).into(),
+        ])
+        .into();
+
+        // encoding from a reference writes the same bytes as `encode`
+        let mut buf = BytesMut::from(&b"+OK\r\n"[..]);
+        frame.encode_to(&mut buf);
+        assert_eq!(buf.len(), 5 + frame.encoded_len());
+        assert_eq!(&buf[5..], &frame.clone().encode()[..]);
+
+        let mut decoded = buf.split_off(5);
+        assert_eq!(RespFrame::decode(&mut decoded), Ok(frame));
+    }
+}
@@ -0,0 +1,260 @@
+use bytes::Bytes;
+
+use crate::{
+    BigNumber, BulkError, BulkString, DecodeLimits, Double, RespArray, RespAttribute, RespError,
+    RespFrame, RespMap, RespNull, RespPush, RespSet, SimpleError, SimpleString, VerbatimString,
+};
+
+use super::{
+    decoder::{parse_len, payload, read_line},
+    CRLF_LEN,
+};
+
+/// A frame borrowed straight from the bytes it was encoded in, for looking
+/// at a frame (a command name, a key) without copying or allocating its
+/// payloads. Map and attribute entries are kept in wire order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RespFrameRef<'a> {
+    SimpleString(&'a str),
+    SimpleError(&'a str),
+    Integer(i64),
+    Double(f64),
+    Boolean(bool),
+    BulkString(Option<&'a [u8]>),
+    StreamedString(Vec<&'a [u8]>),
+    Null,
+    Map(Vec<(RespFrameRef<'a>, RespFrameRef<'a>)>),
+    Array(Option<Vec<RespFrameRef<'a>>>),
+    Set(Vec<RespFrameRef<'a>>),
+    Push(Vec<RespFrameRef<'a>>),
+    VerbatimString(&'a [u8], &'a [u8]),
+    BigNumber(&'a str),
+    BulkError(&'a [u8]),
+    Attribute(
+        Vec<(RespFrameRef<'a>, RespFrameRef<'a>)>,
+        Box<RespFrameRef<'a>>,
+    ),
+}
+
+impl<'a> RespFrameRef<'a> {
+    /// Parse the frame at the start of `buf`, returning it along with the
+    /// number of bytes it takes. Aggregates may nest as deep as the default
+    /// `DecodeLimits` allow.
+    pub fn parse(buf: &'a [u8]) -> Result<(Self, usize), RespError> {
+        Self::parse_nested(buf, DecodeLimits::default().max_depth)
+    }
+
+    fn parse_nested(buf: &'a [u8], depth: usize) -> Result<(Self, usize), RespError> {
+        let (line, next) = read_line(buf, 0)?.ok_or(RespError::NotComplete)?;
+        let text = || std::str::from_utf8(line).map_err(|_| invalid("not valid utf-8"));
+        let frame = match buf[0] {
+            b'+' => RespFrameRef::SimpleString(text()?),
+            b'-' => RespFrameRef::SimpleError(text()?),
+            b':' => RespFrameRef::Integer(text()?.parse()?),
+            b'#' => match line {
+                b"t" => RespFrameRef::Boolean(true),
+                b"f" => RespFrameRef::Boolean(false),
+                _ => return Err(invalid("expect: #t or #f")),
+            },
+            b',' => RespFrameRef::Double(text()?.parse()?),
+            b'_' if line.is_empty() => RespFrameRef::Null,
+            b'(' => {
+                BigNumber::new(text()?)?;
+                RespFrameRef::BigNumber(text()?)
+            }
+            b'$' if line == b"-1" => RespFrameRef::BulkString(None),
+            b'*' if line == b"-1" => RespFrameRef::Array(None),
+            b'$' if line == b"?" => {
+                let mut pos = next;
+                let mut chunks = Vec::new();
+                loop {
+                    let (line, next) = read_line(buf, pos)?.ok_or(RespError::NotComplete)?;
+                    if buf[pos] != b';' {
+                        return Err(invalid("expect: streamed string chunk"));
+                    }
+                    let len = parse_len(line)?;
+                    if len == 0 {
+                        return Ok((RespFrameRef::StreamedString(chunks), next));
+                    }
+                    chunks.push(payload(buf, next, len)?.ok_or(RespError::NotComplete)?);
+                    pos = next + len + CRLF_LEN;
+                }
+            }
+            b'$' | b'=' | b'!' => {
+                let len = parse_len(line)?;
+                let data = payload(buf, next, len)?.ok_or(RespError::NotComplete)?;
+                let frame = match buf[0] {
+                    b'$' => RespFrameRef::BulkString(Some(data)),
+                    b'!' => RespFrameRef::BulkError(data),
+                    _ if data.len() >= 4 && data[3] == b':' => {
+                        RespFrameRef::VerbatimString(&data[..3], &data[4..])
+                    }
+                    _ => return Err(invalid("verbatim string without a format")),
+                };
+                return Ok((frame, next + len + CRLF_LEN));
+            }
+            b'*' | b'~' | b'>' | b'%' | b'|' => {
+                let depth = depth.checked_sub(1).ok_or_else(|| {
+                    RespError::Protocol("too deeply nested aggregate".to_string())
+                })?;
+                let streamed = line == b"?" && matches!(buf[0], b'*' | b'~' | b'%');
+                let count = match (buf[0], streamed) {
+                    (_, true) => None,
+                    (b'%', _) => Some(parse_len(line)? * 2),
+                    (b'|', _) => Some(parse_len(line)? * 2 + 1),
+                    _ => Some(parse_len(line)?),
+                };
+                let mut pos = next;
+                let mut frames = Vec::with_capacity(count.unwrap_or(0).min(1024));
+                loop {
+                    match count {
+                        Some(n) if frames.len() == n => break,
+                        None if buf[pos..].starts_with(b".\r\n") => {
+                            pos += 3;
+                            break;
+                        }
+                        _ => {}
+                    }
+                    let (frame, len) = RespFrameRef::parse_nested(&buf[pos..], depth)?;
+                    frames.push(frame);
+                    pos += len;
+                }
+                let frame = match buf[0] {
+                    b'*' => RespFrameRef::Array(Some(frames)),
+                    b'~' => RespFrameRef::Set(frames),
+                    b'>' => RespFrameRef::Push(frames),
+                    b'%' => RespFrameRef::Map(pairs(frames)?),
+                    _ => {
+                        let reply = frames.pop().ok_or(RespError::NotComplete)?;
+                        RespFrameRef::Attribute(pairs(frames)?, Box::new(reply))
+                    }
+                };
+                return Ok((frame, pos));
+            }
+            _ => {
+                return Err(RespError::InvalidFrameType(format!(
+                    "expect: frame type, got: {:?}",
+                    &buf[..next]
+                )))
+            }
+        };
+        Ok((frame, next))
+    }
+
+    /// The bytes of a simple, bulk or verbatim string.
+    pub fn as_bytes(&self) -> Option<&'a [u8]> {
+        match self {
+            RespFrameRef::SimpleString(s) => Some(s.as_bytes()),
+            RespFrameRef::BulkString(s) => *s,
+            RespFrameRef::VerbatimString(_, data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+impl From<RespFrameRef<'_>> for RespFrame {
+    fn from(frame: RespFrameRef<'_>) -> Self {
+        let owned = |frames: Vec<RespFrameRef<'_>>| -> Vec<RespFrame> {
+            frames.into_iter().map(RespFrame::from).collect()
+        };
+        let map = |pairs: Vec<(RespFrameRef<'_>, RespFrameRef<'_>)>| -> RespMap {
+            RespMap(
+                pairs
+                    .into_iter()
+                    .map(|(key, value)| (key.into(), value.into()))
+                    .collect(),
+            )
+        };
+        match frame {
+            RespFrameRef::SimpleString(s) => SimpleString::new(s).into(),
+            RespFrameRef::SimpleError(s) => SimpleError::new(s).into(),
+            RespFrameRef::Integer(i) => RespFrame::Integer(i),
+            RespFrameRef::Double(d) => Double::from(d).into(),
+            RespFrameRef::Boolean(b) => b.into(),
+            RespFrameRef::BulkString(Some(s)) => BulkString::new(Bytes::copy_from_slice(s)).into(),
+            RespFrameRef::BulkString(None) => BulkString::new_null().into(),
+            RespFrameRef::StreamedString(chunks) => BulkString::new(chunks.concat()).into(),
+            RespFrameRef::Null => RespNull.into(),
+            RespFrameRef::Map(pairs) => map(pairs).into(),
+            RespFrameRef::Array(Some(frames)) => RespArray::new(owned(frames)).into(),
+            RespFrameRef::Array(None) => RespArray::new_null().into(),
+            RespFrameRef::Set(frames) => RespSet::new(owned(frames)).into(),
+            RespFrameRef::Push(frames) => RespPush::new(owned(frames)).into(),
+            RespFrameRef::VerbatimString(format, data) => {
+                let mut f = [0; 3];
+                f.copy_from_slice(format);
+                VerbatimString::new(f, data).into()
+            }
+            RespFrameRef::BigNumber(n) => BigNumber(n.to_string()).into(),
+            RespFrameRef::BulkError(e) => BulkError::new(e).into(),
+            RespFrameRef::Attribute(pairs, reply) => {
+                RespAttribute::new(map(pairs), RespFrame::from(*reply)).into()
+            }
+        }
+    }
+}
+
+// map entries, any frame can be a key
+fn pairs(
+    frames: Vec<RespFrameRef<'_>>,
+) -> Result<Vec<(RespFrameRef<'_>, RespFrameRef<'_>)>, RespError> {
+    let mut pairs = Vec::with_capacity(frames.len() / 2);
+    let mut frames = frames.into_iter();
+    while let (Some(key), Some(value)) = (frames.next(), frames.next()) {
+        pairs.push((key, value));
+    }
+    Ok(pairs)
+}
+
+fn invalid(msg: &str) -> RespError {
+    RespError::InvalidFrame(msg.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_frame_ref_parse() -> Result<()> {
+        let buf = b"*3\r\n$3\r\nset\r\n$-1\r\n%1\r\n$1\r\nk\r\n#t\r\n+extra\r\n";
+        let (frame, len) = RespFrameRef::parse(buf)?;
+        assert_eq!(len, buf.len() - 8);
+        let RespFrameRef::Array(Some(items)) = &frame else {
+            panic!("expect an array");
+        };
+        assert_eq!(items[0].as_bytes(), Some(&b"set"[..]));
+        assert_eq!(items[1], RespFrameRef::BulkString(None));
+        assert_eq!(
+            items[2],
+            RespFrameRef::Map(vec![(
+                RespFrameRef::BulkString(Some(b"k")),
+                RespFrameRef::Boolean(true)
+            )])
+        );
+
+        let mut map = RespMap::new();
+        map.insert(BulkString::from("k").into(), true.into());
+        assert_eq!(
+            RespFrame::from(frame),
+            RespArray::new([
+                BulkString::from("set").into(),
+                BulkString::new_null().into(),
+                map.into()
+            ])
+            .into()
+        );
+
+        assert_eq!(
+            RespFrameRef::parse(b"*2\r\n$3\r\nset\r\n").unwrap_err(),
+            RespError::NotComplete
+        );
+        assert!(RespFrameRef::parse(b"%1\r\n:1\r\n:x\r\n").is_err());
+        let nested = [b"*1\r\n".repeat(100), b":1\r\n".to_vec()].concat();
+        assert!(matches!(
+            RespFrameRef::parse(&nested),
+            Err(RespError::Protocol(_))
+        ));
+        Ok(())
+    }
+}
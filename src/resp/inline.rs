@@ -0,0 +1,157 @@
+use bytes::BytesMut;
+
+use crate::{BulkString, RespArray, RespError, RespFrame};
+
+/// Longest inline command accepted by default, the same limit Redis uses.
+pub const MAX_INLINE_LEN: usize = 64 * 1024;
+
+/// Decode an inline command such as `SET a "b c"\r\n`, the form telnet,
+/// netcat and health checks send, into the array of bulk strings a RESP
+/// client would have sent. A blank line decodes to an empty array, a line
+/// longer than `max_len` is a protocol error.
+pub fn decode_inline(buf: &mut BytesMut, max_len: usize) -> Result<RespArray, RespError> {
+    let Some(end) = buf.iter().position(|&b| b == b'\n') else {
+        if buf.len() > max_len {
+            return Err(RespError::Protocol("too big inline request".to_string()));
+        }
+        return Err(RespError::NotComplete);
+    };
+    if end > max_len {
+        return Err(RespError::Protocol("too big inline request".to_string()));
+    }
+
+    let line = buf.split_to(end + 1);
+    let line = line[..end].strip_suffix(b"\r").unwrap_or(&line[..end]);
+    let args = split_inline_args(line)?
+        .into_iter()
+        .map(|arg| BulkString::new(arg).into())
+        .collect::<Vec<RespFrame>>();
+    Ok(RespArray::new(args))
+}
+
+/// Split a line into arguments the way redis-cli and inline commands do:
+/// whitespace separated, with "double quotes" that understand `\n`, `\xff`
+/// and friends, and 'single quotes' that only understand `\'`.
+pub fn split_inline_args(line: &[u8]) -> Result<Vec<Vec<u8>>, RespError> {
+    let unbalanced = || RespError::Protocol("unbalanced quotes in request".to_string());
+
+    let mut args = Vec::new();
+    let mut i = 0;
+    loop {
+        while i < line.len() && line[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if i == line.len() {
+            return Ok(args);
+        }
+
+        let mut arg = Vec::new();
+        // a quote may open anywhere in an argument, `foo"bar baz"` is one
+        let mut quote = None;
+        loop {
+            match (quote, line.get(i)) {
+                (None, None) => break,
+                (None, Some(b)) if b.is_ascii_whitespace() => break,
+                (None, Some(&q @ (b'"' | b'\''))) => quote = Some(q),
+                (None, Some(&b)) => arg.push(b),
+                (Some(_), None) => return Err(unbalanced()),
+                (Some(q), Some(&b)) if b == q => {
+                    // the closing quote must end the argument
+                    if line.get(i + 1).is_some_and(|b| !b.is_ascii_whitespace()) {
+                        return Err(unbalanced());
+                    }
+                    i += 1;
+                    break;
+                }
+                (Some(b'"'), Some(b'\\')) if i + 1 < line.len() => {
+                    i += 1;
+                    let hex = line
+                        .get(i + 1..i + 3)
+                        .and_then(|h| std::str::from_utf8(h).ok())
+                        .and_then(|h| u8::from_str_radix(h, 16).ok());
+                    match (line[i], hex) {
+                        (b'x', Some(h)) => {
+                            arg.push(h);
+                            i += 2;
+                        }
+                        (b'n', _) => arg.push(b'\n'),
+                        (b'r', _) => arg.push(b'\r'),
+                        (b't', _) => arg.push(b'\t'),
+                        (b'b', _) => arg.push(0x08),
+                        (b'a', _) => arg.push(0x07),
+                        (b, _) => arg.push(b),
+                    }
+                }
+                (Some(b'\''), Some(b'\\')) if line.get(i + 1) == Some(&b'\'') => {
+                    i += 1;
+                    arg.push(b'\'');
+                }
+                (Some(_), Some(&b)) => arg.push(b),
+            }
+            i += 1;
+        }
+        args.push(arg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_decode_inline() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"SET a");
+        assert_eq!(
+            decode_inline(&mut buf, MAX_INLINE_LEN).unwrap_err(),
+            RespError::NotComplete
+        );
+
+        buf.extend_from_slice(b"  b\r\nPING\n");
+        let frame = decode_inline(&mut buf, MAX_INLINE_LEN)?;
+        assert_eq!(
+            frame,
+            RespArray::new([
+                BulkString::from("SET").into(),
+                BulkString::from("a").into(),
+                BulkString::from("b").into(),
+            ])
+        );
+        let frame = decode_inline(&mut buf, MAX_INLINE_LEN)?;
+        assert_eq!(frame, RespArray::new([BulkString::from("PING").into()]));
+
+        buf.extend_from_slice(b"\r\n");
+        assert_eq!(decode_inline(&mut buf, MAX_INLINE_LEN)?, RespArray::new([]));
+        assert!(buf.is_empty());
+
+        buf.extend_from_slice(b"SET a b\r\n");
+        assert!(decode_inline(&mut buf, 6).is_err());
+        buf.clear();
+
+        buf.extend_from_slice(&vec![b'a'; MAX_INLINE_LEN + 1]);
+        assert!(decode_inline(&mut buf, MAX_INLINE_LEN).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_inline_args() -> Result<()> {
+        assert_eq!(
+            split_inline_args(br#"set "a b\n\x41\"" 'it\'s' """#)?,
+            vec![
+                b"set".to_vec(),
+                b"a b\nA\"".to_vec(),
+                b"it's".to_vec(),
+                b"".to_vec()
+            ]
+        );
+        assert_eq!(split_inline_args(b"'a\\nb'")?, vec![b"a\\nb".to_vec()]);
+        assert_eq!(
+            split_inline_args(br#"set foo"bar baz""#)?,
+            vec![b"set".to_vec(), b"foobar baz".to_vec()]
+        );
+        assert!(split_inline_args(b"set \"a").is_err());
+        assert!(split_inline_args(b"set \"a\"b").is_err());
+        Ok(())
+    }
+}
@@ -1,13 +1,16 @@
-use bytes::BytesMut;
+use bytes::{BufMut, BytesMut};
 
 use crate::{RespDecode, RespEncode, RespError};
 
-use super::{extract_simple_frame_data, CRLF_LEN};
+use super::{encode_header, extract_simple_frame_data, header_len, CRLF_LEN};
 
 impl RespEncode for i64 {
-    fn encode(self) -> Vec<u8> {
-        // let sign = if *self < 0 { "" } else { "+" };
-        format!(":{}\r\n", self).into_bytes()
+    fn encode_to<B: BufMut>(&self, buf: &mut B) {
+        encode_header(buf, b':', *self);
+    }
+
+    fn encoded_len(&self) -> usize {
+        header_len(*self)
     }
 }
 
@@ -16,7 +19,8 @@ impl RespDecode for i64 {
 
     fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
         let end = extract_simple_frame_data(buf, Self::PREFIX)?;
-        let s = String::from_utf8_lossy(&buf[Self::PREFIX.len()..end]);
+        let data = buf.split_to(end + CRLF_LEN);
+        let s = String::from_utf8_lossy(&data[Self::PREFIX.len()..end]);
         Ok(s.parse()?)
     }
 
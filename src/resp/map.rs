@@ -1,62 +1,91 @@
-use bytes::{Buf, BytesMut};
+use bytes::{BufMut, BytesMut};
 
-use crate::{RespDecode, RespEncode, RespError, RespFrame, SimpleString};
-use std::{
-    collections::BTreeMap,
-    ops::{Deref, DerefMut},
-};
+use crate::{BulkString, RespDecode, RespDecoder, RespEncode, RespError, RespFrame, SimpleString};
+use std::ops::{Deref, Index};
 
-use super::{calc_total_length, parse_length, BUF_CAP, CRLF_LEN};
+use super::{decode_aggregate, encode_header, header_len};
 
+/// Entries in the order they were inserted or received. Keys may be any
+/// frame, lookups are a linear scan as maps in replies are small.
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord)]
-pub struct RespMap(pub(crate) BTreeMap<String, RespFrame>);
+pub struct RespMap(pub(crate) Vec<(RespFrame, RespFrame)>);
 
 // - map: "%<number-of-entries>\r\n<key-1><value-1>...<key-n><value-n>"
-// we only support string key which encode to SimpleString
 impl RespEncode for RespMap {
-    fn encode(self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(BUF_CAP);
-        buf.extend_from_slice(&format!("%{}\r\n", self.len()).into_bytes());
-        for (key, value) in self.0 {
-            buf.extend_from_slice(&SimpleString::new(key).encode());
-            buf.extend_from_slice(&value.encode());
-        }
-        buf
+    fn encode_to<B: BufMut>(&self, buf: &mut B) {
+        self.encode_with_prefix(buf, b'%');
+    }
+
+    fn encoded_len(&self) -> usize {
+        header_len(self.len())
+            + self
+                .iter()
+                .map(|(key, value)| key.encoded_len() + value.encoded_len())
+                .sum::<usize>()
     }
 }
 
 // - map: "%<number-of-entries>\r\n<key-1><value-1>...<key-n><value-n>"
 impl RespDecode for RespMap {
     const PREFIX: &'static str = "%";
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        let (end, len) = parse_length(buf, Self::PREFIX)?;
-        let total_len = calc_total_length(buf, end, len, Self::PREFIX)?;
 
-        if buf.len() < total_len {
-            return Err(RespError::NotComplete);
-        }
-
-        buf.advance(end + CRLF_LEN);
-
-        let mut frames = RespMap::new();
-        for _ in 0..len {
-            let key = SimpleString::decode(buf)?;
-            let value = RespFrame::decode(buf)?;
-            frames.insert(key.0, value);
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        match decode_aggregate(buf, Self::PREFIX)? {
+            RespFrame::Map(frame) => Ok(frame),
+            _ => unreachable!(),
         }
-
-        Ok(frames)
     }
 
     fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
-        let (end, len) = parse_length(buf, Self::PREFIX)?;
-        calc_total_length(buf, end, len, Self::PREFIX)
+        RespDecoder::frame_len(buf)
     }
 }
 
 impl RespMap {
     pub fn new() -> Self {
-        RespMap(BTreeMap::new())
+        RespMap(Vec::new())
+    }
+
+    /// Set the value of `key`, in place if it's already there, and return
+    /// the previous one.
+    pub fn insert(&mut self, key: RespFrame, value: RespFrame) -> Option<RespFrame> {
+        match self.0.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, old)) => Some(std::mem::replace(old, value)),
+            None => {
+                self.0.push((key, value));
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, key: &RespFrame) -> Option<&RespFrame> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// The value of the entry whose key is `key` as a simple or bulk string.
+    pub fn get_bytes(&self, key: &[u8]) -> Option<&RespFrame> {
+        self.0
+            .iter()
+            .find(|(k, _)| match k {
+                RespFrame::SimpleString(SimpleString(s)) => s.as_bytes() == key,
+                RespFrame::BulkString(BulkString::String(s)) => s == key,
+                _ => false,
+            })
+            .map(|(_, v)| v)
+    }
+
+    pub fn remove(&mut self, key: &RespFrame) -> Option<RespFrame> {
+        let pos = self.0.iter().position(|(k, _)| k == key)?;
+        Some(self.0.remove(pos).1)
+    }
+
+    // attributes are encoded as a map with their own prefix
+    pub(super) fn encode_with_prefix<B: BufMut>(&self, buf: &mut B, prefix: u8) {
+        encode_header(buf, prefix, self.len());
+        for (key, value) in self.iter() {
+            key.encode_to(buf);
+            value.encode_to(buf);
+        }
     }
 }
 
@@ -67,38 +96,73 @@ impl Default for RespMap {
 }
 
 impl Deref for RespMap {
-    type Target = BTreeMap<String, RespFrame>;
+    type Target = [(RespFrame, RespFrame)];
 
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 
-impl DerefMut for RespMap {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+impl Index<&[u8]> for RespMap {
+    type Output = RespFrame;
+
+    fn index(&self, key: &[u8]) -> &Self::Output {
+        self.get_bytes(key).expect("no entry found for key")
+    }
+}
+
+impl Index<&str> for RespMap {
+    type Output = RespFrame;
+
+    fn index(&self, key: &str) -> &Self::Output {
+        &self[key.as_bytes()]
+    }
+}
+
+impl Extend<(RespFrame, RespFrame)> for RespMap {
+    fn extend<T: IntoIterator<Item = (RespFrame, RespFrame)>>(&mut self, iter: T) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl FromIterator<(RespFrame, RespFrame)> for RespMap {
+    fn from_iter<T: IntoIterator<Item = (RespFrame, RespFrame)>>(iter: T) -> Self {
+        let mut map = RespMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl IntoIterator for RespMap {
+    type Item = (RespFrame, RespFrame);
+    type IntoIter = std::vec::IntoIter<(RespFrame, RespFrame)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{BulkString, Double};
+    use crate::{Double, RespArray};
     use anyhow::Result;
 
     #[test]
     fn test_map_encode() {
         let mut map = RespMap::new();
         map.insert(
-            "hello".to_string(),
+            BulkString::from("hello").into(),
             BulkString::new("world".to_string()).into(),
         );
-        map.insert("foo".to_string(), Double(-123456.789f64).into());
+        map.insert("foo".into(), Double(-123456.789f64).into());
 
         let frame: RespFrame = map.into();
         assert_eq!(
             &frame.encode(),
-            b"%2\r\n+foo\r\n,-123456.789\r\n+hello\r\n$5\r\nworld\r\n"
+            b"%2\r\n$5\r\nhello\r\n$5\r\nworld\r\n+foo\r\n,-123456.789\r\n"
         );
     }
 
@@ -111,10 +175,52 @@ mod tests {
         // println!("{:?}", frame);
 
         let mut map = RespMap::new();
-        map.insert("hello".to_string(), BulkString::new("world").into());
-        map.insert("foo".to_string(), BulkString::new("bar").into());
+        map.insert("hello".into(), BulkString::new("world").into());
+        map.insert("foo".into(), BulkString::new("bar").into());
         // println!("{:?}", map);
         assert_eq!(frame, map);
+
+        buf.extend_from_slice(b"%1\r\n$5\r\nhello\r\n:1\r\n");
+        let frame = RespMap::decode(&mut buf)?;
+        assert_eq!(frame["hello"], RespFrame::Integer(1));
+
+        // any frame makes a key, and CRLF survives in a bulk string one
+        let mut map = RespMap::new();
+        map.insert(
+            BulkString::from(b"\xff\r\n\0").into(),
+            RespFrame::Integer(1),
+        );
+        map.insert(RespFrame::Integer(7), BulkString::from("seven").into());
+        map.insert(RespFrame::Integer(-1), RespArray::new([]).into());
+        let mut buf = BytesMut::from(&RespFrame::from(map.clone()).encode()[..]);
+        let frame = RespMap::decode(&mut buf)?;
+        assert_eq!(frame, map);
+        assert_eq!(frame[&b"\xff\r\n\0"[..]], RespFrame::Integer(1));
+        assert_eq!(
+            frame.get(&RespFrame::Integer(7)),
+            Some(&BulkString::from("seven").into())
+        );
         Ok(())
     }
+
+    #[test]
+    fn test_map_keeps_insertion_order() {
+        let mut map = RespMap::new();
+        for key in ["z", "a", "m"] {
+            map.insert(key.into(), RespFrame::Integer(0));
+        }
+        assert_eq!(
+            map.insert("a".into(), RespFrame::Integer(1)),
+            Some(RespFrame::Integer(0))
+        );
+        assert_eq!(map.len(), 3);
+        assert_eq!(
+            map.iter().map(|(k, _)| k.clone()).collect::<Vec<_>>(),
+            vec!["z".into(), "a".into(), "m".into()]
+        );
+        assert_eq!(map["a"], RespFrame::Integer(1));
+
+        assert_eq!(map.remove(&"z".into()), Some(RespFrame::Integer(0)));
+        assert_eq!(map.get(&"z".into()), None);
+    }
 }
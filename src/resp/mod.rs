@@ -1,31 +1,76 @@
 mod array;
+mod attribute;
+mod big_number;
 mod bool;
+mod bulk_error;
 mod bulk_string;
+mod convert;
+mod de;
+mod decoder;
 mod double;
 mod frame;
+mod frame_ref;
+mod inline;
 mod integer;
 mod map;
 mod null;
+mod push;
+mod ser;
 mod set;
 mod simple_error;
 mod simple_string;
+mod streamed;
+mod verbatim_string;
 
-use bytes::{Buf, BytesMut};
+use bytes::{Buf, BufMut, BytesMut};
 use enum_dispatch::enum_dispatch;
+use memchr::memmem;
 use thiserror::Error;
 
 pub use self::{
-    array::RespArray, bulk_string::BulkString, double::Double, frame::RespFrame, map::RespMap,
-    null::RespNull, set::RespSet, simple_error::SimpleError, simple_string::SimpleString,
+    array::RespArray,
+    attribute::RespAttribute,
+    big_number::BigNumber,
+    bulk_error::BulkError,
+    bulk_string::BulkString,
+    convert::{FromRespFrame, IntoRespFrame},
+    de::{from_frame, FrameDeserializer},
+    decoder::{DecodeLimits, RespDecoder},
+    double::Double,
+    frame::RespFrame,
+    frame_ref::RespFrameRef,
+    inline::{decode_inline, split_inline_args, MAX_INLINE_LEN},
+    map::RespMap,
+    null::RespNull,
+    push::RespPush,
+    ser::{to_flat_frame, to_frame, FrameSerializer},
+    set::RespSet,
+    simple_error::SimpleError,
+    simple_string::SimpleString,
+    streamed::{StreamedAggregate, StreamedString},
+    verbatim_string::VerbatimString,
 };
 
-const BUF_CAP: usize = 4096;
 const CRLF: &[u8] = b"\r\n";
 const CRLF_LEN: usize = CRLF.len();
 
+/// Encoding works from a reference and writes straight into the caller's
+/// buffer, so a reply is encoded without any intermediate allocation.
 #[enum_dispatch]
 pub trait RespEncode {
-    fn encode(self) -> Vec<u8>;
+    fn encode_to<B: BufMut>(&self, buf: &mut B);
+
+    /// The number of bytes `encode_to` writes.
+    fn encoded_len(&self) -> usize;
+
+    fn encode(self) -> Vec<u8>
+    where
+        Self: Sized,
+    {
+        let mut buf = Vec::with_capacity(self.encoded_len());
+        self.encode_to(&mut buf);
+        buf
+    }
 }
 
 pub trait RespDecode {
@@ -47,6 +92,16 @@ pub enum RespError {
     #[error("Invalid frame length: {0}")]
     InvalidFrameLength(isize),
 
+    /// A peer sent something the server won't take, the connection is
+    /// closed after telling it so.
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+
+    /// A value that can't be turned into a frame, or a reply that doesn't
+    /// have the shape of the type it's read into.
+    #[error("Conversion error: {0}")]
+    Conversion(String),
+
     #[error("Frame not complete yet")]
     NotComplete,
 
@@ -61,6 +116,36 @@ pub enum RespError {
 }
 
 // utility functions
+
+// "<prefix><n>\r\n", which starts integers, bulk strings and aggregates
+fn encode_header<B: BufMut>(buf: &mut B, prefix: u8, n: impl itoa::Integer) {
+    buf.put_u8(prefix);
+    buf.put_slice(itoa::Buffer::new().format(n).as_bytes());
+    buf.put_slice(CRLF);
+}
+
+fn header_len(n: impl itoa::Integer) -> usize {
+    1 + itoa::Buffer::new().format(n).len() + CRLF_LEN
+}
+
+// "<prefix><line>\r\n"
+fn encode_line<B: BufMut>(buf: &mut B, prefix: u8, line: &[u8]) {
+    buf.put_u8(prefix);
+    buf.put_slice(line);
+    buf.put_slice(CRLF);
+}
+
+// "<prefix><len>\r\n<data>\r\n"
+fn encode_bulk<B: BufMut>(buf: &mut B, prefix: u8, data: &[u8]) {
+    encode_header(buf, prefix, data.len());
+    buf.put_slice(data);
+    buf.put_slice(CRLF);
+}
+
+fn bulk_len(data_len: usize) -> usize {
+    header_len(data_len) + data_len + CRLF_LEN
+}
+
 fn extract_fixed_data(
     buf: &mut BytesMut,
     expect: &str,
@@ -99,17 +184,10 @@ pub fn extract_simple_frame_data(buf: &[u8], prefix: &str) -> Result<usize, Resp
 
 // find nth CRLF in the buffer
 fn find_crlf(buf: &[u8], nth: usize) -> Option<usize> {
-    // buf.windows(CRLF_LEN).position(|window| window == CRLF)
-    let mut count = 0;
-    for i in 1..buf.len() - 1 {
-        if buf[i..i + CRLF_LEN] == *CRLF {
-            count += 1;
-            if count == nth {
-                return Some(i);
-            }
-        }
-    }
-    None
+    // the type byte is never part of a CRLF
+    memmem::find_iter(buf.get(1..)?, CRLF)
+        .nth(nth.checked_sub(1)?)
+        .map(|i| i + 1)
 }
 
 fn parse_length(buf: &[u8], prefix: &str) -> Result<(usize, usize), RespError> {
@@ -140,35 +218,18 @@ fn parse_length2(buf: &[u8], prefix: &str) -> Result<(usize, isize), RespError>
     Ok((end, len))
 }
 
-fn calc_total_length(buf: &[u8], end: usize, len: usize, prefix: &str) -> Result<usize, RespError> {
-    let mut total = end + CRLF_LEN;
-    let mut data = &buf[total..];
-    match prefix {
-        "*" | "~" => {
-            // find nth CRLF in the buffer, for array and set, we need to find 1 CRLF for each element
-            for _ in 0..len {
-                let len = RespFrame::expect_length(data)?;
-                data = &data[len..];
-                total += len;
-            }
-            Ok(total)
-        }
-        "%" => {
-            // find nth CRLF in the buffer. For map, we need to find 2 CRLF for each key-value pair
-            for _ in 0..len {
-                let len = SimpleString::expect_length(data)?;
-
-                data = &data[len..];
-                total += len;
-
-                let len = RespFrame::expect_length(data)?;
-                data = &data[len..];
-                total += len;
-            }
-            Ok(total)
-        }
-        _ => Ok(len + CRLF_LEN),
+// decode a whole aggregate frame, which has to start with `prefix`
+fn decode_aggregate(buf: &mut BytesMut, prefix: &str) -> Result<RespFrame, RespError> {
+    if buf.is_empty() {
+        return Err(RespError::NotComplete);
+    }
+    if !buf.starts_with(prefix.as_bytes()) {
+        return Err(RespError::InvalidFrameType(format!(
+            "expect: {}, got: {:?}",
+            prefix, buf
+        )));
     }
+    RespFrame::decode(buf)
 }
 
 #[cfg(test)]
@@ -0,0 +1,96 @@
+use bytes::{BufMut, BytesMut};
+
+use crate::{RespDecode, RespDecoder, RespEncode, RespError, RespFrame};
+use std::ops::Deref;
+
+use super::{decode_aggregate, encode_header, header_len};
+
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord)]
+pub struct RespPush(pub(crate) Vec<RespFrame>);
+
+// - push: "><number-of-elements>\r\n<element-1>...<element-n>"
+impl RespEncode for RespPush {
+    fn encode_to<B: BufMut>(&self, buf: &mut B) {
+        encode_header(buf, b'>', self.len());
+        for frame in &self.0 {
+            frame.encode_to(buf);
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        header_len(self.len()) + self.0.iter().map(RespEncode::encoded_len).sum::<usize>()
+    }
+}
+
+// - push: "><number-of-elements>\r\n<element-1>...<element-n>"
+impl RespDecode for RespPush {
+    const PREFIX: &'static str = ">";
+
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        match decode_aggregate(buf, Self::PREFIX)? {
+            RespFrame::Push(frame) => Ok(frame),
+            _ => unreachable!(),
+        }
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        RespDecoder::frame_len(buf)
+    }
+}
+
+impl RespPush {
+    pub fn new(s: impl Into<Vec<RespFrame>>) -> Self {
+        RespPush(s.into())
+    }
+
+    pub fn into_inner(self) -> Vec<RespFrame> {
+        self.0
+    }
+}
+
+impl Deref for RespPush {
+    type Target = Vec<RespFrame>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulkString;
+    use anyhow::Result;
+
+    #[test]
+    fn test_push_encode() {
+        let frame: RespFrame = RespPush::new([
+            BulkString::new("message").into(),
+            BulkString::new("news").into(),
+            BulkString::new("hello").into(),
+        ])
+        .into();
+        assert_eq!(
+            frame.encode(),
+            b">3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n"
+        );
+    }
+
+    #[test]
+    fn test_push_decode() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b">2\r\n$7\r\nmessage\r\n:1\r\n");
+
+        let frame = RespPush::decode(&mut buf)?;
+        assert_eq!(
+            frame,
+            RespPush::new([BulkString::new("message").into(), 1.into()])
+        );
+
+        buf.extend_from_slice(b">2\r\n$7\r\nmessage\r\n");
+        let ret = RespPush::decode(&mut buf);
+        assert_eq!(ret.unwrap_err(), RespError::NotComplete);
+
+        Ok(())
+    }
+}
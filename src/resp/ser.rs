@@ -0,0 +1,498 @@
+use std::fmt::Display;
+
+use serde::{ser, Serialize};
+
+use crate::{BigNumber, BulkString, Double, RespArray, RespError, RespFrame, RespMap, RespNull};
+
+/// Turns any `Serialize` value into a frame. Strings and bytes become bulk
+/// strings, `None` and `()` become null, sequences and tuples arrays, and
+/// maps and structs RESP3 maps, or with `flat`, `[field, value, ...]`
+/// arrays as taken by HSET. Enum variants are sent by name, with their
+/// data in a single entry map.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameSerializer {
+    flat: bool,
+}
+
+pub fn to_frame<T: Serialize + ?Sized>(value: &T) -> Result<RespFrame, RespError> {
+    value.serialize(FrameSerializer::new())
+}
+
+/// Like `to_frame`, with maps and structs flattened into arrays.
+pub fn to_flat_frame<T: Serialize + ?Sized>(value: &T) -> Result<RespFrame, RespError> {
+    value.serialize(FrameSerializer::flat())
+}
+
+impl FrameSerializer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn flat() -> Self {
+        Self { flat: true }
+    }
+
+    fn entries(self, entries: Vec<(RespFrame, RespFrame)>) -> RespFrame {
+        if self.flat {
+            let frames: Vec<_> = entries.into_iter().flat_map(|(k, v)| [k, v]).collect();
+            RespArray::new(frames).into()
+        } else {
+            RespMap(entries).into()
+        }
+    }
+}
+
+impl ser::Error for RespError {
+    fn custom<T: Display>(msg: T) -> Self {
+        RespError::Conversion(msg.to_string())
+    }
+}
+
+pub(super) fn integer(i: impl TryInto<i64> + ToString + Copy) -> RespFrame {
+    match i.try_into() {
+        Ok(i) => RespFrame::Integer(i),
+        // the digits of a u64 or i128 are always a valid big number
+        Err(_) => BigNumber(i.to_string()).into(),
+    }
+}
+
+fn variant(name: &str, value: RespFrame) -> RespFrame {
+    RespMap(vec![(BulkString::from(name).into(), value)]).into()
+}
+
+impl ser::Serializer for FrameSerializer {
+    type Ok = RespFrame;
+    type Error = RespError;
+
+    type SerializeSeq = SerializeVec;
+    type SerializeTuple = SerializeVec;
+    type SerializeTupleStruct = SerializeVec;
+    type SerializeTupleVariant = SerializeVec;
+    type SerializeMap = SerializeEntries;
+    type SerializeStruct = SerializeEntries;
+    type SerializeStructVariant = SerializeEntries;
+
+    fn serialize_bool(self, v: bool) -> Result<RespFrame, RespError> {
+        Ok(v.into())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<RespFrame, RespError> {
+        Ok(integer(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<RespFrame, RespError> {
+        Ok(integer(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<RespFrame, RespError> {
+        Ok(integer(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<RespFrame, RespError> {
+        Ok(integer(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<RespFrame, RespError> {
+        Ok(integer(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<RespFrame, RespError> {
+        Ok(integer(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<RespFrame, RespError> {
+        Ok(integer(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<RespFrame, RespError> {
+        Ok(integer(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<RespFrame, RespError> {
+        Ok(integer(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<RespFrame, RespError> {
+        Ok(integer(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<RespFrame, RespError> {
+        Ok(Double::from(v as f64).into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<RespFrame, RespError> {
+        Ok(Double::from(v).into())
+    }
+
+    fn serialize_char(self, v: char) -> Result<RespFrame, RespError> {
+        Ok(BulkString::from(v.encode_utf8(&mut [0; 4]) as &str).into())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<RespFrame, RespError> {
+        Ok(BulkString::from(v).into())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<RespFrame, RespError> {
+        Ok(BulkString::from(v).into())
+    }
+
+    fn serialize_none(self) -> Result<RespFrame, RespError> {
+        Ok(RespNull.into())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<RespFrame, RespError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<RespFrame, RespError> {
+        Ok(RespNull.into())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<RespFrame, RespError> {
+        Ok(RespNull.into())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<RespFrame, RespError> {
+        Ok(BulkString::from(variant).into())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<RespFrame, RespError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        name: &'static str,
+        value: &T,
+    ) -> Result<RespFrame, RespError> {
+        Ok(variant(name, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeVec, RespError> {
+        Ok(SerializeVec {
+            ser: self,
+            frames: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeVec, RespError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeVec, RespError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVec, RespError> {
+        let mut seq = self.serialize_seq(Some(len))?;
+        seq.variant = Some(variant);
+        Ok(seq)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeEntries, RespError> {
+        Ok(SerializeEntries {
+            ser: self,
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeEntries, RespError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeEntries, RespError> {
+        let mut map = self.serialize_map(Some(len))?;
+        map.variant = Some(variant);
+        Ok(map)
+    }
+}
+
+#[doc(hidden)]
+pub struct SerializeVec {
+    ser: FrameSerializer,
+    frames: Vec<RespFrame>,
+    variant: Option<&'static str>,
+}
+
+impl SerializeVec {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RespError> {
+        self.frames.push(value.serialize(self.ser)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<RespFrame, RespError> {
+        let array = RespArray::new(self.frames).into();
+        Ok(match self.variant {
+            Some(name) => variant(name, array),
+            None => array,
+        })
+    }
+}
+
+impl ser::SerializeSeq for SerializeVec {
+    type Ok = RespFrame;
+    type Error = RespError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RespError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RespFrame, RespError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeVec {
+    type Ok = RespFrame;
+    type Error = RespError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RespError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RespFrame, RespError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeVec {
+    type Ok = RespFrame;
+    type Error = RespError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RespError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RespFrame, RespError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeVec {
+    type Ok = RespFrame;
+    type Error = RespError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RespError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RespFrame, RespError> {
+        self.finish()
+    }
+}
+
+#[doc(hidden)]
+pub struct SerializeEntries {
+    ser: FrameSerializer,
+    entries: Vec<(RespFrame, RespFrame)>,
+    // a key waiting for its value, see SerializeMap
+    key: Option<RespFrame>,
+    variant: Option<&'static str>,
+}
+
+impl SerializeEntries {
+    fn field<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), RespError> {
+        let value = value.serialize(self.ser)?;
+        self.entries.push((BulkString::from(key).into(), value));
+        Ok(())
+    }
+
+    fn finish(self) -> Result<RespFrame, RespError> {
+        let frame = self.ser.entries(self.entries);
+        Ok(match self.variant {
+            Some(name) => variant(name, frame),
+            None => frame,
+        })
+    }
+}
+
+impl ser::SerializeMap for SerializeEntries {
+    type Ok = RespFrame;
+    type Error = RespError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), RespError> {
+        self.key = Some(key.serialize(self.ser)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RespError> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| RespError::Conversion("map value without a key".to_string()))?;
+        self.entries.push((key, value.serialize(self.ser)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<RespFrame, RespError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeEntries {
+    type Ok = RespFrame;
+    type Error = RespError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), RespError> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<RespFrame, RespError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeEntries {
+    type Ok = RespFrame;
+    type Error = RespError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), RespError> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<RespFrame, RespError> {
+        self.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Serialize;
+
+    use super::*;
+
+    #[derive(Serialize)]
+    struct User {
+        name: String,
+        age: u8,
+        email: Option<String>,
+        tags: Vec<&'static str>,
+    }
+
+    #[derive(Serialize)]
+    enum Event {
+        Ping,
+        Move(i32, i32),
+        Rename { to: String },
+    }
+
+    #[test]
+    fn test_serialize_struct() -> Result<(), RespError> {
+        let user = User {
+            name: "alice".to_string(),
+            age: 30,
+            email: None,
+            tags: vec!["a", "b"],
+        };
+        let mut map = RespMap::new();
+        map.insert(
+            BulkString::from("name").into(),
+            BulkString::from("alice").into(),
+        );
+        map.insert(BulkString::from("age").into(), RespFrame::Integer(30));
+        map.insert(BulkString::from("email").into(), RespNull.into());
+        map.insert(
+            BulkString::from("tags").into(),
+            RespArray::new([BulkString::from("a").into(), BulkString::from("b").into()]).into(),
+        );
+        assert_eq!(to_frame(&user)?, map.into());
+
+        // flat, as HSET takes them
+        let frame = to_flat_frame(&BTreeMap::from([("f1", 1.5), ("f2", 2.0)]))?;
+        assert_eq!(
+            frame,
+            RespArray::new([
+                BulkString::from("f1").into(),
+                Double::from(1.5).into(),
+                BulkString::from("f2").into(),
+                Double::from(2.0).into(),
+            ])
+            .into()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_scalars_and_enums() -> Result<(), RespError> {
+        assert_eq!(to_frame(&u64::MAX)?, BigNumber(u64::MAX.to_string()).into());
+        assert_eq!(to_frame(&-7i16)?, RespFrame::Integer(-7));
+        assert_eq!(to_frame(&'é')?, BulkString::from("é").into());
+        assert_eq!(to_frame(&Some(true))?, true.into());
+        assert_eq!(to_frame(&())?, RespNull.into());
+        assert_eq!(
+            to_frame(&serde_bytes_like(b"\xff\x00"))?,
+            BulkString::from(b"\xff\x00").into()
+        );
+
+        assert_eq!(to_frame(&Event::Ping)?, BulkString::from("Ping").into());
+        assert_eq!(
+            to_frame(&Event::Move(1, -1))?,
+            variant(
+                "Move",
+                RespArray::new([RespFrame::Integer(1), RespFrame::Integer(-1)]).into()
+            )
+        );
+        let mut rename = RespMap::new();
+        rename.insert(BulkString::from("to").into(), BulkString::from("b").into());
+        assert_eq!(
+            to_frame(&Event::Rename { to: "b".into() })?,
+            variant("Rename", rename.into())
+        );
+        Ok(())
+    }
+
+    // serializes as bytes rather than a sequence of integers
+    fn serde_bytes_like(data: &'static [u8]) -> impl Serialize {
+        struct Bytes(&'static [u8]);
+        impl Serialize for Bytes {
+            fn serialize<S: ser::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                s.serialize_bytes(self.0)
+            }
+        }
+        Bytes(data)
+    }
+}
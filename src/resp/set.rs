@@ -1,50 +1,40 @@
-use bytes::{Buf, BytesMut};
+use bytes::{BufMut, BytesMut};
 
-use crate::{RespDecode, RespEncode, RespError, RespFrame};
+use crate::{RespDecode, RespDecoder, RespEncode, RespError, RespFrame};
 use std::ops::Deref;
 
-use super::{calc_total_length, parse_length, BUF_CAP, CRLF_LEN};
+use super::{decode_aggregate, encode_header, header_len};
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord)]
 pub struct RespSet(pub(crate) Vec<RespFrame>);
 
 // - set: "~<number-of-elements>\r\n<element-1>...<element-n>"
 impl RespEncode for RespSet {
-    fn encode(self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(BUF_CAP);
-        buf.extend_from_slice(&format!("~{}\r\n", self.len()).into_bytes());
-        for frame in self.0 {
-            buf.extend_from_slice(&frame.encode());
+    fn encode_to<B: BufMut>(&self, buf: &mut B) {
+        encode_header(buf, b'~', self.len());
+        for frame in &self.0 {
+            frame.encode_to(buf);
         }
-        buf
+    }
+
+    fn encoded_len(&self) -> usize {
+        header_len(self.len()) + self.0.iter().map(RespEncode::encoded_len).sum::<usize>()
     }
 }
 
 // - set: "~<number-of-elements>\r\n<element-1>...<element-n>"
 impl RespDecode for RespSet {
     const PREFIX: &'static str = "~";
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        let (end, len) = parse_length(buf, Self::PREFIX)?;
 
-        let total_len = calc_total_length(buf, end, len, Self::PREFIX)?;
-
-        if buf.len() < total_len {
-            return Err(RespError::NotComplete);
-        }
-
-        buf.advance(end + CRLF_LEN);
-
-        let mut frames = Vec::new();
-        for _ in 0..len {
-            frames.push(RespFrame::decode(buf)?);
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        match decode_aggregate(buf, Self::PREFIX)? {
+            RespFrame::Set(frame) => Ok(frame),
+            _ => unreachable!(),
         }
-
-        Ok(RespSet::new(frames))
     }
 
     fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
-        let (end, len) = parse_length(buf, Self::PREFIX)?;
-        calc_total_length(buf, end, len, Self::PREFIX)
+        RespDecoder::frame_len(buf)
     }
 }
 
@@ -1,17 +1,21 @@
 use std::ops::Deref;
 
-use bytes::BytesMut;
+use bytes::{BufMut, BytesMut};
 
 use crate::{extract_simple_frame_data, RespDecode, RespEncode, RespError};
 
-use super::CRLF_LEN;
+use super::{encode_line, CRLF_LEN};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct SimpleError(pub(crate) String);
 
 impl RespEncode for SimpleError {
-    fn encode(self) -> Vec<u8> {
-        format!("-{}\r\n", self.0).into_bytes()
+    fn encode_to<B: BufMut>(&self, buf: &mut B) {
+        encode_line(buf, b'-', self.0.as_bytes());
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.0.len() + 3
     }
 }
 
@@ -20,7 +24,8 @@ impl RespDecode for SimpleError {
 
     fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
         let end = extract_simple_frame_data(buf, Self::PREFIX)?;
-        let s = String::from_utf8_lossy(&buf[Self::PREFIX.len()..end]);
+        let data = buf.split_to(end + CRLF_LEN);
+        let s = String::from_utf8_lossy(&data[Self::PREFIX.len()..end]);
         Ok(Self(s.to_string()))
     }
 
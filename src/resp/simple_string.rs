@@ -1,10 +1,10 @@
 use std::ops::Deref;
 
-use bytes::BytesMut;
+use bytes::{BufMut, BytesMut};
 
 use crate::{extract_simple_frame_data, resp::CRLF_LEN};
 
-use super::{RespDecode, RespEncode, RespError};
+use super::{encode_line, RespDecode, RespEncode, RespError};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct SimpleString(pub(crate) String);
@@ -17,8 +17,12 @@ impl SimpleString {
 
 // - simple string: "+OK\r\n"
 impl RespEncode for SimpleString {
-    fn encode(self) -> Vec<u8> {
-        format!("+{}\r\n", self.0).into_bytes()
+    fn encode_to<B: BufMut>(&self, buf: &mut B) {
+        encode_line(buf, b'+', self.0.as_bytes());
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.0.len() + 3
     }
 }
 
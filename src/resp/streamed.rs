@@ -0,0 +1,297 @@
+use bytes::{Buf, BufMut, BytesMut};
+
+use crate::{
+    BulkString, RespArray, RespDecode, RespEncode, RespError, RespFrame, RespMap, RespSet,
+};
+
+use super::{bulk_len, decoder::RespDecoder, encode_bulk, parse_length, CRLF, CRLF_LEN};
+
+const END: &[u8] = b".\r\n";
+
+/// A bulk string of unknown length, sent as a run of chunks ended by an
+/// empty one. Decoding it through `RespFrame` yields a plain `BulkString`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamedString(pub(crate) Vec<Vec<u8>>);
+
+/// An aggregate of unknown length, ended by a ".\r\n" marker. Decoding it
+/// through `RespFrame` yields the equivalent sized aggregate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamedAggregate {
+    Array(Vec<RespFrame>),
+    Set(Vec<RespFrame>),
+    Map(Vec<(RespFrame, RespFrame)>),
+}
+
+// - streamed string: "$?\r\n;<length>\r\n<chunk>\r\n...;0\r\n"
+impl RespEncode for StreamedString {
+    fn encode_to<B: BufMut>(&self, buf: &mut B) {
+        buf.put_slice(b"$?\r\n");
+        for chunk in self.chunks() {
+            encode_bulk(buf, b';', chunk);
+        }
+        buf.put_slice(b";0\r\n");
+    }
+
+    fn encoded_len(&self) -> usize {
+        8 + self.chunks().map(|c| bulk_len(c.len())).sum::<usize>()
+    }
+}
+
+impl RespDecode for StreamedString {
+    const PREFIX: &'static str = "$?";
+
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        let total_len = Self::expect_length(buf)?;
+        if buf.len() < total_len {
+            return Err(RespError::NotComplete);
+        }
+
+        buf.advance(Self::PREFIX.len() + CRLF_LEN);
+        let mut chunks = Vec::new();
+        loop {
+            let (end, len) = parse_length(buf, ";")?;
+            buf.advance(end + CRLF_LEN);
+            if len == 0 {
+                return Ok(StreamedString(chunks));
+            }
+            chunks.push(buf.split_to(len).to_vec());
+            buf.advance(CRLF_LEN);
+        }
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        let mut total = streamed_header(buf, Self::PREFIX)?;
+        loop {
+            let (end, len) = parse_length(&buf[total..], ";")?;
+            if len == 0 {
+                return Ok(total + end + CRLF_LEN);
+            }
+            total += end + CRLF_LEN + len + CRLF_LEN;
+            if buf.len() < total {
+                return Err(RespError::NotComplete);
+            }
+        }
+    }
+}
+
+impl StreamedString {
+    pub fn new(chunks: impl Into<Vec<Vec<u8>>>) -> Self {
+        StreamedString(chunks.into())
+    }
+
+    // an empty chunk would end the string early
+    fn chunks(&self) -> impl Iterator<Item = &Vec<u8>> {
+        self.0.iter().filter(|c| !c.is_empty())
+    }
+}
+
+impl From<StreamedString> for BulkString {
+    fn from(s: StreamedString) -> Self {
+        BulkString::new(s.0.concat())
+    }
+}
+
+// - streamed aggregate: "*?\r\n<element-1>...<element-n>.\r\n", same for set
+//   ("~?") and map ("%?") where each element is a key-value pair
+impl RespEncode for StreamedAggregate {
+    fn encode_to<B: BufMut>(&self, buf: &mut B) {
+        buf.put_slice(self.prefix().as_bytes());
+        buf.put_slice(CRLF);
+        match self {
+            StreamedAggregate::Array(frames) | StreamedAggregate::Set(frames) => {
+                for frame in frames {
+                    frame.encode_to(buf);
+                }
+            }
+            StreamedAggregate::Map(pairs) => {
+                for (key, value) in pairs {
+                    key.encode_to(buf);
+                    value.encode_to(buf);
+                }
+            }
+        }
+        buf.put_slice(END);
+    }
+
+    fn encoded_len(&self) -> usize {
+        let elements = match self {
+            StreamedAggregate::Array(frames) | StreamedAggregate::Set(frames) => {
+                frames.iter().map(RespEncode::encoded_len).sum::<usize>()
+            }
+            StreamedAggregate::Map(pairs) => pairs
+                .iter()
+                .map(|(key, value)| key.encoded_len() + value.encoded_len())
+                .sum(),
+        };
+        self.prefix().len() + CRLF_LEN + elements + END.len()
+    }
+}
+
+impl RespDecode for StreamedAggregate {
+    const PREFIX: &'static str = "?";
+
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        let total_len = Self::expect_length(buf)?;
+        if buf.len() < total_len {
+            return Err(RespError::NotComplete);
+        }
+
+        let kind = buf[0];
+        buf.advance(Self::PREFIX.len() + 1 + CRLF_LEN);
+        // the elements are all there, they can be consumed as they're decoded
+        let mut decoder = RespDecoder::new();
+        let mut element = |buf: &mut BytesMut| decoder.decode(buf)?.ok_or(RespError::NotComplete);
+        let mut frames = Vec::new();
+        let mut pairs = Vec::new();
+        while !buf.starts_with(END) {
+            if kind == b'%' {
+                let key = element(buf)?;
+                pairs.push((key, element(buf)?));
+            } else {
+                frames.push(element(buf)?);
+            }
+        }
+        buf.advance(END.len());
+
+        Ok(match kind {
+            b'*' => StreamedAggregate::Array(frames),
+            b'~' => StreamedAggregate::Set(frames),
+            _ => StreamedAggregate::Map(pairs),
+        })
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        let prefix = match buf.first() {
+            Some(b'*') => "*?",
+            Some(b'~') => "~?",
+            Some(b'%') => "%?",
+            Some(_) => {
+                return Err(RespError::InvalidFrameType(format!(
+                    "expect: streamed aggregate, got: {:?}",
+                    buf
+                )))
+            }
+            None => return Err(RespError::NotComplete),
+        };
+        let mut total = streamed_header(buf, prefix)?;
+        // map entries take two frames each
+        let per_entry = if prefix == "%?" { 2 } else { 1 };
+        loop {
+            let data = &buf[total..];
+            if data.starts_with(END) {
+                return Ok(total + END.len());
+            }
+            if data.len() < END.len() {
+                return Err(RespError::NotComplete);
+            }
+            for _ in 0..per_entry {
+                total += RespFrame::expect_length(&buf[total..])?;
+                if buf.len() < total {
+                    return Err(RespError::NotComplete);
+                }
+            }
+        }
+    }
+}
+
+impl StreamedAggregate {
+    fn prefix(&self) -> &'static str {
+        match self {
+            StreamedAggregate::Array(_) => "*?",
+            StreamedAggregate::Set(_) => "~?",
+            StreamedAggregate::Map(_) => "%?",
+        }
+    }
+}
+
+impl From<StreamedAggregate> for RespFrame {
+    fn from(aggregate: StreamedAggregate) -> Self {
+        match aggregate {
+            StreamedAggregate::Array(frames) => RespArray::new(frames).into(),
+            StreamedAggregate::Set(frames) => RespSet::new(frames).into(),
+            StreamedAggregate::Map(pairs) => RespMap(pairs).into(),
+        }
+    }
+}
+
+// checks "<prefix>\r\n" and returns its length
+fn streamed_header(buf: &[u8], prefix: &str) -> Result<usize, RespError> {
+    let len = prefix.len() + CRLF_LEN;
+    if buf.len() < len {
+        return Err(RespError::NotComplete);
+    }
+    if !buf.starts_with(prefix.as_bytes()) || &buf[prefix.len()..len] != CRLF {
+        return Err(RespError::InvalidFrame(format!(
+            "expect: streamed header {}, got: {:?}",
+            prefix, buf
+        )));
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_streamed_string_encode() {
+        let frame = StreamedString::new([b"Hell".to_vec(), b"o world".to_vec()]);
+        assert_eq!(
+            frame.encode(),
+            b"$?\r\n;4\r\nHell\r\n;7\r\no world\r\n;0\r\n"
+        );
+    }
+
+    #[test]
+    fn test_streamed_string_decode() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"$?\r\n;4\r\nHell\r\n;5\r\no wor\r\n");
+        assert_eq!(
+            StreamedString::decode(&mut buf).unwrap_err(),
+            RespError::NotComplete
+        );
+
+        buf.extend_from_slice(b";1\r\nd\r\n;0\r\n");
+        let frame = RespFrame::decode(&mut buf)?;
+        assert_eq!(frame, BulkString::new("Hello word").into());
+        assert!(buf.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_streamed_aggregate_encode() {
+        let frame = StreamedAggregate::Set(vec![RespFrame::Integer(1), true.into()]);
+        assert_eq!(frame.encode(), b"~?\r\n:1\r\n#t\r\n.\r\n");
+    }
+
+    #[test]
+    fn test_streamed_aggregate_decode() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*?\r\n:1\r\n$?\r\n;2\r\nab\r\n;0\r\n");
+        assert_eq!(
+            RespFrame::decode(&mut buf).unwrap_err(),
+            RespError::NotComplete
+        );
+
+        buf.extend_from_slice(b"*?\r\n.\r\n.\r\n");
+        let frame = RespFrame::decode(&mut buf)?;
+        assert_eq!(
+            frame,
+            RespArray::new([
+                RespFrame::Integer(1),
+                BulkString::new("ab").into(),
+                RespArray::new([]).into(),
+            ])
+            .into()
+        );
+
+        buf.extend_from_slice(b"%?\r\n+a\r\n:1\r\n$1\r\nb\r\n:2\r\n.\r\n");
+        let frame = RespFrame::decode(&mut buf)?;
+        let mut map = RespMap::new();
+        map.insert("a".into(), RespFrame::Integer(1));
+        map.insert(BulkString::from("b").into(), RespFrame::Integer(2));
+        assert_eq!(frame, map.into());
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use bytes::{Buf, BufMut, BytesMut};
+
+use crate::{RespDecode, RespEncode, RespError};
+
+use super::{bulk_len, encode_header, parse_length, CRLF, CRLF_LEN};
+
+// the three letter format and the ':' separating it from the data
+const FORMAT_LEN: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VerbatimString {
+    pub(crate) format: [u8; 3],
+    pub(crate) data: Vec<u8>,
+}
+
+// - verbatim string: "=<length>\r\n<format>:<data>\r\n"
+impl RespEncode for VerbatimString {
+    fn encode_to<B: BufMut>(&self, buf: &mut B) {
+        encode_header(buf, b'=', self.data.len() + FORMAT_LEN);
+        buf.put_slice(&self.format);
+        buf.put_u8(b':');
+        buf.put_slice(&self.data);
+        buf.put_slice(CRLF);
+    }
+
+    fn encoded_len(&self) -> usize {
+        bulk_len(self.data.len() + FORMAT_LEN)
+    }
+}
+
+impl RespDecode for VerbatimString {
+    const PREFIX: &'static str = "=";
+
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        let (end, len) = parse_length(buf, Self::PREFIX)?;
+        if buf.len() < end + CRLF_LEN + len + CRLF_LEN {
+            return Err(RespError::NotComplete);
+        }
+        if len < FORMAT_LEN || buf[end + CRLF_LEN + FORMAT_LEN - 1] != b':' {
+            return Err(RespError::InvalidFrame(
+                "verbatim string must start with a 3 bytes format and ':'".to_string(),
+            ));
+        }
+
+        buf.advance(end + CRLF_LEN);
+        let data = buf.split_to(len);
+        buf.advance(CRLF_LEN);
+
+        let mut format = [0; 3];
+        format.copy_from_slice(&data[..3]);
+        Ok(VerbatimString::new(format, &data[FORMAT_LEN..]))
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        let (end, len) = parse_length(buf, Self::PREFIX)?;
+        Ok(end + CRLF_LEN + len + CRLF_LEN)
+    }
+}
+
+impl VerbatimString {
+    pub fn new(format: [u8; 3], data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            format,
+            data: data.into(),
+        }
+    }
+
+    /// Plain text, the format Redis uses for INFO and friends.
+    pub fn text(data: impl Into<Vec<u8>>) -> Self {
+        Self::new(*b"txt", data)
+    }
+
+    pub fn format(&self) -> &[u8; 3] {
+        &self.format
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RespFrame;
+
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_verbatim_string_encode() {
+        let frame: RespFrame = VerbatimString::text("Some string").into();
+        assert_eq!(frame.encode(), b"=15\r\ntxt:Some string\r\n");
+    }
+
+    #[test]
+    fn test_verbatim_string_decode() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"=15\r\ntxt:Some string");
+        assert_eq!(
+            VerbatimString::decode(&mut buf).unwrap_err(),
+            RespError::NotComplete
+        );
+
+        buf.extend_from_slice(b"\r\n");
+        let frame = VerbatimString::decode(&mut buf)?;
+        assert_eq!(frame, VerbatimString::new(*b"txt", "Some string"));
+
+        buf.extend_from_slice(b"=3\r\ntxt\r\n");
+        assert!(VerbatimString::decode(&mut buf).is_err());
+
+        Ok(())
+    }
+}
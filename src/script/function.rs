@@ -0,0 +1,428 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use bytes::Bytes;
+use mlua::Lua;
+
+use crate::{Backend, RespFrame, SimpleError};
+
+use super::{lua, sha1hex, ScriptEngine};
+
+// flags a function can be registered with
+pub(super) const FUNCTION_FLAGS: &[&str] = &[
+    "no-writes",
+    "allow-oom",
+    "allow-stale",
+    "no-cluster",
+    "allow-cross-slot-keys",
+];
+
+// FUNCTION DUMP payloads: header, length-prefixed library codes, SHA1 of
+// the codes
+const DUMP_HEADER: &str = "SRFUNC1\n";
+const CHECKSUM_LEN: usize = 40;
+
+// how long a library's top-level code may run, as in Redis
+const LOAD_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Library {
+    pub(crate) name: String,
+    pub(crate) code: String,
+    pub(crate) functions: BTreeMap<String, FunctionInfo>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct FunctionInfo {
+    pub(crate) description: Option<String>,
+    pub(crate) flags: Vec<String>,
+}
+
+/// Loaded libraries and the Lua VM their functions live in. Functions get
+/// a VM of their own so SCRIPT FLUSH leaves them alone.
+#[derive(Debug, Default)]
+pub(super) struct Functions {
+    vm: Option<Lua>,
+    libraries: BTreeMap<String, Library>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum RestorePolicy {
+    Append,
+    Replace,
+    Flush,
+}
+
+impl ScriptEngine {
+    /// Load a library, returning its name.
+    pub(crate) fn function_load(&self, code: &str, replace: bool) -> Result<String, SimpleError> {
+        let (name, _) = parse_metadata(code)?;
+        let mut functions = self.functions();
+        if !replace && functions.libraries.contains_key(&name) {
+            return Err(SimpleError::new(format!(
+                "ERR Library '{}' already exists",
+                name
+            )));
+        }
+        let mut codes = functions
+            .libraries
+            .values()
+            .filter(|library| library.name != name)
+            .map(|library| library.code.clone())
+            .collect::<Vec<String>>();
+        codes.push(code.to_string());
+        *functions = self.build_functions(codes)?;
+        Ok(name)
+    }
+
+    pub(crate) fn function_delete(&self, name: &str) -> Result<(), SimpleError> {
+        let mut functions = self.functions();
+        if !functions.libraries.contains_key(name) {
+            return Err(SimpleError::new("ERR Library not found"));
+        }
+        let codes = functions
+            .libraries
+            .values()
+            .filter(|library| library.name != name)
+            .map(|library| library.code.clone())
+            .collect::<Vec<String>>();
+        *functions = self.build_functions(codes)?;
+        Ok(())
+    }
+
+    pub(crate) fn function_flush(&self) {
+        *self.functions() = Functions::default();
+    }
+
+    pub(crate) fn libraries(&self) -> Vec<Library> {
+        self.functions().libraries.values().cloned().collect()
+    }
+
+    /// Serialize every library so it can be brought back with RESTORE.
+    pub(crate) fn function_dump(&self) -> String {
+        let body = self
+            .functions()
+            .libraries
+            .values()
+            .map(|library| format!("{}\n{}", library.code.len(), library.code))
+            .collect::<String>();
+        let checksum = sha1hex(&body);
+        format!("{}{}{}", DUMP_HEADER, body, checksum)
+    }
+
+    pub(crate) fn function_restore(
+        &self,
+        payload: &str,
+        policy: RestorePolicy,
+    ) -> Result<(), SimpleError> {
+        let restored = parse_dump(payload)
+            .ok_or_else(|| SimpleError::new("ERR payload version or checksum are wrong"))?;
+        let names = restored
+            .iter()
+            .map(|code| parse_metadata(code).map(|(name, _)| name))
+            .collect::<Result<Vec<String>, SimpleError>>()?;
+
+        let mut functions = self.functions();
+        let mut codes = match policy {
+            RestorePolicy::Flush => Vec::new(),
+            RestorePolicy::Append => {
+                if let Some(name) = names
+                    .iter()
+                    .find(|name| functions.libraries.contains_key(*name))
+                {
+                    return Err(SimpleError::new(format!(
+                        "ERR Library '{}' already exists",
+                        name
+                    )));
+                }
+                functions
+                    .libraries
+                    .values()
+                    .map(|library| library.code.clone())
+                    .collect()
+            }
+            RestorePolicy::Replace => functions
+                .libraries
+                .values()
+                .filter(|library| !names.contains(&library.name))
+                .map(|library| library.code.clone())
+                .collect(),
+        };
+        codes.extend(restored.into_iter().map(String::from));
+        *functions = self.build_functions(codes)?;
+        Ok(())
+    }
+
+    /// Call a registered function. The caller must hold the backend's
+    /// exclusive lock, like for EVAL.
+    pub(crate) fn fcall(
+        &self,
+        backend: &Backend,
+        name: &str,
+        keys: Vec<Bytes>,
+        args: Vec<Bytes>,
+        read_only: bool,
+    ) -> RespFrame {
+        let functions = self.functions();
+        let Some(info) = functions
+            .libraries
+            .values()
+            .find_map(|library| library.functions.get(name))
+        else {
+            return SimpleError::new("ERR Function not found").into();
+        };
+        let no_writes = info.flags.iter().any(|flag| flag == "no-writes");
+        if read_only && !no_writes {
+            return SimpleError::new(
+                "ERR Can not execute a script with write flag using *_ro command.",
+            )
+            .into();
+        }
+
+        let Some(lua) = functions.vm.as_ref() else {
+            return SimpleError::new("ERR Function not found").into();
+        };
+        let function = match lua::registered_function(lua, name) {
+            Ok(function) => function,
+            Err(e) => return e.into(),
+        };
+        let callable = lua::Callable::Function(name);
+        self.run(read_only || no_writes, |run| {
+            lua::call(lua, function, callable, backend, keys, args, run)
+        })
+    }
+
+    // load `codes` into a fresh VM, so a failure leaves the current
+    // libraries untouched. The callers hold the backend's exclusive lock
+    // and run off the async workers, like for FCALL.
+    fn build_functions(&self, codes: Vec<String>) -> Result<Functions, SimpleError> {
+        let lua = self.new_vm()?;
+        let mut libraries = BTreeMap::new();
+        for code in codes {
+            let (name, body) = parse_metadata(&code)?;
+            if libraries.contains_key(&name) {
+                return Err(SimpleError::new(format!(
+                    "ERR Library '{}' already exists",
+                    name
+                )));
+            }
+            let functions = self.with_deadline(LOAD_TIMEOUT, || lua::load_library(&lua, body))?;
+            libraries.insert(
+                name.clone(),
+                Library {
+                    name,
+                    code,
+                    functions,
+                },
+            );
+        }
+        Ok(Functions {
+            vm: Some(lua),
+            libraries,
+        })
+    }
+}
+
+/// Split the `#!lua name=<name>` line off a library's code. The returned
+/// body keeps the line break so Lua reports the right line numbers.
+fn parse_metadata(code: &str) -> Result<(String, &str), SimpleError> {
+    let first = code.lines().next().unwrap_or_default();
+    let Some(shebang) = first.strip_prefix("#!") else {
+        return Err(SimpleError::new("ERR Missing library metadata"));
+    };
+    let mut parts = shebang.split_whitespace();
+    let engine = parts.next().unwrap_or_default();
+    if !engine.eq_ignore_ascii_case("lua") {
+        return Err(SimpleError::new(format!(
+            "ERR Engine '{}' not found",
+            engine
+        )));
+    }
+
+    let mut name = None;
+    for part in parts {
+        match part.split_once('=') {
+            Some(("name", value)) => name = Some(value),
+            _ => {
+                return Err(SimpleError::new(format!(
+                    "ERR Invalid metadata value given: {}",
+                    part
+                )))
+            }
+        }
+    }
+    let Some(name) = name else {
+        return Err(SimpleError::new("ERR Library name was not given"));
+    };
+    if !is_valid_name(name) {
+        return Err(SimpleError::new(
+            "ERR Library names can only contain letters, numbers, or underscores(_) and must be at least one character long",
+        ));
+    }
+    Ok((name.to_string(), &code[first.len()..]))
+}
+
+pub(super) fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'_')
+}
+
+fn parse_dump(payload: &str) -> Option<Vec<&str>> {
+    let rest = payload.strip_prefix(DUMP_HEADER)?;
+    let split = rest.len().checked_sub(CHECKSUM_LEN)?;
+    if !rest.is_char_boundary(split) {
+        return None;
+    }
+    let (mut body, checksum) = rest.split_at(split);
+    if sha1hex(body) != checksum {
+        return None;
+    }
+
+    let mut codes = Vec::new();
+    while !body.is_empty() {
+        let (len, tail) = body.split_once('\n')?;
+        let len = len.parse::<usize>().ok()?;
+        if tail.len() < len || !tail.is_char_boundary(len) {
+            return None;
+        }
+        codes.push(&tail[..len]);
+        body = &tail[len..];
+    }
+    Some(codes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulkString;
+
+    const LIBRARY: &str = "#!lua name=mylib
+redis.register_function('myset', function(keys, args)
+  return redis.call('set', keys[1], args[1])
+end)
+redis.register_function{
+  function_name = 'myget',
+  callback = function(keys) return redis.call('get', keys[1]) end,
+  flags = {'no-writes'},
+  description = 'read a key',
+}";
+
+    #[test]
+    fn test_parse_metadata() {
+        let (name, body) = parse_metadata("#!lua name=lib_1\nreturn 1").unwrap();
+        assert_eq!(name, "lib_1");
+        assert_eq!(body, "\nreturn 1");
+
+        assert!(parse_metadata("return 1").is_err());
+        assert!(parse_metadata("#!js name=lib\n").is_err());
+        assert!(parse_metadata("#!lua name=my-lib\n").is_err());
+        assert!(parse_metadata("#!lua name=lib version=1\n").is_err());
+    }
+
+    #[test]
+    fn test_function_load_and_call() {
+        let backend = Backend::new();
+        let scripts = &backend.scripts;
+        assert_eq!(
+            scripts.function_load(LIBRARY, false),
+            Ok("mylib".to_string())
+        );
+        assert_eq!(
+            scripts.function_load(LIBRARY, false),
+            Err(SimpleError::new("ERR Library 'mylib' already exists"))
+        );
+        assert_eq!(
+            scripts.function_load(
+                "#!lua name=other\nredis.register_function('myget', function() end)",
+                false
+            ),
+            Err(SimpleError::new("ERR Function myget already exists"))
+        );
+        assert_eq!(
+            scripts.function_load("#!lua name=empty\nlocal x = 1", false),
+            Err(SimpleError::new("ERR No functions registered"))
+        );
+
+        let libraries = scripts.libraries();
+        assert_eq!(libraries.len(), 1);
+        assert_eq!(
+            libraries[0].functions["myget"],
+            FunctionInfo {
+                description: Some("read a key".to_string()),
+                flags: vec!["no-writes".to_string()],
+            }
+        );
+
+        let keys = vec!["foo".into()];
+        let reply = scripts.fcall(&backend, "myset", keys.clone(), vec!["bar".into()], true);
+        assert_eq!(
+            reply,
+            SimpleError::new("ERR Can not execute a script with write flag using *_ro command.")
+                .into()
+        );
+        scripts.fcall(&backend, "myset", keys.clone(), vec!["bar".into()], false);
+        let reply = scripts.fcall(&backend, "myget", keys, vec![], true);
+        assert_eq!(reply, BulkString::from("bar").into());
+
+        // SCRIPT FLUSH doesn't touch functions
+        scripts.flush();
+        assert_eq!(
+            scripts.fcall(&backend, "myget", vec!["foo".into()], vec![], false),
+            BulkString::from("bar").into()
+        );
+
+        assert!(scripts.function_delete("mylib").is_ok());
+        assert_eq!(
+            scripts.fcall(&backend, "myget", vec![], vec![], false),
+            SimpleError::new("ERR Function not found").into()
+        );
+    }
+
+    #[test]
+    fn test_function_dump_restore() {
+        let backend = Backend::new();
+        let scripts = &backend.scripts;
+        scripts.function_load(LIBRARY, false).unwrap();
+        let payload = scripts.function_dump();
+
+        assert_eq!(
+            scripts.function_restore(&payload, RestorePolicy::Append),
+            Err(SimpleError::new("ERR Library 'mylib' already exists"))
+        );
+        scripts.function_flush();
+        assert!(scripts.libraries().is_empty());
+        assert!(scripts
+            .function_restore(&payload, RestorePolicy::Append)
+            .is_ok());
+        assert_eq!(scripts.libraries()[0].code, LIBRARY);
+        assert!(scripts
+            .function_restore(&payload, RestorePolicy::Replace)
+            .is_ok());
+
+        let corrupted = payload.replace("myget", "myGET");
+        assert_eq!(
+            scripts.function_restore(&corrupted, RestorePolicy::Flush),
+            Err(SimpleError::new(
+                "ERR payload version or checksum are wrong"
+            ))
+        );
+        assert_eq!(scripts.libraries().len(), 1);
+    }
+
+    #[test]
+    fn test_function_load_timeout() {
+        let backend = Backend::new();
+        let scripts = &backend.scripts;
+        scripts.function_load(LIBRARY, false).unwrap();
+
+        assert_eq!(
+            scripts.function_load("#!lua name=x\nwhile true do end", false),
+            Err(SimpleError::new(
+                "ERR Error registering functions: FUNCTION LOAD timeout"
+            ))
+        );
+        assert_eq!(scripts.libraries().len(), 1);
+        assert_eq!(
+            scripts.fcall(&backend, "myget", vec!["foo".into()], vec![], false),
+            BulkString::new_null().into()
+        );
+    }
+}
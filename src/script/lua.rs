@@ -0,0 +1,674 @@
+use std::{
+    collections::BTreeMap,
+    sync::{atomic::Ordering, Arc},
+};
+
+use bytes::Bytes;
+use mlua::{Function, HookTriggers, Lua, LuaOptions, MultiValue, StdLib, Table, Value};
+use tracing::info;
+
+use crate::{
+    cmd::{Command, CommandExecutor},
+    Backend, BulkString, RespArray, RespFrame, SimpleError, SimpleString,
+};
+
+use super::{
+    function::{is_valid_name, FunctionInfo, FUNCTION_FLAGS},
+    sha1hex, RunState,
+};
+
+// registry slots holding the compiled scripts (by SHA1), the registered
+// functions (by name), the runner and the `redis` table scripts only see
+// through a read-only proxy
+const SCRIPTS: &str = "scripts";
+const FUNCTIONS: &str = "functions";
+const RUNNER: &str = "runner";
+const REDIS: &str = "redis";
+
+// how often the VM checks for SCRIPT KILL and the FUNCTION LOAD time
+// limit, in VM instructions
+const HOOK_INTERVAL: u32 = 1000;
+
+// Every script shares the VM, so nothing one of them can reach may be
+// changed: the globals live behind an empty _G, the libraries behind
+// read-only proxies, and the raw accessors refuse both.
+const PRELUDE: &str = r#"
+local error, pcall, pairs, tostring, type = error, pcall, pairs, tostring, type
+local rawget, rawset, getmetatable, setmetatable = rawget, rawset, getmetatable, setmetatable
+local G, lib = _G, redis
+
+lib.call = function(...)
+  local reply = lib.pcall(...)
+  if type(reply) == 'table' and reply.err then
+    error(reply)
+  end
+  return reply
+end
+
+local protected = {}
+local function readonly(t)
+  local proxy = setmetatable({}, {
+    __index = t,
+    __newindex = function()
+      error("Attempt to modify a readonly table", 2)
+    end,
+    __metatable = false,
+  })
+  protected[proxy] = true
+  return proxy
+end
+
+local env = {}
+for name, value in pairs(G) do
+  env[name] = value
+end
+for name in pairs(env) do
+  rawset(G, name, nil)
+end
+for _, name in pairs({'redis', 'string', 'table', 'math'}) do
+  env[name] = readonly(env[name])
+end
+-- string methods are looked up through the string metatable
+local string_mt = getmetatable('')
+string_mt.__index = env.string
+string_mt.__metatable = false
+
+env.rawset = function(t, k, v)
+  if protected[t] then
+    error("Attempt to modify a readonly table", 2)
+  end
+  return rawset(t, k, v)
+end
+env.rawget = function(t, k)
+  if protected[t] then
+    error("Attempt to access a protected table", 2)
+  end
+  return rawget(t, k)
+end
+-- setfenv(0, ...) would swap the globals of every later script
+env.setfenv, env.getfenv = nil, nil
+
+protected[G] = true
+setmetatable(G, {
+  __newindex = function(_, name)
+    if env[name] ~= nil then
+      error("Attempt to modify a readonly table", 2)
+    end
+    error("Script attempted to create global variable '" .. tostring(name) .. "'", 2)
+  end,
+  __index = function(_, name)
+    local value = env[name]
+    if value == nil then
+      error("Script attempted to access nonexistent global variable '" .. tostring(name) .. "'", 2)
+    end
+    return value
+  end,
+  __metatable = false,
+})
+
+return function(f, ...)
+  return pcall(f, ...)
+end
+"#;
+
+/// A sandboxed Lua 5.1 VM: no io/os/package libraries, no file loading,
+/// read-only globals and libraries, and a `redis` table whose `pcall` is
+/// bound per script run.
+pub(super) fn new_vm(run: Arc<RunState>) -> mlua::Result<Lua> {
+    let lua = Lua::new_with(
+        StdLib::TABLE | StdLib::STRING | StdLib::MATH,
+        LuaOptions::default(),
+    )?;
+    let globals = lua.globals();
+    for name in ["dofile", "loadfile"] {
+        globals.raw_set(name, Value::Nil)?;
+    }
+
+    let redis = lua.create_table()?;
+    redis.set(
+        "status_reply",
+        lua.create_function(|lua, s: mlua::String| reply_table(lua, "ok", s))?,
+    )?;
+    redis.set(
+        "error_reply",
+        lua.create_function(|lua, s: mlua::String| reply_table(lua, "err", s))?,
+    )?;
+    redis.set(
+        "sha1hex",
+        lua.create_function(|_, s: mlua::String| Ok(sha1hex(s.as_bytes())))?,
+    )?;
+    redis.set(
+        "log",
+        lua.create_function(|_, (level, message): (i64, mlua::String)| {
+            info!("script log ({}): {}", level, message.to_string_lossy());
+            Ok(())
+        })?,
+    )?;
+    for (i, level) in ["LOG_DEBUG", "LOG_VERBOSE", "LOG_NOTICE", "LOG_WARNING"]
+        .into_iter()
+        .enumerate()
+    {
+        redis.set(level, i)?;
+    }
+    globals.raw_set("redis", redis.clone())?;
+    drop(globals);
+    lua.set_named_registry_value(REDIS, redis)?;
+
+    lua.set_named_registry_value(SCRIPTS, lua.create_table()?)?;
+    lua.set_named_registry_value(FUNCTIONS, lua.create_table()?)?;
+    let runner: Function = lua.load(PRELUDE).set_name("@prelude").eval()?;
+    lua.set_named_registry_value(RUNNER, runner)?;
+
+    lua.set_hook(
+        HookTriggers::new().every_nth_instruction(HOOK_INTERVAL),
+        move |_, _| {
+            if run.kill.load(Ordering::Relaxed) {
+                return Err(mlua::Error::runtime(
+                    "Script killed by user with SCRIPT KILL...",
+                ));
+            }
+            if run.is_past_deadline() {
+                return Err(mlua::Error::runtime("FUNCTION LOAD timeout"));
+            }
+            Ok(())
+        },
+    );
+    Ok(lua)
+}
+
+/// Look up the compiled script `sha`, compiling `body` if needed.
+pub(super) fn compile<'lua>(
+    lua: &'lua Lua,
+    sha: &str,
+    body: &str,
+) -> Result<Function<'lua>, SimpleError> {
+    let scripts: Table = lua.named_registry_value(SCRIPTS).map_err(vm_error)?;
+    if let Some(function) = scripts
+        .raw_get::<_, Option<Function>>(sha)
+        .map_err(vm_error)?
+    {
+        return Ok(function);
+    }
+    let function = lua
+        .load(body)
+        .set_name("@user_script")
+        .into_function()
+        .map_err(|e| match e {
+            mlua::Error::SyntaxError { message, .. } => SimpleError::new(format!(
+                "ERR Error compiling script (new function): {}",
+                message
+            )),
+            e => vm_error(e),
+        })?;
+    scripts.raw_set(sha, function.clone()).map_err(vm_error)?;
+    Ok(function)
+}
+
+/// Run `body` with `redis.register_function` available, returning the
+/// functions it registered.
+pub(super) fn load_library(
+    lua: &Lua,
+    body: &str,
+) -> Result<BTreeMap<String, FunctionInfo>, SimpleError> {
+    let chunk = lua
+        .load(body)
+        .set_name("@user_function")
+        .into_function()
+        .map_err(|e| match e {
+            mlua::Error::SyntaxError { message, .. } => {
+                SimpleError::new(format!("ERR Error compiling function: {}", message))
+            }
+            e => vm_error(e),
+        })?;
+
+    let mut registered = BTreeMap::new();
+    let mut duplicate = None;
+    let result = lua.scope(|scope| {
+        let register = scope.create_function_mut(|lua, args: MultiValue| {
+            let (name, callback, info) = parse_registration(args)?;
+            let functions: Table = lua.named_registry_value(FUNCTIONS)?;
+            if functions.contains_key(name.as_str())? {
+                duplicate.get_or_insert(name);
+                return Ok(());
+            }
+            functions.raw_set(name.as_str(), callback)?;
+            registered.insert(name, info);
+            Ok(())
+        })?;
+        let redis: Table = lua.named_registry_value(REDIS)?;
+        redis.raw_set("register_function", register)?;
+        let result = chunk.call::<_, ()>(());
+        redis.raw_set("register_function", Value::Nil)?;
+        result
+    });
+    if let Err(e) = result {
+        return Err(SimpleError::new(format!(
+            "ERR Error registering functions: {}",
+            error_message(&e)
+        )));
+    }
+    if let Some(name) = duplicate {
+        return Err(SimpleError::new(format!(
+            "ERR Function {} already exists",
+            name
+        )));
+    }
+    if registered.is_empty() {
+        return Err(SimpleError::new("ERR No functions registered"));
+    }
+    Ok(registered)
+}
+
+// redis.register_function(name, callback) or
+// redis.register_function{function_name=..., callback=..., flags=..., description=...}
+fn parse_registration(args: MultiValue) -> mlua::Result<(String, Function, FunctionInfo)> {
+    let mut args = args.into_iter();
+    let (name, callback, info) = match (args.next(), args.next(), args.next()) {
+        (Some(Value::String(name)), Some(Value::Function(callback)), None) => (
+            name.to_str()?.to_string(),
+            callback,
+            FunctionInfo::default(),
+        ),
+        (Some(Value::Table(table)), None, None) => {
+            let name: String = table.get("function_name")?;
+            let callback: Function = table.get("callback")?;
+            let flags: Option<Vec<String>> = table.get("flags")?;
+            let description: Option<String> = table.get("description")?;
+            let info = FunctionInfo {
+                description,
+                flags: flags.unwrap_or_default(),
+            };
+            (name, callback, info)
+        }
+        _ => {
+            return Err(mlua::Error::runtime(
+                "wrong arguments given to redis.register_function",
+            ))
+        }
+    };
+    if !is_valid_name(&name) {
+        return Err(mlua::Error::runtime(
+            "Function names can only contain letters, numbers, or underscores(_) and must be at least one character long",
+        ));
+    }
+    if let Some(flag) = info
+        .flags
+        .iter()
+        .find(|f| !FUNCTION_FLAGS.contains(&f.as_str()))
+    {
+        return Err(mlua::Error::runtime(format!(
+            "unknown flag given: {}",
+            flag
+        )));
+    }
+    Ok((name, callback, info))
+}
+
+pub(super) fn registered_function<'lua>(
+    lua: &'lua Lua,
+    name: &str,
+) -> Result<Function<'lua>, SimpleError> {
+    let functions: Table = lua.named_registry_value(FUNCTIONS).map_err(vm_error)?;
+    functions
+        .raw_get::<_, Option<Function>>(name)
+        .map_err(vm_error)?
+        .ok_or_else(|| SimpleError::new("ERR Function not found"))
+}
+
+/// What is being run: an EVAL script, named by its SHA1, sees KEYS and
+/// ARGV as globals, a function gets them as its two arguments.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Callable<'a> {
+    Script(&'a str),
+    Function(&'a str),
+}
+
+/// Run a compiled script or registered function, `redis.call` dispatching
+/// into the command table.
+pub(super) fn call(
+    lua: &Lua,
+    function: Function,
+    callable: Callable,
+    backend: &Backend,
+    keys: Vec<Bytes>,
+    args: Vec<Bytes>,
+    run: &RunState,
+) -> RespFrame {
+    let result = lua.scope(|scope| {
+        let pcall = scope.create_function(|lua, args: MultiValue| {
+            frame_to_lua(lua, dispatch(lua, backend, args, run))
+        })?;
+        let globals = lua.globals();
+        let redis: Table = lua.named_registry_value(REDIS)?;
+        redis.raw_set("pcall", pcall)?;
+
+        // keys and arguments are binary safe, Lua strings are byte strings
+        let keys = byte_strings(lua, &keys)?;
+        let args = byte_strings(lua, &args)?;
+        let runner: Function = lua.named_registry_value(RUNNER)?;
+        let (ok, reply): (bool, Value) = match callable {
+            Callable::Script(_) => {
+                globals.raw_set("KEYS", keys)?;
+                globals.raw_set("ARGV", args)?;
+                runner.call(function)?
+            }
+            Callable::Function(_) => runner.call((function, keys, args))?,
+        };
+        Ok(if ok {
+            lua_to_frame(reply)
+        } else {
+            error_to_frame(callable, reply)
+        })
+    });
+    result.unwrap_or_else(|e| vm_error(e).into())
+}
+
+fn byte_strings<'lua>(lua: &'lua Lua, values: &[Bytes]) -> mlua::Result<Vec<mlua::String<'lua>>> {
+    values.iter().map(|v| lua.create_string(v)).collect()
+}
+
+// the body of redis.pcall: errors come back as replies, redis.call raises them
+fn dispatch(lua: &Lua, backend: &Backend, args: MultiValue, run: &RunState) -> RespFrame {
+    let mut frames = Vec::with_capacity(args.len());
+    for arg in args {
+        let arg = match arg {
+            Value::String(_) | Value::Integer(_) | Value::Number(_) => lua.coerce_string(arg),
+            _ => Ok(None),
+        };
+        match arg {
+            Ok(Some(s)) => frames.push(BulkString::from(s.as_bytes()).into()),
+            _ => {
+                return SimpleError::new(
+                    "ERR Lua redis lib command arguments must be strings or integers",
+                )
+                .into()
+            }
+        }
+    }
+    if frames.is_empty() {
+        return SimpleError::new(
+            "ERR Please specify at least one argument for this redis lib call",
+        )
+        .into();
+    }
+
+    let cmd = match Command::parse(RespArray::new(frames).into(), backend) {
+        Ok(Command::Unrecognized(u)) if !u.is_registered(backend) => {
+            return SimpleError::new("ERR Unknown Redis command called from script").into()
+        }
+        Ok(cmd) => cmd,
+        Err(e) => return SimpleError::new(e.to_string()).into(),
+    };
+    if cmd.is_noscript(backend) {
+        return SimpleError::new("ERR This Redis command is not allowed from script").into();
+    }
+    if cmd.is_write(backend) {
+        if run.read_only.load(Ordering::Relaxed) {
+            return SimpleError::new("ERR Write commands are not allowed from read-only scripts.")
+                .into();
+        }
+        run.wrote.store(true, Ordering::Relaxed);
+    }
+    cmd.execute(backend)
+}
+
+fn reply_table<'lua>(lua: &'lua Lua, field: &str, s: mlua::String) -> mlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    table.raw_set(field, s)?;
+    Ok(table)
+}
+
+/// RESP to Lua, following Redis' RESP2 conversion rules.
+fn frame_to_lua<'lua>(lua: &'lua Lua, frame: RespFrame) -> mlua::Result<Value<'lua>> {
+    let value = match frame {
+        RespFrame::Integer(i) => Value::Number(i as f64),
+        RespFrame::BulkString(BulkString::String(s)) => Value::String(lua.create_string(s)?),
+        RespFrame::BulkString(BulkString::Null)
+        | RespFrame::Array(RespArray::Null)
+        | RespFrame::Null(_)
+        | RespFrame::Boolean(false) => Value::Boolean(false),
+        RespFrame::Boolean(true) => Value::Number(1.0),
+        RespFrame::Double(d) => Value::String(lua.create_string(d.0.to_string())?),
+        RespFrame::SimpleString(s) => {
+            Value::Table(reply_table(lua, "ok", lua.create_string(s.0)?)?)
+        }
+        RespFrame::SimpleError(e) => {
+            Value::Table(reply_table(lua, "err", lua.create_string(e.0)?)?)
+        }
+        RespFrame::Array(RespArray::Array(items))
+        | RespFrame::Set(crate::RespSet(items))
+        | RespFrame::Push(crate::RespPush(items)) => {
+            let table = lua.create_table_with_capacity(items.len(), 0)?;
+            for (i, item) in items.into_iter().enumerate() {
+                table.raw_set(i + 1, frame_to_lua(lua, item)?)?;
+            }
+            Value::Table(table)
+        }
+        RespFrame::Map(map) => {
+            let table = lua.create_table_with_capacity(map.len() * 2, 0)?;
+            for (i, (key, value)) in map.into_iter().enumerate() {
+                table.raw_set(i * 2 + 1, frame_to_lua(lua, key)?)?;
+                table.raw_set(i * 2 + 2, frame_to_lua(lua, value)?)?;
+            }
+            Value::Table(table)
+        }
+        frame @ (RespFrame::VerbatimString(_)
+        | RespFrame::BigNumber(_)
+        | RespFrame::BulkError(_)
+        | RespFrame::Attribute(_)) => return frame_to_lua(lua, frame.into_resp2()),
+    };
+    Ok(value)
+}
+
+/// Lua to RESP: numbers are truncated to integers, arrays stop at the
+/// first nil, and `{ok=...}` / `{err=...}` tables become status / error
+/// replies.
+fn lua_to_frame(value: Value) -> RespFrame {
+    match value {
+        Value::Boolean(true) => RespFrame::Integer(1),
+        Value::Integer(i) => RespFrame::Integer(i),
+        Value::Number(n) => RespFrame::Integer(n as i64),
+        Value::String(s) => BulkString::from(s.as_bytes()).into(),
+        Value::Table(table) => {
+            if let Ok(Value::String(e)) = table.raw_get("err") {
+                return SimpleError::new(e.to_string_lossy()).into();
+            }
+            if let Ok(Value::String(s)) = table.raw_get("ok") {
+                return SimpleString::new(s.to_string_lossy()).into();
+            }
+            let mut items = Vec::new();
+            for i in 1.. {
+                match table.raw_get(i) {
+                    Ok(Value::Nil) | Err(_) => break,
+                    Ok(item) => items.push(lua_to_frame(item)),
+                }
+            }
+            RespArray::new(items).into()
+        }
+        _ => BulkString::new_null().into(),
+    }
+}
+
+fn error_to_frame(callable: Callable, error: Value) -> RespFrame {
+    let message = match error {
+        Value::Table(table) => match table.raw_get("err") {
+            Ok(Value::String(e)) => return SimpleError::new(e.to_string_lossy()).into(),
+            _ => "unknown error".to_string(),
+        },
+        Value::String(s) => s.to_string_lossy().to_string(),
+        Value::Error(e) => e.to_string(),
+        _ => "unknown error".to_string(),
+    };
+    let message = match callable {
+        Callable::Script(sha) => {
+            format!("ERR Error running script (call to f_{}): {}", sha, message)
+        }
+        Callable::Function(name) => format!("ERR Error running function '{}': {}", name, message),
+    };
+    SimpleError::new(message).into()
+}
+
+// the innermost message of an error raised through Rust callbacks
+fn error_message(e: &mlua::Error) -> String {
+    match e {
+        mlua::Error::CallbackError { cause, .. } => error_message(cause),
+        mlua::Error::RuntimeError(message) => message.clone(),
+        e => e.to_string(),
+    }
+}
+
+fn vm_error(e: mlua::Error) -> SimpleError {
+    SimpleError::new(format!("ERR Lua VM error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lua_conversion() -> mlua::Result<()> {
+        let lua = Lua::new();
+        let frame: RespFrame = RespArray::new([
+            RespFrame::Integer(42),
+            BulkString::from("hello").into(),
+            BulkString::new_null().into(),
+            SimpleString::new("OK").into(),
+        ])
+        .into();
+        let value = frame_to_lua(&lua, frame.clone())?;
+
+        let table = value.as_table().unwrap();
+        assert_eq!(table.raw_get::<_, f64>(1)?, 42.0);
+        assert_eq!(table.raw_get::<_, String>(2)?, "hello");
+        assert!(!table.raw_get::<_, bool>(3)?);
+
+        // numbers are truncated and the first nil ends the array
+        let value: Value = lua.load("return {1, 2.7, 'x', nil, 4}").eval()?;
+        assert_eq!(
+            lua_to_frame(value),
+            RespArray::new([
+                RespFrame::Integer(1),
+                RespFrame::Integer(2),
+                BulkString::from("x").into(),
+            ])
+            .into()
+        );
+
+        let value: Value = lua.load("return {err='ERR boom'}").eval()?;
+        assert_eq!(lua_to_frame(value), SimpleError::new("ERR boom").into());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sandbox() {
+        let backend = Backend::new();
+        let lua = new_vm(Arc::default()).unwrap();
+        let run = RunState::default();
+
+        let function = compile(&lua, "a", "x = 1").unwrap();
+        let reply = call(
+            &lua,
+            function,
+            Callable::Script("a"),
+            &backend,
+            vec![],
+            vec![],
+            &run,
+        );
+        assert_eq!(
+            reply,
+            SimpleError::new("ERR Error running script (call to f_a): user_script:1: Script attempted to create global variable 'x'").into()
+        );
+
+        let function = compile(&lua, "b", "return os.time()").unwrap();
+        let reply = call(
+            &lua,
+            function,
+            Callable::Script("b"),
+            &backend,
+            vec![],
+            vec![],
+            &run,
+        );
+        assert!(
+            matches!(reply, RespFrame::SimpleError(e) if e.contains("nonexistent global variable 'os'"))
+        );
+
+        assert!(compile(&lua, "c", "return (").is_err());
+
+        let function = compile(&lua, "d", "return redis.call('nosuchcommand')").unwrap();
+        let reply = call(
+            &lua,
+            function,
+            Callable::Script("d"),
+            &backend,
+            vec![],
+            vec![],
+            &run,
+        );
+        assert_eq!(
+            reply,
+            SimpleError::new("ERR Unknown Redis command called from script").into()
+        );
+    }
+
+    #[test]
+    fn test_scripts_cannot_tamper_with_the_vm() {
+        let backend = Backend::new();
+        backend.set("k".into(), BulkString::from("v").into());
+        let lua = new_vm(Arc::default()).unwrap();
+        let run = RunState::default();
+        let run_script = |sha: &str, body: &str| {
+            let function = compile(&lua, sha, body).unwrap();
+            call(
+                &lua,
+                function,
+                Callable::Script(sha),
+                &backend,
+                vec![],
+                vec![],
+                &run,
+            )
+        };
+
+        for (i, body) in [
+            "redis.call = function() return 'hijacked' end",
+            "rawset(redis, 'call', function() return 'hijacked' end)",
+            "rawset(_G, 'x', 5)",
+            "rawget(_G, 'redis')",
+            "tostring = function() return 'hijacked' end",
+            "string.upper = function() return 'hijacked' end",
+            "getmetatable('').__index = {upper = function() return 'hijacked' end}",
+            "setmetatable(_G, nil)",
+            "getmetatable(redis).__index.call = function() return 'hijacked' end",
+            "table.insert = nil",
+            "math.floor = nil",
+            "setfenv(0, {})",
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let reply = run_script(&format!("tamper{}", i), body);
+            assert!(matches!(reply, RespFrame::SimpleError(_)), "{}", body);
+        }
+
+        let reply = run_script(
+            "check",
+            "return {redis.call('GET', 'k'), string.upper('a'), ('b'):upper(), tostring(1)}",
+        );
+        assert_eq!(
+            reply,
+            RespArray::new([
+                BulkString::from("v").into(),
+                BulkString::from("A").into(),
+                BulkString::from("B").into(),
+                BulkString::from("1").into(),
+            ])
+            .into()
+        );
+        let reply = run_script("x", "return x");
+        assert!(
+            matches!(reply, RespFrame::SimpleError(e) if e.contains("nonexistent global variable 'x'"))
+        );
+    }
+}
@@ -0,0 +1,366 @@
+mod function;
+mod lua;
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use mlua::Lua;
+
+use crate::{Backend, RespFrame, SimpleError};
+
+use self::function::Functions;
+pub(crate) use self::function::RestorePolicy;
+
+// busy-reply-threshold (a.k.a. lua-time-limit) default, in milliseconds
+const DEFAULT_BUSY_THRESHOLD: u64 = 5000;
+
+/// Scripts cached by their SHA1, function libraries, the Lua VMs running
+/// them and the state SCRIPT KILL and blocked clients look at while one is
+/// running.
+#[derive(Debug)]
+pub(crate) struct ScriptEngine {
+    cache: DashMap<String, String>,
+    vm: Mutex<Option<Lua>>,
+    functions: Mutex<Functions>,
+    run: Arc<RunState>,
+    busy_threshold: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct RunState {
+    started: Mutex<Option<Instant>>,
+    // when loading a library must be done by, FUNCTION LOAD's time limit
+    deadline: Mutex<Option<Instant>>,
+    kill: AtomicBool,
+    wrote: AtomicBool,
+    read_only: AtomicBool,
+}
+
+impl Default for ScriptEngine {
+    fn default() -> Self {
+        Self {
+            cache: DashMap::new(),
+            vm: Mutex::new(None),
+            functions: Mutex::default(),
+            run: Arc::default(),
+            busy_threshold: AtomicU64::new(DEFAULT_BUSY_THRESHOLD),
+        }
+    }
+}
+
+pub(crate) fn sha1hex(data: impl AsRef<[u8]>) -> String {
+    sha1_smol::Sha1::from(data).digest().to_string()
+}
+
+impl ScriptEngine {
+    /// Compile and cache `body`, returning its SHA1.
+    pub(crate) fn load(&self, body: &str) -> Result<String, SimpleError> {
+        let sha = sha1hex(body);
+        let mut vm = self.vm.lock().unwrap_or_else(|e| e.into_inner());
+        let lua = self.vm(&mut vm)?;
+        lua::compile(lua, &sha, body)?;
+        self.cache.insert(sha.clone(), body.to_string());
+        Ok(sha)
+    }
+
+    pub(crate) fn exists(&self, sha: &str) -> bool {
+        self.cache.contains_key(&sha.to_ascii_lowercase())
+    }
+
+    /// Forget every cached script, the VM is recreated on next use.
+    pub(crate) fn flush(&self) {
+        self.cache.clear();
+        *self.vm.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Ask the running script to stop. Scripts that already wrote can't be
+    /// killed, the dataset would be left half-modified.
+    pub(crate) fn kill(&self) -> Result<(), SimpleError> {
+        if self.running_for().is_none() {
+            return Err(SimpleError::new(
+                "NOTBUSY No scripts in execution right now.",
+            ));
+        }
+        if self.run.wrote.load(Ordering::Relaxed) {
+            return Err(SimpleError::new(
+                "UNKILLABLE Sorry the script already executed write commands against the dataset. You can either wait the script termination or kill the server in a hard way using the SHUTDOWN NOSAVE command.",
+            ));
+        }
+        self.run.kill.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Whether a script has been running longer than busy-reply-threshold,
+    /// other clients get a BUSY error instead of waiting for it.
+    pub(crate) fn is_busy(&self) -> bool {
+        let threshold = Duration::from_millis(self.busy_threshold());
+        self.running_for()
+            .is_some_and(|elapsed| elapsed >= threshold)
+    }
+
+    pub(crate) fn busy_threshold(&self) -> u64 {
+        self.busy_threshold.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_busy_threshold(&self, ms: u64) {
+        self.busy_threshold.store(ms, Ordering::Relaxed);
+    }
+
+    /// Run a script, given either its body (EVAL) or its SHA1 (EVALSHA).
+    /// The caller must hold the backend's exclusive lock so the script runs
+    /// atomically with respect to other clients.
+    pub(crate) fn eval(
+        &self,
+        backend: &Backend,
+        script: &Script,
+        keys: Vec<Bytes>,
+        args: Vec<Bytes>,
+        read_only: bool,
+    ) -> RespFrame {
+        let (sha, body) = match script {
+            Script::Body(body) => {
+                let sha = sha1hex(body);
+                self.cache.insert(sha.clone(), body.clone());
+                (sha, body.clone())
+            }
+            Script::Sha(sha) => {
+                let sha = sha.to_ascii_lowercase();
+                match self.cache.get(&sha) {
+                    Some(body) => {
+                        let body = body.clone();
+                        (sha, body)
+                    }
+                    None => {
+                        return SimpleError::new("NOSCRIPT No matching script. Please use EVAL.")
+                            .into()
+                    }
+                }
+            }
+        };
+
+        let mut vm = self.vm.lock().unwrap_or_else(|e| e.into_inner());
+        let lua = match self.vm(&mut vm) {
+            Ok(lua) => lua,
+            Err(e) => return e.into(),
+        };
+        let function = match lua::compile(lua, &sha, &body) {
+            Ok(function) => function,
+            Err(e) => return e.into(),
+        };
+
+        self.run(read_only, |run| {
+            lua::call(
+                lua,
+                function,
+                lua::Callable::Script(&sha),
+                backend,
+                keys,
+                args,
+                run,
+            )
+        })
+    }
+
+    // track a script or function run for SCRIPT KILL and busy clients
+    fn run(&self, read_only: bool, f: impl FnOnce(&RunState) -> RespFrame) -> RespFrame {
+        self.run.kill.store(false, Ordering::Relaxed);
+        self.run.wrote.store(false, Ordering::Relaxed);
+        self.run.read_only.store(read_only, Ordering::Relaxed);
+        self.set_started(Some(Instant::now()));
+        let reply = f(&self.run);
+        self.set_started(None);
+
+        if self.run.kill.swap(false, Ordering::Relaxed) {
+            return SimpleError::new("ERR Script killed by user with SCRIPT KILL...").into();
+        }
+        reply
+    }
+
+    fn vm<'a>(&self, vm: &'a mut Option<Lua>) -> Result<&'a Lua, SimpleError> {
+        if vm.is_none() {
+            *vm = Some(self.new_vm()?);
+        }
+        Ok(vm.as_ref().expect("VM was just created"))
+    }
+
+    fn new_vm(&self) -> Result<Lua, SimpleError> {
+        lua::new_vm(self.run.clone())
+            .map_err(|e| SimpleError::new(format!("ERR Failed to create Lua VM: {}", e)))
+    }
+
+    fn functions(&self) -> MutexGuard<'_, Functions> {
+        self.functions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn running_for(&self) -> Option<Duration> {
+        let started = self.run.started.lock().unwrap_or_else(|e| e.into_inner());
+        started.map(|t| t.elapsed())
+    }
+
+    fn set_started(&self, started: Option<Instant>) {
+        *self.run.started.lock().unwrap_or_else(|e| e.into_inner()) = started;
+    }
+
+    // run `f` with the VM hook aborting it once `timeout` is up
+    fn with_deadline<R>(&self, timeout: Duration, f: impl FnOnce() -> R) -> R {
+        self.run.set_deadline(Some(Instant::now() + timeout));
+        let result = f();
+        self.run.set_deadline(None);
+        result
+    }
+}
+
+impl RunState {
+    fn is_past_deadline(&self) -> bool {
+        let deadline = self.deadline.lock().unwrap_or_else(|e| e.into_inner());
+        deadline.is_some_and(|t| Instant::now() >= t)
+    }
+
+    fn set_deadline(&self, deadline: Option<Instant>) {
+        *self.deadline.lock().unwrap_or_else(|e| e.into_inner()) = deadline;
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Script {
+    Body(String),
+    Sha(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::{BulkString, RespArray};
+
+    #[test]
+    fn test_sha1hex() {
+        assert_eq!(
+            sha1hex("return 1"),
+            "e0e1f9fabfc9d4800c877a703b823ac0578ff8db"
+        );
+    }
+
+    #[test]
+    fn test_eval_script() {
+        let backend = Backend::new();
+        let reply = backend.scripts.eval(
+            &backend,
+            &Script::Body(
+                "redis.call('set', KEYS[1], ARGV[1]) return redis.call('get', KEYS[1])".to_string(),
+            ),
+            vec!["foo".into()],
+            vec!["bar".into()],
+            false,
+        );
+        assert_eq!(reply, BulkString::from("bar").into());
+
+        let sha = sha1hex("return {1, 'two', {ok='three'}}");
+        let reply =
+            backend
+                .scripts
+                .eval(&backend, &Script::Sha(sha.clone()), vec![], vec![], false);
+        assert_eq!(
+            reply,
+            SimpleError::new("NOSCRIPT No matching script. Please use EVAL.").into()
+        );
+
+        assert_eq!(
+            backend.scripts.load("return {1, 'two', {ok='three'}}"),
+            Ok(sha.clone())
+        );
+        let reply = backend
+            .scripts
+            .eval(&backend, &Script::Sha(sha), vec![], vec![], false);
+        assert_eq!(
+            reply,
+            RespArray::new([
+                RespFrame::Integer(1),
+                BulkString::from("two").into(),
+                crate::SimpleString::new("three").into(),
+            ])
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_read_only_script() {
+        let backend = Backend::new();
+        let reply = backend.scripts.eval(
+            &backend,
+            &Script::Body("return redis.pcall('set', 'foo', 'bar')".to_string()),
+            vec![],
+            vec![],
+            true,
+        );
+        assert_eq!(
+            reply,
+            SimpleError::new("ERR Write commands are not allowed from read-only scripts.").into()
+        );
+        assert_eq!(backend.get(b"foo"), None);
+    }
+
+    #[test]
+    fn test_script_calls_wasm_command() {
+        let backend = Backend::new();
+        backend
+            .wasm
+            .load(&backend, "copy", crate::wasm::tests::MODULE.as_bytes())
+            .unwrap();
+        let eval = |body: &str, read_only| {
+            let script = Script::Body(body.to_string());
+            backend
+                .scripts
+                .eval(&backend, &script, vec![], vec![], read_only)
+        };
+
+        assert_eq!(
+            eval("return redis.call('wcopy', 'foo', 'bar')", false),
+            BulkString::from("bar").into()
+        );
+        assert_eq!(
+            eval("return redis.pcall('wcopy', 'foo', 'baz')", true),
+            SimpleError::new("ERR Write commands are not allowed from read-only scripts.").into()
+        );
+        assert_eq!(
+            eval("return redis.pcall('wspin')", false),
+            SimpleError::new("ERR This Redis command is not allowed from script").into()
+        );
+        assert_eq!(backend.get(b"foo"), Some(BulkString::from("bar").into()));
+    }
+
+    #[test]
+    fn test_kill_busy_script() {
+        let backend = Backend::new();
+        backend.scripts.set_busy_threshold(0);
+        assert!(backend.scripts.kill().is_err());
+
+        let cloned = backend.clone();
+        let handle = thread::spawn(move || {
+            cloned.scripts.eval(
+                &cloned,
+                &Script::Body("while true do end".to_string()),
+                vec![],
+                vec![],
+                false,
+            )
+        });
+        while !backend.scripts.is_busy() {
+            thread::yield_now();
+        }
+        assert!(backend.scripts.kill().is_ok());
+        assert_eq!(
+            handle.join().unwrap(),
+            SimpleError::new("ERR Script killed by user with SCRIPT KILL...").into()
+        );
+        assert!(!backend.scripts.is_busy());
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    collections::BTreeSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use bytes::Bytes;
+use tokio::sync::mpsc;
+
+use crate::{
+    backend::{Caller, TrackingOptions},
+    cmd::Command,
+    Backend, BulkString, RespArray, RespFrame, RespPush, SimpleError, Subscriber,
+};
+
+/// Per-connection state, created by `stream_handler` for every client and
+/// handed to commands that need to know who is calling them.
+#[derive(Debug)]
+pub(crate) struct Session {
+    pub(crate) id: u64,
+    pub(crate) protocol: u8,
+    pub(crate) name: Option<String>,
+    pub(crate) channels: BTreeSet<Bytes>,
+    pub(crate) patterns: BTreeSet<Bytes>,
+    pub(crate) shard_channels: BTreeSet<Bytes>,
+    // commands queued after MULTI, None outside of a transaction
+    pub(crate) multi: Option<Vec<Command>>,
+    // a command failed to parse while queuing, EXEC must abort
+    pub(crate) multi_error: bool,
+    // CLIENT CACHING yes/no, applies to the next command only
+    pub(crate) caching: Option<bool>,
+    watched: Vec<(Bytes, u64)>,
+    backend: Backend,
+    push_tx: mpsc::Sender<RespPush>,
+    overflowed: Arc<AtomicBool>,
+    replies: Vec<RespFrame>,
+}
+
+impl Session {
+    /// Create a session along with the receiving half of its out-of-band
+    /// channel, which carries pub/sub messages to the connection.
+    pub(crate) fn new(backend: Backend) -> (Self, mpsc::Receiver<RespPush>) {
+        let (push_tx, push_rx) = mpsc::channel(backend.subscriber_buffer_cap());
+        let session = Self {
+            id: backend.next_client_id(),
+            protocol: 2,
+            name: None,
+            channels: BTreeSet::new(),
+            patterns: BTreeSet::new(),
+            shard_channels: BTreeSet::new(),
+            multi: None,
+            multi_error: false,
+            caching: None,
+            watched: Vec::new(),
+            backend,
+            push_tx,
+            overflowed: Arc::new(AtomicBool::new(false)),
+            replies: Vec::new(),
+        };
+        (session, push_rx)
+    }
+
+    pub(crate) fn subscriber(&self) -> Subscriber {
+        Subscriber::new(self.id, self.push_tx.clone(), self.overflowed.clone())
+    }
+
+    /// Whether a publisher found this client's output buffer full.
+    pub(crate) fn is_overflowed(&self) -> bool {
+        self.overflowed.load(Ordering::Relaxed)
+    }
+
+    /// Subscribe to `channel`, returning the number of active subscriptions.
+    pub(crate) fn subscribe(&mut self, channel: &[u8]) -> usize {
+        if self.channels.insert(Bytes::copy_from_slice(channel)) {
+            self.backend.pubsub.subscribe(channel, self.subscriber());
+        }
+        self.subscription_count()
+    }
+
+    pub(crate) fn unsubscribe(&mut self, channel: &[u8]) -> usize {
+        if self.channels.remove(channel) {
+            self.backend.pubsub.unsubscribe(channel, self.id);
+        }
+        self.subscription_count()
+    }
+
+    pub(crate) fn psubscribe(&mut self, pattern: &[u8]) -> usize {
+        if self.patterns.insert(Bytes::copy_from_slice(pattern)) {
+            self.backend.pubsub.psubscribe(pattern, self.subscriber());
+        }
+        self.subscription_count()
+    }
+
+    pub(crate) fn punsubscribe(&mut self, pattern: &[u8]) -> usize {
+        if self.patterns.remove(pattern) {
+            self.backend.pubsub.punsubscribe(pattern, self.id);
+        }
+        self.subscription_count()
+    }
+
+    pub(crate) fn ssubscribe(&mut self, channel: &[u8]) -> usize {
+        self.forget_evicted_shard_channels();
+        if self.shard_channels.insert(Bytes::copy_from_slice(channel)) {
+            self.backend.pubsub.ssubscribe(channel, self.subscriber());
+        }
+        self.subscription_count()
+    }
+
+    pub(crate) fn sunsubscribe(&mut self, channel: &[u8]) -> usize {
+        self.forget_evicted_shard_channels();
+        if self.shard_channels.remove(channel) {
+            self.backend.pubsub.sunsubscribe(channel, self.id);
+        }
+        self.subscription_count()
+    }
+
+    /// Drop the shard channels the backend evicted when their slot moved
+    /// away. The `sunsubscribe` push telling us so can be lost to a full
+    /// buffer, so the registry is what counts.
+    pub(crate) fn forget_evicted_shard_channels(&mut self) {
+        let (pubsub, id) = (&self.backend.pubsub, self.id);
+        self.shard_channels
+            .retain(|channel| pubsub.is_shard_subscriber(channel, id));
+    }
+
+    pub(crate) fn subscription_count(&self) -> usize {
+        self.channels.len() + self.patterns.len() + self.shard_channels.len()
+    }
+
+    /// RESP2 clients with active subscriptions can only issue the
+    /// subscription commands and PING.
+    pub(crate) fn in_subscriber_mode(&self) -> bool {
+        self.protocol == 2 && self.subscription_count() > 0
+    }
+
+    /// Shape an out-of-band message for this client: a push frame under
+    /// RESP3, a plain array under RESP2.
+    pub(crate) fn push_frame(&self, push: RespPush) -> RespFrame {
+        if self.protocol >= 3 {
+            push.into()
+        } else {
+            RespArray::new(push.into_inner()).into()
+        }
+    }
+
+    pub(crate) fn in_multi(&self) -> bool {
+        self.multi.is_some()
+    }
+
+    pub(crate) fn watch(&mut self, key: Bytes) {
+        if self.watched.iter().any(|(k, _)| *k == key) {
+            return;
+        }
+        let version = self.backend.watched.watch(&key);
+        self.watched.push((key, version));
+    }
+
+    pub(crate) fn unwatch(&mut self) {
+        for (key, _) in self.watched.drain(..) {
+            self.backend.watched.unwatch(&key);
+        }
+    }
+
+    /// Whether any WATCHed key was modified since it was watched.
+    pub(crate) fn watched_keys_changed(&self) -> bool {
+        self.watched
+            .iter()
+            .any(|(key, version)| self.backend.watched.version(key) != *version)
+    }
+
+    /// Switch protocol after HELLO. Tracking is told too, as RESP2
+    /// connections only get invalidations through a redirect.
+    pub(crate) fn set_protocol(&mut self, protocol: u8) {
+        self.protocol = protocol;
+        if let Some(options) = self.backend.tracking.options(self.id) {
+            self.track(options);
+        }
+    }
+
+    pub(crate) fn set_name(&mut self, name: String) -> Result<(), SimpleError> {
+        if name.chars().any(|c| !('!'..='~').contains(&c)) {
+            return Err(SimpleError::new(
+                "ERR Client names cannot contain spaces, newlines or special characters.",
+            ));
+        }
+        // an empty name removes it
+        self.name = (!name.is_empty()).then_some(name);
+        Ok(())
+    }
+
+    /// Start CLIENT TRACKING, invalidations come through the out-of-band
+    /// channel.
+    pub(crate) fn track(&mut self, options: TrackingOptions) {
+        self.backend
+            .tracking
+            .enable(self.id, options, self.subscriber(), self.protocol);
+    }
+
+    pub(crate) fn untrack(&mut self) {
+        self.backend.tracking.disable(self.id);
+        self.caching = None;
+    }
+
+    /// Who the backend sees running the next command. Consumes the
+    /// CLIENT CACHING answer given for it.
+    pub(crate) fn caller(&mut self) -> Caller {
+        let caching = self.caching.take();
+        let track_reads = match self.backend.tracking.options(self.id) {
+            Some(options) if options.bcast => false,
+            Some(options) if options.optin => caching == Some(true),
+            Some(options) if options.optout => caching != Some(false),
+            Some(_) => true,
+            None => false,
+        };
+        Caller {
+            id: self.id,
+            track_reads,
+        }
+    }
+
+    /// Turn a message received from the backend into the frame sent to the
+    /// client. A bare `sunsubscribe` means the backend dropped one of our
+    /// shard channels because its slot moved away from this node.
+    pub(crate) fn push_message(&mut self, push: RespPush) -> RespFrame {
+        let mut frames = push.into_inner();
+        if frames.len() == 2 && frames[0] == BulkString::from("sunsubscribe").into() {
+            self.forget_evicted_shard_channels();
+            frames.push(RespFrame::Integer(self.subscription_count() as i64));
+        }
+        self.push_frame(RespPush::new(frames))
+    }
+
+    /// Commands like SUBSCRIBE answer with one frame per argument. All but
+    /// the last are queued here and sent ahead of the returned frame.
+    pub(crate) fn reply_many(&mut self, frames: Vec<RespFrame>) -> RespFrame {
+        let mut frames = frames;
+        let last = frames.pop().unwrap_or_else(|| RespArray::new([]).into());
+        self.replies.extend(frames);
+        last
+    }
+
+    pub(crate) fn take_replies(&mut self) -> Vec<RespFrame> {
+        std::mem::take(&mut self.replies)
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.unwatch();
+        self.untrack();
+        for channel in &self.channels {
+            self.backend.pubsub.unsubscribe(channel, self.id);
+        }
+        for pattern in &self.patterns {
+            self.backend.pubsub.punsubscribe(pattern, self.id);
+        }
+        for channel in &self.shard_channels {
+            self.backend.pubsub.sunsubscribe(channel, self.id);
+        }
+    }
+}
@@ -0,0 +1,572 @@
+//! Commands implemented as WebAssembly modules.
+//!
+//! A module imports its host functions from the `redis` namespace and
+//! exports its linear memory as `memory`, an `init` function and one
+//! `() -> ()` function per command it registers:
+//!
+//! - `register_command(name_ptr, name_len)`: only valid inside `init`, the
+//!   command is served by the export of the same (lowercase) name and is
+//!   flagged `write`
+//! - `register_command_flags(name_ptr, name_len, flags_ptr, flags_len)`:
+//!   the same with space separated flags instead, `write` if the command
+//!   modifies keys and `noscript` if scripts may not call it
+//! - `arg_count() -> i32`, `arg_len(i) -> i32`, `arg_read(i, ptr) -> i32`:
+//!   the command arguments, lengths are -1 past the last argument
+//! - `get(key_ptr, key_len, ptr, cap) -> i32`: copies up to `cap` bytes of
+//!   the string at `key` and returns its full length, -1 if the key is
+//!   missing and -2 if it doesn't hold a string
+//! - `set(key_ptr, key_len, value_ptr, value_len)`
+//! - `reply_bulk(ptr, len)`, `reply_status(ptr, len)`,
+//!   `reply_error(ptr, len)`, `reply_integer(i64)`, `reply_null()`: the
+//!   reply, a null bulk string if none is set
+//!
+//! Every call runs with a fuel budget so a module stuck in a loop traps
+//! instead of hanging the server.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+use anyhow::{anyhow, bail};
+use wasmtime::{
+    Caller, Config, Engine, Instance, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap,
+};
+
+use crate::{cmd::command_exists, Backend, BulkString, RespFrame, SimpleError, SimpleString};
+
+// instructions (roughly) a single init or command call may execute
+const DEFAULT_FUEL_LIMIT: u64 = 10_000_000;
+// how big a module's memories may grow, wasm-memory-limit
+const DEFAULT_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+// what register_command_flags accepts
+const COMMAND_FLAGS: &[&str] = &["write", "noscript"];
+
+pub(crate) struct WasmHost {
+    engine: Engine,
+    linker: Linker<HostState>,
+    modules: Mutex<BTreeMap<String, WasmModule>>,
+    fuel_limit: AtomicU64,
+    memory_limit: AtomicUsize,
+}
+
+struct WasmModule {
+    commands: Vec<WasmCommand>,
+    store: Store<HostState>,
+    instance: Instance,
+}
+
+struct WasmCommand {
+    name: String,
+    flags: Vec<String>,
+}
+
+// what the host functions see during a call
+struct HostState {
+    backend: Option<Backend>,
+    args: Vec<Vec<u8>>,
+    reply: Option<RespFrame>,
+    registering: Option<Vec<WasmCommand>>,
+    limits: StoreLimits,
+}
+
+impl fmt::Debug for WasmHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WasmHost")
+            .field("modules", &self.modules().keys().collect::<Vec<_>>())
+            .field("fuel_limit", &self.fuel_limit)
+            .field("memory_limit", &self.memory_limit)
+            .finish()
+    }
+}
+
+impl Default for WasmHost {
+    fn default() -> Self {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).expect("valid wasm engine config");
+        let linker = host_linker(&engine).expect("host functions are defined once");
+        Self {
+            engine,
+            linker,
+            modules: Mutex::default(),
+            fuel_limit: AtomicU64::new(DEFAULT_FUEL_LIMIT),
+            memory_limit: AtomicUsize::new(DEFAULT_MEMORY_LIMIT),
+        }
+    }
+}
+
+impl WasmHost {
+    /// Compile and instantiate a module (binary or text format), run its
+    /// `init` and return the commands it registered.
+    pub(crate) fn load(
+        &self,
+        backend: &Backend,
+        name: &str,
+        bytes: &[u8],
+    ) -> Result<Vec<String>, SimpleError> {
+        let mut modules = self.modules();
+        if modules.contains_key(name) {
+            return Err(SimpleError::new(format!(
+                "ERR module '{}' already loaded",
+                name
+            )));
+        }
+
+        let module = Module::new(&self.engine, bytes)
+            .map_err(|e| SimpleError::new(format!("ERR failed to compile module: {}", e)))?;
+        let state = HostState {
+            backend: None,
+            args: Vec::new(),
+            reply: None,
+            registering: Some(Vec::new()),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(self.memory_limit())
+                .build(),
+        };
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limits);
+        self.refuel(&mut store);
+        let instance = self
+            .linker
+            .instantiate(&mut store, &module)
+            .map_err(|e| SimpleError::new(format!("ERR failed to instantiate module: {}", e)))?;
+        instance
+            .get_typed_func::<(), ()>(&mut store, "init")
+            .and_then(|init| init.call(&mut store, ()))
+            .map_err(|e| SimpleError::new(format!("ERR module init failed: {}", e.root_cause())))?;
+
+        let commands = store.data_mut().registering.take().unwrap_or_default();
+        if commands.is_empty() {
+            return Err(SimpleError::new("ERR module registered no commands"));
+        }
+        for command in &commands {
+            let name = &command.name;
+            if command_exists(backend, name) || modules.values().any(|m| m.command(name).is_some())
+            {
+                return Err(SimpleError::new(format!(
+                    "ERR command '{}' already exists",
+                    name
+                )));
+            }
+            if instance.get_typed_func::<(), ()>(&mut store, name).is_err() {
+                return Err(SimpleError::new(format!(
+                    "ERR module doesn't export a '{}' function",
+                    name
+                )));
+            }
+        }
+
+        let names = commands.iter().map(|c| c.name.clone()).collect();
+        modules.insert(
+            name.to_string(),
+            WasmModule {
+                commands,
+                store,
+                instance,
+            },
+        );
+        Ok(names)
+    }
+
+    pub(crate) fn unload(&self, name: &str) -> Result<(), SimpleError> {
+        match self.modules().remove(name) {
+            Some(_) => Ok(()),
+            None => Err(SimpleError::new(format!("ERR no such module '{}'", name))),
+        }
+    }
+
+    /// Loaded modules with the commands they serve.
+    pub(crate) fn list(&self) -> Vec<(String, Vec<String>)> {
+        self.modules()
+            .iter()
+            .map(|(name, module)| {
+                let commands = module.commands.iter().map(|c| c.name.clone()).collect();
+                (name.clone(), commands)
+            })
+            .collect()
+    }
+
+    pub(crate) fn has_command(&self, name: &str) -> bool {
+        self.command_flags(name).is_some()
+    }
+
+    /// The flags a module registered `name` with, `None` if no module did.
+    pub(crate) fn command_flags(&self, name: &str) -> Option<Vec<String>> {
+        self.modules()
+            .values()
+            .find_map(|module| module.command(name))
+            .map(|command| command.flags.clone())
+    }
+
+    /// Run the command `name` if a module registered it.
+    pub(crate) fn call(
+        &self,
+        backend: &Backend,
+        name: &str,
+        args: Vec<RespFrame>,
+    ) -> Option<RespFrame> {
+        let mut modules = self.modules();
+        let module = modules
+            .values_mut()
+            .find(|module| module.command(name).is_some())?;
+        let store = &mut module.store;
+
+        let state = store.data_mut();
+        state.backend = Some(backend.clone());
+        state.args = args
+            .into_iter()
+            .map(|arg| match arg {
+                RespFrame::BulkString(BulkString::String(s)) => s.into(),
+                _ => Vec::new(),
+            })
+            .collect();
+        state.reply = None;
+        self.refuel(store);
+
+        let result = module
+            .instance
+            .get_typed_func::<(), ()>(&mut *store, name)
+            .and_then(|func| func.call(&mut *store, ()));
+        let state = store.data_mut();
+        state.backend = None;
+        state.args.clear();
+        let reply = state.reply.take();
+
+        Some(match result {
+            Ok(()) => reply.unwrap_or_else(|| BulkString::new_null().into()),
+            Err(e) if e.downcast_ref::<Trap>() == Some(&Trap::OutOfFuel) => {
+                SimpleError::new(format!("ERR WASM command '{}' ran out of fuel", name)).into()
+            }
+            Err(e) => SimpleError::new(format!("ERR WASM command '{}' failed: {}", name, e)).into(),
+        })
+    }
+
+    pub(crate) fn fuel_limit(&self) -> u64 {
+        self.fuel_limit.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_fuel_limit(&self, fuel: u64) {
+        self.fuel_limit.store(fuel, Ordering::Relaxed);
+    }
+
+    /// Only modules loaded afterwards get the new limit.
+    pub(crate) fn memory_limit(&self) -> usize {
+        self.memory_limit.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_memory_limit(&self, bytes: usize) {
+        self.memory_limit.store(bytes, Ordering::Relaxed);
+    }
+
+    fn refuel(&self, store: &mut Store<HostState>) {
+        store
+            .set_fuel(self.fuel_limit())
+            .expect("fuel consumption is enabled");
+    }
+
+    fn modules(&self) -> MutexGuard<'_, BTreeMap<String, WasmModule>> {
+        self.modules.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl WasmModule {
+    fn command(&self, name: &str) -> Option<&WasmCommand> {
+        self.commands.iter().find(|c| c.name == name)
+    }
+}
+
+fn host_linker(engine: &Engine) -> anyhow::Result<Linker<HostState>> {
+    let mut linker = Linker::new(engine);
+    linker.func_wrap(
+        "redis",
+        "register_command",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> anyhow::Result<()> {
+            let name = String::from_utf8(read_memory(&mut caller, ptr, len)?)?;
+            register(&mut caller, name, "write".to_string())
+        },
+    )?;
+    linker.func_wrap(
+        "redis",
+        "register_command_flags",
+        |mut caller: Caller<'_, HostState>,
+         name_ptr: i32,
+         name_len: i32,
+         flags_ptr: i32,
+         flags_len: i32|
+         -> anyhow::Result<()> {
+            let name = String::from_utf8(read_memory(&mut caller, name_ptr, name_len)?)?;
+            let flags = String::from_utf8(read_memory(&mut caller, flags_ptr, flags_len)?)?;
+            register(&mut caller, name, flags)
+        },
+    )?;
+    linker.func_wrap("redis", "arg_count", |caller: Caller<'_, HostState>| {
+        caller.data().args.len() as i32
+    })?;
+    linker.func_wrap(
+        "redis",
+        "arg_len",
+        |caller: Caller<'_, HostState>, i: i32| -> i32 {
+            match caller.data().args.get(i as usize) {
+                Some(arg) => arg.len() as i32,
+                None => -1,
+            }
+        },
+    )?;
+    linker.func_wrap(
+        "redis",
+        "arg_read",
+        |mut caller: Caller<'_, HostState>, i: i32, ptr: i32| -> anyhow::Result<i32> {
+            let Some(arg) = caller.data().args.get(i as usize).cloned() else {
+                return Ok(-1);
+            };
+            write_memory(&mut caller, ptr, &arg)?;
+            Ok(arg.len() as i32)
+        },
+    )?;
+    linker.func_wrap(
+        "redis",
+        "get",
+        |mut caller: Caller<'_, HostState>,
+         key_ptr: i32,
+         key_len: i32,
+         ptr: i32,
+         cap: i32|
+         -> anyhow::Result<i32> {
+            let key = read_memory(&mut caller, key_ptr, key_len)?;
+            let value = match backend(&caller)?.get(&key) {
+                None => return Ok(-1),
+                Some(RespFrame::BulkString(BulkString::String(s))) => s.into(),
+                Some(RespFrame::SimpleString(s)) => s.0.into_bytes(),
+                Some(RespFrame::Integer(i)) => i.to_string().into_bytes(),
+                Some(_) => return Ok(-2),
+            };
+            let n = value.len().min(cap.max(0) as usize);
+            write_memory(&mut caller, ptr, &value[..n])?;
+            Ok(value.len() as i32)
+        },
+    )?;
+    linker.func_wrap(
+        "redis",
+        "set",
+        |mut caller: Caller<'_, HostState>,
+         key_ptr: i32,
+         key_len: i32,
+         value_ptr: i32,
+         value_len: i32|
+         -> anyhow::Result<()> {
+            let key = read_memory(&mut caller, key_ptr, key_len)?;
+            let value = read_memory(&mut caller, value_ptr, value_len)?;
+            backend(&caller)?.set(key.into(), BulkString::new(value).into());
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        "redis",
+        "reply_bulk",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> anyhow::Result<()> {
+            let data = read_memory(&mut caller, ptr, len)?;
+            caller.data_mut().reply = Some(BulkString::new(data).into());
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        "redis",
+        "reply_status",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> anyhow::Result<()> {
+            let data = String::from_utf8(read_memory(&mut caller, ptr, len)?)?;
+            caller.data_mut().reply = Some(SimpleString::new(data).into());
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        "redis",
+        "reply_error",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> anyhow::Result<()> {
+            let data = String::from_utf8(read_memory(&mut caller, ptr, len)?)?;
+            caller.data_mut().reply = Some(SimpleError::new(data).into());
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        "redis",
+        "reply_integer",
+        |mut caller: Caller<'_, HostState>, i: i64| {
+            caller.data_mut().reply = Some(RespFrame::Integer(i));
+        },
+    )?;
+    linker.func_wrap(
+        "redis",
+        "reply_null",
+        |mut caller: Caller<'_, HostState>| {
+            caller.data_mut().reply = Some(BulkString::new_null().into());
+        },
+    )?;
+    Ok(linker)
+}
+
+fn register(caller: &mut Caller<'_, HostState>, name: String, flags: String) -> anyhow::Result<()> {
+    let flags: Vec<String> = flags.split_whitespace().map(str::to_lowercase).collect();
+    if let Some(flag) = flags.iter().find(|f| !COMMAND_FLAGS.contains(&f.as_str())) {
+        bail!("unknown command flag '{}'", flag);
+    }
+    match caller.data_mut().registering.as_mut() {
+        Some(commands) => {
+            commands.push(WasmCommand {
+                name: name.to_lowercase(),
+                flags,
+            });
+            Ok(())
+        }
+        None => bail!("register_command can only be called from init"),
+    }
+}
+
+fn backend(caller: &Caller<'_, HostState>) -> anyhow::Result<Backend> {
+    caller
+        .data()
+        .backend
+        .clone()
+        .ok_or_else(|| anyhow!("keys can only be accessed from a command"))
+}
+
+fn read_memory(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> anyhow::Result<Vec<u8>> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .ok_or_else(|| anyhow!("module doesn't export its memory"))?;
+    let mut buf = vec![0; usize::try_from(len)?];
+    memory.read(&*caller, usize::try_from(ptr)?, &mut buf)?;
+    Ok(buf)
+}
+
+fn write_memory(caller: &mut Caller<'_, HostState>, ptr: i32, data: &[u8]) -> anyhow::Result<()> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .ok_or_else(|| anyhow!("module doesn't export its memory"))?;
+    memory.write(&mut *caller, usize::try_from(ptr)?, data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // wcopy key value: SET key value and reply with the value
+    // wspin: never returns, flagged noscript
+    pub(crate) const MODULE: &str = r#"
+(module
+  (import "redis" "register_command" (func $register (param i32 i32)))
+  (import "redis" "register_command_flags"
+    (func $register_flags (param i32 i32 i32 i32)))
+  (import "redis" "arg_read" (func $arg_read (param i32 i32) (result i32)))
+  (import "redis" "set" (func $set (param i32 i32 i32 i32)))
+  (import "redis" "reply_bulk" (func $reply_bulk (param i32 i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "wcopy")
+  (data (i32.const 16) "wspin")
+  (data (i32.const 32) "NOSCRIPT")
+  (func (export "init")
+    (call $register (i32.const 0) (i32.const 5))
+    (call $register_flags (i32.const 16) (i32.const 5) (i32.const 32) (i32.const 8)))
+  (func (export "wcopy")
+    (local $klen i32)
+    (local $vlen i32)
+    (local.set $klen (call $arg_read (i32.const 0) (i32.const 1024)))
+    (local.set $vlen (call $arg_read (i32.const 1) (i32.const 2048)))
+    (call $set (i32.const 1024) (local.get $klen) (i32.const 2048) (local.get $vlen))
+    (call $reply_bulk (i32.const 2048) (local.get $vlen)))
+  (func (export "wspin")
+    (loop $forever (br $forever))))
+"#;
+
+    #[test]
+    fn test_wasm_module() {
+        let backend = Backend::new();
+        let wasm = &backend.wasm;
+        assert_eq!(
+            wasm.load(&backend, "copy", MODULE.as_bytes()),
+            Ok(vec!["wcopy".to_string(), "wspin".to_string()])
+        );
+        assert!(wasm.has_command("wcopy"));
+        assert_eq!(wasm.command_flags("wcopy"), Some(vec!["write".to_string()]));
+        assert_eq!(
+            wasm.command_flags("wspin"),
+            Some(vec!["noscript".to_string()])
+        );
+        assert_eq!(wasm.command_flags("nope"), None);
+        assert_eq!(
+            wasm.load(&backend, "copy", MODULE.as_bytes()),
+            Err(SimpleError::new("ERR module 'copy' already loaded"))
+        );
+        assert_eq!(
+            wasm.load(&backend, "again", MODULE.as_bytes()),
+            Err(SimpleError::new("ERR command 'wcopy' already exists"))
+        );
+
+        let reply = wasm.call(
+            &backend,
+            "wcopy",
+            vec![
+                BulkString::from("foo").into(),
+                BulkString::from("bar").into(),
+            ],
+        );
+        assert_eq!(reply, Some(BulkString::from("bar").into()));
+        assert_eq!(backend.get(b"foo"), Some(BulkString::from("bar").into()));
+
+        wasm.set_fuel_limit(10_000);
+        assert_eq!(
+            wasm.call(&backend, "wspin", vec![]),
+            Some(SimpleError::new("ERR WASM command 'wspin' ran out of fuel").into())
+        );
+        assert_eq!(wasm.call(&backend, "nope", vec![]), None);
+
+        assert!(wasm.unload("copy").is_ok());
+        assert!(!wasm.has_command("wcopy"));
+        assert!(wasm.unload("copy").is_err());
+    }
+
+    #[test]
+    fn test_wasm_builtin_name() {
+        let backend = Backend::new();
+        let module = r#"
+(module
+  (import "redis" "register_command" (func $register (param i32 i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "GET")
+  (func (export "init") (call $register (i32.const 0) (i32.const 3)))
+  (func (export "get")))
+"#;
+        assert_eq!(
+            backend.wasm.load(&backend, "m", module.as_bytes()),
+            Err(SimpleError::new("ERR command 'get' already exists"))
+        );
+    }
+
+    #[test]
+    fn test_wasm_unknown_flag() {
+        let backend = Backend::new();
+        let module = r#"
+(module
+  (import "redis" "register_command_flags" (func $register (param i32 i32 i32 i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "wfast")
+  (data (i32.const 16) "write fast")
+  (func (export "init")
+    (call $register (i32.const 0) (i32.const 5) (i32.const 16) (i32.const 10)))
+  (func (export "wfast")))
+"#;
+        let err = backend
+            .wasm
+            .load(&backend, "m", module.as_bytes())
+            .unwrap_err();
+        assert!(err.0.contains("unknown command flag 'fast'"), "{}", err.0);
+        assert!(!backend.wasm.has_command("wfast"));
+    }
+}